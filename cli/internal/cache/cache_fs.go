@@ -8,6 +8,9 @@ package cache
 import (
 	"encoding/json"
 	"fmt"
+	"path"
+	"strings"
+	"time"
 
 	"github.com/vercel/turbo/cli/internal/analytics"
 	"github.com/vercel/turbo/cli/internal/cacheitem"
@@ -59,13 +62,19 @@ func (f *fsCache) Fetch(anchor turbopath.AbsoluteSystemPath, hash string, _ []st
 		return ItemStatus{Local: false}, nil, 0, restoreErr
 	}
 
-	meta, err := ReadCacheMetaFile(f.cacheDirectory.UntypedJoin(hash + "-meta.json"))
+	metaPath := f.cacheDirectory.UntypedJoin(hash + "-meta.json")
+	meta, err := ReadCacheMetaFile(metaPath)
 	if err != nil {
 		_ = cacheItem.Close()
 		return ItemStatus{Local: false}, nil, 0, fmt.Errorf("error reading cache metadata: %w", err)
 	}
 	f.logFetch(true, hash, meta.Duration)
 
+	// Record the access so that `turbo cache prune` can evict least recently
+	// used artifacts first. Failing to do so shouldn't fail the restore.
+	meta.LastAccessed = time.Now().UnixMilli()
+	_ = WriteCacheMetaFile(metaPath, meta)
+
 	// Wait to see what happens with close.
 	closeErr := cacheItem.Close()
 	if closeErr != nil {
@@ -117,8 +126,10 @@ func (f *fsCache) Put(anchor turbopath.AbsoluteSystemPath, hash string, duration
 	}
 
 	writeErr := WriteCacheMetaFile(f.cacheDirectory.UntypedJoin(hash+"-meta.json"), &CacheMetadata{
-		Duration: duration,
-		Hash:     hash,
+		Duration:     duration,
+		Hash:         hash,
+		LastAccessed: time.Now().UnixMilli(),
+		Task:         taskFromOutputs(files),
 	})
 
 	if writeErr != nil {
@@ -144,6 +155,37 @@ func (f *fsCache) Shutdown() {}
 type CacheMetadata struct {
 	Hash     string `json:"hash"`
 	Duration int    `json:"duration"`
+	// LastAccessed is the time, in milliseconds since the unix epoch, at which
+	// this entry was last written or restored
+	LastAccessed int64 `json:"lastAccessed,omitempty"`
+	// Task is the task that produced this entry, e.g. "apps/web#build", so
+	// that `turbo cache ls` doesn't need to open the artifact to find it
+	Task string `json:"task,omitempty"`
+}
+
+// taskFromOutputs finds the task log, <package>/.turbo/turbo-<task>.log, among
+// the files of an artifact and returns the task it belongs to, using "//" for
+// the root package. Returns "" if there is no log.
+func taskFromOutputs(files []turbopath.AnchoredSystemPath) string {
+	for _, file := range files {
+		unixPath := file.ToUnixPath().ToString()
+		fileName := path.Base(unixPath)
+		if !strings.HasPrefix(fileName, "turbo-") || !strings.HasSuffix(fileName, ".log") {
+			continue
+		}
+		turboDir := path.Dir(unixPath)
+		if path.Base(turboDir) != ".turbo" {
+			continue
+		}
+
+		task := strings.TrimSuffix(strings.TrimPrefix(fileName, "turbo-"), ".log")
+		packageDir := path.Dir(turboDir)
+		if packageDir == "." {
+			packageDir = "//"
+		}
+		return packageDir + "#" + task
+	}
+	return ""
 }
 
 // WriteCacheMetaFile writes cache metadata file at a path
@@ -250,4 +250,44 @@ func TestFetch(t *testing.T) {
 	circleTarget, circleReadlinkErr := dstCirclePath.Readlink()
 	assert.NilError(t, circleReadlinkErr, "Circle Readlink")
 	assert.Equal(t, circleTarget, srcCircleLinkTarget.ToString())
+
+	// Assert that the access was recorded in the metadata
+	meta, err := ReadCacheMetaFile(metadataPath)
+	assert.NilError(t, err, "ReadCacheMetaFile")
+	if meta.LastAccessed == 0 {
+		t.Error("LastAccessed got 0, want the time of the last fetch")
+	}
+}
+
+func TestTaskFromOutputs(t *testing.T) {
+	testCases := []struct {
+		name  string
+		files []string
+		want  string
+	}{
+		{
+			name:  "package task",
+			files: []string{"apps/web/dist/index.js", "apps/web/.turbo/turbo-build.log"},
+			want:  "apps/web#build",
+		},
+		{
+			name:  "root task",
+			files: []string{".turbo/turbo-lint.log"},
+			want:  "//#lint",
+		},
+		{
+			name:  "no log",
+			files: []string{"dist/turbo-build.log", "dist/index.js"},
+			want:  "",
+		},
+	}
+	for _, tc := range testCases {
+		files := make([]turbopath.AnchoredSystemPath, len(tc.files))
+		for i, file := range tc.files {
+			files[i] = turbopath.AnchoredUnixPath(file).ToSystemPath()
+		}
+		if got := taskFromOutputs(files); got != tc.want {
+			t.Errorf("%v: taskFromOutputs got %v, want %v", tc.name, got, tc.want)
+		}
+	}
 }
@@ -9,6 +9,7 @@ import (
 	"path/filepath"
 	"sort"
 	"strings"
+	"time"
 
 	"github.com/muhammadmuzzammil1998/jsonc"
 	"github.com/pkg/errors"
@@ -98,6 +99,8 @@ type rawTaskWithDefaults struct {
 	Env            []string                        `json:"env"`
 	PassThroughEnv []string                        `json:"passThroughEnv"`
 	DotEnv         turbopath.AnchoredUnixPathArray `json:"dotEnv"`
+	Timeout        string                          `json:"timeout,omitempty"`
+	Retries        *rawTaskRetries                 `json:"retries,omitempty"`
 }
 
 // rawTask exists to Unmarshal from json. When fields are omitted, we _want_
@@ -112,6 +115,36 @@ type rawTask struct {
 	Env            []string             `json:"env,omitempty"`
 	PassThroughEnv []string             `json:"passThroughEnv,omitempty"`
 	DotEnv         []string             `json:"dotEnv,omitempty"`
+	Timeout        *string              `json:"timeout,omitempty"`
+	Retries        *rawTaskRetries      `json:"retries,omitempty"`
+}
+
+// rawTaskRetries accepts either a bare count (`"retries": 2`) or an object
+// with a count and a backoff (`"retries": {"count": 2, "backoff": "1s"}`).
+type rawTaskRetries struct {
+	Count   int    `json:"count"`
+	Backoff string `json:"backoff,omitempty"`
+}
+
+// UnmarshalJSON deserializes either form of the "retries" key
+func (r *rawTaskRetries) UnmarshalJSON(data []byte) error {
+	trimmed := strings.TrimSpace(string(data))
+	if strings.HasPrefix(trimmed, "{") {
+		// Alias the type so that we don't recurse back into this method
+		type plainRetries rawTaskRetries
+		return json.Unmarshal(data, (*plainRetries)(r))
+	}
+	if err := json.Unmarshal(data, &r.Count); err != nil {
+		return fmt.Errorf("\"retries\" must be a number or an object with \"count\" and \"backoff\" keys, found %s", trimmed)
+	}
+	return nil
+}
+
+// TaskRetries configures how many more times a task is run after it fails,
+// and how long to wait before each retry.
+type TaskRetries struct {
+	Count   int
+	Backoff time.Duration
 }
 
 // taskDefinitionHashable exists as a definition for PristinePipeline, which is used down
@@ -128,6 +161,8 @@ type taskDefinitionHashable struct {
 	Env                     []string
 	PassThroughEnv          []string
 	DotEnv                  turbopath.AnchoredUnixPathArray
+	Timeout                 time.Duration
+	Retries                 TaskRetries
 }
 
 // taskDefinitionExperiments is a list of config fields in a task definition that are considered
@@ -189,6 +224,14 @@ type TaskDefinition struct {
 
 	// rawTask.DotEnv
 	DotEnv turbopath.AnchoredUnixPathArray
+
+	// Timeout is how long a single attempt of the Task may run before its
+	// process group is killed. Zero means no timeout.
+	Timeout time.Duration
+
+	// Retries determines how many times a failed Task is re-run before the
+	// failure is reported.
+	Retries TaskRetries
 }
 
 // GetTask returns a TaskDefinition based on the ID (package#task format) or name (e.g. "build")
@@ -408,6 +451,8 @@ func (btd BookkeepingTaskDefinition) GetTaskDefinition() TaskDefinition {
 		Env:                     btd.TaskDefinition.Env,
 		DotEnv:                  btd.TaskDefinition.DotEnv,
 		PassThroughEnv:          btd.TaskDefinition.PassThroughEnv,
+		Timeout:                 btd.TaskDefinition.Timeout,
+		Retries:                 btd.TaskDefinition.Retries,
 	}
 }
 
@@ -465,6 +510,14 @@ func MergeTaskDefinitions(taskDefinitions []BookkeepingTaskDefinition) (*TaskDef
 		if bookkeepingTaskDef.hasField("DotEnv") {
 			mergedTaskDefinition.DotEnv = taskDef.DotEnv
 		}
+
+		if bookkeepingTaskDef.hasField("Timeout") {
+			mergedTaskDefinition.Timeout = taskDef.Timeout
+		}
+
+		if bookkeepingTaskDef.hasField("Retries") {
+			mergedTaskDefinition.Retries = taskDef.Retries
+		}
 	}
 
 	return mergedTaskDefinition, nil
@@ -610,6 +663,30 @@ func (btd *BookkeepingTaskDefinition) UnmarshalJSON(data []byte) error {
 	} else {
 		btd.TaskDefinition.Persistent = false
 	}
+
+	if task.Timeout != nil {
+		btd.definedFields.Add("Timeout")
+		timeout, err := time.ParseDuration(*task.Timeout)
+		if err != nil || timeout <= 0 {
+			return fmt.Errorf("\"timeout\" must be a positive duration such as \"90s\" or \"10m\", found %q", *task.Timeout)
+		}
+		btd.TaskDefinition.Timeout = timeout
+	}
+
+	if task.Retries != nil {
+		btd.definedFields.Add("Retries")
+		if task.Retries.Count < 0 {
+			return fmt.Errorf("\"retries\" must not be negative, found %d", task.Retries.Count)
+		}
+		btd.TaskDefinition.Retries = TaskRetries{Count: task.Retries.Count}
+		if task.Retries.Backoff != "" {
+			backoff, err := time.ParseDuration(task.Retries.Backoff)
+			if err != nil || backoff < 0 {
+				return fmt.Errorf("\"retries.backoff\" must be a duration such as \"500ms\" or \"5s\", found %q", task.Retries.Backoff)
+			}
+			btd.TaskDefinition.Retries.Backoff = backoff
+		}
+	}
 	return nil
 }
 
@@ -626,6 +703,8 @@ func (c taskDefinitionHashable) MarshalJSON() ([]byte, error) {
 		c.Env,
 		c.PassThroughEnv,
 		c.DotEnv,
+		c.Timeout,
+		c.Retries,
 	)
 	return json.Marshal(task)
 }
@@ -643,6 +722,8 @@ func (c TaskDefinition) MarshalJSON() ([]byte, error) {
 		c.Env,
 		c.PassThroughEnv,
 		c.DotEnv,
+		c.Timeout,
+		c.Retries,
 	)
 	return json.Marshal(task)
 }
@@ -747,6 +828,8 @@ func makeRawTask(
 	env []string,
 	passThroughEnv []string,
 	dotEnv turbopath.AnchoredUnixPathArray,
+	timeout time.Duration,
+	retries TaskRetries,
 ) *rawTaskWithDefaults {
 	// Initialize with empty arrays, so we get empty arrays serialized into JSON
 	task := &rawTaskWithDefaults{
@@ -763,6 +846,17 @@ func makeRawTask(
 	// This should _not_ be sorted.
 	task.DotEnv = dotEnv
 
+	if timeout > 0 {
+		task.Timeout = timeout.String()
+	}
+
+	if retries.Count > 0 {
+		task.Retries = &rawTaskRetries{Count: retries.Count}
+		if retries.Backoff > 0 {
+			task.Retries.Backoff = retries.Backoff.String()
+		}
+	}
+
 	if len(inputs) > 0 {
 		task.Inputs = inputs
 	}
@@ -1,11 +1,13 @@
 package fs
 
 import (
+	"encoding/json"
 	"os"
 	"reflect"
 	"sort"
 	"strings"
 	"testing"
+	"time"
 
 	"github.com/stretchr/testify/assert"
 	"github.com/vercel/turbo/cli/internal/turbopath"
@@ -477,6 +479,52 @@ func Test_ReadTurboConfig_EnvDeclarations(t *testing.T) {
 	assert.EqualValues(t, sortedArray([]string{"somefile.txt"}), sortedArray(turboJSON.GlobalDeps))
 }
 
+func Test_ReadTurboConfig_TimeoutAndRetries(t *testing.T) {
+	testDir := getTestDir(t, "timeout-retries")
+	turboJSON, turboJSONReadErr := readTurboConfig(testDir.UntypedJoin("turbo.json"))
+	if turboJSONReadErr != nil {
+		t.Fatalf("invalid parse: %#v", turboJSONReadErr)
+	}
+
+	pipeline := turboJSON.Pipeline
+	assert.Equal(t, 10*time.Minute, pipeline["build"].TaskDefinition.Timeout)
+	assert.Equal(t, TaskRetries{}, pipeline["build"].TaskDefinition.Retries)
+	assert.Equal(t, 90*time.Second, pipeline["test"].TaskDefinition.Timeout)
+	assert.Equal(t, TaskRetries{Count: 2}, pipeline["test"].TaskDefinition.Retries)
+	assert.Equal(t, time.Duration(0), pipeline["e2e"].TaskDefinition.Timeout)
+	assert.Equal(t, TaskRetries{Count: 3, Backoff: 5 * time.Second}, pipeline["e2e"].TaskDefinition.Retries)
+
+	// Snapshot test of serialization.
+	bytes, _ := json.Marshal(pipeline["e2e"].GetTaskDefinition())
+	assert.Equal(t, "{\"outputs\":[],\"cache\":true,\"dependsOn\":[],\"inputs\":[],\"outputMode\":\"full\",\"persistent\":false,\"env\":[],\"passThroughEnv\":null,\"dotEnv\":null,\"retries\":{\"count\":3,\"backoff\":\"5s\"}}", string(bytes))
+}
+
+func Test_ReadTurboConfig_InvalidTimeout(t *testing.T) {
+	testDir := getTestDir(t, "invalid-timeout")
+	_, turboJSONReadErr := readTurboConfig(testDir.UntypedJoin("turbo.json"))
+	expectedErrorMsg := "turbo.json: \"timeout\" must be a positive duration such as \"90s\" or \"10m\", found \"ten minutes\""
+	assert.EqualErrorf(t, turboJSONReadErr, expectedErrorMsg, "Error should be: %v, got: %v", expectedErrorMsg, turboJSONReadErr)
+}
+
+func Test_ReadTurboConfig_InvalidRetries(t *testing.T) {
+	testDir := getTestDir(t, "invalid-retries")
+	_, turboJSONReadErr := readTurboConfig(testDir.UntypedJoin("turbo.json"))
+	expectedErrorMsg := "turbo.json: \"retries\" must not be negative, found -1"
+	assert.EqualErrorf(t, turboJSONReadErr, expectedErrorMsg, "Error should be: %v, got: %v", expectedErrorMsg, turboJSONReadErr)
+}
+
+func Test_MergeTaskDefinitions_TimeoutAndRetries(t *testing.T) {
+	root := BookkeepingTaskDefinition{}
+	assert.NoError(t, json.Unmarshal([]byte(`{"timeout": "5m", "retries": 2}`), &root))
+	workspace := BookkeepingTaskDefinition{}
+	assert.NoError(t, json.Unmarshal([]byte(`{"retries": 0}`), &workspace))
+
+	merged, err := MergeTaskDefinitions([]BookkeepingTaskDefinition{root, workspace})
+	assert.NoError(t, err)
+	assert.Equal(t, 5*time.Minute, merged.Timeout)
+	assert.Equal(t, TaskRetries{}, merged.Retries)
+}
+
 func Test_TaskOutputsSort(t *testing.T) {
 	inclusions := []string{"foo/**", "bar"}
 	exclusions := []string{"special-file", ".hidden/**"}
@@ -26,6 +26,17 @@ func (ce *ChildExit) Error() string {
 	return fmt.Sprintf("command %s exited (%d)", ce.Command, ce.ExitCode)
 }
 
+// ChildTimeout is returned when a child process is killed because it did not
+// exit within its timeout
+type ChildTimeout struct {
+	Timeout time.Duration
+	Command string
+}
+
+func (ct *ChildTimeout) Error() string {
+	return fmt.Sprintf("command %s timed out after %v", ct.Command, ct.Timeout)
+}
+
 // Manager tracks all of the child processes that have been spawned
 type Manager struct {
 	done     bool
@@ -49,6 +60,13 @@ func NewManager(logger hclog.Logger) *Manager {
 // successfully, ErrClosing if the manager closed during execution, and
 // a ChildExit error if the child process exited with a non-zero exit code.
 func (m *Manager) Exec(cmd *exec.Cmd) error {
+	return m.ExecWithTimeout(cmd, 0)
+}
+
+// ExecWithTimeout behaves like Exec, except that if timeout is non-zero and the
+// child process is still running once it elapses, the child's entire process
+// group is killed and a ChildTimeout error is returned.
+func (m *Manager) ExecWithTimeout(cmd *exec.Cmd, timeout time.Duration) error {
 	m.mu.Lock()
 	if m.done {
 		m.mu.Unlock()
@@ -78,14 +96,33 @@ func (m *Manager) Exec(cmd *exec.Cmd) error {
 		m.mu.Unlock()
 		return err
 	}
+	var timeoutCh <-chan time.Time
+	if timeout > 0 {
+		timer := time.NewTimer(timeout)
+		defer timer.Stop()
+		timeoutCh = timer.C
+	}
 	err = nil
-	exitCode, ok := <-child.ExitCh()
-	if !ok {
-		err = ErrClosing
-	} else if exitCode != ExitCodeOK {
-		err = &ChildExit{
-			ExitCode: exitCode,
-			Command:  child.Command(),
+	select {
+	case exitCode, ok := <-child.ExitCh():
+		if !ok {
+			err = ErrClosing
+		} else if exitCode != ExitCodeOK {
+			err = &ChildExit{
+				ExitCode: exitCode,
+				Command:  child.Command(),
+			}
+		}
+	case <-timeoutCh:
+		// The child is in its own process group, so this takes down
+		// anything it spawned as well.
+		if killErr := child.Signal(os.Kill); killErr != nil {
+			m.logger.Debug("failed to kill timed out process", "error", killErr)
+		}
+		<-child.ExitCh()
+		err = &ChildTimeout{
+			Timeout: timeout,
+			Command: child.Command(),
 		}
 	}
 
@@ -374,7 +374,7 @@ func (ec *execContext) exec(ctx gocontext.Context, packageTask *nodes.PackageTas
 	}
 
 	// Run the command
-	if err := ec.processes.Exec(cmd); err != nil {
+	if err := ec.execWithRetries(packageTask, cmd, prefixedUI, taskExecutionSummary); err != nil {
 		// close off our outputs. We errored, so we mostly don't care if we fail to close
 		_ = closeOutputs()
 		// if we already know we're in the process of exiting,
@@ -428,3 +428,52 @@ func (ec *execContext) exec(ctx gocontext.Context, packageTask *nodes.PackageTas
 	progressLogger.Debug("done", "status", "complete", "duration", taskExecutionSummary.Duration)
 	return taskExecutionSummary, nil
 }
+
+// execWithRetries runs cmd, killing it if it outlives the task's timeout, and runs it
+// again for as long as it keeps failing and the task has retries left. The error from
+// the final attempt is returned.
+func (ec *execContext) execWithRetries(packageTask *nodes.PackageTask, cmd *exec.Cmd, prefixedUI cli.Ui, taskExecutionSummary *runsummary.TaskExecutionSummary) error {
+	timeout := packageTask.TaskDefinition.Timeout
+	retries := packageTask.TaskDefinition.Retries
+	recordAttempts := timeout > 0 || retries.Count > 0
+
+	for attempt := 0; ; attempt++ {
+		attemptCmd := cmd
+		if attempt > 0 {
+			// An exec.Cmd can only be started once, so every retry gets a fresh copy
+			attemptCmd = &exec.Cmd{
+				Path:   cmd.Path,
+				Args:   cmd.Args,
+				Dir:    cmd.Dir,
+				Env:    cmd.Env,
+				Stdout: cmd.Stdout,
+				Stderr: cmd.Stderr,
+			}
+		}
+
+		startAt := time.Now()
+		err := ec.processes.ExecWithTimeout(attemptCmd, timeout)
+		if errors.Is(err, process.ErrClosing) {
+			return err
+		}
+
+		if recordAttempts {
+			var exitCode *int
+			var childExit *process.ChildExit
+			var childTimeout *process.ChildTimeout
+			if err == nil {
+				exitCode = new(int)
+			} else if errors.As(err, &childExit) {
+				exitCode = &childExit.ExitCode
+			}
+			taskExecutionSummary.AddAttempt(startAt, exitCode, errors.As(err, &childTimeout))
+		}
+
+		if err == nil || attempt >= retries.Count {
+			return err
+		}
+
+		prefixedUI.Warn(fmt.Sprintf("command finished with error: %s, retrying (%d/%d)...", err, attempt+1, retries.Count))
+		time.Sleep(retries.Backoff)
+	}
+}
@@ -74,6 +74,44 @@ type TaskExecutionSummary struct {
 	err      string             // only populated for failure statuses
 	Duration time.Duration      // updated during the task execution
 	exitCode *int               // pointer so we can distinguish between 0 and unknown.
+	attempts []TaskAttempt      // only recorded for tasks with a timeout or retries
+}
+
+// TaskAttempt records a single execution of a task's command. Tasks configured
+// with a timeout or retries may be executed more than once.
+type TaskAttempt struct {
+	startAt  time.Time
+	Duration time.Duration
+	ExitCode *int // nil if the command did not exit on its own
+	TimedOut bool
+}
+
+// MarshalJSON munges the TaskAttempt into a format we want
+func (ta TaskAttempt) MarshalJSON() ([]byte, error) {
+	serializable := struct {
+		Start    int64 `json:"startTime"`
+		End      int64 `json:"endTime"`
+		ExitCode *int  `json:"exitCode"`
+		TimedOut bool  `json:"timedOut"`
+	}{
+		Start:    ta.startAt.UnixMilli(),
+		End:      ta.startAt.Add(ta.Duration).UnixMilli(),
+		ExitCode: ta.ExitCode,
+		TimedOut: ta.TimedOut,
+	}
+
+	return json.Marshal(&serializable)
+}
+
+// AddAttempt records an execution of the task's command that started at startAt
+// and has just finished.
+func (ts *TaskExecutionSummary) AddAttempt(startAt time.Time, exitCode *int, timedOut bool) {
+	ts.attempts = append(ts.attempts, TaskAttempt{
+		startAt:  startAt,
+		Duration: time.Since(startAt),
+		ExitCode: exitCode,
+		TimedOut: timedOut,
+	})
 }
 
 func (ts *TaskExecutionSummary) endTime() time.Time {
@@ -84,15 +122,17 @@ func (ts *TaskExecutionSummary) endTime() time.Time {
 // We'll use an anonmyous, private struct for this, so it's not confusingly duplicated
 func (ts *TaskExecutionSummary) MarshalJSON() ([]byte, error) {
 	serializable := struct {
-		Start    int64  `json:"startTime"`
-		End      int64  `json:"endTime"`
-		Err      string `json:"error,omitempty"`
-		ExitCode *int   `json:"exitCode"`
+		Start    int64         `json:"startTime"`
+		End      int64         `json:"endTime"`
+		Err      string        `json:"error,omitempty"`
+		ExitCode *int          `json:"exitCode"`
+		Attempts []TaskAttempt `json:"attempts,omitempty"`
 	}{
 		Start:    ts.startAt.UnixMilli(),
 		End:      ts.endTime().UnixMilli(),
 		Err:      ts.err,
 		ExitCode: ts.exitCode,
+		Attempts: ts.attempts,
 	}
 
 	return json.Marshal(&serializable)
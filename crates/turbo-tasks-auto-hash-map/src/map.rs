@@ -190,6 +190,19 @@ impl<K: Eq + Hash, V, H: BuildHasher + Default> AutoMap<K, V, H> {
         }
     }
 
+    /// see [HashMap::retain](https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.retain)
+    pub fn retain(&mut self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        match self {
+            AutoMap::List(list) => list.retain_mut(|(k, v)| f(k, v)),
+            AutoMap::Map(map) => {
+                map.retain(f);
+                if map.len() < MIN_HASH_SIZE {
+                    self.convert_to_list();
+                }
+            }
+        }
+    }
+
     /// see [HashMap::shrink_to_fit](https://doc.rust-lang.org/std/collections/struct.HashMap.html#method.shrink_to_fit)
     pub fn shrink_to_fit(&mut self) {
         match self {
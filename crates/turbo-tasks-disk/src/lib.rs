@@ -0,0 +1,812 @@
+//! A [PersistedGraph] that stores task types, cell contents, dependencies and
+//! dirty flags in a directory, so a later process can reuse prior work.
+//!
+//! Use it with `turbo_tasks_memory::MemoryBackendWithPersistedGraph`. Tasks
+//! that read state which doesn't survive a restart (e.g. by creating an
+//! invalidator) are marked as session dependent and are re-executed in the
+//! next session.
+//!
+//! Values of [turbo_tasks::State]s are stored separately from the cells that
+//! contain them, so a changed state is persisted without the creating task.
+
+mod store;
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use turbo_tasks::{
+    backend::PersistentTaskType,
+    persisted_graph::{
+        ActivateResult, DeactivateResult, PersistResult, PersistTaskState, PersistedGraph,
+        PersistedGraphApi, ReadTaskState, TaskCell, TaskData,
+    },
+    registry, with_state_mapping, with_task_id_mapping, IdMapping, RawVc, RestoreStateFn,
+    StateContent, StateId, StateMapping, TaskId,
+};
+use turbo_tasks_hash::Xxh3Hash64Hasher;
+
+use crate::store::{DataRecord, Pid, Store, StoredVc, FORMAT_VERSION};
+
+/// A stored state, addressed by its task and its index within the task
+type StateKey = (Pid, u32);
+
+/// Activeness of a stored task in the current session. It's not persisted,
+/// every session starts with all tasks inactive.
+#[derive(Default)]
+struct Activeness {
+    /// Kept active by the memory graph
+    external: bool,
+    internal_parents: u32,
+    active: bool,
+}
+
+struct State {
+    store: Store,
+    task_ids: HashMap<Pid, TaskId>,
+    pids: HashMap<TaskId, Pid>,
+    activeness: HashMap<Pid, Activeness>,
+    /// Live states, so their values can be persisted when they change
+    states: HashMap<StateKey, Arc<dyn StateContent>>,
+}
+
+pub struct DiskPersistedGraph {
+    state: Mutex<State>,
+}
+
+impl DiskPersistedGraph {
+    /// Opens or creates the persisted graph in `path`.
+    ///
+    /// Data is discarded when it was written with a different `version` or a
+    /// different set of registered functions and value types. `version`
+    /// should change whenever the implementation of any task might have
+    /// changed, e.g. it could identify the build of the executable.
+    ///
+    /// All functions and value types must be registered before calling this.
+    pub fn new(path: impl AsRef<Path>, version: &str) -> Result<Self> {
+        let mut store = Store::open(path.as_ref(), schema_key(version))?;
+        let session_dependent = store
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.data.as_ref().map_or(false, |d| d.session_dependent))
+            .map(|(&pid, _)| pid)
+            .collect::<Vec<_>>();
+        for pid in session_dependent {
+            store.set_dirty(pid, true)?;
+        }
+        store.commit()?;
+        Ok(Self {
+            state: Mutex::new(State {
+                store,
+                task_ids: HashMap::new(),
+                pids: HashMap::new(),
+                activeness: HashMap::new(),
+                states: HashMap::new(),
+            }),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+/// Identifies the format of the stored data. Functions, value types and traits
+/// are stored by name, so the set of registered names is part of it.
+fn schema_key(version: &str) -> u64 {
+    let mut hasher = Xxh3Hash64Hasher::new();
+    hasher.write_value(FORMAT_VERSION);
+    hasher.write_value(version);
+    for name in registry::registered_global_names() {
+        hasher.write_ref(&name);
+    }
+    hasher.finish()
+}
+
+/// Maps [TaskId]s to [Pid]s while serializing. Tasks that are not stored
+/// yet are collected, so they can be added before trying again.
+struct PidMapping<'a> {
+    pids: &'a HashMap<TaskId, Pid>,
+    missing: RefCell<Vec<TaskId>>,
+    refs: RefCell<Vec<Pid>>,
+}
+
+impl IdMapping<TaskId> for PidMapping<'_> {
+    fn forward(&self, id: TaskId) -> usize {
+        if let Some(&pid) = self.pids.get(&id) {
+            self.refs.borrow_mut().push(pid);
+            pid as usize
+        } else {
+            self.missing.borrow_mut().push(id);
+            0
+        }
+    }
+
+    fn backward(&self, _id: usize) -> TaskId {
+        unreachable!("PidMapping is only used for serialization")
+    }
+}
+
+/// Maps [Pid]s to [TaskId]s while deserializing. All referenced tasks need to
+/// be resolved before.
+struct TaskIdMapping<'a> {
+    task_ids: &'a HashMap<Pid, TaskId>,
+    missing: Cell<bool>,
+}
+
+impl IdMapping<TaskId> for TaskIdMapping<'_> {
+    fn forward(&self, _id: TaskId) -> usize {
+        unreachable!("TaskIdMapping is only used for deserialization")
+    }
+
+    fn backward(&self, id: usize) -> TaskId {
+        if let Some(&task) = u32::try_from(id).ok().and_then(|id| self.task_ids.get(&id)) {
+            task
+        } else {
+            // The value is discarded anyway
+            self.missing.set(true);
+            TaskId::from(0)
+        }
+    }
+}
+
+/// Collects the [turbo_tasks::State]s contained in a serialized value.
+#[derive(Default)]
+struct StateCollector {
+    states: RefCell<Vec<(StateId, Arc<dyn StateContent>)>>,
+}
+
+impl StateMapping for StateCollector {
+    fn serialize_state(&self, id: StateId, content: Arc<dyn StateContent>) {
+        self.states.borrow_mut().push((id, content));
+    }
+
+    fn deserialize_state(&self, _id: StateId, _restore: &mut RestoreStateFn<'_>) -> bool {
+        unreachable!("StateCollector is only used for serialization")
+    }
+}
+
+type RestoredStates = Vec<(StateKey, Arc<dyn StateContent>)>;
+
+/// Restores [turbo_tasks::State]s from their stored values while
+/// deserializing.
+struct StateRestorer<'a> {
+    store: RefCell<&'a mut Store>,
+    pids: &'a HashMap<TaskId, Pid>,
+    restored: RefCell<RestoredStates>,
+}
+
+impl StateMapping for StateRestorer<'_> {
+    fn serialize_state(&self, _id: StateId, _content: Arc<dyn StateContent>) {
+        unreachable!("StateRestorer is only used for deserialization")
+    }
+
+    fn deserialize_state(&self, id: StateId, restore: &mut RestoreStateFn<'_>) -> bool {
+        let Some(&pid) = self.pids.get(&id.task) else {
+            return false;
+        };
+        let bytes = self.store.borrow_mut().read_state(pid, id.index);
+        let Ok(Some(bytes)) = bytes else {
+            return false;
+        };
+        let mut deserializer = postcard::Deserializer::from_bytes(&bytes);
+        let mut deserializer = <dyn erased_serde::Deserializer>::erase(&mut deserializer);
+        match restore(&mut deserializer) {
+            Ok(content) => {
+                self.restored.borrow_mut().push(((pid, id.index), content));
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// A value serialized by [State::serialize].
+struct Serialized {
+    bytes: Vec<u8>,
+    /// Stored tasks referenced from within the value
+    refs: Vec<Pid>,
+    /// States contained in the value. Their values are stored separately.
+    states: Vec<(StateId, Arc<dyn StateContent>)>,
+}
+
+impl State {
+    /// Serializes a value that might contain [TaskId]s. Returns None when the
+    /// value can't be serialized or references tasks that can't be stored.
+    /// When `add_missing` is false, referencing unknown tasks fails too.
+    fn serialize<T: Serialize + ?Sized>(
+        &mut self,
+        value: &T,
+        add_missing: bool,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<Serialized>> {
+        loop {
+            let mapping = PidMapping {
+                pids: &self.pids,
+                missing: RefCell::new(Vec::new()),
+                refs: RefCell::new(Vec::new()),
+            };
+            let collector = StateCollector::default();
+            let Ok(bytes) = with_task_id_mapping(&mapping, || {
+                with_state_mapping(&collector, || postcard::to_allocvec(value))
+            }) else {
+                return Ok(None);
+            };
+            let missing = mapping.missing.into_inner();
+            if missing.is_empty() {
+                let mut refs = mapping.refs.into_inner();
+                refs.sort_unstable();
+                refs.dedup();
+                return Ok(Some(Serialized {
+                    bytes,
+                    refs,
+                    states: collector.states.into_inner(),
+                }));
+            }
+            if !add_missing {
+                return Ok(None);
+            }
+            for task in missing {
+                if self.pid_or_add(task, api)?.is_none() {
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(&mut self, bytes: &[u8]) -> Option<T> {
+        let mapping = TaskIdMapping {
+            task_ids: &self.task_ids,
+            missing: Cell::new(false),
+        };
+        let restorer = StateRestorer {
+            store: RefCell::new(&mut self.store),
+            pids: &self.pids,
+            restored: RefCell::new(Vec::new()),
+        };
+        let value = with_task_id_mapping(&mapping, || {
+            with_state_mapping(&restorer, || postcard::from_bytes(bytes))
+        })
+        .ok()?;
+        if mapping.missing.get() {
+            return None;
+        }
+        self.states.extend(restorer.restored.into_inner());
+        Some(value)
+    }
+
+    /// Stores the current value of a state and keeps it to store changes
+    /// later.
+    fn write_state(
+        &mut self,
+        id: StateId,
+        content: Arc<dyn StateContent>,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<()> {
+        let Some(&pid) = self.pids.get(&id.task) else {
+            return Ok(());
+        };
+        // States nested in the value are not supported and can't be restored
+        if let Some(Serialized { bytes, refs, .. }) = self.serialize(&*content, true, api)? {
+            self.store.write_state(pid, id.index, bytes, refs)?;
+        }
+        self.states.insert((pid, id.index), content);
+        Ok(())
+    }
+
+    fn map(&mut self, pid: Pid, task: TaskId) {
+        self.task_ids.entry(pid).or_insert(task);
+        self.pids.insert(task, pid);
+    }
+
+    /// Returns the stored id of a task, adding the task type to the store when
+    /// needed. Returns None for tasks that can't be stored.
+    fn pid_or_add(&mut self, task: TaskId, api: &dyn PersistedGraphApi) -> Result<Option<Pid>> {
+        if let Some(&pid) = self.pids.get(&task) {
+            return Ok(Some(pid));
+        }
+        let Some(ty) = api.lookup_task_type(task) else {
+            return Ok(None);
+        };
+        let Some(Serialized { bytes, refs, .. }) = self.serialize(ty, true, api)? else {
+            return Ok(None);
+        };
+        let pid = match self.store.by_type.get(&bytes) {
+            Some(&pid) => pid,
+            None => self.store.add_task(bytes, refs)?,
+        };
+        self.map(pid, task);
+        Ok(Some(pid))
+    }
+
+    /// Returns the [TaskId] of a stored task, creating the task in the
+    /// backend when needed. Returns None when the task type can't be read.
+    fn task_id(&mut self, pid: Pid, api: &dyn PersistedGraphApi) -> Result<Option<TaskId>> {
+        if let Some(&task) = self.task_ids.get(&pid) {
+            return Ok(Some(task));
+        }
+        let Some(stored) = self.store.tasks.get(&pid) else {
+            return Ok(None);
+        };
+        let refs = stored.refs.clone();
+        for r in refs {
+            if self.task_id(r, api)?.is_none() {
+                return Ok(None);
+            }
+        }
+        let ty = self.store.tasks[&pid].ty.clone();
+        let Some(ty) = self.deserialize::<PersistentTaskType>(&ty) else {
+            return Ok(None);
+        };
+        let task = api.get_or_create_task_type(ty);
+        self.map(pid, task);
+        Ok(Some(task))
+    }
+
+    fn stored_vc(
+        &mut self,
+        vc: RawVc,
+        add_missing: bool,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<StoredVc>> {
+        let task = vc.get_task_id();
+        let pid = if add_missing {
+            self.pid_or_add(task, api)?
+        } else {
+            self.pids.get(&task).copied()
+        };
+        Ok(pid.map(|pid| match vc {
+            RawVc::TaskOutput(_) => StoredVc::Output(pid),
+            RawVc::TaskCell(_, index) => StoredVc::Cell(pid, index),
+        }))
+    }
+
+    fn raw_vc(&mut self, vc: StoredVc, api: &dyn PersistedGraphApi) -> Result<Option<RawVc>> {
+        Ok(self.task_id(vc.task(), api)?.map(|task| match vc {
+            StoredVc::Output(_) => RawVc::TaskOutput(task),
+            StoredVc::Cell(_, index) => RawVc::TaskCell(task, index),
+        }))
+    }
+
+    fn children(&self, pid: Pid) -> Vec<Pid> {
+        self.store
+            .tasks
+            .get(&pid)
+            .and_then(|task| task.data.as_ref())
+            .map(|data| data.children.clone())
+            .unwrap_or_default()
+    }
+
+    fn is_active(&self, pid: Pid) -> bool {
+        self.activeness.get(&pid).map_or(false, |a| a.active)
+    }
+
+    /// Adds an internal active parent to each child. Returns the children that
+    /// need to be activated.
+    fn increment_internal(
+        &mut self,
+        children: impl IntoIterator<Item = Pid>,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Vec<TaskId>> {
+        let mut to_activate = Vec::new();
+        for child in children {
+            let activeness = self.activeness.entry(child).or_default();
+            activeness.internal_parents += 1;
+            if activeness.internal_parents == 1 {
+                if let Some(task) = self.task_id(child, api)? {
+                    to_activate.push(task);
+                }
+            }
+        }
+        Ok(to_activate)
+    }
+
+    /// Removes an internal active parent from each child. Returns the children
+    /// that need to be deactivated.
+    fn decrement_internal(
+        &mut self,
+        children: impl IntoIterator<Item = Pid>,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Vec<TaskId>> {
+        let mut to_deactivate = Vec::new();
+        for child in children {
+            let activeness = self.activeness.entry(child).or_default();
+            if activeness.internal_parents == 0 {
+                continue;
+            }
+            activeness.internal_parents -= 1;
+            if activeness.internal_parents == 0 {
+                if let Some(task) = self.task_id(child, api)? {
+                    to_deactivate.push(task);
+                }
+            }
+        }
+        Ok(to_deactivate)
+    }
+}
+
+impl PersistedGraph for DiskPersistedGraph {
+    fn read(
+        &self,
+        task: TaskId,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<(TaskData, ReadTaskState)>> {
+        let mut state = self.state();
+        let Some(&pid) = state.pids.get(&task) else {
+            return Ok(None);
+        };
+        let Some(record) = state.store.read_data(pid)? else {
+            return Ok(None);
+        };
+        let DataRecord {
+            output,
+            children,
+            dependencies,
+            collectibles,
+            cells,
+            refs,
+            ..
+        } = record;
+        for r in refs {
+            if state.task_id(r, api)?.is_none() {
+                return Ok(None);
+            }
+        }
+        // Tasks referenced from the values of states created by this task. States
+        // of other tasks are only restored when their references are known already.
+        let state_refs = state.store.tasks[&pid]
+            .states
+            .values()
+            .flat_map(|s| s.refs.iter().copied())
+            .collect::<Vec<_>>();
+        for r in state_refs {
+            state.task_id(r, api)?;
+        }
+        let Some(output) = state.raw_vc(output, api)? else {
+            return Ok(None);
+        };
+        let mut data = TaskData {
+            children: Vec::with_capacity(children.len()),
+            dependencies: Vec::with_capacity(dependencies.len()),
+            cells: Vec::with_capacity(cells.len()),
+            output,
+            collectibles: Vec::with_capacity(collectibles.len()),
+        };
+        for child in children {
+            let Some(child) = state.task_id(child, api)? else {
+                return Ok(None);
+            };
+            data.children.push(child);
+        }
+        for dep in dependencies {
+            let Some(dep) = state.raw_vc(dep, api)? else {
+                return Ok(None);
+            };
+            data.dependencies.push(dep);
+        }
+        for (trait_id, collectible, count) in collectibles {
+            let Some(collectible) = state.raw_vc(collectible, api)? else {
+                return Ok(None);
+            };
+            data.collectibles.push((trait_id, collectible, count));
+        }
+        for (index, content) in cells {
+            let cell = content
+                .and_then(|bytes| state.deserialize(&bytes))
+                .map_or(TaskCell::NeedComputation, TaskCell::Content);
+            data.cells.push((index, cell));
+        }
+        let clean = !state.store.tasks[&pid].dirty;
+        let keeps_external_active = state
+            .activeness
+            .get(&pid)
+            .map_or(false, |a| a.internal_parents > 0);
+        Ok(Some((
+            data,
+            ReadTaskState {
+                clean,
+                keeps_external_active,
+            },
+        )))
+    }
+
+    fn lookup(
+        &self,
+        _partial_task_type: &PersistentTaskType,
+        _api: &dyn PersistedGraphApi,
+    ) -> Result<bool> {
+        // Task types are only indexed by their full serialized form
+        Ok(false)
+    }
+
+    fn lookup_one(
+        &self,
+        task_type: &PersistentTaskType,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<TaskId>> {
+        let mut state = self.state();
+        let Some(Serialized { bytes, .. }) = state.serialize(task_type, false, api)? else {
+            return Ok(None);
+        };
+        let Some(&pid) = state.store.by_type.get(&bytes) else {
+            return Ok(None);
+        };
+        state.task_id(pid, api)
+    }
+
+    fn is_persisted(&self, task: TaskId, _api: &dyn PersistedGraphApi) -> Result<bool> {
+        let state = self.state();
+        Ok(state
+            .pids
+            .get(&task)
+            .and_then(|pid| state.store.tasks.get(pid))
+            .map_or(false, |task| task.data.is_some()))
+    }
+
+    fn persist(
+        &self,
+        task: TaskId,
+        data: TaskData,
+        task_state: PersistTaskState,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<PersistResult>> {
+        let mut state = self.state();
+        let Some(pid) = state.pid_or_add(task, api)? else {
+            return Ok(None);
+        };
+        let Some(output) = state.stored_vc(data.output, true, api)? else {
+            return Ok(None);
+        };
+        let mut dependencies = Vec::with_capacity(data.dependencies.len());
+        for dep in data.dependencies {
+            let Some(dep) = state.stored_vc(dep, true, api)? else {
+                return Ok(None);
+            };
+            dependencies.push(dep);
+        }
+        let mut collectibles = Vec::with_capacity(data.collectibles.len());
+        for (trait_id, collectible, count) in data.collectibles {
+            let Some(collectible) = state.stored_vc(collectible, true, api)? else {
+                return Ok(None);
+            };
+            collectibles.push((trait_id, collectible, count));
+        }
+        let mut children = Vec::with_capacity(data.children.len());
+        for child in data.children {
+            // Children only affect activeness, it's fine to skip ones that can't be
+            // stored
+            if let Some(child) = state.pid_or_add(child, api)? {
+                children.push(child);
+            }
+        }
+        let mut refs = Vec::new();
+        let mut states = Vec::new();
+        let mut cells = Vec::with_capacity(data.cells.len());
+        for (index, cell) in data.cells {
+            let content = match cell {
+                TaskCell::Content(content) => state.serialize(&content, true, api)?.map(
+                    |Serialized {
+                         bytes,
+                         refs: cell_refs,
+                         states: cell_states,
+                     }| {
+                        refs.extend(cell_refs);
+                        states.extend(cell_states);
+                        bytes
+                    },
+                ),
+                TaskCell::NeedComputation => None,
+            };
+            cells.push((index, content));
+        }
+        refs.sort_unstable();
+        refs.dedup();
+
+        let old_children = state.children(pid);
+        state.store.write_data(DataRecord {
+            id: pid,
+            session_dependent: task_state.session_dependent,
+            output,
+            children: children.clone(),
+            dependencies,
+            collectibles,
+            cells,
+            refs,
+        })?;
+        for (id, content) in states {
+            state.write_state(id, content, api)?;
+        }
+
+        let activeness = state.activeness.entry(pid).or_default();
+        let was_active = activeness.active;
+        activeness.external = task_state.externally_active;
+        activeness.active = activeness.external || activeness.internal_parents > 0;
+        let is_active = activeness.active;
+        let before = if was_active {
+            old_children.into_iter().collect()
+        } else {
+            HashSet::new()
+        };
+        let after = if is_active {
+            children.into_iter().collect()
+        } else {
+            HashSet::new()
+        };
+        let tasks_to_activate =
+            state.increment_internal(after.difference(&before).copied(), api)?;
+        let tasks_to_deactivate =
+            state.decrement_internal(before.difference(&after).copied(), api)?;
+        Ok(Some(PersistResult {
+            tasks_to_activate,
+            tasks_to_deactivate,
+        }))
+    }
+
+    fn activate_when_needed(
+        &self,
+        task: TaskId,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<ActivateResult>> {
+        let mut state = self.state();
+        let Some(&pid) = state.pids.get(&task) else {
+            return Ok(None);
+        };
+        let (has_data, dirty) = state
+            .store
+            .tasks
+            .get(&pid)
+            .map_or((false, false), |task| (task.data.is_some(), task.dirty));
+        let activeness = state.activeness.entry(pid).or_default();
+        let keeps_external_active = activeness.internal_parents > 0;
+        let mut more_tasks_to_activate = Vec::new();
+        if !activeness.active && (activeness.external || keeps_external_active) {
+            activeness.active = true;
+            let children = state.children(pid);
+            more_tasks_to_activate = state.increment_internal(children, api)?;
+        }
+        Ok(Some(ActivateResult {
+            keeps_external_active,
+            external: !has_data,
+            dirty: has_data && dirty && state.is_active(pid),
+            more_tasks_to_activate,
+        }))
+    }
+
+    fn deactivate_when_needed(
+        &self,
+        task: TaskId,
+        api: &dyn PersistedGraphApi,
+    ) -> Result<Option<DeactivateResult>> {
+        let mut state = self.state();
+        let Some(&pid) = state.pids.get(&task) else {
+            return Ok(None);
+        };
+        let activeness = state.activeness.entry(pid).or_default();
+        if activeness.internal_parents > 0 {
+            return Ok(None);
+        }
+        let mut more_tasks_to_deactivate = Vec::new();
+        if activeness.active && !activeness.external {
+            activeness.active = false;
+            let children = state.children(pid);
+            more_tasks_to_deactivate = state.decrement_internal(children, api)?;
+        }
+        Ok(Some(DeactivateResult {
+            more_tasks_to_deactivate,
+        }))
+    }
+
+    fn set_externally_active(&self, task: TaskId, _api: &dyn PersistedGraphApi) -> Result<bool> {
+        let mut state = self.state();
+        let Some(&pid) = state.pids.get(&task) else {
+            return Ok(false);
+        };
+        let activeness = state.activeness.entry(pid).or_default();
+        activeness.external = true;
+        Ok(!activeness.active)
+    }
+
+    fn unset_externally_active(&self, task: TaskId, _api: &dyn PersistedGraphApi) -> Result<bool> {
+        let mut state = self.state();
+        let Some(&pid) = state.pids.get(&task) else {
+            return Ok(false);
+        };
+        let activeness = state.activeness.entry(pid).or_default();
+        activeness.external = false;
+        Ok(activeness.active && activeness.internal_parents == 0)
+    }
+
+    fn remove_outdated_externally_active(
+        &self,
+        _api: &dyn PersistedGraphApi,
+    ) -> Result<Vec<TaskId>> {
+        // External activeness is not persisted
+        Ok(Vec::new())
+    }
+
+    fn make_dirty(&self, task: TaskId, _api: &dyn PersistedGraphApi) -> Result<bool> {
+        let mut state = self.state();
+        let Some(&pid) = state.pids.get(&task) else {
+            return Ok(false);
+        };
+        state.store.set_dirty(pid, true)?;
+        let dirty = state.store.tasks.get(&pid).map_or(false, |task| task.dirty);
+        Ok(dirty && state.is_active(pid))
+    }
+
+    fn make_clean(&self, task: TaskId, _api: &dyn PersistedGraphApi) -> Result<()> {
+        let mut state = self.state();
+        if let Some(&pid) = state.pids.get(&task) {
+            state.store.set_dirty(pid, false)?;
+        }
+        Ok(())
+    }
+
+    fn make_dependent_dirty(&self, vc: RawVc, api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>> {
+        let mut state = self.state();
+        let Some(vc) = state.stored_vc(vc, false, api)? else {
+            return Ok(Vec::new());
+        };
+        let Some(dependents) = state.store.dependents.get(&vc) else {
+            return Ok(Vec::new());
+        };
+        let mut dependents = dependents.iter().copied().collect::<Vec<_>>();
+        dependents.sort_unstable();
+        let mut tasks = Vec::new();
+        for pid in dependents {
+            if state.store.set_dirty(pid, true)? && state.is_active(pid) {
+                if let Some(task) = state.task_id(pid, api)? {
+                    tasks.push(task);
+                }
+            }
+        }
+        Ok(tasks)
+    }
+
+    fn get_active_external_tasks(&self, _api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>> {
+        // Activeness is not persisted, so nothing is active on startup
+        Ok(Vec::new())
+    }
+
+    fn get_dirty_active_tasks(&self, _api: &dyn PersistedGraphApi) -> Result<Vec<TaskId>> {
+        Ok(Vec::new())
+    }
+
+    fn get_pending_active_update(
+        &self,
+        _api: &dyn PersistedGraphApi,
+    ) -> Result<(Vec<TaskId>, Vec<TaskId>)> {
+        Ok((Vec::new(), Vec::new()))
+    }
+
+    fn persist_states(&self, states: Vec<StateId>, api: &dyn PersistedGraphApi) -> Result<()> {
+        let mut state = self.state();
+        for id in states {
+            let Some(&pid) = state.pids.get(&id.task) else {
+                continue;
+            };
+            if let Some(content) = state.states.get(&(pid, id.index)).cloned() {
+                state.write_state(id, content, api)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn stop(&self, _api: &dyn PersistedGraphApi) -> Result<()> {
+        let mut state = self.state();
+        let removed = state.store.commit()?;
+        for pid in removed {
+            if let Some(task) = state.task_ids.remove(&pid) {
+                state.pids.remove(&task);
+            }
+            state.activeness.remove(&pid);
+            state.states.retain(|&(state_pid, _), _| state_pid != pid);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,883 @@
+//! The on-disk format of the persisted graph.
+//!
+//! A store directory contains an append-only log (`<generation>.log`) and an
+//! `index`. The log is a header followed by framed records
+//! (`len: u32, checksum: u64, payload`). The index is a snapshot of the
+//! in-memory state together with the length of the log it covers.
+//!
+//! Opening a store loads the index and replays the log from that position. A
+//! torn write at the end of the log fails its checksum and is truncated.
+//!
+//! The index is the commit point. It's written to a temporary file and
+//! renamed into place. Compaction writes a new log generation and only
+//! switches to it by committing a new index, so a crash while compacting
+//! leaves the previous generation intact.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{BufReader, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use turbo_tasks::{CellId, TraitTypeId};
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+/// Bumped whenever the format of the log or the index changes.
+pub const FORMAT_VERSION: u32 = 2;
+
+const LOG_MAGIC: &[u8; 8] = b"TTDSKLOG";
+const INDEX_MAGIC: &[u8; 8] = b"TTDSKIDX";
+/// magic + key
+const HEADER_LEN: u64 = 16;
+/// len + checksum
+const FRAME_HEADER_LEN: u64 = 12;
+const INDEX_FILE: &str = "index";
+const INDEX_TMP_FILE: &str = "index.tmp";
+const LOG_EXTENSION: &str = "log";
+
+/// Logs smaller than this are never compacted.
+const COMPACTION_MIN_LEN: u64 = 16 * 1024 * 1024;
+
+/// The id of a task in the store. Unlike a `TaskId` it's stable across
+/// sessions.
+pub type Pid = u32;
+
+/// A reference to a task output or cell in the store.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum StoredVc {
+    Output(Pid),
+    Cell(Pid, CellId),
+}
+
+impl StoredVc {
+    pub fn task(&self) -> Pid {
+        match *self {
+            StoredVc::Output(task) | StoredVc::Cell(task, _) => task,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum Record {
+    /// A task type was added to the store
+    Task {
+        id: Pid,
+        ty: Vec<u8>,
+        refs: Vec<Pid>,
+    },
+    /// A task has been executed
+    Data(DataRecord),
+    /// The dirty flag of a task has changed
+    Dirty { id: Pid, dirty: bool },
+    /// The value of a state created by a task has changed
+    State {
+        id: Pid,
+        index: u32,
+        value: Vec<u8>,
+        refs: Vec<Pid>,
+    },
+}
+
+/// The result of a task execution.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct DataRecord {
+    pub id: Pid,
+    pub session_dependent: bool,
+    pub output: StoredVc,
+    pub children: Vec<Pid>,
+    pub dependencies: Vec<StoredVc>,
+    pub collectibles: Vec<(TraitTypeId, StoredVc, i32)>,
+    /// Serialized cell contents. None when the content couldn't be serialized
+    /// and the task need to be executed again to read the cell.
+    pub cells: Vec<(CellId, Option<Vec<u8>>)>,
+    /// Tasks referenced from within the cell contents
+    pub refs: Vec<Pid>,
+}
+
+/// The parts of a [DataRecord] that are kept in memory.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DataMeta {
+    offset: u64,
+    len: u64,
+    pub session_dependent: bool,
+    pub children: Vec<Pid>,
+    pub dependencies: Vec<StoredVc>,
+    /// Other tasks referenced by the data
+    refs: Vec<Pid>,
+}
+
+impl DataMeta {
+    fn new(record: &DataRecord, offset: u64, len: u64) -> Self {
+        let mut refs = record.refs.clone();
+        refs.push(record.output.task());
+        refs.extend(record.collectibles.iter().map(|(_, vc, _)| vc.task()));
+        refs.sort_unstable();
+        refs.dedup();
+        Self {
+            offset,
+            len,
+            session_dependent: record.session_dependent,
+            children: record.children.clone(),
+            dependencies: record.dependencies.clone(),
+            refs,
+        }
+    }
+}
+
+/// The location of the latest value of a state.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StateMeta {
+    offset: u64,
+    len: u64,
+    /// Tasks referenced from within the value
+    pub refs: Vec<Pid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredTask {
+    /// The serialized `PersistentTaskType`
+    pub ty: Vec<u8>,
+    /// Tasks referenced from within the task type
+    pub refs: Vec<Pid>,
+    pub data: Option<DataMeta>,
+    pub dirty: bool,
+    /// States created by the task, by their index. They are kept when the task
+    /// is executed again, as the new execution creates them again.
+    pub states: HashMap<u32, StateMeta>,
+}
+
+#[derive(Serialize)]
+struct IndexRef<'a> {
+    generation: u64,
+    log_len: u64,
+    next_pid: Pid,
+    tasks: &'a HashMap<Pid, StoredTask>,
+}
+
+#[derive(Deserialize)]
+struct Index {
+    generation: u64,
+    log_len: u64,
+    next_pid: Pid,
+    tasks: HashMap<Pid, StoredTask>,
+}
+
+struct Log {
+    file: File,
+    len: u64,
+}
+
+fn log_file_name(generation: u64) -> String {
+    format!("{generation:08}.{LOG_EXTENSION}")
+}
+
+fn log_path(dir: &Path, generation: u64) -> PathBuf {
+    dir.join(log_file_name(generation))
+}
+
+fn header(magic: &[u8; 8], key: u64) -> [u8; HEADER_LEN as usize] {
+    let mut header = [0; HEADER_LEN as usize];
+    header[..8].copy_from_slice(magic);
+    header[8..].copy_from_slice(&key.to_le_bytes());
+    header
+}
+
+impl Log {
+    fn create(path: &Path, key: u64) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .with_context(|| format!("unable to create {}", path.display()))?;
+        file.write_all(&header(LOG_MAGIC, key))?;
+        Ok(Self {
+            file,
+            len: HEADER_LEN,
+        })
+    }
+
+    /// Opens an existing log. Returns None when it's missing or written with
+    /// a different key.
+    fn open(path: &Path, key: u64) -> Result<Option<Self>> {
+        let mut file = match OpenOptions::new().read(true).write(true).open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| format!("unable to open {}", path.display()))
+            }
+        };
+        let mut actual = [0; HEADER_LEN as usize];
+        if file.read_exact(&mut actual).is_err() || actual != header(LOG_MAGIC, key) {
+            return Ok(None);
+        }
+        let len = file.metadata()?.len();
+        Ok(Some(Self { file, len }))
+    }
+
+    /// Appends a record and returns the offset and length of its frame.
+    fn append(&mut self, payload: &[u8]) -> Result<(u64, u64)> {
+        let len = u32::try_from(payload.len()).context("record too large")?;
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN as usize + payload.len());
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(&hash_xxh3_hash64(payload).to_le_bytes());
+        frame.extend_from_slice(payload);
+        let offset = self.len;
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&frame)?;
+        self.len += frame.len() as u64;
+        Ok((offset, frame.len() as u64))
+    }
+
+    /// Reads a frame that is at most `available` bytes long.
+    fn read_frame(reader: &mut impl Read, available: u64) -> Option<Vec<u8>> {
+        let mut frame_header = [0; FRAME_HEADER_LEN as usize];
+        reader.read_exact(&mut frame_header).ok()?;
+        let len = u32::from_le_bytes(frame_header[..4].try_into().unwrap());
+        if FRAME_HEADER_LEN + len as u64 > available {
+            return None;
+        }
+        let checksum = u64::from_le_bytes(frame_header[4..].try_into().unwrap());
+        let mut payload = vec![0; len as usize];
+        reader.read_exact(&mut payload).ok()?;
+        (hash_xxh3_hash64(&payload[..]) == checksum).then_some(payload)
+    }
+
+    /// Reads the payload of the record at `offset`. Returns None when the
+    /// record is damaged.
+    fn read_at(&mut self, offset: u64) -> Result<Option<Vec<u8>>> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        let available = self.len.saturating_sub(offset);
+        Ok(Self::read_frame(&mut self.file, available))
+    }
+
+    /// Calls `f` with offset, frame length and payload of all records after
+    /// `from`. The log is truncated at the first damaged record or when `f`
+    /// returns false.
+    fn replay(&mut self, from: u64, mut f: impl FnMut(u64, u64, &[u8]) -> bool) -> Result<()> {
+        self.file.seek(SeekFrom::Start(from))?;
+        let mut reader = BufReader::new(&mut self.file);
+        let mut offset = from;
+        while offset < self.len {
+            let Some(payload) = Self::read_frame(&mut reader, self.len - offset) else {
+                break;
+            };
+            let frame_len = FRAME_HEADER_LEN + payload.len() as u64;
+            if !f(offset, frame_len, &payload) {
+                break;
+            }
+            offset += frame_len;
+        }
+        if offset < self.len {
+            self.file.set_len(offset)?;
+            self.len = offset;
+        }
+        Ok(())
+    }
+
+    fn sync(&self) -> Result<()> {
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
+
+pub struct Store {
+    dir: PathBuf,
+    key: u64,
+    generation: u64,
+    log: Log,
+    pub tasks: HashMap<Pid, StoredTask>,
+    pub by_type: HashMap<Vec<u8>, Pid>,
+    pub dependents: HashMap<StoredVc, HashSet<Pid>>,
+    next_pid: Pid,
+}
+
+impl Store {
+    /// Opens the store in `dir`. An existing store that was written with a
+    /// different `key` or can't be read is discarded.
+    pub fn open(dir: &Path, key: u64) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("unable to create {}", dir.display()))?;
+        // An unreadable store is not an error, it's only discarded
+        if let Ok(Some(store)) = Self::load(dir, key) {
+            return Ok(store);
+        }
+        Self::create(dir, key)
+    }
+
+    fn load(dir: &Path, key: u64) -> Result<Option<Self>> {
+        let Some(Index {
+            generation,
+            log_len,
+            next_pid,
+            mut tasks,
+        }) = read_index(dir, key)?
+        else {
+            return Ok(None);
+        };
+        let Some(mut log) = Log::open(&log_path(dir, generation), key)? else {
+            return Ok(None);
+        };
+        if log.len < log_len {
+            return Ok(None);
+        }
+        log.replay(log_len, |offset, len, payload| {
+            let Ok(record) = postcard::from_bytes(payload) else {
+                return false;
+            };
+            match record {
+                Record::Task { id, ty, refs } => {
+                    tasks.insert(
+                        id,
+                        StoredTask {
+                            ty,
+                            refs,
+                            data: None,
+                            dirty: false,
+                            states: HashMap::new(),
+                        },
+                    );
+                }
+                Record::Data(record) => {
+                    if let Some(task) = tasks.get_mut(&record.id) {
+                        task.data = Some(DataMeta::new(&record, offset, len));
+                        task.dirty = false;
+                    }
+                }
+                Record::Dirty { id, dirty } => {
+                    if let Some(task) = tasks.get_mut(&id) {
+                        task.dirty = dirty;
+                    }
+                }
+                Record::State {
+                    id, index, refs, ..
+                } => {
+                    if let Some(task) = tasks.get_mut(&id) {
+                        task.states.insert(index, StateMeta { offset, len, refs });
+                    }
+                }
+            }
+            true
+        })?;
+        remove_files(dir, |name| {
+            name == INDEX_TMP_FILE
+                || (name.ends_with(LOG_EXTENSION) && name != log_file_name(generation))
+        })?;
+        let mut store = Self {
+            dir: dir.to_path_buf(),
+            key,
+            generation,
+            log,
+            tasks,
+            by_type: HashMap::new(),
+            dependents: HashMap::new(),
+            next_pid,
+        };
+        store.rebuild_lookups();
+        Ok(Some(store))
+    }
+
+    fn create(dir: &Path, key: u64) -> Result<Self> {
+        remove_files(dir, |name| {
+            name == INDEX_FILE || name == INDEX_TMP_FILE || name.ends_with(LOG_EXTENSION)
+        })?;
+        let generation = 1;
+        let log = Log::create(&log_path(dir, generation), key)?;
+        let store = Self {
+            dir: dir.to_path_buf(),
+            key,
+            generation,
+            log,
+            tasks: HashMap::new(),
+            by_type: HashMap::new(),
+            dependents: HashMap::new(),
+            next_pid: 0,
+        };
+        store.write_index()?;
+        Ok(store)
+    }
+
+    fn rebuild_lookups(&mut self) {
+        self.by_type.clear();
+        self.dependents.clear();
+        for (&pid, task) in self.tasks.iter() {
+            self.by_type.insert(task.ty.clone(), pid);
+            if let Some(data) = &task.data {
+                for dep in data.dependencies.iter() {
+                    self.dependents.entry(*dep).or_default().insert(pid);
+                }
+            }
+            self.next_pid = self.next_pid.max(pid + 1);
+        }
+    }
+
+    /// Adds a new task type and returns its id.
+    pub fn add_task(&mut self, ty: Vec<u8>, refs: Vec<Pid>) -> Result<Pid> {
+        let id = self.next_pid;
+        self.next_pid += 1;
+        let payload = postcard::to_allocvec(&Record::Task {
+            id,
+            ty: ty.clone(),
+            refs: refs.clone(),
+        })?;
+        self.log.append(&payload)?;
+        self.by_type.insert(ty.clone(), id);
+        self.tasks.insert(
+            id,
+            StoredTask {
+                ty,
+                refs,
+                data: None,
+                dirty: false,
+                states: HashMap::new(),
+            },
+        );
+        Ok(id)
+    }
+
+    /// Stores the result of a task execution. The task is considered clean
+    /// afterwards.
+    pub fn write_data(&mut self, record: DataRecord) -> Result<()> {
+        let id = record.id;
+        anyhow::ensure!(self.tasks.contains_key(&id), "unknown task {id}");
+        let mut data = DataMeta::new(&record, 0, 0);
+        let payload = postcard::to_allocvec(&Record::Data(record))?;
+        (data.offset, data.len) = self.log.append(&payload)?;
+        let task = self.tasks.get_mut(&id).unwrap();
+        if let Some(old) = task.data.take() {
+            for dep in old.dependencies {
+                if let Some(dependents) = self.dependents.get_mut(&dep) {
+                    dependents.remove(&id);
+                }
+            }
+        }
+        for dep in data.dependencies.iter() {
+            self.dependents.entry(*dep).or_default().insert(id);
+        }
+        task.data = Some(data);
+        task.dirty = false;
+        Ok(())
+    }
+
+    /// Reads the result of a task execution. Returns None when the task
+    /// hasn't been executed or the data is damaged.
+    pub fn read_data(&mut self, id: Pid) -> Result<Option<DataRecord>> {
+        let Some(data) = self.tasks.get(&id).and_then(|task| task.data.as_ref()) else {
+            return Ok(None);
+        };
+        let Some(payload) = self.log.read_at(data.offset)? else {
+            return Ok(None);
+        };
+        match postcard::from_bytes(&payload) {
+            Ok(Record::Data(record)) if record.id == id => Ok(Some(record)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Stores the value of a state created by a task.
+    pub fn write_state(
+        &mut self,
+        id: Pid,
+        index: u32,
+        value: Vec<u8>,
+        refs: Vec<Pid>,
+    ) -> Result<()> {
+        anyhow::ensure!(self.tasks.contains_key(&id), "unknown task {id}");
+        let payload = postcard::to_allocvec(&Record::State {
+            id,
+            index,
+            value,
+            refs: refs.clone(),
+        })?;
+        let (offset, len) = self.log.append(&payload)?;
+        let task = self.tasks.get_mut(&id).unwrap();
+        task.states.insert(index, StateMeta { offset, len, refs });
+        Ok(())
+    }
+
+    /// Reads the latest value of a state. Returns None when it hasn't been
+    /// stored or the data is damaged.
+    pub fn read_state(&mut self, id: Pid, index: u32) -> Result<Option<Vec<u8>>> {
+        let Some(state) = self.tasks.get(&id).and_then(|task| task.states.get(&index)) else {
+            return Ok(None);
+        };
+        let Some(payload) = self.log.read_at(state.offset)? else {
+            return Ok(None);
+        };
+        match postcard::from_bytes(&payload) {
+            Ok(Record::State {
+                id: record_id,
+                index: record_index,
+                value,
+                ..
+            }) if record_id == id && record_index == index => Ok(Some(value)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Updates the dirty flag of an executed task. Returns true when it has
+    /// changed.
+    pub fn set_dirty(&mut self, id: Pid, dirty: bool) -> Result<bool> {
+        let Some(task) = self.tasks.get_mut(&id) else {
+            return Ok(false);
+        };
+        if task.data.is_none() || task.dirty == dirty {
+            return Ok(false);
+        }
+        task.dirty = dirty;
+        let payload = postcard::to_allocvec(&Record::Dirty { id, dirty })?;
+        self.log.append(&payload)?;
+        Ok(true)
+    }
+
+    fn live_len(&self) -> u64 {
+        HEADER_LEN
+            + self
+                .tasks
+                .values()
+                .map(|task| {
+                    FRAME_HEADER_LEN
+                        + task.ty.len() as u64
+                        + task.data.as_ref().map_or(0, |data| data.len)
+                        + task.states.values().map(|state| state.len).sum::<u64>()
+                })
+                .sum::<u64>()
+    }
+
+    fn needs_compaction(&self) -> bool {
+        self.log.len > COMPACTION_MIN_LEN && self.log.len > 2 * self.live_len()
+    }
+
+    /// Tasks that have been executed and all tasks referenced by them.
+    fn live_tasks(&self) -> HashSet<Pid> {
+        let mut live = HashSet::new();
+        let mut queue = self
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.data.is_some())
+            .map(|(&pid, _)| pid)
+            .collect::<Vec<_>>();
+        while let Some(pid) = queue.pop() {
+            if !live.insert(pid) {
+                continue;
+            }
+            let Some(task) = self.tasks.get(&pid) else {
+                continue;
+            };
+            queue.extend(task.refs.iter().copied());
+            for state in task.states.values() {
+                queue.extend(state.refs.iter().copied());
+            }
+            if let Some(data) = &task.data {
+                queue.extend(data.children.iter().copied());
+                queue.extend(data.dependencies.iter().map(|dep| dep.task()));
+                queue.extend(data.refs.iter().copied());
+            }
+        }
+        live
+    }
+
+    /// Rewrites the log without superseded records and unreferenced tasks.
+    /// Returns the ids of the removed tasks.
+    pub fn compact(&mut self) -> Result<Vec<Pid>> {
+        let live = self.live_tasks();
+        let generation = self.generation + 1;
+        let mut log = Log::create(&log_path(&self.dir, generation), self.key)?;
+        let mut pids = live.iter().copied().collect::<Vec<_>>();
+        pids.sort_unstable();
+        let mut tasks = HashMap::with_capacity(pids.len());
+        for pid in pids {
+            let Some(task) = self.tasks.get(&pid) else {
+                continue;
+            };
+            let mut task = task.clone();
+            log.append(&postcard::to_allocvec(&Record::Task {
+                id: pid,
+                ty: task.ty.clone(),
+                refs: task.refs.clone(),
+            })?)?;
+            if let Some(mut data) = task.data.take() {
+                if let Some(payload) = self.log.read_at(data.offset)? {
+                    (data.offset, data.len) = log.append(&payload)?;
+                    task.data = Some(data);
+                    if task.dirty {
+                        log.append(&postcard::to_allocvec(&Record::Dirty {
+                            id: pid,
+                            dirty: true,
+                        })?)?;
+                    }
+                }
+            }
+            let mut indices = task.states.keys().copied().collect::<Vec<_>>();
+            indices.sort_unstable();
+            for index in indices {
+                let mut state = task.states.remove(&index).unwrap();
+                if let Some(payload) = self.log.read_at(state.offset)? {
+                    (state.offset, state.len) = log.append(&payload)?;
+                    task.states.insert(index, state);
+                }
+            }
+            tasks.insert(pid, task);
+        }
+        log.sync()?;
+        let removed = self
+            .tasks
+            .keys()
+            .filter(|pid| !tasks.contains_key(pid))
+            .copied()
+            .collect();
+        let old_path = log_path(&self.dir, self.generation);
+        self.generation = generation;
+        self.log = log;
+        self.tasks = tasks;
+        self.rebuild_lookups();
+        self.write_index()?;
+        // The old log is no longer referenced, a leftover would be removed on the next
+        // open
+        let _ = fs::remove_file(old_path);
+        Ok(removed)
+    }
+
+    /// Makes all changes durable. Compacts the log when it has grown too
+    /// much. Returns the ids of the tasks removed by compaction.
+    pub fn commit(&mut self) -> Result<Vec<Pid>> {
+        if self.needs_compaction() {
+            return self.compact();
+        }
+        self.log.sync()?;
+        self.write_index()?;
+        Ok(Vec::new())
+    }
+
+    fn write_index(&self) -> Result<()> {
+        let payload = postcard::to_allocvec(&IndexRef {
+            generation: self.generation,
+            log_len: self.log.len,
+            next_pid: self.next_pid,
+            tasks: &self.tasks,
+        })?;
+        let mut content = Vec::with_capacity(HEADER_LEN as usize + 8 + payload.len());
+        content.extend_from_slice(&header(INDEX_MAGIC, self.key));
+        content.extend_from_slice(&hash_xxh3_hash64(&payload[..]).to_le_bytes());
+        content.extend_from_slice(&payload);
+
+        let tmp_path = self.dir.join(INDEX_TMP_FILE);
+        let mut file = File::create(&tmp_path)
+            .with_context(|| format!("unable to create {}", tmp_path.display()))?;
+        file.write_all(&content)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp_path, self.dir.join(INDEX_FILE))?;
+        // Persist the rename. Not supported on all platforms.
+        if let Ok(dir) = File::open(&self.dir) {
+            let _ = dir.sync_all();
+        }
+        Ok(())
+    }
+}
+
+fn read_index(dir: &Path, key: u64) -> Result<Option<Index>> {
+    let content = match fs::read(dir.join(INDEX_FILE)) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let header_len = HEADER_LEN as usize;
+    if content.len() < header_len + 8 || content[..header_len] != header(INDEX_MAGIC, key) {
+        return Ok(None);
+    }
+    let checksum = u64::from_le_bytes(content[header_len..header_len + 8].try_into().unwrap());
+    let payload = &content[header_len + 8..];
+    if hash_xxh3_hash64(payload) != checksum {
+        return Ok(None);
+    }
+    Ok(postcard::from_bytes(payload).ok())
+}
+
+/// Removes files of the store from `dir`, leaving unrelated files alone.
+fn remove_files(dir: &Path, filter: impl Fn(&str) -> bool) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if let Some(name) = entry.file_name().to_str() {
+            if entry.file_type()?.is_file() && filter(name) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::OpenOptions;
+
+    use anyhow::Result;
+
+    use super::{log_path, DataRecord, Store, StoredVc};
+
+    fn data(id: u32, dependencies: Vec<StoredVc>) -> DataRecord {
+        DataRecord {
+            id,
+            session_dependent: false,
+            output: StoredVc::Output(id),
+            children: Vec::new(),
+            dependencies,
+            collectibles: Vec::new(),
+            cells: Vec::new(),
+            refs: vec![id],
+        }
+    }
+
+    #[test]
+    fn replays_the_log_without_commit() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (a, b) = {
+            let mut store = Store::open(dir.path(), 1)?;
+            let a = store.add_task(b"a".to_vec(), Vec::new())?;
+            let b = store.add_task(b"b".to_vec(), vec![a])?;
+            store.write_data(data(b, vec![StoredVc::Output(a)]))?;
+            store.write_data(data(a, Vec::new()))?;
+            store.set_dirty(a, true)?;
+            (a, b)
+        };
+
+        let mut store = Store::open(dir.path(), 1)?;
+        assert_eq!(store.by_type.get(&b"b"[..]), Some(&b));
+        assert_eq!(store.tasks[&b].refs, vec![a]);
+        assert!(store.tasks[&a].dirty);
+        assert!(!store.tasks[&b].dirty);
+        assert_eq!(
+            store.dependents[&StoredVc::Output(a)]
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![&b]
+        );
+        assert_eq!(
+            store.read_data(b)?,
+            Some(data(b, vec![StoredVc::Output(a)]))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn truncates_a_torn_write() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (a, b) = {
+            let mut store = Store::open(dir.path(), 1)?;
+            let a = store.add_task(b"a".to_vec(), Vec::new())?;
+            store.write_data(data(a, Vec::new()))?;
+            let b = store.add_task(b"b".to_vec(), Vec::new())?;
+            store.write_data(data(b, Vec::new()))?;
+            (a, b)
+        };
+        let log = OpenOptions::new()
+            .write(true)
+            .open(log_path(dir.path(), 1))?;
+        let len = log.metadata()?.len();
+        log.set_len(len - 3)?;
+        drop(log);
+
+        let mut store = Store::open(dir.path(), 1)?;
+        assert!(store.tasks[&b].data.is_none());
+        assert_eq!(store.read_data(a)?, Some(data(a, Vec::new())));
+        // Writes after the truncated record are readable again
+        store.write_data(data(b, Vec::new()))?;
+        drop(store);
+        let mut store = Store::open(dir.path(), 1)?;
+        assert_eq!(store.read_data(b)?, Some(data(b, Vec::new())));
+        Ok(())
+    }
+
+    #[test]
+    fn compaction_keeps_live_data() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (a, b, unused) = {
+            let mut store = Store::open(dir.path(), 1)?;
+            let a = store.add_task(b"a".to_vec(), Vec::new())?;
+            let b = store.add_task(b"b".to_vec(), Vec::new())?;
+            let unused = store.add_task(b"unused".to_vec(), Vec::new())?;
+            for _ in 0..10 {
+                store.write_data(data(a, vec![StoredVc::Output(b)]))?;
+            }
+            store.set_dirty(a, true)?;
+            let removed = store.compact()?;
+            assert_eq!(removed, vec![unused]);
+            (a, b, unused)
+        };
+        assert!(!log_path(dir.path(), 1).exists());
+
+        let mut store = Store::open(dir.path(), 1)?;
+        assert!(store.tasks.contains_key(&b));
+        assert!(!store.tasks.contains_key(&unused));
+        assert!(store.tasks[&a].dirty);
+        assert_eq!(
+            store.read_data(a)?,
+            Some(data(a, vec![StoredVc::Output(b)]))
+        );
+        // Ids are not reused
+        assert!(store.add_task(b"c".to_vec(), Vec::new())? > unused);
+        Ok(())
+    }
+
+    #[test]
+    fn compaction_keeps_latest_state_value() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (a, b) = {
+            let mut store = Store::open(dir.path(), 1)?;
+            let a = store.add_task(b"a".to_vec(), Vec::new())?;
+            let b = store.add_task(b"b".to_vec(), Vec::new())?;
+            store.write_data(data(a, Vec::new()))?;
+            store.write_state(a, 0, b"first".to_vec(), Vec::new())?;
+            store.write_state(a, 0, b"second".to_vec(), vec![b])?;
+            store.write_state(a, 1, b"other".to_vec(), Vec::new())?;
+            // b is only referenced by the state
+            assert_eq!(store.compact()?, Vec::<u32>::new());
+            (a, b)
+        };
+
+        let mut store = Store::open(dir.path(), 1)?;
+        assert!(store.tasks.contains_key(&b));
+        assert_eq!(store.read_state(a, 0)?, Some(b"second".to_vec()));
+        assert_eq!(store.read_state(a, 1)?, Some(b"other".to_vec()));
+        assert_eq!(store.read_state(a, 2)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn interrupted_compaction_keeps_previous_generation() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let a = {
+            let mut store = Store::open(dir.path(), 1)?;
+            let a = store.add_task(b"a".to_vec(), Vec::new())?;
+            store.write_data(data(a, Vec::new()))?;
+            a
+        };
+        // A new generation that was never committed by the index
+        std::fs::write(log_path(dir.path(), 2), b"partial")?;
+
+        let mut store = Store::open(dir.path(), 1)?;
+        assert_eq!(store.read_data(a)?, Some(data(a, Vec::new())));
+        assert!(!log_path(dir.path(), 2).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn discards_store_with_different_key() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        {
+            let mut store = Store::open(dir.path(), 1)?;
+            let a = store.add_task(b"a".to_vec(), Vec::new())?;
+            store.write_data(data(a, Vec::new()))?;
+            store.commit()?;
+        }
+        std::fs::write(dir.path().join("unrelated"), b"")?;
+
+        let store = Store::open(dir.path(), 2)?;
+        assert!(store.tasks.is_empty());
+        assert!(dir.path().join("unrelated").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+#![feature(min_specialization)]
+
+use std::{
+    future::Future,
+    path::Path,
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+};
+
+use anyhow::Result;
+use turbo_tasks::{
+    primitives::{StringVc, U32Vc},
+    trace::TraceRawVcs,
+    State, TurboTasks,
+};
+use turbo_tasks_disk::DiskPersistedGraph;
+use turbo_tasks_memory::MemoryBackendWithPersistedGraph;
+use turbo_tasks_testing::register;
+
+register!();
+
+/// Runs `f` in a new session on the graph persisted in `dir` and waits until
+/// everything has been persisted.
+async fn session<T: TraceRawVcs + Send + 'static>(
+    dir: &Path,
+    version: &str,
+    f: impl Future<Output = Result<T>> + Send + 'static,
+) -> Result<T> {
+    lazy_static::initialize(&REGISTER);
+    let pg = DiskPersistedGraph::new(dir, version)?;
+    let tt = TurboTasks::new(MemoryBackendWithPersistedGraph::new(pg));
+    let result = tt.run_once(f).await;
+    tt.stop_and_wait().await;
+    result
+}
+
+static DOUBLE_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+static ADD_ONE_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[turbo_tasks::function]
+fn double(value: u32) -> U32Vc {
+    DOUBLE_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    U32Vc::cell(value * 2)
+}
+
+#[turbo_tasks::function]
+async fn add_one(value: U32Vc) -> Result<U32Vc> {
+    ADD_ONE_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    Ok(U32Vc::cell(*value.await? + 1))
+}
+
+#[tokio::test]
+async fn reuses_results_of_previous_session() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    for _ in 0..2 {
+        let result = session(dir.path(), "1", async { Ok(*add_one(double(21)).await?) }).await?;
+        assert_eq!(result, 43);
+        assert_eq!(DOUBLE_EXECUTIONS.load(Ordering::SeqCst), 1);
+        assert_eq!(ADD_ONE_EXECUTIONS.load(Ordering::SeqCst), 1);
+    }
+    Ok(())
+}
+
+static TRIPLE_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[turbo_tasks::function]
+fn triple(value: u32) -> U32Vc {
+    TRIPLE_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    U32Vc::cell(value * 3)
+}
+
+#[tokio::test]
+async fn discards_results_of_other_version() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    for (version, executions) in [("1", 1), ("1", 1), ("2", 2)] {
+        let result = session(dir.path(), version, async { Ok(*triple(3).await?) }).await?;
+        assert_eq!(result, 9);
+        assert_eq!(TRIPLE_EXECUTIONS.load(Ordering::SeqCst), executions);
+    }
+    Ok(())
+}
+
+static SETTING: AtomicU32 = AtomicU32::new(1);
+static READ_SETTING_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+static DESCRIBE_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[turbo_tasks::function]
+fn read_setting() -> U32Vc {
+    READ_SETTING_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    turbo_tasks::mark_session_dependent();
+    U32Vc::cell(SETTING.load(Ordering::SeqCst))
+}
+
+#[turbo_tasks::function]
+async fn describe_setting() -> Result<StringVc> {
+    DESCRIBE_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    Ok(StringVc::cell(format!(
+        "setting = {}",
+        *read_setting().await?
+    )))
+}
+
+#[tokio::test]
+async fn reexecutes_session_dependent_tasks() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let describe = || async { Ok(describe_setting().await?.clone_value()) };
+
+    let result = session(dir.path(), "1", describe()).await?;
+    assert_eq!(result, "setting = 1");
+    assert_eq!(READ_SETTING_EXECUTIONS.load(Ordering::SeqCst), 1);
+    assert_eq!(DESCRIBE_EXECUTIONS.load(Ordering::SeqCst), 1);
+
+    // The setting is read again, but dependents are reused when it's unchanged
+    let result = session(dir.path(), "1", describe()).await?;
+    assert_eq!(result, "setting = 1");
+    assert_eq!(READ_SETTING_EXECUTIONS.load(Ordering::SeqCst), 2);
+    assert_eq!(DESCRIBE_EXECUTIONS.load(Ordering::SeqCst), 1);
+
+    SETTING.store(2, Ordering::SeqCst);
+    let result = session(dir.path(), "1", describe()).await?;
+    assert_eq!(result, "setting = 2");
+    assert_eq!(READ_SETTING_EXECUTIONS.load(Ordering::SeqCst), 3);
+    assert_eq!(DESCRIBE_EXECUTIONS.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+static COUNTER_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[turbo_tasks::value(eq = "manual", cell = "new")]
+struct Counter {
+    value: State<u32>,
+}
+
+#[turbo_tasks::function]
+fn counter() -> CounterVc {
+    COUNTER_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    Counter {
+        value: State::new(0),
+    }
+    .cell()
+}
+
+#[tokio::test]
+async fn restores_changed_state() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    for expected in 1..=3 {
+        let value = session(dir.path(), "1", async {
+            let counter = counter().await?;
+            counter.value.update_conditionally(|value| {
+                *value += 1;
+                true
+            });
+            let value = *counter.value.get_untracked();
+            Ok(value)
+        })
+        .await?;
+        assert_eq!(value, expected);
+        // The state is restored without executing the task that created it
+        assert_eq!(COUNTER_EXECUTIONS.load(Ordering::SeqCst), 1);
+    }
+    Ok(())
+}
+
+static ONCE_EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[turbo_tasks::function]
+fn once_counter() -> CounterVc {
+    Counter {
+        value: State::new(0),
+    }
+    .cell()
+}
+
+#[tokio::test]
+async fn drops_strongly_consistent_once_tasks_after_completion() -> Result<()> {
+    lazy_static::initialize(&REGISTER);
+    let dir = tempfile::tempdir()?;
+    let pg = DiskPersistedGraph::new(dir.path(), "1")?;
+    let tt = TurboTasks::new(MemoryBackendWithPersistedGraph::new(pg));
+    let counter = tt.run_once(async { Ok(once_counter()) }).await?;
+    let value = tt
+        .run_once_strongly_consistent(move || async move {
+            ONCE_EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+            let value = *counter.await?.value.get();
+            Ok(value)
+        })
+        .await?;
+    assert_eq!(value, 0);
+
+    // Invalidations after the execution has finished don't execute it again
+    tt.run_once(async move {
+        counter.await?.value.set(1);
+        Ok(())
+    })
+    .await?;
+    tt.wait_foreground_done().await;
+    assert_eq!(ONCE_EXECUTIONS.load(Ordering::SeqCst), 1);
+    tt.stop_and_wait().await;
+    Ok(())
+}
@@ -26,6 +26,8 @@ fn env_snapshot() -> IndexMap<String, String> {
 impl ProcessEnv for CommandLineProcessEnv {
     #[turbo_tasks::function]
     fn read_all(&self) -> EnvMapVc {
+        // The process env can differ between sessions
+        turbo_tasks::mark_session_dependent();
         EnvMapVc::cell(env_snapshot())
     }
 }
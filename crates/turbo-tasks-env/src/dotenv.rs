@@ -42,6 +42,8 @@ impl DotenvProcessEnvVc {
             let res;
             let vars;
             {
+                // The process env can differ between sessions
+                turbo_tasks::mark_session_dependent();
                 let lock = GLOBAL_ENV_LOCK.lock().unwrap();
 
                 // Unfortunately, dotenvy only looks up variable references from the global env.
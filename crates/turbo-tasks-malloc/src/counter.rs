@@ -4,6 +4,8 @@ use std::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 
+use crate::AllocationCounters;
+
 static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
 const KB: usize = 1024;
 /// When global counter is updates we will keep a thread-local buffer of this
@@ -19,10 +21,15 @@ struct ThreadLocalCounter {
     /// means the global counter is always equal or greater than the real
     /// value.
     buffer: usize,
+    /// Running totals of all allocations and deallocations on this thread.
+    /// They are never reset, so readers compare two snapshots.
+    allocation_counters: AllocationCounters,
 }
 
 impl ThreadLocalCounter {
     fn add(&mut self, size: usize) {
+        self.allocation_counters.allocations += size;
+        self.allocation_counters.allocation_count += 1;
         if self.buffer >= size {
             self.buffer -= size;
         } else {
@@ -33,6 +40,8 @@ impl ThreadLocalCounter {
     }
 
     fn remove(&mut self, size: usize) {
+        self.allocation_counters.deallocations += size;
+        self.allocation_counters.deallocation_count += 1;
         self.buffer += size;
         if self.buffer > MAX_BUFFER {
             let offset = self.buffer - TARGET_BUFFER;
@@ -50,7 +59,10 @@ impl ThreadLocalCounter {
 }
 
 thread_local! {
-  static LOCAL_COUNTER: UnsafeCell<ThreadLocalCounter> = UnsafeCell::new(ThreadLocalCounter { buffer: 0 });
+  static LOCAL_COUNTER: UnsafeCell<ThreadLocalCounter> = UnsafeCell::new(ThreadLocalCounter {
+    buffer: 0,
+    allocation_counters: AllocationCounters::default(),
+  });
 }
 
 pub fn get() -> usize {
@@ -76,6 +88,14 @@ pub fn remove(size: usize) {
     with_local_counter(|local| local.remove(size));
 }
 
+/// Returns the running totals of allocations and deallocations on the current
+/// thread.
+pub fn allocation_counters() -> AllocationCounters {
+    let mut counters = AllocationCounters::default();
+    with_local_counter(|local| counters = local.allocation_counters.clone());
+    counters
+}
+
 /// Flushes the thread-local buffer to the global counter. This should be called
 /// e. g. when a thread is stopped or goes to sleep for a long time.
 pub fn flush() {
@@ -112,4 +132,22 @@ mod tests {
         expected -= MAX_BUFFER + 100;
         assert_eq!(get(), expected);
     }
+
+    #[test]
+    fn allocation_counters_track_the_current_thread() {
+        let start = allocation_counters();
+        add(100);
+        add(50);
+        remove(30);
+        let info = start.until_now();
+        assert_eq!(info.allocations, 150);
+        assert_eq!(info.allocation_count, 2);
+        assert_eq!(info.deallocations, 30);
+        assert_eq!(info.deallocation_count, 1);
+        assert_eq!(info.memory_usage(), 120);
+
+        // Other threads have their own counters
+        let other_thread = std::thread::spawn(allocation_counters).join().unwrap();
+        assert_eq!(other_thread, AllocationCounters::default());
+    }
 }
@@ -1,9 +1,85 @@
 mod counter;
 
-use std::alloc::{GlobalAlloc, Layout};
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    ops::{Add, AddAssign},
+};
 
 use self::counter::{add, flush, get, remove};
 
+/// Running totals of allocations on a thread. Take a snapshot with
+/// [TurboMalloc::allocation_counters] and compute the allocations since then
+/// with [AllocationCounters::until_now].
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct AllocationCounters {
+    pub allocations: usize,
+    pub deallocations: usize,
+    pub allocation_count: usize,
+    pub deallocation_count: usize,
+}
+
+impl AllocationCounters {
+    /// Returns the allocations on the current thread since this snapshot was
+    /// taken.
+    pub fn until_now(&self) -> AllocationInfo {
+        let now = TurboMalloc::allocation_counters();
+        AllocationInfo {
+            allocations: now.allocations.saturating_sub(self.allocations),
+            deallocations: now.deallocations.saturating_sub(self.deallocations),
+            allocation_count: now.allocation_count.saturating_sub(self.allocation_count),
+            deallocation_count: now
+                .deallocation_count
+                .saturating_sub(self.deallocation_count),
+        }
+    }
+}
+
+/// Information about allocations made during a period of time, e. g. while
+/// executing a task.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocationInfo {
+    /// Bytes allocated.
+    pub allocations: usize,
+    /// Bytes deallocated.
+    pub deallocations: usize,
+    /// Number of allocations.
+    pub allocation_count: usize,
+    /// Number of deallocations.
+    pub deallocation_count: usize,
+}
+
+impl AllocationInfo {
+    /// Bytes that have been allocated and not deallocated again. Memory
+    /// deallocated in this period but allocated before is not subtracted
+    /// below zero.
+    pub fn memory_usage(&self) -> usize {
+        self.allocations.saturating_sub(self.deallocations)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allocation_count == 0 && self.deallocation_count == 0
+    }
+}
+
+impl Add for AllocationInfo {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            allocations: self.allocations + other.allocations,
+            deallocations: self.deallocations + other.deallocations,
+            allocation_count: self.allocation_count + other.allocation_count,
+            deallocation_count: self.deallocation_count + other.deallocation_count,
+        }
+    }
+}
+
+impl AddAssign for AllocationInfo {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
 /// Turbo's preferred global allocator. This is a new type instead of a type
 /// alias because you can't use type aliases to instantiate unit types (E0423).
 pub struct TurboMalloc;
@@ -16,6 +92,12 @@ impl TurboMalloc {
     pub fn thread_stop() {
         flush();
     }
+
+    /// Returns the running totals of allocations on the current thread. Only
+    /// allocations made through [TurboMalloc] are counted.
+    pub fn allocation_counters() -> AllocationCounters {
+        counter::allocation_counters()
+    }
 }
 
 #[cfg(all(
@@ -38,7 +120,7 @@ unsafe impl GlobalAlloc for TurboMalloc {
 
     unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
         let ret = mimalloc::MiMalloc.alloc_zeroed(layout);
-        if ret.is_null() {
+        if !ret.is_null() {
             add(layout.size());
         }
         ret
@@ -78,7 +160,7 @@ unsafe impl GlobalAlloc for TurboMalloc {
 
     unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
         let ret = std::alloc::System.alloc_zeroed(layout);
-        if ret.is_null() {
+        if !ret.is_null() {
             add(layout.size());
         }
         ret
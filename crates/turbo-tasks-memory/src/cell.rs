@@ -8,7 +8,7 @@ use nohash_hasher::BuildNoHashHasher;
 use turbo_tasks::{
     backend::CellContent,
     event::{Event, EventListener},
-    TaskId, TurboTasksBackendApi,
+    TaskId, TurboTasksBackendApi, ValueTypeId,
 };
 
 use crate::MemoryBackend;
@@ -319,3 +319,81 @@ impl Cell {
         }
     }
 }
+
+/// A cell addressed by a key instead of the order of creation. Dependencies
+/// are tracked per key, so only readers of changed keys are invalidated.
+#[derive(Default, Debug)]
+pub(crate) struct KeyedCell {
+    content: CellContent,
+    /// true, when the key has been assigned during the current execution.
+    assigned: bool,
+    dependent_tasks: AutoSet<TaskId, BuildNoHashHasher<TaskId>>,
+}
+
+impl KeyedCell {
+    /// Read the content of the cell. Registers the reader as dependent task
+    /// when given. Keys without content read as empty content.
+    pub fn read_content(&mut self, reader: Option<TaskId>) -> CellContent {
+        if let Some(reader) = reader {
+            self.dependent_tasks.insert(reader);
+        }
+        self.content.clone()
+    }
+
+    /// Removes a task from the list of dependent tasks.
+    pub fn remove_dependent_task(&mut self, task: TaskId) {
+        self.dependent_tasks.remove(&task);
+    }
+
+    /// Returns the value type of the content, if any.
+    pub fn value_type(&self) -> Option<ValueTypeId> {
+        self.content.0.as_ref().and_then(|content| content.0)
+    }
+
+    pub fn assign(
+        &mut self,
+        content: CellContent,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) {
+        self.assigned = true;
+        if content != self.content {
+            if !self.dependent_tasks.is_empty() {
+                turbo_tasks.schedule_notify_tasks_set(&self.dependent_tasks);
+                self.dependent_tasks.clear();
+            }
+            self.content = content;
+        }
+    }
+
+    /// Called when an execution of the task produced its result. Removes the
+    /// content when the key hasn't been assigned by the execution. Returns
+    /// false when the cell is no longer needed.
+    pub fn execution_completed(
+        &mut self,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) -> bool {
+        if !self.assigned {
+            self.assign(CellContent(None), turbo_tasks);
+        }
+        self.assigned = false;
+        self.content.0.is_some() || !self.dependent_tasks.is_empty()
+    }
+
+    /// Called when an execution has been invalidated before it completed. The
+    /// next execution will assign the keys again.
+    pub fn execution_invalidated(&mut self) {
+        self.assigned = false;
+    }
+
+    /// Reduces memory needs to the minimum.
+    pub fn shrink_to_fit(&mut self) {
+        self.dependent_tasks.shrink_to_fit();
+    }
+
+    /// Drops the cell after GC. Will notify all dependent tasks.
+    pub fn gc_drop(self, turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>) {
+        if !self.dependent_tasks.is_empty() {
+            turbo_tasks.schedule_notify_tasks_set(&self.dependent_tasks);
+        }
+    }
+}
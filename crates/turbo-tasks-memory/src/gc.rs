@@ -29,6 +29,9 @@ pub enum GcPriority {
         /// Aggregated recompute time. Stored as 2^x milliseconds to bucket
         /// tasks and avoid frequent revalidation.
         total_compute_duration: u8,
+        /// The memory retained by the last execution. Stored as 2^x bytes to
+        /// bucket tasks. Tasks holding more memory are unloaded first.
+        memory_usage: Reverse<u8>,
     },
     /// Unload cells that are currently not read by any task. This might cause
     /// the task to recompute when these cells are read.
@@ -41,6 +44,9 @@ pub enum GcPriority {
         /// Aggregated recompute time. Stored as 2^x milliseconds to bucket
         /// tasks and avoid frequent revalidation.
         total_compute_duration: u8,
+        /// The memory retained by the last execution. Stored as 2^x bytes to
+        /// bucket tasks. Tasks holding more memory are emptied first.
+        memory_usage: Reverse<u8>,
         /// The age of the task. Stored as 2^x seconds to
         /// bucket tasks and avoid frequent revalidation.
         age: Reverse<u8>,
@@ -132,11 +138,13 @@ impl GcQueue {
                     GcPriority::EmptyCells {
                         age,
                         total_compute_duration,
+                        memory_usage,
                     } => {
                         // Convert to the higher priority inactive version.
                         *value = Reverse(GcPriority::InactiveUnload {
                             age: *age,
                             total_compute_duration: *total_compute_duration,
+                            memory_usage: *memory_usage,
                         })
                     }
                     GcPriority::Placeholder => unreachable!(),
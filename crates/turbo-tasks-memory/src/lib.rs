@@ -15,10 +15,13 @@ mod memory_backend;
 mod memory_backend_with_pg;
 mod output;
 mod priority_pair;
+mod provenance;
+mod scheduler;
 pub mod scope;
 pub mod stats;
 mod task;
 pub mod viz;
+mod wait_graph;
 
 pub use memory_backend::MemoryBackend;
 pub use memory_backend_with_pg::MemoryBackendWithPersistedGraph;
@@ -10,6 +10,7 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    thread::available_parallelism,
     time::{Duration, Instant},
 };
 
@@ -27,20 +28,25 @@ use turbo_tasks::{
     },
     event::EventListener,
     primitives::RawVcSetVc,
-    util::{IdFactory, NoMoveVec},
-    CellId, RawVc, TaskId, TraitTypeId, TurboTasksBackendApi, Unused,
+    util::{IdFactory, NoMoveVec, StaticOrArc},
+    CellId, InvalidationProvenance, InvalidationReason, KeyedCellId, RawVc, TaskId, TaskPriority,
+    TraitTypeId, TurboTasksBackendApi, Unused,
 };
+use turbo_tasks_malloc::AllocationInfo;
 
 use crate::{
     cell::RecomputingCell,
     gc::GcQueue,
     output::Output,
     priority_pair::PriorityPair,
+    provenance::Provenance,
+    scheduler::Scheduler,
     scope::{TaskScope, TaskScopeId},
     task::{
         run_add_to_scope_queue, run_remove_from_scope_queue, Task, TaskDependency,
-        DEPENDENCIES_TO_TRACK,
+        DEPENDENCIES_TO_TRACK, EXECUTING_TASK,
     },
+    wait_graph::{format_cycle, WaitGraph},
 };
 
 pub struct MemoryBackend {
@@ -55,6 +61,8 @@ pub struct MemoryBackend {
     gc_queue: Option<GcQueue>,
     idle_gc_active: AtomicBool,
     scope_add_remove_priority: PriorityPair,
+    wait_graph: Arc<WaitGraph>,
+    scheduler: Arc<Scheduler>,
 }
 
 impl Default for MemoryBackend {
@@ -83,9 +91,21 @@ impl MemoryBackend {
             gc_queue: (memory_limit != usize::MAX).then(GcQueue::new),
             idle_gc_active: AtomicBool::new(false),
             scope_add_remove_priority: PriorityPair::new(),
+            wait_graph: Default::default(),
+            scheduler: Arc::new(Scheduler::new(
+                available_parallelism().map_or(4, |n| n.get()).max(4),
+            )),
         }
     }
 
+    /// Limits the number of tasks that execute at the same time. Tasks that
+    /// wait on other tasks don't count towards the limit. Defaults to the
+    /// available parallelism, but at least 4.
+    pub fn with_max_running_tasks(mut self, max_running: usize) -> Self {
+        self.scheduler = Arc::new(Scheduler::new(max_running));
+        self
+    }
+
     fn connect_task_child(
         &self,
         parent: TaskId,
@@ -93,10 +113,62 @@ impl MemoryBackend {
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) {
         self.with_task(parent, |parent| {
+            // Raise the priority first, as connecting might schedule the child
+            let priority = parent.priority();
+            self.raise_task_priority(child, priority, turbo_tasks);
             parent.connect_child(child, self, turbo_tasks)
         });
     }
 
+    /// Queues a task for execution in the lane of its priority.
+    pub(crate) fn schedule(
+        &self,
+        task: TaskId,
+        priority: TaskPriority,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) {
+        self.scheduler.schedule(task, priority, turbo_tasks);
+    }
+
+    /// Called when `reader` has to wait on `task` until `listener` is done.
+    /// Fails when `task` (transitively) waits on the reader, as that would
+    /// never finish. Otherwise `task` inherits the priority of the reader and
+    /// the reader gives up its execution slot until the listener is done.
+    fn wait_for(
+        &self,
+        reader: TaskId,
+        task: TaskId,
+        listener: EventListener,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) -> Result<EventListener> {
+        if let Some(cycle) = self.wait_graph.start_waiting(reader, task) {
+            bail!(format_cycle(&cycle, |task| self.get_task_description(task)));
+        }
+        let priority = self.with_task(reader, |reader| reader.priority());
+        self.raise_task_priority(task, priority, turbo_tasks);
+        self.scheduler.wait(reader, turbo_tasks);
+        let wait_graph = self.wait_graph.clone();
+        let scheduler = self.scheduler.clone();
+        Ok(listener.on_done(move || {
+            wait_graph.stop_waiting(reader, task);
+            scheduler.resume(reader);
+        }))
+    }
+
+    /// Like [MemoryBackend::wait_for], for reads that don't know the reader.
+    /// Only reads from within a task execution are accounted.
+    fn wait_for_untracked(
+        &self,
+        task: TaskId,
+        listener: EventListener,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) -> Result<EventListener> {
+        match EXECUTING_TASK.try_with(|&reader| reader) {
+            Ok(reader) => self.wait_for(reader, task, listener, turbo_tasks),
+            Err(_) => Ok(listener),
+        }
+    }
+
     pub(crate) fn create_backend_job(&self, job: Job) -> BackendJobId {
         job.before_schedule(self);
         let id = self.backend_job_id_factory.get();
@@ -405,7 +477,8 @@ impl Backend for MemoryBackend {
     }
 
     fn invalidate_task(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>) {
-        self.with_task(task, |task| task.invalidate(self, turbo_tasks));
+        let provenance = Arc::new(Provenance::Invalidator(None));
+        self.with_task(task, |task| task.invalidate(provenance, self, turbo_tasks));
     }
 
     fn invalidate_tasks(
@@ -413,25 +486,57 @@ impl Backend for MemoryBackend {
         tasks: Vec<TaskId>,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) {
+        let provenance = Arc::new(Provenance::Invalidator(None));
         for task in tasks.into_iter() {
             self.with_task(task, |task| {
-                task.invalidate(self, turbo_tasks);
+                task.invalidate(provenance.clone(), self, turbo_tasks);
             });
         }
     }
 
+    fn invalidate_task_with_reason(
+        &self,
+        task: TaskId,
+        reason: StaticOrArc<dyn InvalidationReason>,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) {
+        let provenance = Arc::new(Provenance::Invalidator(Some(reason)));
+        self.with_task(task, |task| task.invalidate(provenance, self, turbo_tasks));
+    }
+
+    fn invalidate_dependent_tasks(
+        &self,
+        source: TaskId,
+        tasks: Vec<TaskId>,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) {
+        let provenance = self.with_task(source, |task| task.dependency_provenance());
+        for task in tasks.into_iter() {
+            self.with_task(task, |task| {
+                task.invalidate(provenance.clone(), self, turbo_tasks);
+            });
+        }
+    }
+
+    fn get_task_invalidation_provenance(&self, task: TaskId) -> Option<InvalidationProvenance> {
+        self.with_task(task, |task| task.get_invalidation_provenance())
+    }
+
     fn get_task_description(&self, task: TaskId) -> String {
         self.with_task(task, |task| task.get_description())
     }
 
     type ExecutionScopeFuture<T: Future<Output = Result<()>> + Send + 'static> =
-        TaskLocalFuture<RefCell<AutoSet<TaskDependency>>, T>;
+        TaskLocalFuture<TaskId, TaskLocalFuture<RefCell<AutoSet<TaskDependency>>, T>>;
     fn execution_scope<T: Future<Output = Result<()>> + Send + 'static>(
         &self,
-        _task: TaskId,
+        task: TaskId,
         future: T,
     ) -> Self::ExecutionScopeFuture<T> {
-        DEPENDENCIES_TO_TRACK.scope(Default::default(), future)
+        EXECUTING_TASK.scope(
+            task,
+            DEPENDENCIES_TO_TRACK.scope(Default::default(), future),
+        )
     }
 
     fn try_start_task_execution(
@@ -439,7 +544,11 @@ impl Backend for MemoryBackend {
         task: TaskId,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) -> Option<TaskExecutionSpec> {
-        self.with_task(task, |task| task.execute(self, turbo_tasks))
+        let execution = self.with_task(task, |task| task.execute(self, turbo_tasks));
+        if execution.is_none() {
+            self.scheduler.release(task, turbo_tasks);
+        }
+        execution
     }
 
     fn task_execution_result(
@@ -458,13 +567,16 @@ impl Backend for MemoryBackend {
         task_id: TaskId,
         duration: Duration,
         instant: Instant,
+        allocations: AllocationInfo,
         stateful: bool,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) -> bool {
+        self.wait_graph.execution_completed(task_id);
         let reexecute = self.with_task(task_id, |task| {
-            task.execution_completed(duration, instant, stateful, self, turbo_tasks)
+            task.execution_completed(duration, instant, allocations, stateful, self, turbo_tasks)
         });
         if !reexecute {
+            self.scheduler.release(task_id, turbo_tasks);
             self.run_gc(false, turbo_tasks);
             if let Some(gc_queue) = &self.gc_queue {
                 gc_queue.task_executed(task_id, duration);
@@ -483,7 +595,7 @@ impl Backend for MemoryBackend {
         if task == reader {
             bail!("reading it's own output is not possible");
         }
-        self.try_get_output(
+        let result = self.try_get_output(
             task,
             strongly_consistent,
             move || format!("reading task output from {reader}"),
@@ -492,7 +604,14 @@ impl Backend for MemoryBackend {
                 Task::add_dependency_to_current(TaskDependency::TaskOutput(task));
                 output.read(reader)
             },
-        )
+        )?;
+        match result {
+            Ok(output) => Ok(Ok(output)),
+            Err(listener) => {
+                let listener = self.wait_for(reader, task, listener, turbo_tasks)?;
+                Ok(Err(listener))
+            }
+        }
     }
 
     fn try_read_task_output_untracked(
@@ -501,13 +620,19 @@ impl Backend for MemoryBackend {
         strongly_consistent: bool,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) -> Result<Result<RawVc, EventListener>> {
-        self.try_get_output(
+        match self.try_get_output(
             task,
             strongly_consistent,
             || "reading task output untracked".to_string(),
             turbo_tasks,
             |output| output.read_untracked(),
-        )
+        )? {
+            Ok(output) => Ok(Ok(output)),
+            Err(listener) => {
+                let listener = self.wait_for_untracked(task, listener, turbo_tasks)?;
+                Ok(Err(listener))
+            }
+        }
     }
 
     fn try_read_task_cell(
@@ -523,7 +648,7 @@ impl Backend for MemoryBackend {
             })))
         } else {
             Task::add_dependency_to_current(TaskDependency::TaskCell(task_id, index));
-            self.with_task(task_id, |task| {
+            match self.with_task(task_id, |task| {
                 match task.with_cell_mut(index, |cell| {
                     cell.read_content(
                         reader,
@@ -531,15 +656,21 @@ impl Backend for MemoryBackend {
                         move || format!("reading {} {} from {}", task_id, index, reader),
                     )
                 }) {
-                    Ok(content) => Ok(Ok(content)),
+                    Ok(content) => Ok(content),
                     Err(RecomputingCell { listener, schedule }) => {
                         if schedule {
                             task.recompute(self, turbo_tasks);
                         }
-                        Ok(Err(listener))
+                        Err(listener)
                     }
                 }
-            })
+            }) {
+                Ok(content) => Ok(Ok(content)),
+                Err(listener) => {
+                    let listener = self.wait_for(reader, task_id, listener, turbo_tasks)?;
+                    Ok(Err(listener))
+                }
+            }
         }
     }
 
@@ -560,22 +691,74 @@ impl Backend for MemoryBackend {
         index: CellId,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) -> Result<Result<CellContent, EventListener>> {
-        self.with_task(task_id, |task| {
+        match self.with_task(task_id, |task| {
             match task.with_cell_mut(index, |cell| {
                 cell.read_content_untracked(
                     move || format!("{task_id}"),
                     move || format!("reading {} {} untracked", task_id, index),
                 )
             }) {
-                Ok(content) => Ok(Ok(content)),
+                Ok(content) => Ok(content),
                 Err(RecomputingCell { listener, schedule }) => {
                     if schedule {
                         task.recompute(self, turbo_tasks);
                     }
-                    Ok(Err(listener))
+                    Err(listener)
                 }
             }
-        })
+        }) {
+            Ok(content) => Ok(Ok(content)),
+            Err(listener) => {
+                let listener = self.wait_for_untracked(task_id, listener, turbo_tasks)?;
+                Ok(Err(listener))
+            }
+        }
+    }
+
+    fn try_read_task_keyed_cell(
+        &self,
+        task_id: TaskId,
+        id: KeyedCellId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) -> Result<Result<CellContent, EventListener>> {
+        let tracked_reader = if task_id == reader {
+            None
+        } else {
+            Task::add_dependency_to_current(TaskDependency::TaskKeyedCell(task_id, id));
+            Some(reader)
+        };
+        match self.with_task(task_id, |task| {
+            match task.read_keyed_cell(id, tracked_reader, move || {
+                format!("reading {} {} from {}", task_id, id, reader)
+            }) {
+                Ok(content) => Ok(content),
+                Err(RecomputingCell { listener, schedule }) => {
+                    if schedule {
+                        task.recompute(self, turbo_tasks);
+                    }
+                    Err(listener)
+                }
+            }
+        }) {
+            Ok(content) => Ok(Ok(content)),
+            Err(listener) => {
+                let listener = self.wait_for(reader, task_id, listener, turbo_tasks)?;
+                Ok(Err(listener))
+            }
+        }
+    }
+
+    fn try_read_own_task_keyed_cell_untracked(
+        &self,
+        current_task: TaskId,
+        id: KeyedCellId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) -> Result<CellContent> {
+        Ok(self.with_task(current_task, |task| {
+            task.with_keyed_cell_mut_if_available(id, |cell| cell.read_content(None))
+                .unwrap_or_default()
+        }))
     }
 
     fn read_task_collectibles(
@@ -626,6 +809,19 @@ impl Backend for MemoryBackend {
         })
     }
 
+    fn update_task_keyed_cell(
+        &self,
+        task: TaskId,
+        id: KeyedCellId,
+        content: CellContent,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) -> Result<()> {
+        self.with_task(task, |task| {
+            task.with_keyed_cell_mut(id, |cell| cell.assign(content, turbo_tasks))
+        });
+        Ok(())
+    }
+
     /// SAFETY: Must only called once with the same id
     fn run_backend_job<'a>(
         &'a self,
@@ -700,6 +896,22 @@ impl Backend for MemoryBackend {
         self.with_task(task, |task| task.mark_as_finished(self))
     }
 
+    fn is_own_task_cancelled(
+        &self,
+        task: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) -> bool {
+        self.with_task(task, |task| task.is_execution_cancelled())
+    }
+
+    fn listen_to_own_task_cancellation(
+        &self,
+        task: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) -> Result<(), Option<EventListener>> {
+        self.with_task(task, |task| task.listen_to_cancellation())
+    }
+
     fn create_transient_task(
         &self,
         task_type: TransientTaskType,
@@ -717,6 +929,9 @@ impl Backend for MemoryBackend {
         let task = match task_type {
             TransientTaskType::Root(f) => Task::new_root(id, scope, move || f() as _, stats_type),
             TransientTaskType::Once(f) => Task::new_once(id, scope, f, stats_type),
+            TransientTaskType::StronglyConsistentOnce(f) => {
+                Task::new_strongly_consistent_once(id, scope, move || f() as _, stats_type)
+            }
         };
         // SAFETY: We have a fresh task id where nobody knows about yet
         #[allow(unused_variables)]
@@ -725,6 +940,17 @@ impl Backend for MemoryBackend {
         println!("new {scope} for {task}");
         id
     }
+
+    fn raise_task_priority(
+        &self,
+        task: TaskId,
+        priority: TaskPriority,
+        _turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) {
+        if self.with_task(task, |task| task.raise_priority(priority)) {
+            self.scheduler.promote(task, priority);
+        }
+    }
 }
 
 pub(crate) enum Job {
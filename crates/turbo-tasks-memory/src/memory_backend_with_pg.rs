@@ -7,7 +7,7 @@ use std::{
     pin::Pin,
     sync::{
         atomic::{AtomicU32, AtomicUsize, Ordering},
-        Mutex, MutexGuard,
+        Arc, Mutex, MutexGuard,
     },
     time::{Duration, Instant},
 };
@@ -27,10 +27,11 @@ use turbo_tasks::{
         ActivateResult, DeactivateResult, PersistResult, PersistTaskState, PersistedGraph,
         PersistedGraphApi, ReadTaskState, TaskCell, TaskData,
     },
-    primitives::RawVcSetVc,
+    primitives::{RawVcSet, RawVcSetVc},
     util::{IdFactory, NoMoveVec, SharedError},
-    CellId, RawVc, TaskId, TraitTypeId, TurboTasksBackendApi, Unused,
+    CellId, RawVc, StateId, TaskId, TraitTypeId, TryJoinIterExt, TurboTasksBackendApi, Unused,
 };
+use turbo_tasks_malloc::AllocationInfo;
 
 type RootTaskFn =
     Box<dyn Fn() -> Pin<Box<dyn Future<Output = Result<RawVc>> + Send>> + Send + Sync>;
@@ -39,6 +40,11 @@ enum TaskType {
     Persistent(PersistentTaskType),
     Root(RootTaskFn),
     Once(Mutex<Pin<Box<dyn Future<Output = Result<RawVc>> + Send + 'static>>>),
+    /// Like a root task while it executes, but deactivated once an execution
+    /// completes without being invalidated.
+    StronglyConsistentOnce(RootTaskFn),
+    /// Reads all collectibles of a trait emitted by a task and its children
+    ReadCollectibles(TaskId, TraitTypeId),
 }
 
 impl Debug for TaskType {
@@ -47,6 +53,12 @@ impl Debug for TaskType {
             Self::Persistent(ty) => f.debug_tuple("Persistent").field(ty).finish(),
             Self::Root(_) => f.debug_tuple("Root").finish(),
             Self::Once(_) => f.debug_tuple("Once").finish(),
+            Self::StronglyConsistentOnce(_) => f.debug_tuple("StronglyConsistentOnce").finish(),
+            Self::ReadCollectibles(task, trait_id) => f
+                .debug_tuple("ReadCollectibles")
+                .field(task)
+                .field(trait_id)
+                .finish(),
         }
     }
 }
@@ -71,6 +83,16 @@ struct MemoryTaskState {
     output_dependent: AutoSet<TaskId, BuildNoHashHasher<TaskId>>,
     dependencies: AutoSet<RawVc>,
     children: AutoSet<TaskId, BuildNoHashHasher<TaskId>>,
+    collectibles: HashMap<(TraitTypeId, RawVc), i32>,
+    /// The collectibles before the current execution started, to detect
+    /// changes on completion
+    previous_collectibles: Option<HashMap<(TraitTypeId, RawVc), i32>>,
+    collectibles_dependent: AutoSet<TaskId, BuildNoHashHasher<TaskId>>,
+    /// The task read state that doesn't survive a restart
+    session_dependent: bool,
+    /// The task was restored clean from the persisted graph and only needs to
+    /// verify its dependencies on the next execution
+    verify: bool,
     event: Event,
     event_cells: Event,
 }
@@ -86,6 +108,11 @@ impl MemoryTaskState {
             output_dependent: Default::default(),
             dependencies: Default::default(),
             children: Default::default(),
+            collectibles: Default::default(),
+            previous_collectibles: Default::default(),
+            collectibles_dependent: Default::default(),
+            session_dependent: Default::default(),
+            verify: Default::default(),
             event: Event::new(move || format!("MemoryTaskState({task})::event")),
             event_cells: Event::new(move || format!("MemoryTaskState({task})::event_cells")),
         }
@@ -133,6 +160,7 @@ pub struct MemoryBackendWithPersistedGraph<P: PersistedGraph + 'static> {
     pub pg: P,
     tasks: NoMoveVec<Task>,
     cache: DashMap<PersistentTaskType, TaskId>,
+    read_collectibles_tasks: DashMap<(TaskId, TraitTypeId), TaskId>,
     background_job_id_factory: IdFactory<BackendJobId>,
     background_jobs: NoMoveVec<BackgroundJob>,
     only_known_to_memory_tasks: DashSet<TaskId>,
@@ -140,6 +168,7 @@ pub struct MemoryBackendWithPersistedGraph<P: PersistedGraph + 'static> {
     persist_queue1: ConcurrentQueue<TaskId>,
     persist_queue1_queued: DashSet<TaskId>,
     need_persisting: DashSet<TaskId>,
+    changed_states: DashSet<StateId>,
     /// Task sorted by importance, sharded to avoid lock contention
     persist_queue_by_duration: [Mutex<BinaryHeap<(Duration, TaskId)>>; 64],
     persist_capacity: AtomicUsize,
@@ -162,12 +191,14 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
             pg,
             tasks: NoMoveVec::new(),
             cache: DashMap::new(),
+            read_collectibles_tasks: DashMap::new(),
             background_job_id_factory,
             background_jobs: NoMoveVec::new(),
             only_known_to_memory_tasks: DashSet::new(),
             persist_queue1: ConcurrentQueue::unbounded(),
             persist_queue1_queued: DashSet::new(),
             need_persisting: DashSet::new(),
+            changed_states: DashSet::new(),
             persist_queue_by_duration: [(); 64].map(|_| Mutex::new(BinaryHeap::new())),
             persist_capacity: AtomicUsize::new(num_cpus::get()),
             persist_job,
@@ -259,12 +290,11 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                 task_state.persisted = Some(PersistedTaskState {
                     clean: Some(state.clean),
                 });
+                // Data from a previous session can't be trusted until the
+                // dependencies have been verified, so a clean task is
+                // scheduled for verification instead of execution
                 let mem_state = MemoryTaskState {
-                    freshness: if state.clean {
-                        TaskFreshness::Done
-                    } else {
-                        TaskFreshness::Dirty
-                    },
+                    freshness: TaskFreshness::Dirty,
                     cells: data
                         .cells
                         .into_iter()
@@ -274,6 +304,15 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                     output_dependent: AutoSet::default(),
                     dependencies: data.dependencies.into_iter().collect(),
                     children: data.children.into_iter().collect(),
+                    collectibles: data
+                        .collectibles
+                        .into_iter()
+                        .map(|(trait_id, collectible, count)| ((trait_id, collectible), count))
+                        .collect(),
+                    previous_collectibles: Default::default(),
+                    collectibles_dependent: Default::default(),
+                    session_dependent: Default::default(),
+                    verify: state.clean,
                     need_persist: Default::default(),
                     has_changes: Default::default(),
                     event: Event::new(move || format!("MemoryTaskState({task})::event")),
@@ -297,7 +336,7 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                 }
                 task_state.persisted_to_mem_active = state.keeps_external_active;
                 task_state.memory = Some(mem_state);
-                if !state.clean && task_state.active && !task_state.scheduled {
+                if task_state.active && !task_state.scheduled {
                     task_state.scheduled = true;
                     #[cfg(feature = "log_scheduled_tasks")]
                     println!("schedule({task}) in ensure_task_in_memory");
@@ -413,8 +452,20 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
             for task in more_tasks_to_activate {
                 self.schedule_background_job(BackgroundJob::ActivatePersisted(task), turbo_tasks);
             }
+            // A completed execution that is not persisted yet is newer than the
+            // dirty flag of the persisted graph
+            let dirty = dirty && !self.need_persisting.contains(&task);
             let (mut state, task_info) = self.state_mut(task, turbo_tasks);
-            if dirty && state.memory.is_none() && !state.scheduled {
+            if dirty {
+                // The persisted graph might have made the task dirty while it was inactive
+                if let Some(mem_state) = &mut state.memory {
+                    mem_state.verify = false;
+                    if mem_state.freshness == TaskFreshness::Done {
+                        mem_state.freshness = TaskFreshness::Dirty;
+                    }
+                }
+            }
+            if dirty && !state.scheduled {
                 state.scheduled = true;
                 #[cfg(feature = "log_scheduled_tasks")]
                 println!("schedule({task}) in activate_persisted");
@@ -754,10 +805,275 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
         }
     }
 
+    fn update_collectible_count(
+        &self,
+        trait_id: TraitTypeId,
+        collectible: RawVc,
+        by: i32,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+    ) {
+        let (mut state, _) = self.mem_state_mut(task, turbo_tasks);
+        let mem_state = state.memory.as_mut().unwrap();
+        let count = mem_state
+            .collectibles
+            .entry((trait_id, collectible))
+            .or_default();
+        *count += by;
+        if *count == 0 {
+            mem_state.collectibles.remove(&(trait_id, collectible));
+        }
+    }
+
+    fn is_persistent_task(&self, task: TaskId) -> bool {
+        matches!(
+            self.tasks.get(*task).unwrap().task_type,
+            TaskType::Persistent(_)
+        )
+    }
+
+    /// Schedules tasks that the persisted graph has made dirty. These might be
+    /// in memory already, so the memory version need to be invalidated too.
+    fn schedule_dirty_dependents(
+        &self,
+        tasks: Vec<TaskId>,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+    ) {
+        for task in tasks {
+            let (mut state, _) = self.state_mut(task, turbo_tasks);
+            if let Some(PersistedTaskState { clean }) = &mut state.persisted {
+                *clean = Some(false);
+            }
+            if let Some(MemoryTaskState {
+                freshness, verify, ..
+            }) = &mut state.memory
+            {
+                *freshness = TaskFreshness::Dirty;
+                *verify = false;
+            }
+            if !state.scheduled {
+                state.scheduled = true;
+                #[cfg(feature = "log_scheduled_tasks")]
+                println!("schedule({task}) in schedule_dirty_dependents");
+                turbo_tasks.schedule(task);
+            }
+        }
+    }
+
+    fn get_or_create_read_collectibles_task(
+        &self,
+        task: TaskId,
+        trait_id: TraitTypeId,
+        parent_task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+    ) -> TaskId {
+        let read_task = match self.read_collectibles_tasks.entry((task, trait_id)) {
+            Entry::Occupied(e) => *e.get(),
+            Entry::Vacant(e) => {
+                let read_task = turbo_tasks.get_fresh_task_id().into();
+                let new_task = Task {
+                    active_parents: AtomicU32::new(0),
+                    task_state: Mutex::new(TaskState {
+                        memory: Some(MemoryTaskState::new(
+                            read_task,
+                            TaskFreshness::NeverExecuted,
+                        )),
+                        ..Default::default()
+                    }),
+                    task_type: TaskType::ReadCollectibles(task, trait_id),
+                };
+                // SAFETY: It's a fresh task id
+                unsafe {
+                    self.tasks.insert(*read_task, new_task);
+                }
+                self.only_known_to_memory_tasks.insert(read_task);
+                *e.insert(read_task)
+            }
+        };
+        self.connect(parent_task, read_task, turbo_tasks);
+        read_task
+    }
+
+    /// Returns the collectibles emitted by the task itself and its children,
+    /// or waits until the task has finished.
+    #[allow(clippy::type_complexity)]
+    fn try_read_collectibles_and_children(
+        &self,
+        task: TaskId,
+        trait_id: TraitTypeId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+    ) -> Result<(Vec<(RawVc, i32)>, Vec<TaskId>), EventListener> {
+        let (mut state, _) = self.mem_state_mut(task, turbo_tasks);
+        let TaskState {
+            ref mut scheduled,
+            ref mut memory,
+            ..
+        } = *state;
+        let mem_state = memory.as_mut().unwrap();
+        if mem_state.freshness != TaskFreshness::Done {
+            let listener = mem_state.event.listen();
+            if !*scheduled {
+                *scheduled = true;
+                #[cfg(feature = "log_scheduled_tasks")]
+                println!("schedule({task}) in try_read_collectibles_and_children");
+                turbo_tasks.schedule(task);
+            }
+            return Err(listener);
+        }
+        mem_state.collectibles_dependent.insert(reader);
+        let collectibles = mem_state
+            .collectibles
+            .iter()
+            .filter(|((t, _), _)| *t == trait_id)
+            .map(|(&(_, collectible), &count)| (collectible, count))
+            .collect();
+        let children = mem_state
+            .children
+            .iter()
+            .copied()
+            .filter(|&child| {
+                !matches!(
+                    self.tasks.get(*child).unwrap().task_type,
+                    TaskType::ReadCollectibles(..)
+                )
+            })
+            .collect();
+        Ok((collectibles, children))
+    }
+
+    /// Waits for all dependencies of a task restored from the persisted graph
+    /// to be up to date. A changed dependency makes the task dirty, which
+    /// leads to a real execution once the verification has completed.
+    async fn verify_dependencies(
+        task: TaskId,
+        dependencies: Vec<RawVc>,
+        output: RawVc,
+        turbo_tasks: Arc<dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>>,
+    ) -> Result<RawVc> {
+        let backend = turbo_tasks.backend();
+        for dep in dependencies {
+            let dep_task = dep.get_task_id();
+            // An error output is a change that has already been propagated to
+            // the dependents
+            while let Ok(Err(listener)) =
+                backend.try_read_task_output_untracked(dep_task, false, &*turbo_tasks)
+            {
+                listener.await;
+            }
+            // Read it again tracked to be notified about future changes
+            loop {
+                let listener = match dep {
+                    RawVc::TaskOutput(_) => {
+                        match backend.try_read_task_output(dep_task, task, false, &*turbo_tasks) {
+                            Ok(Err(listener)) => listener,
+                            _ => break,
+                        }
+                    }
+                    RawVc::TaskCell(_, index) => {
+                        match backend.try_read_task_cell(dep_task, index, task, &*turbo_tasks) {
+                            Ok(Err(listener)) => listener,
+                            _ => break,
+                        }
+                    }
+                };
+                listener.await;
+            }
+        }
+        Ok(output)
+    }
+
+    async fn execute_read_collectibles(
+        read_task: TaskId,
+        task: TaskId,
+        trait_id: TraitTypeId,
+        turbo_tasks: Arc<dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>>,
+    ) -> Result<RawVc> {
+        let backend = turbo_tasks.backend();
+        // Keeps the task active while its collectibles are read
+        backend.connect(read_task, task, &*turbo_tasks);
+        let (collectibles, children) = loop {
+            match backend.try_read_collectibles_and_children(
+                task,
+                trait_id,
+                read_task,
+                &*turbo_tasks,
+            ) {
+                Ok(r) => break r,
+                Err(listener) => listener.await,
+            }
+        };
+        let children = children
+            .into_iter()
+            .map(|child| {
+                let child_read_task = backend.get_or_create_read_collectibles_task(
+                    child,
+                    trait_id,
+                    read_task,
+                    &*turbo_tasks,
+                );
+                // Safety: RawVcSet is a transparent value
+                unsafe {
+                    RawVc::TaskOutput(child_read_task)
+                        .into_transparent_read::<RawVcSet, AutoSet<RawVc>>()
+                }
+            })
+            .try_join()
+            .await?;
+        let mut counts: HashMap<RawVc, i32> = collectibles.into_iter().collect();
+        for child in children {
+            for collectible in child.iter() {
+                *counts.entry(*collectible).or_default() += 1;
+            }
+        }
+        Ok(RawVcSetVc::cell(
+            counts
+                .into_iter()
+                .filter(|&(_, count)| count > 0)
+                .map(|(collectible, _)| collectible)
+                .collect(),
+        )
+        .into())
+    }
+
+    /// Stores the values of states that have changed since they were persisted.
+    /// Returns true when there was something to do.
+    fn persist_changed_states(
+        &self,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+    ) -> bool {
+        let states = self
+            .changed_states
+            .iter()
+            .map(|state| *state)
+            .collect::<Vec<_>>();
+        if states.is_empty() {
+            return false;
+        }
+        // The values are read when persisting, so a change after removing it
+        // here is either included or marks the state again
+        for state in states.iter() {
+            self.changed_states.remove(state);
+        }
+        self.pg
+            .persist_states(
+                states,
+                &MemoryBackendPersistedGraphApi {
+                    backend: self,
+                    turbo_tasks,
+                },
+            )
+            .unwrap();
+        true
+    }
+
     fn persist(
         &self,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
     ) -> bool {
+        if self.persist_changed_states(turbo_tasks) {
+            return true;
+        }
         loop {
             if let Ok(mut task) = self.persist_queue1.pop() {
                 self.persist_queue1_queued.remove(&task);
@@ -776,6 +1092,8 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                                 ref children,
                                 ref dependencies,
                                 ref cells,
+                                ref collectibles,
+                                session_dependent,
                                 ..
                             }),
                         ..
@@ -805,20 +1123,45 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
                                                 self.only_known_to_memory_tasks.remove(&task);
                                             }
                                         }
+                                        // Transient tasks can't be referenced after a restart.
+                                        // Children only matter for activeness, but depending
+                                        // on them requires to re-execute the task in the next
+                                        // session.
+                                        let mut session_dependent = session_dependent;
                                         let data = TaskData {
-                                            children: children.iter().cloned().collect(),
-                                            dependencies: dependencies.iter().cloned().collect(),
+                                            children: children
+                                                .iter()
+                                                .copied()
+                                                .filter(|&child| self.is_persistent_task(child))
+                                                .collect(),
+                                            dependencies: dependencies
+                                                .iter()
+                                                .copied()
+                                                .filter(|dep| {
+                                                    let persistent =
+                                                        self.is_persistent_task(dep.get_task_id());
+                                                    session_dependent |= !persistent;
+                                                    persistent
+                                                })
+                                                .collect(),
                                             cells: cells
                                                 .iter()
                                                 .map(|(k, (s, _))| (*k, s.clone()))
                                                 .collect(),
                                             output: *output,
+                                            collectibles: collectibles
+                                                .iter()
+                                                .map(|(&(trait_id, collectible), &count)| {
+                                                    (trait_id, collectible, count)
+                                                })
+                                                .collect(),
                                         };
                                         let externally_active =
                                             task_info.active_parents.load(Ordering::Acquire) > 0;
                                         let task_state =
                                             turbo_tasks::persisted_graph::PersistTaskState {
                                                 externally_active,
+                                                session_dependent,
                                             };
                                         if let Some(PersistResult {
                                             tasks_to_activate,
@@ -892,6 +1235,7 @@ impl<P: PersistedGraph> MemoryBackendWithPersistedGraph<P> {
 
     fn has_persist_work(&self) -> bool {
         !self.persist_queue1.is_empty()
+            || !self.changed_states.is_empty()
             || self
                 .persist_queue_by_duration
                 .iter()
@@ -1009,7 +1353,11 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
     ) {
         let (mut state, _) = self.state_mut(task, turbo_tasks);
 
-        if let Some(MemoryTaskState { freshness, .. }) = &mut state.memory {
+        if let Some(MemoryTaskState {
+            freshness, verify, ..
+        }) = &mut state.memory
+        {
+            *verify = false;
             if *freshness != TaskFreshness::Dirty {
                 *freshness = TaskFreshness::Dirty;
                 if state.active && !state.scheduled {
@@ -1071,6 +1419,29 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             println!("start {} {:?}", task, task_info.task_type);
         }
         mem_state.freshness = TaskFreshness::NeverExecuted;
+        if take(&mut mem_state.verify) {
+            // Dependencies, children and collectibles are kept as they are
+            // only checked here
+            if let Some(Ok(output)) = mem_state.output.clone() {
+                let dependencies = mem_state.dependencies.iter().copied().collect();
+                drop(state);
+                return Some(TaskExecutionSpec {
+                    future: Box::pin(Self::verify_dependencies(
+                        task,
+                        dependencies,
+                        output,
+                        turbo_tasks.pin(),
+                    )),
+                });
+            }
+        }
+        mem_state.session_dependent = false;
+        // Collectibles are emitted again during execution
+        if mem_state.previous_collectibles.is_none() {
+            mem_state.previous_collectibles = Some(take(&mut mem_state.collectibles));
+        } else {
+            mem_state.collectibles.clear();
+        }
         let deps = take(&mut mem_state.dependencies);
         let children = take(&mut mem_state.children);
         drop(state);
@@ -1093,7 +1464,7 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
         }
         let future = match &task_info.task_type {
             TaskType::Persistent(t) => t.clone().run(turbo_tasks.pin()),
-            TaskType::Root(root) => root(),
+            TaskType::Root(root) | TaskType::StronglyConsistentOnce(root) => root(),
             TaskType::Once(once) => {
                 let mut m = once.lock().unwrap();
                 replace(
@@ -1101,6 +1472,9 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
                     Box::pin(async { Err(anyhow::anyhow!("Once task can only be executed once")) }),
                 )
             }
+            &TaskType::ReadCollectibles(source, trait_id) => Box::pin(
+                Self::execute_read_collectibles(task, source, trait_id, turbo_tasks.pin()),
+            ),
         };
         Some(TaskExecutionSpec { future })
     }
@@ -1143,6 +1517,7 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
         task: TaskId,
         duration: Duration,
         _instant: Instant,
+        _allocations: AllocationInfo,
         _stateful: bool,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
     ) -> bool {
@@ -1169,6 +1544,13 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
         mem_state.event.notify(usize::MAX);
         mem_state.event_cells.notify(usize::MAX);
         mem_state.need_persist = true;
+        let collectibles_dependent = match mem_state.previous_collectibles.take() {
+            Some(previous) if previous != mem_state.collectibles => {
+                mem_state.has_changes = true;
+                take(&mut mem_state.collectibles_dependent)
+            }
+            _ => AutoSet::default(),
+        };
         let has_changes = mem_state.has_changes;
         let is_persisted = persisted.is_some();
         let is_dirty_persisted = persisted
@@ -1177,17 +1559,14 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             .unwrap_or_default();
         drop(state);
 
+        if !collectibles_dependent.is_empty() {
+            turbo_tasks.schedule_notify_tasks_set(&collectibles_dependent);
+        }
+
         if let TaskType::Persistent(_) = task_info.task_type {
             if has_changes && (is_persisted || !self.only_known_to_memory_tasks.contains(&task)) {
-                for task in self.pg_make_dependent_dirty(RawVc::TaskOutput(task), turbo_tasks) {
-                    let (mut state, _) = self.state_mut(task, turbo_tasks);
-                    if !state.scheduled {
-                        state.scheduled = true;
-                        #[cfg(feature = "log_scheduled_tasks")]
-                        println!("schedule({task}) in task_execution_completed");
-                        turbo_tasks.schedule(task);
-                    }
-                }
+                let dirty = self.pg_make_dependent_dirty(RawVc::TaskOutput(task), turbo_tasks);
+                self.schedule_dirty_dependents(dirty, turbo_tasks);
             }
             if has_changes || is_dirty_persisted {
                 self.need_persisting.insert(task);
@@ -1199,6 +1578,11 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             }
         }
 
+        if let TaskType::StronglyConsistentOnce(_) = task_info.task_type {
+            // The result is consistent now, later invalidations must not execute it again
+            self.decrement_active_parents(task, 1, turbo_tasks);
+        }
+
         false
     }
 
@@ -1320,17 +1704,6 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             ..
         } = *state;
         let mem_state = memory.as_mut().unwrap();
-        if mem_state.freshness == TaskFreshness::NeverExecuted {
-            if !*scheduled {
-                *scheduled = true;
-                #[cfg(feature = "log_scheduled_tasks")]
-                println!("schedule({task}) in try_read_task_cell[NeverExecuted]");
-                turbo_tasks.schedule(task);
-            }
-            #[cfg(feature = "log_running_tasks")]
-            println!("waiting (fresh task) {} waits on {}", reader, task);
-            return Ok(Err(mem_state.event_cells.listen()));
-        }
         if let Some((cell, dependent)) = mem_state.cells.get_mut(&index) {
             match cell {
                 TaskCell::Content(content) => {
@@ -1345,6 +1718,7 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
                     Ok(Ok(content))
                 }
                 TaskCell::NeedComputation => {
+                    mem_state.verify = false;
                     if mem_state.freshness != TaskFreshness::Dirty {
                         mem_state.freshness = TaskFreshness::Dirty;
                         if !*scheduled {
@@ -1396,6 +1770,7 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
                 Ok(Ok(content))
             }
             TaskCell::NeedComputation => {
+                mem_state.verify = false;
                 if mem_state.freshness != TaskFreshness::Dirty {
                     mem_state.freshness = TaskFreshness::Dirty;
                     if !*scheduled {
@@ -1430,32 +1805,34 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
 
     fn read_task_collectibles(
         &self,
-        _task: TaskId,
-        _trait_id: TraitTypeId,
-        _reader: TaskId,
-        _turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+        task: TaskId,
+        trait_id: TraitTypeId,
+        reader: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
     ) -> RawVcSetVc {
-        todo!()
+        let read_task =
+            self.get_or_create_read_collectibles_task(task, trait_id, reader, turbo_tasks);
+        RawVcSetVc::from(RawVc::TaskOutput(read_task))
     }
 
     fn emit_collectible(
         &self,
-        _trait_id: TraitTypeId,
-        _collectible: RawVc,
-        _task: TaskId,
-        _turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+        trait_id: TraitTypeId,
+        collectible: RawVc,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
     ) {
-        todo!()
+        self.update_collectible_count(trait_id, collectible, 1, task, turbo_tasks);
     }
 
     fn unemit_collectible(
         &self,
-        _trait_id: TraitTypeId,
-        _collectible: RawVc,
-        _task: TaskId,
-        _turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+        trait_id: TraitTypeId,
+        collectible: RawVc,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
     ) {
-        todo!()
+        self.update_collectible_count(trait_id, collectible, -1, task, turbo_tasks);
     }
 
     fn update_task_cell(
@@ -1486,16 +1863,8 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
         }
         if let TaskType::Persistent(_) = task_info.task_type {
             if is_persisted || !self.only_known_to_memory_tasks.contains(&task) {
-                for task in self.pg_make_dependent_dirty(RawVc::TaskCell(task, index), turbo_tasks)
-                {
-                    let (mut state, _) = self.state_mut(task, turbo_tasks);
-                    if !state.scheduled {
-                        state.scheduled = true;
-                        #[cfg(feature = "log_scheduled_tasks")]
-                        println!("schedule({task}) in update_task_cell");
-                        turbo_tasks.schedule(task);
-                    }
-                }
+                let dirty = self.pg_make_dependent_dirty(RawVc::TaskCell(task, index), turbo_tasks);
+                self.schedule_dirty_dependents(dirty, turbo_tasks);
             }
         }
     }
@@ -1559,11 +1928,30 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
 
     fn connect_task(
         &self,
-        _task: TaskId,
-        _parent_task: TaskId,
-        _turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+        task: TaskId,
+        parent_task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
     ) {
-        todo!()
+        self.connect(parent_task, task, turbo_tasks);
+    }
+
+    fn mark_own_task_as_session_dependent(
+        &self,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+    ) {
+        let (mut state, _) = self.mem_state_mut(task, turbo_tasks);
+        state.memory.as_mut().unwrap().session_dependent = true;
+    }
+
+    fn mark_state_as_changed(
+        &self,
+        state: StateId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackendWithPersistedGraph<P>>,
+    ) {
+        if self.changed_states.insert(state) {
+            self.increase_persist_workers(1, turbo_tasks);
+        }
     }
 
     fn create_transient_task(
@@ -1585,6 +1973,7 @@ impl<P: PersistedGraph> Backend for MemoryBackendWithPersistedGraph<P> {
             task_type: match task_type {
                 TransientTaskType::Root(r) => TaskType::Root(r),
                 TransientTaskType::Once(o) => TaskType::Once(Mutex::new(o)),
+                TransientTaskType::StronglyConsistentOnce(r) => TaskType::StronglyConsistentOnce(r),
             },
         };
         // SAFETY: It's a fresh task id
@@ -1634,11 +2023,11 @@ impl<'a, P: PersistedGraph> PersistedGraphApi for MemoryBackendPersistedGraphApi
         }
     }
 
-    fn lookup_task_type(&self, id: TaskId) -> &PersistentTaskType {
+    fn lookup_task_type(&self, id: TaskId) -> Option<&PersistentTaskType> {
         let task = self.backend.tasks.get(*id).unwrap();
         match &task.task_type {
-            TaskType::Persistent(ty) => ty,
-            _ => panic!("lookup_task_type should only be used for PersistentTaskType"),
+            TaskType::Persistent(ty) => Some(ty),
+            _ => None,
         }
     }
 }
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use turbo_tasks::{util::StaticOrArc, InvalidationProvenance, InvalidationReason, TaskId};
+
+/// Invalidations caused by feedback loops would otherwise grow the chain with
+/// every execution.
+const MAX_DEPTH: u32 = 256;
+
+/// Why a task has been invalidated. Chains are shared between all tasks
+/// invalidated by the same execution.
+pub(crate) enum Provenance {
+    /// The task has been invalidated directly, e.g. by an
+    /// [Invalidator](turbo_tasks::Invalidator).
+    Invalidator(Option<StaticOrArc<dyn InvalidationReason>>),
+    /// The task depends on values changed by an execution of `task`, which
+    /// happened because of `cause`.
+    Dependency {
+        task: TaskId,
+        depth: u32,
+        cause: Option<Arc<Provenance>>,
+    },
+}
+
+impl Provenance {
+    pub(crate) fn dependency(task: TaskId, cause: Option<Arc<Provenance>>) -> Self {
+        let depth = match cause.as_deref() {
+            Some(Provenance::Dependency { depth, .. }) => depth + 1,
+            _ => 1,
+        };
+        if depth > MAX_DEPTH {
+            return Provenance::Dependency {
+                task,
+                depth: 1,
+                cause: None,
+            };
+        }
+        Provenance::Dependency { task, depth, cause }
+    }
+
+    /// Flattens the chain into an [InvalidationProvenance] that ends at the
+    /// invalidated `task`.
+    pub(crate) fn resolve(&self, task: TaskId) -> InvalidationProvenance {
+        let mut path = vec![task];
+        let mut current = self;
+        let reason = loop {
+            match current {
+                Provenance::Invalidator(reason) => break reason.clone(),
+                Provenance::Dependency { task, cause, .. } => {
+                    path.push(*task);
+                    match cause {
+                        Some(cause) => current = cause,
+                        None => break None,
+                    }
+                }
+            }
+        };
+        path.reverse();
+        InvalidationProvenance { reason, path }
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::VecDeque;
+
+use auto_hash_map::{AutoMap, AutoSet};
+use nohash_hasher::BuildNoHashHasher;
+use parking_lot::Mutex;
+use turbo_tasks::{TaskId, TaskPriority, TurboTasksBackendApi};
+
+use crate::MemoryBackend;
+
+/// How often a non-empty lane can be passed over in favor of higher lanes
+/// before it is served first.
+const MAX_SKIPPED: u32 = 8;
+
+/// Dispatches scheduled tasks by priority lane.
+///
+/// Only a limited number of tasks hold an execution slot at a time. A task
+/// gives up its slot when it completes or starts waiting on another task, so
+/// waiting tasks can't block the tasks they wait on. It takes the slot back
+/// when it resumes, even when that exceeds the limit for a while.
+pub(crate) struct Scheduler {
+    state: Mutex<SchedulerState>,
+    max_running: usize,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    /// Queued tasks for each [TaskPriority]. Can contain stale entries for
+    /// tasks that have been promoted to a higher lane.
+    lanes: [VecDeque<TaskId>; TaskPriority::COUNT],
+    /// The lane of each queued task.
+    queued: AutoMap<TaskId, TaskPriority, BuildNoHashHasher<TaskId>>,
+    /// Tasks that hold an execution slot.
+    running: AutoSet<TaskId, BuildNoHashHasher<TaskId>>,
+    /// Executing tasks that gave up their slot to wait, with the number of
+    /// reads they are waiting on.
+    waiting: AutoMap<TaskId, usize, BuildNoHashHasher<TaskId>>,
+    /// How often each lane has been passed over while it was not empty.
+    skipped: [u32; TaskPriority::COUNT],
+}
+
+impl Scheduler {
+    pub(crate) fn new(max_running: usize) -> Self {
+        Self {
+            state: Default::default(),
+            max_running,
+        }
+    }
+
+    /// Queues a task in the lane of `priority` and dispatches tasks while
+    /// execution slots are available.
+    pub(crate) fn schedule(
+        &self,
+        task: TaskId,
+        priority: TaskPriority,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) {
+        let mut state = self.state.lock();
+        state.enqueue(task, priority);
+        self.dispatch(state, turbo_tasks);
+    }
+
+    /// Moves a queued task to a higher lane. Does nothing when the task is
+    /// not queued.
+    pub(crate) fn promote(&self, task: TaskId, priority: TaskPriority) {
+        let mut state = self.state.lock();
+        if state.queued.contains_key(&task) {
+            state.enqueue(task, priority);
+        }
+    }
+
+    /// Gives up the execution slot of a task, if it holds one, and dispatches
+    /// the next task.
+    pub(crate) fn release(
+        &self,
+        task: TaskId,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) {
+        let mut state = self.state.lock();
+        state.waiting.remove(&task);
+        if state.running.remove(&task) {
+            self.dispatch(state, turbo_tasks);
+        }
+    }
+
+    /// Called when an executing task starts waiting on a read. The task gives
+    /// up its execution slot until all its reads are done, see
+    /// [Scheduler::resume].
+    pub(crate) fn wait(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>) {
+        let mut state = self.state.lock();
+        if let Some(count) = state.waiting.get_mut(&task) {
+            *count += 1;
+        } else if state.running.remove(&task) {
+            state.waiting.insert(task, 1);
+            self.dispatch(state, turbo_tasks);
+        }
+    }
+
+    /// Called when a read of a waiting task is done. The task takes its
+    /// execution slot back once it no longer waits on any read.
+    pub(crate) fn resume(&self, task: TaskId) {
+        let mut state = self.state.lock();
+        let Some(count) = state.waiting.get_mut(&task) else {
+            return;
+        };
+        *count -= 1;
+        if *count == 0 {
+            state.waiting.remove(&task);
+            state.running.insert(task);
+        }
+    }
+
+    fn dispatch(
+        &self,
+        mut state: parking_lot::MutexGuard<'_, SchedulerState>,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) {
+        let mut tasks = Vec::new();
+        while state.running.len() < self.max_running {
+            let Some(task) = state.next() else {
+                break;
+            };
+            state.running.insert(task);
+            tasks.push(task);
+        }
+        drop(state);
+        for task in tasks {
+            turbo_tasks.schedule(task);
+        }
+    }
+}
+
+impl SchedulerState {
+    fn enqueue(&mut self, task: TaskId, priority: TaskPriority) {
+        match self.queued.get(&task) {
+            Some(&queued) if queued >= priority => {}
+            _ => {
+                self.queued.insert(task, priority);
+                self.lanes[priority.index()].push_back(task);
+            }
+        }
+    }
+
+    /// Takes the next task from the highest non-empty lane, unless a lower
+    /// lane has been passed over too often.
+    fn next(&mut self) -> Option<TaskId> {
+        loop {
+            let non_empty = |lane: usize| !self.lanes[lane].is_empty();
+            let lane = (0..TaskPriority::COUNT)
+                .find(|&lane| non_empty(lane) && self.skipped[lane] >= MAX_SKIPPED)
+                .or_else(|| (0..TaskPriority::COUNT).rev().find(|&lane| non_empty(lane)))?;
+            for other in 0..TaskPriority::COUNT {
+                if other != lane && non_empty(other) {
+                    self.skipped[other] += 1;
+                }
+            }
+            self.skipped[lane] = 0;
+            let task = self.lanes[lane].pop_front().unwrap();
+            // Skip stale entries of tasks that have been promoted to a higher lane
+            if self.queued.get(&task) == Some(&TaskPriority::from_index(lane)) {
+                self.queued.remove(&task);
+                return Some(task);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use turbo_tasks::{TaskId, TaskPriority};
+
+    use super::{SchedulerState, MAX_SKIPPED};
+
+    fn drain(state: &mut SchedulerState) -> Vec<usize> {
+        std::iter::from_fn(|| state.next()).map(|id| *id).collect()
+    }
+
+    #[test]
+    fn runs_higher_lanes_first() {
+        let mut state = SchedulerState::default();
+        state.enqueue(TaskId::from(1), TaskPriority::Background);
+        state.enqueue(TaskId::from(2), TaskPriority::Normal);
+        state.enqueue(TaskId::from(3), TaskPriority::Interactive);
+        state.enqueue(TaskId::from(4), TaskPriority::Interactive);
+        assert_eq!(drain(&mut state), vec![3, 4, 2, 1]);
+    }
+
+    #[test]
+    fn promotes_queued_tasks() {
+        let mut state = SchedulerState::default();
+        state.enqueue(TaskId::from(1), TaskPriority::Normal);
+        state.enqueue(TaskId::from(2), TaskPriority::Background);
+        state.enqueue(TaskId::from(2), TaskPriority::Interactive);
+        assert_eq!(drain(&mut state), vec![2, 1]);
+    }
+
+    #[test]
+    fn does_not_starve_lower_lanes() {
+        let mut state = SchedulerState::default();
+        state.enqueue(TaskId::from(1), TaskPriority::Background);
+        for id in 2..100 {
+            state.enqueue(TaskId::from(id), TaskPriority::Interactive);
+        }
+        let order = drain(&mut state);
+        let position = order.iter().position(|&id| id == 1).unwrap();
+        assert_eq!(position, MAX_SKIPPED as usize);
+    }
+}
@@ -6,7 +6,8 @@ use std::{
     time::Duration,
 };
 
-use turbo_tasks::{registry, FunctionId, TaskId, TraitTypeId};
+use turbo_tasks::{registry, FunctionId, TaskId, TraitTypeId, ValueTypeId};
+use turbo_tasks_malloc::AllocationInfo;
 
 use crate::{
     scope::TaskScopeId,
@@ -72,6 +73,8 @@ pub struct ExportedTaskStats {
     pub total_current_duration: Duration,
     pub total_update_duration: Duration,
     pub max_duration: Duration,
+    pub total_allocations: Option<AllocationInfo>,
+    pub total_memory_usage: usize,
     pub references: HashMap<(ReferenceType, StatsTaskType), ReferenceStats>,
 }
 
@@ -88,13 +91,27 @@ impl Default for ExportedTaskStats {
             total_current_duration: Duration::ZERO,
             total_update_duration: Duration::ZERO,
             max_duration: Duration::ZERO,
+            total_allocations: None,
+            total_memory_usage: 0,
             references: Default::default(),
         }
     }
 }
 
+/// Memory attributed to a value type. The memory retained by a task execution
+/// is split between the cells of the task that hold a value, so this is an
+/// approximation.
+#[derive(Default, Clone, Debug)]
+pub struct ExportedValueTypeStats {
+    /// Number of cells holding a value of this type.
+    pub cells: usize,
+    /// Bytes attributed to the cells.
+    pub memory_usage: usize,
+}
+
 pub struct Stats {
     tasks: HashMap<StatsTaskType, ExportedTaskStats>,
+    value_types: HashMap<ValueTypeId, ExportedValueTypeStats>,
 }
 
 impl Default for Stats {
@@ -107,9 +124,15 @@ impl Stats {
     pub fn new() -> Self {
         Self {
             tasks: Default::default(),
+            value_types: Default::default(),
         }
     }
 
+    /// Returns the memory attributed to each value type.
+    pub fn value_types(&self) -> &HashMap<ValueTypeId, ExportedValueTypeStats> {
+        &self.value_types
+    }
+
     pub fn add(&mut self, backend: &MemoryBackend, task: &Task) {
         self.add_conditional(backend, task, |_, _| true)
     }
@@ -129,6 +152,9 @@ impl Stats {
             total_duration,
             last_duration,
             executions,
+            total_allocations,
+            memory_usage,
+            cells_by_value_type,
             root_scoped,
             child_scopes,
             active,
@@ -153,6 +179,19 @@ impl Stats {
         if let Some(executions) = executions {
             *stats.executions.get_or_insert(0) += executions;
         }
+        if let Some(total_allocations) = total_allocations {
+            *stats.total_allocations.get_or_insert_with(Default::default) += total_allocations;
+        }
+        stats.total_memory_usage += memory_usage;
+        let cells = cells_by_value_type
+            .iter()
+            .map(|(_, count)| count)
+            .sum::<usize>();
+        for (value_type, count) in cells_by_value_type {
+            let value_type_stats = self.value_types.entry(value_type).or_default();
+            value_type_stats.cells += count;
+            value_type_stats.memory_usage += memory_usage * count / cells;
+        }
         if root_scoped {
             stats.roots += 1;
         }
@@ -11,7 +11,10 @@ use std::{
     hash::Hash,
     mem::{replace, take},
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{self, AtomicU8},
+        Arc,
+    },
     time::{Duration, Instant},
 };
 
@@ -22,20 +25,22 @@ use parking_lot::{Mutex, RwLock};
 use stats::TaskStats;
 use tokio::task_local;
 use turbo_tasks::{
-    backend::{PersistentTaskType, TaskExecutionSpec},
+    backend::{CellContent, PersistentTaskType, TaskExecutionSpec},
     event::{Event, EventListener},
     get_invalidator,
     primitives::{RawVcSet, RawVcSetVc},
-    registry, CellId, Invalidator, RawVc, StatsType, TaskId, TraitTypeId, TryJoinIterExt,
-    TurboTasksBackendApi, ValueTypeId,
+    registry, CellId, InvalidationProvenance, Invalidator, KeyedCellId, RawVc, StatsType, TaskId,
+    TaskPriority, TraitTypeId, TryJoinIterExt, TurboTasksBackendApi, ValueTypeId,
 };
+use turbo_tasks_malloc::AllocationInfo;
 
 use crate::{
-    cell::Cell,
+    cell::{Cell, KeyedCell, RecomputingCell},
     count_hash_set::CountHashSet,
     gc::{to_exp_u8, GcPriority, GcStats, GcTaskState},
     memory_backend::Job,
     output::{Output, OutputContent},
+    provenance::Provenance,
     scope::{ScopeChildChangeEffect, TaskScopeId, TaskScopes},
     stats::{ReferenceType, StatsReferences, StatsTaskType},
     MemoryBackend,
@@ -55,6 +60,7 @@ macro_rules! log_scope_update {
 pub enum TaskDependency {
     TaskOutput(TaskId),
     TaskCell(TaskId, CellId),
+    TaskKeyedCell(TaskId, KeyedCellId),
     ScopeChildren(TaskScopeId),
     ScopeCollectibles(TaskScopeId, TraitTypeId),
 }
@@ -63,6 +69,10 @@ task_local! {
     /// Vc/Scopes that are read during task execution
     /// These will be stored as dependencies when the execution has finished
     pub(crate) static DEPENDENCIES_TO_TRACK: RefCell<AutoSet<TaskDependency>>;
+
+    /// The task that is currently executing, so untracked reads know who is
+    /// waiting on them.
+    pub(crate) static EXECUTING_TASK: TaskId;
 }
 
 type OnceTaskFn = Mutex<Option<Pin<Box<dyn Future<Output = Result<RawVc>> + Send + 'static>>>>;
@@ -93,6 +103,12 @@ enum TaskType {
     /// applied.
     Once(Box<OnceTaskFn>),
 
+    // Note: double boxed to reduce TaskType size
+    /// A single root task execution that observes a consistent snapshot. It
+    /// tracks dependencies and re-executes when they change before the
+    /// execution has finished. It won't become dirty after that.
+    StronglyConsistentOnce(Box<NativeTaskFn>),
+
     /// A task that reads all collectibles of a certain trait from a
     /// [TaskScope]. It will do that by recursively calling
     /// ReadScopeCollectibles on child scopes, so that results by scope are
@@ -120,7 +136,7 @@ impl TaskTypeForDescription {
     fn from(task_type: &TaskType) -> Self {
         match task_type {
             TaskType::Root(..) => Self::Root,
-            TaskType::Once(..) => Self::Once,
+            TaskType::Once(..) | TaskType::StronglyConsistentOnce(..) => Self::Once,
             TaskType::ReadTaskCollectibles(box ReadTaskCollectiblesTaskType {
                 trait_type, ..
             }) => Self::ReadTaskCollectibles(*trait_type),
@@ -138,6 +154,7 @@ impl Debug for TaskType {
         match self {
             Self::Root(..) => f.debug_tuple("Root").finish(),
             Self::Once(..) => f.debug_tuple("Once").finish(),
+            Self::StronglyConsistentOnce(..) => f.debug_tuple("StronglyConsistentOnce").finish(),
             Self::ReadScopeCollectibles(box ReadScopeCollectiblesTaskType {
                 scope,
                 trait_type,
@@ -161,6 +178,7 @@ impl Display for TaskType {
         match self {
             Self::Root(..) => f.debug_tuple("Root").finish(),
             Self::Once(..) => f.debug_tuple("Once").finish(),
+            Self::StronglyConsistentOnce(..) => f.debug_tuple("StronglyConsistentOnce").finish(),
             Self::ReadTaskCollectibles(..) => f.debug_tuple("ReadTaskCollectibles").finish(),
             Self::ReadScopeCollectibles(..) => f.debug_tuple("ReadScopeCollectibles").finish(),
             Self::Persistent(ty) => Display::fmt(ty, f),
@@ -185,6 +203,8 @@ pub struct Task {
     /// The mutable state of the task
     /// Unset state is equal to a Dirty task that has not been executed yet
     state: RwLock<TaskMetaState>,
+    /// The index of the [TaskPriority], inherited from parent tasks
+    priority: AtomicU8,
 }
 
 impl Debug for Task {
@@ -233,6 +253,12 @@ struct TaskState {
 
     output: Output,
     cells: AutoMap<ValueTypeId, Vec<Cell>, BuildNoHashHasher<ValueTypeId>>,
+    keyed_cells: AutoMap<KeyedCellId, KeyedCell>,
+
+    /// Why the task has been invalidated, until the next execution starts
+    pending_invalidation: Option<Arc<Provenance>>,
+    /// Why the latest execution happened
+    execution_cause: Option<Arc<Provenance>>,
 
     // GC state:
     gc: GcTaskState,
@@ -242,6 +268,23 @@ struct TaskState {
 }
 
 impl TaskState {
+    /// Returns the number of cells that hold a value, per value type.
+    fn cells_by_value_type(&self) -> Vec<(ValueTypeId, usize)> {
+        let mut counts = AutoMap::<ValueTypeId, usize, BuildNoHashHasher<ValueTypeId>>::default();
+        for (&ty, cells) in self.cells.iter() {
+            let count = cells.iter().filter(|cell| cell.has_value()).count();
+            if count > 0 {
+                *counts.entry(ty).or_default() += count;
+            }
+        }
+        for cell in self.keyed_cells.values() {
+            if let Some(ty) = cell.value_type() {
+                *counts.entry(ty).or_default() += 1;
+            }
+        }
+        counts.into_iter().collect()
+    }
+
     fn new(
         description: impl Fn() -> String + Send + Sync + 'static,
         stats_type: StatsType,
@@ -257,6 +300,9 @@ impl TaskState {
             output: Default::default(),
             prepared_type: PrepareTaskType::None,
             cells: Default::default(),
+            keyed_cells: Default::default(),
+            pending_invalidation: None,
+            execution_cause: None,
             gc: Default::default(),
             stats: TaskStats::new(stats_type),
             #[cfg(feature = "track_wait_dependencies")]
@@ -280,6 +326,9 @@ impl TaskState {
             output: Default::default(),
             prepared_type: PrepareTaskType::None,
             cells: Default::default(),
+            keyed_cells: Default::default(),
+            pending_invalidation: None,
+            execution_cause: None,
             gc: Default::default(),
             stats: TaskStats::new(stats_type),
             #[cfg(feature = "track_wait_dependencies")]
@@ -303,6 +352,9 @@ impl TaskState {
             output: Default::default(),
             prepared_type: PrepareTaskType::None,
             cells: Default::default(),
+            keyed_cells: Default::default(),
+            pending_invalidation: None,
+            execution_cause: None,
             gc: Default::default(),
             stats: TaskStats::new(stats_type),
             #[cfg(feature = "track_wait_dependencies")]
@@ -334,6 +386,9 @@ impl PartialTaskState {
             prepared_type: PrepareTaskType::None,
             output: Default::default(),
             cells: Default::default(),
+            keyed_cells: Default::default(),
+            pending_invalidation: None,
+            execution_cause: None,
             gc: Default::default(),
             stats: TaskStats::new(self.stats_type),
         }
@@ -367,6 +422,9 @@ impl UnloadedTaskState {
             prepared_type: PrepareTaskType::None,
             output: Default::default(),
             cells: Default::default(),
+            keyed_cells: Default::default(),
+            pending_invalidation: None,
+            execution_cause: None,
             gc: Default::default(),
             stats: TaskStats::new(self.stats_type),
         }
@@ -486,10 +544,14 @@ enum TaskStateType {
     InProgress {
         event: Event,
         count_as_finished: bool,
+        /// Notified when the execution is cancelled by an invalidation.
+        cancellation: Event,
     },
 
     /// Invalid execution is happening
     ///
+    /// The execution is considered cancelled, its result will be discarded
+    ///
     /// on finish this will move to Dirty or Scheduled depending on active flag
     InProgressDirty { event: Event },
 }
@@ -528,6 +590,7 @@ impl Task {
         Self {
             id,
             ty,
+            priority: AtomicU8::new(TaskPriority::default().index() as u8),
             state: RwLock::new(TaskMetaState::Full(Box::new(TaskState::new(
                 description,
                 stats_type,
@@ -546,6 +609,7 @@ impl Task {
         Self {
             id,
             ty,
+            priority: AtomicU8::new(TaskPriority::default().index() as u8),
             state: RwLock::new(TaskMetaState::Full(Box::new(
                 TaskState::new_scheduled_in_scope(description, scope, stats_type),
             ))),
@@ -563,6 +627,25 @@ impl Task {
         Self {
             id,
             ty,
+            priority: AtomicU8::new(TaskPriority::default().index() as u8),
+            state: RwLock::new(TaskMetaState::Full(Box::new(
+                TaskState::new_scheduled_in_scope(description, scope, stats_type),
+            ))),
+        }
+    }
+
+    pub(crate) fn new_strongly_consistent_once(
+        id: TaskId,
+        scope: TaskScopeId,
+        functor: impl Fn() -> NativeTaskFuture + Sync + Send + 'static,
+        stats_type: StatsType,
+    ) -> Self {
+        let ty = TaskType::StronglyConsistentOnce(Box::new(Box::new(functor)));
+        let description = Self::get_event_description_static(id, &ty);
+        Self {
+            id,
+            ty,
+            priority: AtomicU8::new(TaskPriority::default().index() as u8),
             state: RwLock::new(TaskMetaState::Full(Box::new(
                 TaskState::new_scheduled_in_scope(description, scope, stats_type),
             ))),
@@ -583,6 +666,7 @@ impl Task {
         Self {
             id,
             ty,
+            priority: AtomicU8::new(TaskPriority::default().index() as u8),
             state: RwLock::new(TaskMetaState::Full(Box::new(TaskState::new(
                 description,
                 stats_type,
@@ -605,6 +689,7 @@ impl Task {
         Self {
             id,
             ty,
+            priority: AtomicU8::new(TaskPriority::default().index() as u8),
             state: RwLock::new(TaskMetaState::Full(Box::new(TaskState::new_root_scoped(
                 description,
                 scope,
@@ -620,6 +705,7 @@ impl Task {
             TaskType::ReadScopeCollectibles(..) => true,
             TaskType::Root(_) => false,
             TaskType::Once(_) => false,
+            TaskType::StronglyConsistentOnce(_) => false,
         }
     }
 
@@ -705,6 +791,13 @@ impl Task {
                     });
                 });
             }
+            TaskDependency::TaskKeyedCell(task, id) => {
+                backend.with_task(task, |task| {
+                    task.with_keyed_cell_mut_if_available(id, |cell| {
+                        cell.remove_dependent_task(reader);
+                    });
+                });
+            }
             TaskDependency::ScopeChildren(scope) => backend.with_scope(scope, |scope| {
                 scope.remove_dependent_task(reader);
             }),
@@ -794,11 +887,16 @@ impl Task {
                 return false;
             }
             Scheduled { ref mut event } => {
+                let description = self.get_event_description();
                 state.state_type = InProgress {
                     event: event.take(),
                     count_as_finished: false,
+                    cancellation: Event::new(move || {
+                        format!("TaskState({})::cancellation", description())
+                    }),
                 };
                 state.stats.increment_executions();
+                state.execution_cause = state.pending_invalidation.take();
                 // TODO we need to reconsider the approach of doing scope changes in background
                 // since they affect collectibles and need to be computed eagerly to allow
                 // strongly_consistent to work properly.
@@ -837,7 +935,7 @@ impl Task {
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) -> Pin<Box<dyn Future<Output = Result<RawVc>> + Send>> {
         match &self.ty {
-            TaskType::Root(bound_fn) => {
+            TaskType::Root(bound_fn) | TaskType::StronglyConsistentOnce(bound_fn) => {
                 drop(state);
                 bound_fn()
             }
@@ -931,6 +1029,30 @@ impl Task {
         }
     }
 
+    /// Returns true when the current execution of the task has been
+    /// invalidated and its result will be discarded.
+    pub(crate) fn is_execution_cancelled(&self) -> bool {
+        let TaskMetaStateReadGuard::Full(state) = self.state() else {
+            return false;
+        };
+        matches!(state.state_type, InProgressDirty { .. })
+    }
+
+    /// Returns a listener that is notified when the current execution of the
+    /// task is cancelled. `Ok(())` means it has already been cancelled.
+    pub(crate) fn listen_to_cancellation(&self) -> Result<(), Option<EventListener>> {
+        let TaskMetaStateReadGuard::Full(state) = self.state() else {
+            return Err(None);
+        };
+        match state.state_type {
+            InProgress {
+                ref cancellation, ..
+            } => Err(Some(cancellation.listen())),
+            InProgressDirty { .. } => Ok(()),
+            _ => Err(None),
+        }
+    }
+
     pub(crate) fn execution_result(
         &self,
         result: Result<Result<RawVc>, Option<Cow<'static, str>>>,
@@ -938,6 +1060,14 @@ impl Task {
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) {
         let mut state = self.full_state_mut();
+        if let InProgress { .. } = state.state_type {
+            // Keys that haven't been assigned by this execution are removed. This
+            // needs to happen before the execution completes, so readers are
+            // notified together with the other changes of this execution.
+            state
+                .keyed_cells
+                .retain(|_, cell| cell.execution_completed(turbo_tasks));
+        }
         match state.state_type {
             InProgress { .. } => match result {
                 Ok(Ok(result)) => {
@@ -985,6 +1115,7 @@ impl Task {
         &self,
         duration: Duration,
         instant: Instant,
+        allocations: AllocationInfo,
         stateful: bool,
         backend: &MemoryBackend,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
@@ -994,13 +1125,16 @@ impl Task {
         {
             let mut state = self.full_state_mut();
 
-            state
-                .stats
-                .register_execution(duration, turbo_tasks.program_duration_until(instant));
+            state.stats.register_execution(
+                duration,
+                turbo_tasks.program_duration_until(instant),
+                allocations,
+            );
             match state.state_type {
                 InProgress {
                     ref mut event,
                     count_as_finished,
+                    ..
                 } => {
                     let event = event.take();
                     let mut dependencies = take(&mut dependencies);
@@ -1010,6 +1144,10 @@ impl Task {
                         cells.shrink_to_fit();
                     }
                     state.cells.shrink_to_fit();
+                    for cell in state.keyed_cells.values_mut() {
+                        cell.shrink_to_fit();
+                    }
+                    state.keyed_cells.shrink_to_fit();
                     state.stateful = stateful;
                     state.state_type = Done { dependencies };
                     if !count_as_finished {
@@ -1023,6 +1161,9 @@ impl Task {
                 }
                 InProgressDirty { ref mut event } => {
                     let event = event.take();
+                    for cell in state.keyed_cells.values_mut() {
+                        cell.execution_invalidated();
+                    }
                     state.state_type = Scheduled { event };
                     schedule_task = true;
                 }
@@ -1038,8 +1179,13 @@ impl Task {
             self.clear_dependencies(dependencies, backend);
         }
 
-        if let TaskType::Once(_) = self.ty {
-            self.remove_root_or_initial_scope(backend, turbo_tasks);
+        match self.ty {
+            TaskType::Once(_) => self.remove_root_or_initial_scope(backend, turbo_tasks),
+            // Executed again as it has been invalidated during the execution
+            TaskType::StronglyConsistentOnce(_) if !schedule_task => {
+                self.remove_root_or_initial_scope(backend, turbo_tasks)
+            }
+            _ => {}
         }
 
         schedule_task
@@ -1103,15 +1249,17 @@ impl Task {
 
     fn make_dirty(
         &self,
+        provenance: Option<Arc<Provenance>>,
         backend: &MemoryBackend,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) {
-        self.make_dirty_internal(false, backend, turbo_tasks);
+        self.make_dirty_internal(false, provenance, backend, turbo_tasks);
     }
 
     fn make_dirty_internal(
         &self,
         force_schedule: bool,
+        provenance: Option<Arc<Provenance>>,
         backend: &MemoryBackend,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) {
@@ -1126,6 +1274,16 @@ impl Task {
             self.state_mut()
         };
         if let TaskMetaStateWriteGuard::Full(mut state) = state {
+            if let (TaskType::StronglyConsistentOnce(_), Done { .. }) =
+                (&self.ty, &state.state_type)
+            {
+                // the consistent execution has finished, it won't become dirty anymore
+                return;
+            }
+            if state.pending_invalidation.is_none() {
+                // keep the first invalidation when the task is already dirty
+                state.pending_invalidation = provenance;
+            }
             let mut clear_dependencies = AutoSet::default();
 
             match state.state_type {
@@ -1142,7 +1300,7 @@ impl Task {
                             }),
                         };
                         drop(state);
-                        turbo_tasks.schedule(self.id);
+                        self.schedule(backend, turbo_tasks);
                     } else {
                         // already dirty
                         drop(state);
@@ -1167,7 +1325,7 @@ impl Task {
                         if cfg!(feature = "print_task_invalidation") {
                             println!("invalidated Task {{ id: {}, name: {} }}", *self.id, self.ty);
                         }
-                        turbo_tasks.schedule(self.id);
+                        self.schedule(backend, turbo_tasks);
                     } else {
                         state.state_type = Dirty {
                             event: Event::new(move || {
@@ -1180,8 +1338,10 @@ impl Task {
                 InProgress {
                     ref mut event,
                     count_as_finished,
+                    ref cancellation,
                 } => {
                     let event = event.take();
+                    cancellation.notify(usize::MAX);
                     if count_as_finished {
                         for scope in state.scopes.iter() {
                             backend.with_scope(scope, |scope| {
@@ -1216,7 +1376,7 @@ impl Task {
                 })
             }
             drop(state);
-            turbo_tasks.schedule(self.id);
+            self.schedule(backend, turbo_tasks);
         }
     }
 
@@ -1293,7 +1453,7 @@ impl Task {
                 drop(state);
 
                 if schedule_self {
-                    turbo_tasks.schedule(self.id);
+                    self.schedule(backend, turbo_tasks);
                 }
             }
         }
@@ -1756,7 +1916,7 @@ impl Task {
                 // I think that will never happen since it should already be scheduled by the
                 // old scopes. Anyway let just do it to be safe:
                 if schedule_self {
-                    turbo_tasks.schedule(self.id);
+                    self.schedule(backend, turbo_tasks);
                 }
 
                 // Remove children from old scopes
@@ -1800,10 +1960,33 @@ impl Task {
     /// active it will be scheduled for execution.
     pub(crate) fn invalidate(
         &self,
+        provenance: Arc<Provenance>,
         backend: &MemoryBackend,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) {
-        self.make_dirty(backend, turbo_tasks)
+        self.make_dirty(Some(provenance), backend, turbo_tasks)
+    }
+
+    /// Returns the provenance for tasks that are invalidated by the current
+    /// execution of the [Task].
+    pub(crate) fn dependency_provenance(&self) -> Arc<Provenance> {
+        let cause = if let TaskMetaStateReadGuard::Full(state) = self.state() {
+            state.execution_cause.clone()
+        } else {
+            None
+        };
+        Arc::new(Provenance::dependency(self.id, cause))
+    }
+
+    /// Returns why the latest execution of the [Task] happened.
+    pub(crate) fn get_invalidation_provenance(&self) -> Option<InvalidationProvenance> {
+        let TaskMetaStateReadGuard::Full(state) = self.state() else {
+            return None;
+        };
+        state
+            .execution_cause
+            .as_ref()
+            .map(|cause| cause.resolve(self.id))
     }
 
     /// Called when the task need to be recomputed because a gc'ed cell was
@@ -1813,7 +1996,7 @@ impl Task {
         backend: &MemoryBackend,
         turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
     ) {
-        self.make_dirty_internal(true, backend, turbo_tasks)
+        self.make_dirty_internal(true, None, backend, turbo_tasks)
     }
 
     /// Access to the output cell.
@@ -1865,6 +2048,77 @@ impl Task {
         }
     }
 
+    /// Reads a keyed cell. Registers the reader as dependent task when given.
+    /// Missing keys of a task that hasn't been executed since it became dirty
+    /// (e.g. after unloading) are only known after a recomputation.
+    pub(crate) fn read_keyed_cell(
+        &self,
+        id: KeyedCellId,
+        reader: Option<TaskId>,
+        note: impl Fn() -> String + Sync + Send + 'static,
+    ) -> Result<CellContent, RecomputingCell> {
+        let mut state = self.full_state_mut();
+        if let Some(cell) = state.keyed_cells.get_mut(&id) {
+            return Ok(cell.read_content(reader));
+        }
+        match state.state_type {
+            Dirty { ref event } => Err(RecomputingCell {
+                listener: event.listen_with_note(note),
+                schedule: true,
+            }),
+            Scheduled { ref event } => Err(RecomputingCell {
+                listener: event.listen_with_note(note),
+                schedule: false,
+            }),
+            _ => {
+                let cell = state.keyed_cells.entry(id).or_default();
+                Ok(cell.read_content(reader))
+            }
+        }
+    }
+
+    /// Access to a keyed cell.
+    pub(crate) fn with_keyed_cell_mut<T>(
+        &self,
+        id: KeyedCellId,
+        func: impl FnOnce(&mut KeyedCell) -> T,
+    ) -> T {
+        let mut state = self.full_state_mut();
+        func(state.keyed_cells.entry(id).or_default())
+    }
+
+    /// Access to a keyed cell.
+    pub(crate) fn with_keyed_cell_mut_if_available<T>(
+        &self,
+        id: KeyedCellId,
+        func: impl FnOnce(&mut KeyedCell) -> T,
+    ) -> Option<T> {
+        self.state_mut()
+            .as_full_mut()
+            .and_then(|state| state.keyed_cells.get_mut(&id))
+            .map(func)
+    }
+
+    pub(crate) fn priority(&self) -> TaskPriority {
+        TaskPriority::from_index(self.priority.load(atomic::Ordering::Relaxed) as usize)
+    }
+
+    /// Raises the priority of the task. Returns true when it has been changed.
+    pub(crate) fn raise_priority(&self, priority: TaskPriority) -> bool {
+        let old = self
+            .priority
+            .fetch_max(priority.index() as u8, atomic::Ordering::Relaxed);
+        (old as usize) < priority.index()
+    }
+
+    fn schedule(
+        &self,
+        backend: &MemoryBackend,
+        turbo_tasks: &dyn TurboTasksBackendApi<MemoryBackend>,
+    ) {
+        backend.schedule(self.id, self.priority(), turbo_tasks);
+    }
+
     /// For testing purposes
     pub fn reset_executions(&self) {
         if let TaskMetaStateWriteGuard::Full(mut state) = self.state_mut() {
@@ -1889,19 +2143,24 @@ impl Task {
     pub fn get_stats_info(&self, backend: &MemoryBackend) -> TaskStatsInfo {
         match self.state() {
             TaskMetaStateReadGuard::Full(state) => {
-                let (total_duration, last_duration, executions) = match &state.stats {
-                    TaskStats::Essential(stats) => (None, stats.last_duration(), None),
-                    TaskStats::Full(stats) => (
-                        Some(stats.total_duration()),
-                        stats.last_duration(),
-                        Some(stats.executions()),
-                    ),
-                };
+                let (total_duration, last_duration, executions, total_allocations) =
+                    match &state.stats {
+                        TaskStats::Essential(stats) => (None, stats.last_duration(), None, None),
+                        TaskStats::Full(stats) => (
+                            Some(stats.total_duration()),
+                            stats.last_duration(),
+                            Some(stats.executions()),
+                            Some(stats.total_allocations()),
+                        ),
+                    };
 
                 TaskStatsInfo {
                     total_duration,
                     last_duration,
                     executions,
+                    total_allocations,
+                    memory_usage: state.stats.last_memory_usage(),
+                    cells_by_value_type: state.cells_by_value_type(),
                     root_scoped: matches!(state.scopes, TaskScopes::Root(_)),
                     child_scopes: match state.scopes {
                         TaskScopes::Root(_) => 1,
@@ -1917,6 +2176,9 @@ impl Task {
                 total_duration: None,
                 last_duration: Duration::ZERO,
                 executions: None,
+                total_allocations: None,
+                memory_usage: 0,
+                cells_by_value_type: Vec::new(),
                 root_scoped: false,
                 child_scopes: if let TaskScopes::Inner(ref set, _) = state.scopes {
                     set.len()
@@ -1930,6 +2192,9 @@ impl Task {
                 total_duration: None,
                 last_duration: Duration::ZERO,
                 executions: None,
+                total_allocations: None,
+                memory_usage: 0,
+                cells_by_value_type: Vec::new(),
                 root_scoped: false,
                 child_scopes: 0,
                 active: false,
@@ -1941,7 +2206,7 @@ impl Task {
     pub fn get_stats_type(self: &Task) -> StatsTaskType {
         match &self.ty {
             TaskType::Root(_) => StatsTaskType::Root(self.id),
-            TaskType::Once(_) => StatsTaskType::Once(self.id),
+            TaskType::Once(_) | TaskType::StronglyConsistentOnce(_) => StatsTaskType::Once(self.id),
             TaskType::ReadTaskCollectibles(box ReadTaskCollectiblesTaskType {
                 trait_type, ..
             }) => StatsTaskType::ReadCollectibles(*trait_type),
@@ -1969,7 +2234,9 @@ impl Task {
             if let Done { ref dependencies } = state.state_type {
                 for dep in dependencies.iter() {
                     match dep {
-                        TaskDependency::TaskOutput(task) | TaskDependency::TaskCell(task, _) => {
+                        TaskDependency::TaskOutput(task)
+                        | TaskDependency::TaskCell(task, _)
+                        | TaskDependency::TaskKeyedCell(task, _) => {
                             refs.push((ReferenceType::Dependency, *task))
                         }
                         TaskDependency::ScopeChildren(scope)
@@ -2160,7 +2427,7 @@ impl Task {
                 Ok(Ok(result))
             }
             Dirty { ref mut event } => {
-                turbo_tasks.schedule(self.id);
+                self.schedule(backend, turbo_tasks);
                 let event = event.take();
                 let listener = event.listen_with_note(note);
                 state.state_type = Scheduled { event };
@@ -2448,15 +2715,18 @@ impl Task {
                         .saturating_sub(state.stats.last_execution_relative_to_start()))
                     .as_secs(),
                 );
+                let memory_usage = Reverse(to_exp_u8(state.stats.last_memory_usage() as u64));
 
                 let min_prio_that_needs_total_duration = if active {
                     GcPriority::EmptyCells {
                         total_compute_duration: to_exp_u8(last_duration.as_millis() as u64),
+                        memory_usage,
                         age: Reverse(age),
                     }
                 } else {
                     GcPriority::InactiveUnload {
                         total_compute_duration: to_exp_u8(last_duration.as_millis() as u64),
+                        memory_usage,
                         age: Reverse(age),
                     }
                 };
@@ -2494,6 +2764,7 @@ impl Task {
                                 total_compute_duration: to_exp_u8(
                                     Duration::from(compute_duration).as_millis() as u64,
                                 ),
+                                memory_usage,
                                 age: Reverse(age),
                             });
                         } else {
@@ -2506,6 +2777,7 @@ impl Task {
                             total_compute_duration: to_exp_u8(
                                 Duration::from(compute_duration).as_millis() as u64,
                             ),
+                            memory_usage,
                             age: Reverse(age),
                         });
                     } else {
@@ -2514,6 +2786,7 @@ impl Task {
                             total_compute_duration: to_exp_u8(
                                 Duration::from(compute_duration).as_millis() as u64,
                             ),
+                            memory_usage,
                             age: Reverse(age),
                         });
                     }
@@ -2565,6 +2838,7 @@ impl Task {
                             new_priority = GcPriority::InactiveUnload {
                                 age: Reverse(age),
                                 total_compute_duration: total_compute_duration_u8,
+                                memory_usage,
                             };
                             if new_priority <= max_priority {
                                 // Unload task
@@ -2575,6 +2849,7 @@ impl Task {
                                     // unloading will fail if the task go active again
                                     return Some(GcPriority::EmptyCells {
                                         total_compute_duration: total_compute_duration_u8,
+                                        memory_usage,
                                         age: Reverse(age),
                                     });
                                 }
@@ -2586,6 +2861,7 @@ impl Task {
                         if active && (has_unused_cells || has_used_cells) {
                             new_priority = GcPriority::EmptyCells {
                                 total_compute_duration: total_compute_duration_u8,
+                                memory_usage,
                                 age: Reverse(age),
                             };
                             if new_priority <= max_priority {
@@ -2621,6 +2897,7 @@ impl Task {
                                 stats.empty_unused += 1;
                                 return Some(GcPriority::EmptyCells {
                                     total_compute_duration: total_compute_duration_u8,
+                                    memory_usage,
                                     age: Reverse(age),
                                 });
                             }
@@ -2738,6 +3015,7 @@ impl Task {
         let TaskState {
             children,
             cells,
+            keyed_cells,
             output,
             collectibles,
             scopes,
@@ -2750,6 +3028,9 @@ impl Task {
             state_type: _,
             // can be dropped as only gc meta info
             gc: _,
+            // can be dropped as the task is no longer invalidated or executed
+            pending_invalidation: _,
+            execution_cause: _,
         } = old_state.into_full().unwrap();
 
         // Remove all children, as they will be added again when this task is executed
@@ -2795,6 +3076,9 @@ impl Task {
                 cell.gc_drop(turbo_tasks);
             }
         }
+        for cell in keyed_cells.into_values() {
+            cell.gc_drop(turbo_tasks);
+        }
         output.gc_drop(turbo_tasks);
 
         // We can clear the dependencies as we are already marked as dirty
@@ -2959,6 +3243,12 @@ pub struct TaskStatsInfo {
     pub total_duration: Option<Duration>,
     pub last_duration: Duration,
     pub executions: Option<u32>,
+    /// Allocations of all executions, only available with full stats.
+    pub total_allocations: Option<AllocationInfo>,
+    /// Bytes retained by the last execution.
+    pub memory_usage: usize,
+    /// The number of cells holding a value, per value type.
+    pub cells_by_value_type: Vec<(ValueTypeId, usize)>,
     pub root_scoped: bool,
     pub child_scopes: usize,
     pub active: bool,
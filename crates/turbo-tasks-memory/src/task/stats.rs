@@ -1,9 +1,10 @@
 use std::time::Duration;
 
 use turbo_tasks::{small_duration::SmallDuration, StatsType};
+use turbo_tasks_malloc::AllocationInfo;
 
-/// Keeps track of the number of times a task has been executed, and its
-/// duration.
+/// Keeps track of the number of times a task has been executed, its duration
+/// and the memory it allocated.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum TaskStats {
     Essential(TaskStatsEssential),
@@ -35,16 +36,24 @@ impl TaskStats {
         }
     }
 
-    /// Registers a task duration.
-    pub fn register_execution(&mut self, duration: Duration, duration_since_start: Duration) {
+    /// Registers a task duration and the allocations of the execution.
+    pub fn register_execution(
+        &mut self,
+        duration: Duration,
+        duration_since_start: Duration,
+        allocations: AllocationInfo,
+    ) {
         match self {
             Self::Full(stats) => {
                 stats.total_duration += duration;
                 stats.last_duration = duration;
+                stats.total_allocations += allocations;
+                stats.last_memory_usage = allocations.memory_usage();
             }
             Self::Essential(stats) => {
                 stats.last_duration = duration.into();
                 stats.last_execution_relative_to_start = duration_since_start.into();
+                stats.last_memory_usage = allocations.memory_usage().try_into().unwrap_or(u32::MAX);
             }
         }
     }
@@ -56,10 +65,13 @@ impl TaskStats {
                 stats.executions = 0;
                 stats.total_duration = Duration::ZERO;
                 stats.last_duration = Duration::ZERO;
+                stats.total_allocations = AllocationInfo::default();
+                stats.last_memory_usage = 0;
             }
             Self::Essential(stats) => {
                 stats.last_duration = SmallDuration::MIN;
                 stats.last_execution_relative_to_start = SmallDuration::MIN;
+                stats.last_memory_usage = 0;
             }
         }
     }
@@ -80,6 +92,15 @@ impl TaskStats {
             Self::Essential(stats) => stats.last_execution_relative_to_start(),
         }
     }
+
+    /// Returns the bytes the last execution of the task allocated and didn't
+    /// deallocate. This approximates the memory held by the task's cells.
+    pub fn last_memory_usage(&self) -> usize {
+        match self {
+            Self::Full(stats) => stats.last_memory_usage(),
+            Self::Essential(stats) => stats.last_memory_usage(),
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
@@ -89,6 +110,9 @@ pub struct TaskStatsEssential {
     /// The last execution of the task relative to the start of the program,
     /// with a precision of 1 millisecond.
     last_execution_relative_to_start: SmallDuration<1_000_000>,
+    /// The bytes retained by the last execution of the task, saturated at
+    /// [u32::MAX].
+    last_memory_usage: u32,
 }
 
 impl TaskStatsEssential {
@@ -103,6 +127,11 @@ impl TaskStatsEssential {
     pub fn last_execution_relative_to_start(&self) -> Duration {
         self.last_execution_relative_to_start.into()
     }
+
+    /// Returns the bytes retained by the last execution of the task.
+    pub fn last_memory_usage(&self) -> usize {
+        self.last_memory_usage as usize
+    }
 }
 
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
@@ -116,6 +145,10 @@ pub struct TaskStatsFull {
     /// The last execution of the task relative to the start of the program,
     /// with a precision of 1 millisecond.
     last_execution_relative_to_start: SmallDuration<1_000_000>,
+    /// The allocations of all executions of the task.
+    total_allocations: AllocationInfo,
+    /// The bytes retained by the last execution of the task.
+    last_memory_usage: usize,
 }
 
 impl TaskStatsFull {
@@ -140,4 +173,14 @@ impl TaskStatsFull {
     pub fn last_execution_relative_to_start(&self) -> Duration {
         self.last_execution_relative_to_start.into()
     }
+
+    /// Returns the allocations of all executions of the task.
+    pub fn total_allocations(&self) -> AllocationInfo {
+        self.total_allocations
+    }
+
+    /// Returns the bytes retained by the last execution of the task.
+    pub fn last_memory_usage(&self) -> usize {
+        self.last_memory_usage
+    }
 }
@@ -0,0 +1,69 @@
+use turbo_tasks::{InvalidationProvenance, TaskId};
+
+use super::*;
+
+pub fn wrap_html(list_html: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset=\"utf-8\">
+  <title>turbo-tasks invalidations</title>
+  <style>
+    body {{ margin: 0; font-family: monospace; }}
+    ul {{ list-style: none; margin: 0; padding: 1rem; }}
+    li {{ padding: 0.2rem 0; }}
+  </style>
+</head>
+<body>
+  {list_html}
+</body>
+</html>"#
+    )
+}
+
+/// Explains a single execution, e.g. "task X re-ran because file Y changed
+/// via A → B → X".
+pub fn describe_provenance(
+    task: TaskId,
+    provenance: &InvalidationProvenance,
+    describe_task: impl Fn(TaskId) -> String,
+) -> String {
+    let mut out = format!("task {} re-ran because ", describe_task(task));
+    match (&provenance.reason, provenance.path.first()) {
+        (Some(reason), _) => write!(out, "{}", reason).unwrap(),
+        (None, Some(&origin)) if origin != task => {
+            write!(out, "{} was invalidated", describe_task(origin)).unwrap()
+        }
+        (None, _) => out.push_str("it was invalidated"),
+    }
+    if provenance.path.len() > 1 {
+        out.push_str(" via ");
+        for (i, &task) in provenance.path.iter().enumerate() {
+            if i > 0 {
+                out.push_str(" → ");
+            }
+            out.push_str(&describe_task(task));
+        }
+    }
+    out
+}
+
+/// Creates a list of all executions that were caused by an invalidation.
+pub fn create_list(
+    executions: impl IntoIterator<Item = (TaskId, InvalidationProvenance)>,
+    describe_task: impl Fn(TaskId) -> String,
+) -> String {
+    let mut out = String::new();
+    out += "<ul>\n";
+    for (task, provenance) in executions {
+        writeln!(
+            out,
+            "<li>{}</li>",
+            escape_html(&describe_provenance(task, &provenance, &describe_task))
+        )
+        .unwrap();
+    }
+    out += "</ul>";
+    out
+}
@@ -1,4 +1,5 @@
 pub mod graph;
+pub mod invalidations;
 pub mod table;
 
 use std::{
@@ -34,6 +35,8 @@ struct MaxValues {
     pub total_update_duration: Duration,
     pub avg_duration: Option<Duration>,
     pub max_duration: Duration,
+    pub total_allocations: Option<usize>,
+    pub total_memory_usage: usize,
     pub count: usize,
     pub active_count: usize,
     pub unloaded_count: usize,
@@ -80,6 +83,8 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
     let mut max_total_update_duration = Duration::ZERO;
     let mut max_avg_duration = None;
     let mut max_max_duration = Duration::ZERO;
+    let mut max_total_allocations = None;
+    let mut max_total_memory_usage = 0;
     let mut max_count = 0;
     let mut max_active_count = 0;
     let mut max_unloaded_count = 0;
@@ -106,6 +111,14 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
             }
         }
         max_max_duration = max(max_max_duration, s.max_duration);
+        if let Some(total_allocations) = s.total_allocations {
+            max_total_allocations = max_total_allocations
+                .map(|max_total_allocations| {
+                    max(max_total_allocations, total_allocations.allocations)
+                })
+                .or(Some(total_allocations.allocations));
+        }
+        max_total_memory_usage = max(max_total_memory_usage, s.total_memory_usage);
         max_count = max(max_count, s.count);
         max_active_count = max(max_active_count, s.active_count);
         max_unloaded_count = max(max_unloaded_count, s.unloaded_count);
@@ -135,6 +148,8 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
             total_update_duration,
             avg_duration,
             max_duration,
+            total_allocations,
+            total_memory_usage,
             count,
             active_count,
             unloaded_count,
@@ -152,6 +167,10 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
         max_total_update_duration = max(max_total_update_duration, total_update_duration);
         max_avg_duration = max_avg_duration.zip(avg_duration).map(|(a, b)| max(a, b));
         max_max_duration = max(max_max_duration, max_duration);
+        max_total_allocations = max_total_allocations
+            .zip(total_allocations)
+            .map(|(a, b)| max(a, b));
+        max_total_memory_usage = max(max_total_memory_usage, total_memory_usage);
         max_count = max(max_count, count);
         max_active_count = max(max_active_count, active_count);
         max_unloaded_count = max(max_unloaded_count, unloaded_count);
@@ -168,6 +187,8 @@ fn get_max_values_internal(depth: u32, node: &GroupTree) -> MaxValues {
         total_update_duration: max_total_update_duration,
         avg_duration: max_avg_duration,
         max_duration: max_max_duration,
+        total_allocations: max_total_allocations,
+        total_memory_usage: max_total_memory_usage,
         count: max_count,
         active_count: max_active_count,
         unloaded_count: max_unloaded_count,
@@ -1,6 +1,11 @@
-use turbo_tasks::{util::FormatDuration, StatsType};
+use turbo_tasks::{
+    registry,
+    util::{FormatBytes, FormatDuration},
+    StatsType,
+};
 
 use super::*;
+use crate::stats::Stats;
 
 pub fn wrap_html(table_html: &str) -> String {
     format!(
@@ -50,6 +55,8 @@ pub fn create_table(root: GroupTree, stats_type: StatsType) -> String {
     out += r#"<th>total update duration</th>"#;
     out += r#"<th>avg duration</th>"#;
     out += r#"<th>max duration</th>"#;
+    out += r#"<th>memory</th>"#;
+    out += r#"<th>total allocated</th>"#;
     out += r#"<th>root scopes</th>"#;
     out += r#"<th>avg scopes</th>"#;
     out += r#"<th>avg dependencies</th>"#;
@@ -189,6 +196,32 @@ pub fn create_table(root: GroupTree, stats_type: StatsType) -> String {
             stats.max_duration.as_micros(),
             FormatDuration(stats.max_duration)
         )?;
+        // memory
+        write!(
+            out,
+            "<td bgcolor=\"{}\" data-sort=\"{}\">{}</td>",
+            as_frac_color(stats.total_memory_usage, max_values.total_memory_usage),
+            stats.total_memory_usage,
+            FormatBytes(stats.total_memory_usage)
+        )?;
+        // total allocated
+        let (total_allocations_bytes, total_allocations_label, total_allocations_color) =
+            if let Some((total_allocations, max_total_allocations)) =
+                stats.total_allocations.zip(max_values.total_allocations)
+            {
+                (
+                    format!("{}", total_allocations.allocations),
+                    FormatBytes(total_allocations.allocations).to_string(),
+                    as_frac_color(total_allocations.allocations, max_total_allocations),
+                )
+            } else {
+                (String::new(), "N/A".to_string(), "white".to_string())
+            };
+        write!(
+            out,
+            "<td bgcolor=\"{}\" data-sort=\"{}\">{}</td>",
+            total_allocations_color, total_allocations_bytes, total_allocations_label
+        )?;
         // root scopes
         write!(
             out,
@@ -265,3 +298,39 @@ pub fn create_table(root: GroupTree, stats_type: StatsType) -> String {
     out += r#"</table>"#;
     out
 }
+
+pub fn create_value_type_table(stats: &Stats) -> String {
+    let value_types = stats.value_types();
+    let max_cells = value_types.values().map(|s| s.cells).max().unwrap_or(0);
+    let max_memory_usage = value_types
+        .values()
+        .map(|s| s.memory_usage)
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    out += r#"<table class="sortable"><thead><tr>"#;
+    out += r#"<th>value type</th>"#;
+    out += r#"<th>cells</th>"#;
+    out += r#"<th>memory</th>"#;
+    out += r#"</tr></thead>"#;
+    out += r#"<tbody>"#;
+    for (&ty, stats) in value_types {
+        let name = &registry::get_value_type(ty).name;
+        write!(
+            out,
+            "<tr><td bgcolor=\"{}\">{}</td><td bgcolor=\"{}\">{}</td><td bgcolor=\"{}\" \
+             data-sort=\"{}\">{}</td></tr>",
+            as_hash_color(name),
+            escape_html(name),
+            as_frac_color(stats.cells, max_cells),
+            stats.cells,
+            as_frac_color(stats.memory_usage, max_memory_usage),
+            stats.memory_usage,
+            FormatBytes(stats.memory_usage)
+        )
+        .unwrap();
+    }
+    out += r#"</tbody>"#;
+    out += r#"</table>"#;
+    out
+}
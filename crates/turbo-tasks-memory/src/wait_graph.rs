@@ -0,0 +1,99 @@
+use std::{fmt::Write, hash::BuildHasherDefault};
+
+use auto_hash_map::{AutoMap, AutoSet};
+use dashmap::DashMap;
+use nohash_hasher::BuildNoHashHasher;
+use rustc_hash::FxHasher;
+use turbo_tasks::TaskId;
+
+/// Keeps track of executing tasks that wait on the output of other tasks, to
+/// detect cycles that would otherwise never resolve.
+///
+/// Edges are only added when a read has to wait, and removed once the waiting
+/// is done or the execution of the waiting task completes. A reader can wait on
+/// the same task multiple times concurrently, so edges are counted.
+#[derive(Default)]
+pub(crate) struct WaitGraph {
+    edges: DashMap<
+        TaskId,
+        AutoMap<TaskId, usize, BuildNoHashHasher<TaskId>>,
+        BuildHasherDefault<FxHasher>,
+    >,
+}
+
+impl WaitGraph {
+    /// Records that `reader` waits on `task`. When this closes a cycle, the
+    /// edge is not recorded and the cycle is returned, starting and ending
+    /// with `reader`.
+    pub(crate) fn start_waiting(&self, reader: TaskId, task: TaskId) -> Option<Vec<TaskId>> {
+        *self
+            .edges
+            .entry(reader)
+            .or_default()
+            .entry(task)
+            .or_default() += 1;
+        let path = self.find_path(task, reader)?;
+        self.stop_waiting(reader, task);
+        let mut cycle = Vec::with_capacity(path.len() + 1);
+        cycle.push(reader);
+        cycle.extend(path);
+        Some(cycle)
+    }
+
+    /// Removes one edge from `reader` to `task`, if any.
+    pub(crate) fn stop_waiting(&self, reader: TaskId, task: TaskId) {
+        if let Some(mut waits) = self.edges.get_mut(&reader) {
+            if let Some(count) = waits.get_mut(&task) {
+                *count -= 1;
+                if *count == 0 {
+                    waits.remove(&task);
+                }
+            }
+            if waits.is_empty() {
+                drop(waits);
+                self.edges.remove_if(&reader, |_, waits| waits.is_empty());
+            }
+        }
+    }
+
+    /// Removes all edges from `reader`, e.g. when its execution completed.
+    pub(crate) fn execution_completed(&self, reader: TaskId) {
+        self.edges.remove(&reader);
+    }
+
+    /// Depth-first search for a path of waiting tasks from `from` to `to`.
+    /// The returned path starts with `from` and ends with `to`.
+    fn find_path(&self, from: TaskId, to: TaskId) -> Option<Vec<TaskId>> {
+        let mut visited = AutoSet::<TaskId, BuildNoHashHasher<TaskId>>::default();
+        // Stack of (task, index in path) to backtrack the path
+        let mut stack = vec![(from, 0)];
+        let mut path = Vec::new();
+        while let Some((task, depth)) = stack.pop() {
+            path.truncate(depth);
+            path.push(task);
+            if task == to {
+                return Some(path);
+            }
+            if !visited.insert(task) {
+                continue;
+            }
+            // Copy the edges to avoid holding the lock while visiting other tasks
+            let next = match self.edges.get(&task) {
+                Some(waits) => waits.iter().map(|(&task, _)| task).collect::<Vec<_>>(),
+                None => continue,
+            };
+            stack.extend(next.into_iter().map(|next| (next, depth + 1)));
+        }
+        None
+    }
+}
+
+/// Formats a cycle of waiting tasks in a readable way, one task per line.
+pub(crate) fn format_cycle(cycle: &[TaskId], describe: impl Fn(TaskId) -> String) -> String {
+    let mut message = String::from("Cycle detected, the task would wait on itself:");
+    for (i, &task) in cycle.iter().enumerate() {
+        let prefix = if i == 0 { "" } else { "waits on " };
+        write!(message, "\n  {prefix}{}", describe(task)).unwrap();
+    }
+    message
+}
@@ -0,0 +1,81 @@
+#![feature(min_specialization)]
+
+use std::{
+    future::pending,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use turbo_tasks::{cancellable, get_invalidator, is_cancelled, Invalidator};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+
+#[tokio::test]
+async fn cancels_stale_execution() {
+    run! {
+        let input = InputVc::cell(Input { value: Mutex::new((0, None)) });
+        let output = slow(input);
+
+        let (value, ()) = tokio::join!(output.strongly_consistent(), async {
+            while !STARTED.load(Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            input.await.unwrap().incr();
+        });
+
+        assert_eq!(*value?, 1);
+        assert!(CANCELLED.load(Ordering::SeqCst));
+    }
+}
+
+#[turbo_tasks::value(transparent)]
+struct InputValue(usize);
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Input {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    value: Mutex<(usize, Option<Invalidator>)>,
+}
+
+impl Input {
+    fn incr(&self) {
+        let mut lock = self.value.lock().unwrap();
+        lock.0 += 1;
+        if let Some(i) = lock.1.take() {
+            i.invalidate();
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl InputVc {
+    #[turbo_tasks::function]
+    async fn get_value(self) -> Result<InputValueVc> {
+        let this = self.await?;
+        let mut lock = this.value.lock().unwrap();
+        lock.1 = Some(get_invalidator());
+        Ok(InputValueVc::cell(lock.0))
+    }
+}
+
+#[turbo_tasks::function]
+async fn slow(input: InputVc) -> Result<InputValueVc> {
+    let value = *input.get_value().await?;
+    if value == 0 {
+        // The first execution never finishes on its own, it can only be
+        // cancelled by the invalidation of the input.
+        STARTED.store(true, Ordering::SeqCst);
+        let result = cancellable(pending::<()>()).await;
+        CANCELLED.store(result.is_err() && is_cancelled(), Ordering::SeqCst);
+        result?;
+    }
+    Ok(InputValueVc::cell(value))
+}
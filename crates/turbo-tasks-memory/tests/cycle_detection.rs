@@ -0,0 +1,28 @@
+#![feature(min_specialization)]
+
+use anyhow::Result;
+use turbo_tasks::primitives::U32Vc;
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+#[tokio::test]
+async fn reports_wait_cycle() {
+    run! {
+        let err = resolve_loop(1).await.unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("Cycle detected"), "{message}");
+        assert!(message.contains("resolve_loop"), "{message}");
+        assert!(message.contains("analyze_loop"), "{message}");
+    }
+}
+
+#[turbo_tasks::function]
+async fn resolve_loop(value: u32) -> Result<U32Vc> {
+    Ok(U32Vc::cell(*analyze_loop(value).await? + 1))
+}
+
+#[turbo_tasks::function]
+async fn analyze_loop(value: u32) -> Result<U32Vc> {
+    Ok(U32Vc::cell(*resolve_loop(value).await? + 1))
+}
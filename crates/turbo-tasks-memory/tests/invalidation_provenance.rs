@@ -0,0 +1,92 @@
+#![feature(min_specialization)]
+
+use std::{fmt::Display, sync::Mutex};
+
+use anyhow::Result;
+use turbo_tasks::{get_invalidator, turbo_tasks, InvalidationReason, Invalidator, RawVc, TaskId};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+#[tokio::test]
+async fn records_invalidation_chain() {
+    run! {
+        let source = SourceVc::cell(Source { value: Mutex::new((0, None)) });
+        let value = source.get_value();
+        // Resolve the input, so `double` is called directly instead of through a resolve task
+        let doubled = double(value.resolve().await?);
+
+        assert_eq!(*doubled.strongly_consistent().await?, 0);
+        assert!(turbo_tasks().get_invalidation_provenance(task_of(doubled)).is_none());
+
+        source.await?.bump();
+
+        assert_eq!(*value.strongly_consistent().await?, 1);
+        assert_eq!(*doubled.strongly_consistent().await?, 2);
+
+        let provenance = turbo_tasks()
+            .get_invalidation_provenance(task_of(value))
+            .unwrap();
+        assert_eq!(provenance.path, vec![task_of(value)]);
+        assert_eq!(provenance.reason.unwrap().to_string(), "source bumped");
+
+        let provenance = turbo_tasks()
+            .get_invalidation_provenance(task_of(doubled))
+            .unwrap();
+        assert_eq!(provenance.path, vec![task_of(value), task_of(doubled)]);
+        assert_eq!(provenance.reason.unwrap().to_string(), "source bumped");
+    }
+}
+
+fn task_of(vc: impl Into<RawVc>) -> TaskId {
+    match vc.into() {
+        RawVc::TaskOutput(task) => task,
+        RawVc::TaskCell(task, _) => task,
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct Bumped;
+
+impl Display for Bumped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "source bumped")
+    }
+}
+
+impl InvalidationReason for Bumped {}
+
+#[turbo_tasks::value(transparent)]
+struct Number(usize);
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Source {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    value: Mutex<(usize, Option<Invalidator>)>,
+}
+
+impl Source {
+    fn bump(&self) {
+        let mut lock = self.value.lock().unwrap();
+        lock.0 += 1;
+        if let Some(i) = lock.1.take() {
+            i.invalidate_with_reason(Bumped);
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceVc {
+    #[turbo_tasks::function]
+    async fn get_value(self) -> Result<NumberVc> {
+        let this = self.await?;
+        let mut lock = this.value.lock().unwrap();
+        lock.1 = Some(get_invalidator());
+        Ok(NumberVc::cell(lock.0))
+    }
+}
+
+#[turbo_tasks::function]
+async fn double(value: NumberVc) -> Result<NumberVc> {
+    Ok(NumberVc::cell(*value.await? * 2))
+}
@@ -0,0 +1,131 @@
+#![feature(min_specialization)]
+
+use std::{
+    hash::{Hash, Hasher},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use anyhow::Result;
+use turbo_tasks::{cell_keyed, get_invalidator, read_key, Invalidator, ReadRef};
+use turbo_tasks_testing::{register, run};
+
+register!();
+
+static EXECUTIONS: [AtomicUsize; 2] = [AtomicUsize::new(0), AtomicUsize::new(0)];
+
+#[tokio::test]
+async fn only_invalidates_readers_of_changed_keys() {
+    run! {
+        let source = SourceVc::cell(Source { value: Mutex::new((vec![1, 2], None)) });
+        let entries = source.entries();
+        // Resolve the input, so `read_entry` is called directly instead of through a resolve task
+        let resolved = entries.resolve().await?;
+        let a = read_entry(resolved, 0);
+        let b = read_entry(resolved, 1);
+
+        assert_eq!(a.strongly_consistent().await?.value, Some(1));
+        assert_eq!(b.strongly_consistent().await?.value, Some(2));
+        assert_eq!(executions(), [1, 1]);
+
+        source.await?.set(vec![1, 5]);
+        entries.strongly_consistent().await?;
+
+        assert_eq!(a.strongly_consistent().await?.value, Some(1));
+        assert_eq!(b.strongly_consistent().await?.value, Some(5));
+        assert_eq!(executions(), [1, 2]);
+
+        // Keys that are no longer stored are removed
+        source.await?.set(vec![1]);
+        entries.strongly_consistent().await?;
+
+        assert_eq!(a.strongly_consistent().await?.value, Some(1));
+        assert_eq!(b.strongly_consistent().await?.value, None);
+        assert_eq!(executions(), [1, 3]);
+    }
+}
+
+#[tokio::test]
+async fn detects_colliding_keys() {
+    run! {
+        let entries = colliding_entries().resolve().await?;
+
+        let last: Option<ReadRef<Entry>> = read_key(entries, &CollidingKey(1)).await?;
+        assert_eq!(last.unwrap().value, Some(1));
+        // The key stored first shares the cell with the key stored last
+        assert!(read_key::<_, Entry>(entries, &CollidingKey(0)).await.is_err());
+    }
+}
+
+fn executions() -> [usize; 2] {
+    [
+        EXECUTIONS[0].load(Ordering::SeqCst),
+        EXECUTIONS[1].load(Ordering::SeqCst),
+    ]
+}
+
+#[turbo_tasks::value]
+struct Entry {
+    value: Option<usize>,
+}
+
+#[turbo_tasks::value]
+struct Entries {
+    len: usize,
+}
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Source {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    value: Mutex<(Vec<usize>, Option<Invalidator>)>,
+}
+
+impl Source {
+    fn set(&self, values: Vec<usize>) {
+        let mut lock = self.value.lock().unwrap();
+        lock.0 = values;
+        if let Some(i) = lock.1.take() {
+            i.invalidate();
+        }
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceVc {
+    #[turbo_tasks::function]
+    async fn entries(self) -> Result<EntriesVc> {
+        let this = self.await?;
+        let mut lock = this.value.lock().unwrap();
+        lock.1 = Some(get_invalidator());
+        for (key, &value) in lock.0.iter().enumerate() {
+            cell_keyed(&(key as u32), Entry { value: Some(value) })?;
+        }
+        Ok(EntriesVc::cell(Entries { len: lock.0.len() }))
+    }
+}
+
+/// A key whose values all have the same hash
+#[derive(Clone, PartialEq, Eq)]
+struct CollidingKey(u32);
+
+impl Hash for CollidingKey {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+#[turbo_tasks::function]
+fn colliding_entries() -> Result<EntriesVc> {
+    cell_keyed(&CollidingKey(0), Entry { value: Some(0) })?;
+    cell_keyed(&CollidingKey(1), Entry { value: Some(1) })?;
+    Ok(EntriesVc::cell(Entries { len: 2 }))
+}
+
+#[turbo_tasks::function]
+async fn read_entry(entries: EntriesVc, key: u32) -> Result<EntryVc> {
+    EXECUTIONS[key as usize].fetch_add(1, Ordering::SeqCst);
+    let entry: Option<ReadRef<Entry>> = read_key(entries, &key).await?;
+    Ok(EntryVc::cell(Entry {
+        value: entry.and_then(|entry| entry.value),
+    }))
+}
@@ -0,0 +1,132 @@
+#![feature(min_specialization)]
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use turbo_tasks::{
+    primitives::U32Vc, turbo_tasks, with_priority, Completion, CompletionVc, RawVc, TaskPriority,
+    TurboTasks,
+};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+const MAX_RUNNING: usize = 2;
+const READERS: u32 = 8;
+
+/// Waits until `condition` holds without giving up the execution slot.
+async fn spin(condition: impl Fn() -> bool) {
+    while !condition() {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+}
+
+async fn with_timeout<T>(future: impl Future<Output = T>) -> T {
+    tokio::time::timeout(Duration::from_secs(60), future)
+        .await
+        .expect("the scheduler is stuck")
+}
+
+static STARTED_READERS: AtomicUsize = AtomicUsize::new(0);
+
+#[tokio::test]
+async fn blocking_readers_release_their_slot() {
+    lazy_static::initialize(&REGISTER);
+    let tt = TurboTasks::new(MemoryBackend::default().with_max_running_tasks(MAX_RUNNING));
+    with_timeout(tt.run_once(async {
+        let readers = (0..READERS).map(reader).collect::<Vec<_>>();
+        for (i, reader) in readers.into_iter().enumerate() {
+            assert_eq!(*reader.await?, i as u32);
+        }
+        Ok(())
+    }))
+    .await
+    .unwrap();
+}
+
+/// Only finishes once all readers have started, which needs more execution
+/// slots than available when waiting readers keep theirs.
+#[turbo_tasks::function]
+async fn gate() -> CompletionVc {
+    spin(|| STARTED_READERS.load(Ordering::SeqCst) == READERS as usize).await;
+    CompletionVc::new()
+}
+
+#[turbo_tasks::function]
+async fn reader(i: u32) -> Result<U32Vc> {
+    STARTED_READERS.fetch_add(1, Ordering::SeqCst);
+    if i % 2 == 0 {
+        gate().await?;
+    } else {
+        let gate: RawVc = gate().into();
+        gate.into_read_untracked::<Completion>(&*turbo_tasks())
+            .await?;
+    }
+    Ok(U32Vc::cell(i))
+}
+
+static BLOCKED: AtomicBool = AtomicBool::new(false);
+static RELEASED: AtomicBool = AtomicBool::new(false);
+static QUEUED: AtomicUsize = AtomicUsize::new(0);
+static ORDER: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+#[tokio::test]
+async fn runs_tasks_by_priority() {
+    lazy_static::initialize(&REGISTER);
+    let tt = TurboTasks::new(MemoryBackend::default().with_max_running_tasks(1));
+    with_timeout(async {
+        // Occupy the only execution slot, so the other tasks are queued
+        let blocker = tt.run_once(async {
+            block().await?;
+            Ok(())
+        });
+        let queue = async {
+            spin(|| BLOCKED.load(Ordering::SeqCst)).await;
+            let background = with_priority(TaskPriority::Background, tt.run_once(record_all(0)));
+            let interactive =
+                with_priority(TaskPriority::Interactive, tt.run_once(record_all(100)));
+            let release = async {
+                spin(|| QUEUED.load(Ordering::SeqCst) == 2).await;
+                RELEASED.store(true, Ordering::SeqCst);
+            };
+            let (background, interactive, _) = tokio::join!(background, interactive, release);
+            background.and(interactive)
+        };
+        let (blocker, queue) = tokio::join!(blocker, queue);
+        blocker.and(queue)
+    })
+    .await
+    .unwrap();
+    assert_eq!(*ORDER.lock().unwrap(), vec![100, 101, 102, 0, 1, 2]);
+}
+
+#[turbo_tasks::function]
+async fn block() -> U32Vc {
+    BLOCKED.store(true, Ordering::SeqCst);
+    spin(|| RELEASED.load(Ordering::SeqCst)).await;
+    U32Vc::cell(0)
+}
+
+/// Queues three [record] tasks starting at `base` and waits for them.
+async fn record_all(base: u32) -> Result<()> {
+    let tasks = (base..base + 3).map(record).collect::<Vec<_>>();
+    QUEUED.fetch_add(1, Ordering::SeqCst);
+    for task in tasks {
+        task.await?;
+    }
+    Ok(())
+}
+
+#[turbo_tasks::function]
+fn record(value: u32) -> U32Vc {
+    ORDER.lock().unwrap().push(value);
+    U32Vc::cell(value)
+}
@@ -0,0 +1,116 @@
+#![feature(min_specialization)]
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+use anyhow::Result;
+use turbo_tasks::{get_invalidator, Invalidator, TurboTasks};
+use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_testing::register;
+
+register!();
+
+#[tokio::test]
+async fn sees_consistent_snapshot() -> Result<()> {
+    lazy_static::initialize(&REGISTER);
+    static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let source = tt
+        .run_once(async {
+            Ok(SourceVc::cell(Source {
+                value: Mutex::new((0, Vec::new())),
+            }))
+        })
+        .await?;
+
+    let (a, b) = tt
+        .run_once_strongly_consistent(move || async move {
+            let a = *source.get_a().await?;
+            if EXECUTIONS.fetch_add(1, Ordering::SeqCst) == 0 {
+                // Races an invalidation against the reads of the first execution
+                source.await?.bump();
+            }
+            let b = *source.get_b().await?;
+            Ok((a, b))
+        })
+        .await?;
+
+    assert_eq!((a, b), (1, 1));
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn executes_once_without_invalidations() -> Result<()> {
+    lazy_static::initialize(&REGISTER);
+    static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+    let tt = TurboTasks::new(MemoryBackend::default());
+    let source = tt
+        .run_once(async {
+            Ok(SourceVc::cell(Source {
+                value: Mutex::new((0, Vec::new())),
+            }))
+        })
+        .await?;
+
+    let (a, b) = tt
+        .run_once_strongly_consistent(move || async move {
+            EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+            Ok((*source.get_a().await?, *source.get_b().await?))
+        })
+        .await?;
+
+    assert_eq!((a, b), (0, 0));
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 1);
+
+    // Invalidations after the execution has finished don't execute it again
+    tt.run_once(async move {
+        source.await?.bump();
+        Ok(())
+    })
+    .await?;
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+#[turbo_tasks::value(transparent)]
+struct SourceValue(usize);
+
+#[turbo_tasks::value(serialization = "none", cell = "new", eq = "manual")]
+struct Source {
+    #[turbo_tasks(debug_ignore, trace_ignore)]
+    value: Mutex<(usize, Vec<Invalidator>)>,
+}
+
+impl Source {
+    fn bump(&self) {
+        let mut lock = self.value.lock().unwrap();
+        lock.0 += 1;
+        for invalidator in lock.1.drain(..) {
+            invalidator.invalidate();
+        }
+    }
+
+    fn read(&self) -> usize {
+        let mut lock = self.value.lock().unwrap();
+        lock.1.push(get_invalidator());
+        lock.0
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceVc {
+    #[turbo_tasks::function]
+    async fn get_a(self) -> Result<SourceValueVc> {
+        Ok(SourceValueVc::cell(self.await?.read()))
+    }
+
+    #[turbo_tasks::function]
+    async fn get_b(self) -> Result<SourceValueVc> {
+        Ok(SourceValueVc::cell(self.await?.read()))
+    }
+}
@@ -0,0 +1,45 @@
+#![feature(min_specialization)]
+
+use turbo_tasks::{primitives::StringVc, registry, TurboTasks};
+use turbo_tasks_malloc::TurboMalloc;
+use turbo_tasks_memory::{stats::Stats, MemoryBackend};
+use turbo_tasks_testing::register;
+
+register!();
+
+#[global_allocator]
+static ALLOC: TurboMalloc = TurboMalloc;
+
+const SIZE: usize = 1024 * 1024;
+
+#[tokio::test]
+async fn attributes_memory_to_value_types() {
+    lazy_static::initialize(&REGISTER);
+    let tt = TurboTasks::new(MemoryBackend::default());
+    tt.run_once(async {
+        large_string().await?;
+        Ok(())
+    })
+    .await
+    .unwrap();
+
+    let mut stats = Stats::new();
+    let backend = tt.backend();
+    backend.with_all_cached_tasks(|task| stats.add_id(backend, task));
+    let (_, string_stats) = stats
+        .value_types()
+        .iter()
+        .find(|(&ty, _)| registry::get_value_type(ty).name.ends_with("String"))
+        .expect("no cells of StringVc");
+    assert_eq!(string_stats.cells, 1);
+    assert!(
+        string_stats.memory_usage >= SIZE,
+        "{} bytes attributed",
+        string_stats.memory_usage
+    );
+}
+
+#[turbo_tasks::function]
+fn large_string() -> StringVc {
+    StringVc::cell("x".repeat(SIZE))
+}
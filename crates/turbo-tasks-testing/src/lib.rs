@@ -11,28 +11,35 @@ use std::{
     sync::{Arc, Mutex, Weak},
 };
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use auto_hash_map::AutoSet;
 use turbo_tasks::{
     backend::CellContent,
     event::{Event, EventListener},
     primitives::RawVcSetVc,
     registry,
-    test_helpers::with_turbo_tasks_for_testing,
-    util::StaticOrArc,
-    CellId, InvalidationReason, RawVc, TaskId, TraitTypeId, TurboTasksApi, TurboTasksCallApi,
+    test_helpers::{current_task_for_testing, with_turbo_tasks_for_testing},
+    util::{SharedError, StaticOrArc},
+    CellId, InvalidationProvenance, InvalidationReason, KeyedCellId, RawVc, StateId, TaskId,
+    TraitTypeId, TurboTasksApi, TurboTasksCallApi,
 };
 
 enum Task {
     Spawned(Event),
-    Finished(RawVc),
+    Finished(Result<RawVc, SharedError>),
 }
 
 #[derive(Default)]
 pub struct VcStorage {
     this: Weak<Self>,
     cells: Mutex<HashMap<(TaskId, CellId), CellContent>>,
+    keyed_cells: Mutex<HashMap<(TaskId, KeyedCellId), CellContent>>,
     tasks: Mutex<Vec<Task>>,
+    /// Tasks spawned by each task, to collect collectibles transitively.
+    children: Mutex<HashMap<TaskId, Vec<TaskId>>>,
+    /// How often each task emitted a collectible. Taking collectibles unemits
+    /// them in the reading task, so counts can become negative.
+    collectibles: Mutex<HashMap<(TaskId, TraitTypeId), HashMap<RawVc, i32>>>,
 }
 
 impl TurboTasksCallApi for VcStorage {
@@ -41,30 +48,8 @@ impl TurboTasksCallApi for VcStorage {
         func: turbo_tasks::FunctionId,
         inputs: Vec<turbo_tasks::TaskInput>,
     ) -> RawVc {
-        let this = self.this.upgrade().unwrap();
         let func = registry::get_function(func).bind(&inputs);
-        let handle = tokio::runtime::Handle::current();
-        let future = func();
-        let i = {
-            let mut tasks = self.tasks.lock().unwrap();
-            let i = tasks.len();
-            tasks.push(Task::Spawned(Event::new(move || {
-                format!("Task({i})::event")
-            })));
-            i
-        };
-        handle.spawn(with_turbo_tasks_for_testing(
-            this.clone(),
-            TaskId::from(i),
-            async move {
-                let result = future.await.unwrap();
-                let mut tasks = this.tasks.lock().unwrap();
-                if let Task::Spawned(event) = replace(&mut tasks[i], Task::Finished(result)) {
-                    event.notify(usize::MAX);
-                }
-            },
-        ));
-        RawVc::TaskOutput(i.into())
+        RawVc::TaskOutput(self.spawn(func()))
     }
 
     fn native_call(
@@ -120,6 +105,10 @@ impl TurboTasksApi for VcStorage {
         unreachable!()
     }
 
+    fn get_invalidation_provenance(&self, _task: TaskId) -> Option<InvalidationProvenance> {
+        None
+    }
+
     fn notify_scheduled_tasks(&self) {
         // ignore
     }
@@ -133,7 +122,7 @@ impl TurboTasksApi for VcStorage {
         let task = tasks.get(*task).unwrap();
         match task {
             Task::Spawned(event) => Ok(Err(event.listen())),
-            Task::Finished(result) => Ok(Ok(*result)),
+            Task::Finished(result) => Ok(Ok(result.clone()?)),
         }
     }
 
@@ -179,24 +168,64 @@ impl TurboTasksApi for VcStorage {
         self.read_own_task_cell(current_task, index)
     }
 
-    fn emit_collectible(&self, _trait_type: turbo_tasks::TraitTypeId, _collectible: RawVc) {
-        unimplemented!()
+    fn try_read_task_keyed_cell(
+        &self,
+        task: TaskId,
+        id: KeyedCellId,
+    ) -> Result<Result<CellContent, EventListener>> {
+        Ok(Ok(self.read_own_task_keyed_cell(task, id)?))
+    }
+
+    fn emit_collectible(&self, trait_type: turbo_tasks::TraitTypeId, collectible: RawVc) {
+        self.update_collectible_count(trait_type, collectible, 1);
     }
 
-    fn unemit_collectible(&self, _trait_type: turbo_tasks::TraitTypeId, _collectible: RawVc) {
-        unimplemented!()
+    fn unemit_collectible(&self, trait_type: turbo_tasks::TraitTypeId, collectible: RawVc) {
+        self.update_collectible_count(trait_type, collectible, -1);
     }
 
     fn unemit_collectibles(
         &self,
-        _trait_type: turbo_tasks::TraitTypeId,
-        _collectibles: &AutoSet<RawVc>,
+        trait_type: turbo_tasks::TraitTypeId,
+        collectibles: &AutoSet<RawVc>,
     ) {
-        unimplemented!()
+        for collectible in collectibles {
+            self.update_collectible_count(trait_type, *collectible, -1);
+        }
     }
 
-    fn read_task_collectibles(&self, _task: TaskId, _trait_id: TraitTypeId) -> RawVcSetVc {
-        unimplemented!()
+    fn read_task_collectibles(&self, task: TaskId, trait_id: TraitTypeId) -> RawVcSetVc {
+        let this = self.this.upgrade().unwrap();
+        let reader = current_task_for_testing();
+        let read_task = self.spawn(Box::pin(async move {
+            // Collectibles of a task include the collectibles of all tasks it spawned, so
+            // wait for the whole subtree to finish
+            let mut counts = HashMap::<RawVc, i32>::new();
+            let mut queue = vec![task];
+            while let Some(task) = queue.pop() {
+                if task == reader {
+                    // The reader can't finish while it waits on the collectibles
+                    bail!("reading the collectibles of a task from within it is not possible");
+                }
+                this.wait_for_task(task).await;
+                if let Some(collectibles) = this.collectibles.lock().unwrap().get(&(task, trait_id))
+                {
+                    for (collectible, count) in collectibles {
+                        *counts.entry(*collectible).or_default() += count;
+                    }
+                }
+                if let Some(children) = this.children.lock().unwrap().get(&task) {
+                    queue.extend(children.iter().copied());
+                }
+            }
+            let set = counts
+                .into_iter()
+                .filter(|&(_, count)| count > 0)
+                .map(|(collectible, _)| collectible)
+                .collect();
+            anyhow::Ok(RawVc::from(RawVcSetVc::cell(set)))
+        }));
+        RawVcSetVc::from(RawVc::TaskOutput(read_task))
     }
 
     fn read_own_task_cell(&self, task: TaskId, index: CellId) -> Result<CellContent> {
@@ -214,6 +243,22 @@ impl TurboTasksApi for VcStorage {
         *cell = content;
     }
 
+    fn read_own_task_keyed_cell(&self, task: TaskId, id: KeyedCellId) -> Result<CellContent> {
+        let map = self.keyed_cells.lock().unwrap();
+        Ok(map.get(&(task, id)).cloned().unwrap_or_default())
+    }
+
+    fn update_own_task_keyed_cell(
+        &self,
+        task: TaskId,
+        id: KeyedCellId,
+        content: CellContent,
+    ) -> Result<()> {
+        let mut map = self.keyed_cells.lock().unwrap();
+        map.insert((task, id), content);
+        Ok(())
+    }
+
     fn connect_task(&self, _task: TaskId) {
         // no-op
     }
@@ -222,15 +267,93 @@ impl TurboTasksApi for VcStorage {
         // no-op
     }
 
+    fn mark_own_task_as_session_dependent(&self, _task: TaskId) {
+        // no-op
+    }
+
+    fn mark_state_as_changed(&self, _state: StateId) {
+        // no-op
+    }
+
+    fn is_own_task_cancelled(&self, _task: TaskId) -> bool {
+        false
+    }
+
+    fn listen_to_own_task_cancellation(&self, _task: TaskId) -> Result<(), Option<EventListener>> {
+        Err(None)
+    }
+
     fn detached(
         &self,
-        _f: std::pin::Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>,
+        f: std::pin::Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>,
     ) -> std::pin::Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>> {
-        unimplemented!()
+        Box::pin(with_turbo_tasks_for_testing(
+            self.this.upgrade().unwrap(),
+            current_task_for_testing(),
+            f,
+        ))
     }
 }
 
 impl VcStorage {
+    /// Spawns `future` as a child task of the current task.
+    fn spawn(
+        &self,
+        future: std::pin::Pin<Box<dyn Future<Output = Result<RawVc>> + Send + 'static>>,
+    ) -> TaskId {
+        let this = self.this.upgrade().unwrap();
+        let handle = tokio::runtime::Handle::current();
+        let i = {
+            let mut tasks = self.tasks.lock().unwrap();
+            let i = tasks.len();
+            tasks.push(Task::Spawned(Event::new(move || {
+                format!("Task({i})::event")
+            })));
+            i
+        };
+        let task = TaskId::from(i);
+        self.children
+            .lock()
+            .unwrap()
+            .entry(current_task_for_testing())
+            .or_default()
+            .push(task);
+        handle.spawn(with_turbo_tasks_for_testing(
+            this.clone(),
+            task,
+            async move {
+                let result = future.await.map_err(SharedError::new);
+                let mut tasks = this.tasks.lock().unwrap();
+                if let Task::Spawned(event) = replace(&mut tasks[i], Task::Finished(result)) {
+                    event.notify(usize::MAX);
+                }
+            },
+        ));
+        task
+    }
+
+    /// Waits until a spawned task has finished. The task passed to
+    /// [VcStorage::with] is not tracked and returns immediately.
+    async fn wait_for_task(&self, task: TaskId) {
+        loop {
+            let listener = match self.tasks.lock().unwrap().get(*task) {
+                Some(Task::Spawned(event)) => event.listen(),
+                Some(Task::Finished(_)) | None => return,
+            };
+            listener.await;
+        }
+    }
+
+    fn update_collectible_count(&self, trait_type: TraitTypeId, collectible: RawVc, count: i32) {
+        let task = current_task_for_testing();
+        let mut collectibles = self.collectibles.lock().unwrap();
+        *collectibles
+            .entry((task, trait_type))
+            .or_default()
+            .entry(collectible)
+            .or_default() += count;
+    }
+
     pub fn with<T>(f: impl Future<Output = T>) -> impl Future<Output = T> {
         with_turbo_tasks_for_testing(
             Arc::new_cyclic(|weak| VcStorage {
@@ -0,0 +1,103 @@
+#![feature(min_specialization)]
+
+use anyhow::{bail, Result};
+use turbo_tasks::{
+    emit, primitives::StringVc, test_helpers::current_task_for_testing, CollectiblesSource, RawVc,
+    ValueToString, ValueToStringVc,
+};
+use turbo_tasks_testing::{register, VcStorage};
+register!();
+
+#[tokio::test]
+async fn collectibles() {
+    lazy_static::initialize(&REGISTER);
+    VcStorage::with(async {
+        let result = my_transitive_emitting_function("");
+        let list = result.peek_collectibles::<ValueToStringVc>().await?;
+        assert_eq!(list.len(), 2);
+
+        let result = my_collecting_function_indirect();
+        let list = result.peek_collectibles::<ValueToStringVc>().await?;
+        // my_collecting_function already took the collectibles
+        assert!(list.is_empty());
+        anyhow::Ok(())
+    })
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn reading_own_collectibles() {
+    lazy_static::initialize(&REGISTER);
+    VcStorage::with(async {
+        let Err(err) = my_self_collecting_function().await else {
+            panic!("reading its own collectibles should fail");
+        };
+        assert!(
+            format!("{err:#}").contains("not possible"),
+            "unexpected error: {err:#}"
+        );
+        anyhow::Ok(())
+    })
+    .await
+    .unwrap();
+}
+
+#[turbo_tasks::function]
+async fn my_collecting_function() -> Result<ThingVc> {
+    let result = my_transitive_emitting_function("");
+    let list = result.take_collectibles::<ValueToStringVc>().await?;
+    if list.len() != 2 {
+        bail!("Expected 2 collectibles, got {}", list.len());
+    }
+    Ok(result)
+}
+
+#[turbo_tasks::function]
+async fn my_collecting_function_indirect() -> Result<ThingVc> {
+    let result = my_collecting_function();
+    let list = result.peek_collectibles::<ValueToStringVc>().await?;
+    // my_collecting_function already processed the collectibles so the list should
+    // be empty
+    if !list.is_empty() {
+        bail!("Expected 0 collectibles, got {}", list.len());
+    }
+    Ok(result)
+}
+
+#[turbo_tasks::function]
+async fn my_self_collecting_function() -> Result<ThingVc> {
+    emit(ThingVc::new(1).as_value_to_string());
+    let this = ThingVc::from(RawVc::TaskOutput(current_task_for_testing()));
+    this.peek_collectibles::<ValueToStringVc>().await?;
+    Ok(ThingVc::new(0))
+}
+
+#[turbo_tasks::function]
+fn my_transitive_emitting_function(key: &str) -> ThingVc {
+    my_emitting_function(key);
+    ThingVc::cell(Thing(0))
+}
+
+#[turbo_tasks::function]
+fn my_emitting_function(_key: &str) {
+    emit(ThingVc::new(123).as_value_to_string());
+    emit(ThingVc::new(42).as_value_to_string());
+}
+
+#[turbo_tasks::value(shared)]
+struct Thing(u32);
+
+impl ThingVc {
+    fn new(v: u32) -> Self {
+        Self::cell(Thing(v))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for Thing {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> StringVc {
+        StringVc::cell(self.0.to_string())
+    }
+}
@@ -11,12 +11,14 @@ use std::{
 
 use anyhow::{anyhow, bail, Result};
 use serde::{Deserialize, Serialize};
+use turbo_tasks_malloc::AllocationInfo;
 
 pub use crate::id::BackendJobId;
 use crate::{
-    event::EventListener, manager::TurboTasksBackendApi, primitives::RawVcSetVc, raw_vc::CellId,
-    registry, task_input::SharedReference, FunctionId, RawVc, ReadRef, TaskId, TaskIdProvider,
-    TaskInput, TraitRef, TraitTypeId, ValueTraitVc,
+    event::EventListener, keyed_cell::KeyedCellId, manager::TurboTasksBackendApi,
+    primitives::RawVcSetVc, raw_vc::CellId, registry, state::StateId, task_input::SharedReference,
+    util::StaticOrArc, FunctionId, InvalidationProvenance, InvalidationReason, RawVc, ReadRef,
+    TaskId, TaskIdProvider, TaskInput, TaskPriority, TraitRef, TraitTypeId, ValueTraitVc,
 };
 
 pub enum TaskType {
@@ -39,14 +41,21 @@ pub enum TransientTaskType {
     /// Always active. Automatically scheduled.
     Root(TransientTaskRoot),
 
-    // TODO implement these strongly consistency
     /// A single root task execution. It won't track dependencies.
     /// Task will definitely include all invalidations that happened before the
     /// start of the task. It may or may not include invalidations that
     /// happened after that. It may see these invalidations partially
-    /// applied.
+    /// applied. Use [TransientTaskType::StronglyConsistentOnce] when that's not
+    /// acceptable.
     /// Active until done. Automatically scheduled.
     Once(Pin<Box<dyn Future<Output = Result<RawVc>> + Send + 'static>>),
+
+    /// A single root task execution that observes a consistent snapshot. All
+    /// task outputs are read strongly consistent and the task is executed
+    /// again when it is invalidated before the execution has finished. After
+    /// that it won't track dependencies.
+    /// Active until done. Automatically scheduled.
+    StronglyConsistentOnce(TransientTaskRoot),
 }
 
 impl Debug for TransientTaskType {
@@ -54,6 +63,7 @@ impl Debug for TransientTaskType {
         match self {
             Self::Root(_) => f.debug_tuple("Root").finish(),
             Self::Once(_) => f.debug_tuple("Once").finish(),
+            Self::StronglyConsistentOnce(_) => f.debug_tuple("StronglyConsistentOnce").finish(),
         }
     }
 }
@@ -204,6 +214,34 @@ pub trait Backend: Sync + Send {
 
     fn invalidate_tasks(&self, tasks: Vec<TaskId>, turbo_tasks: &dyn TurboTasksBackendApi<Self>);
 
+    /// Like [Backend::invalidate_task], but with the reason of the
+    /// invalidation.
+    fn invalidate_task_with_reason(
+        &self,
+        task: TaskId,
+        _reason: StaticOrArc<dyn InvalidationReason>,
+        turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) {
+        self.invalidate_task(task, turbo_tasks);
+    }
+
+    /// Invalidates tasks that depend on values changed by the execution of
+    /// `source`.
+    fn invalidate_dependent_tasks(
+        &self,
+        _source: TaskId,
+        tasks: Vec<TaskId>,
+        turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) {
+        self.invalidate_tasks(tasks, turbo_tasks);
+    }
+
+    /// Returns why the latest execution of the task happened, when it was
+    /// caused by an invalidation.
+    fn get_task_invalidation_provenance(&self, _task: TaskId) -> Option<InvalidationProvenance> {
+        None
+    }
+
     fn get_task_description(&self, task: TaskId) -> String;
 
     type ExecutionScopeFuture<T: Future<Output = Result<()>> + Send + 'static>: Future<Output = Result<()>>
@@ -229,11 +267,14 @@ pub trait Backend: Sync + Send {
         turbo_tasks: &dyn TurboTasksBackendApi<Self>,
     );
 
+    /// `allocations` are the allocations made while polling the execution,
+    /// as counted by [turbo_tasks_malloc::TurboMalloc].
     fn task_execution_completed(
         &self,
         task: TaskId,
         duration: Duration,
         instant: Instant,
+        allocations: AllocationInfo,
         stateful: bool,
         turbo_tasks: &dyn TurboTasksBackendApi<Self>,
     ) -> bool;
@@ -324,6 +365,42 @@ pub trait Backend: Sync + Send {
         turbo_tasks: &dyn TurboTasksBackendApi<Self>,
     );
 
+    /// Reads a keyed cell of a task and makes `reader` depend on this key
+    /// only. Returns empty content when the task has no value for the key.
+    fn try_read_task_keyed_cell(
+        &self,
+        _task: TaskId,
+        _id: KeyedCellId,
+        _reader: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) -> Result<Result<CellContent, EventListener>> {
+        bail!("keyed cells are not supported by this backend")
+    }
+
+    /// INVALIDATION: Be careful with this, it will not track dependencies, so
+    /// using it could break cache invalidation.
+    fn try_read_own_task_keyed_cell_untracked(
+        &self,
+        _current_task: TaskId,
+        _id: KeyedCellId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) -> Result<CellContent> {
+        bail!("keyed cells are not supported by this backend")
+    }
+
+    /// Stores the content of a keyed cell of the currently executing task.
+    /// Keys that are not updated during an execution are removed when it
+    /// completes.
+    fn update_task_keyed_cell(
+        &self,
+        _task: TaskId,
+        _id: KeyedCellId,
+        _content: CellContent,
+        _turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) -> Result<()> {
+        bail!("keyed cells are not supported by this backend")
+    }
+
     fn get_or_create_persistent_task(
         &self,
         task_type: PersistentTaskType,
@@ -346,11 +423,60 @@ pub trait Backend: Sync + Send {
         // Do nothing by default
     }
 
+    fn mark_own_task_as_session_dependent(
+        &self,
+        _task: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) {
+        // Do nothing by default
+    }
+
+    fn mark_state_as_changed(
+        &self,
+        _state: StateId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) {
+        // Do nothing by default
+    }
+
+    /// Returns true when the current execution of the task has been
+    /// superseded by an invalidation and its result will be discarded.
+    fn is_own_task_cancelled(
+        &self,
+        _task: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) -> bool {
+        // Executions are never cancelled by default
+        false
+    }
+
+    /// Returns `Ok(())` when the current execution of the task is already
+    /// cancelled, otherwise a listener that is notified on cancellation, or
+    /// `None` when the execution can't be cancelled.
+    fn listen_to_own_task_cancellation(
+        &self,
+        _task: TaskId,
+        _turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) -> Result<(), Option<EventListener>> {
+        // Executions are never cancelled by default
+        Err(None)
+    }
+
     fn create_transient_task(
         &self,
         task_type: TransientTaskType,
         turbo_tasks: &dyn TurboTasksBackendApi<Self>,
     ) -> TaskId;
+
+    /// Raises the scheduling priority of a task. Child tasks inherit it.
+    fn raise_task_priority(
+        &self,
+        _task: TaskId,
+        _priority: TaskPriority,
+        _turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) {
+        // All tasks share one priority by default
+    }
 }
 
 impl PersistentTaskType {
@@ -30,6 +30,7 @@ impl Event {
     pub fn listen(&self) -> EventListener {
         EventListener {
             listener: self.event.listen(),
+            on_done: None,
         }
     }
 
@@ -40,6 +41,7 @@ impl Event {
     ) -> EventListener {
         EventListener {
             listener: self.event.listen(),
+            on_done: None,
         }
     }
 
@@ -72,6 +74,7 @@ impl Event {
                 self.event.listen(),
             ))),
             duration: Duration::from_secs(10),
+            on_done: None,
         }
     }
 
@@ -88,6 +91,7 @@ impl Event {
                 self.event.listen(),
             ))),
             duration: Duration::from_secs(10),
+            on_done: None,
         }
     }
 
@@ -119,6 +123,7 @@ impl Debug for Event {
 #[cfg(not(feature = "hanging_detection"))]
 pub struct EventListener {
     listener: event_listener::EventListener,
+    on_done: Option<OnDone>,
 }
 
 #[cfg(not(feature = "hanging_detection"))]
@@ -136,7 +141,12 @@ impl Future for EventListener {
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
-        unsafe { Pin::new_unchecked(&mut self.get_unchecked_mut().listener) }.poll(cx)
+        let this = unsafe { self.get_unchecked_mut() };
+        let result = unsafe { Pin::new_unchecked(&mut this.listener) }.poll(cx);
+        if result.is_ready() {
+            this.done();
+        }
+        result
     }
 }
 
@@ -148,6 +158,7 @@ pub struct EventListener {
     // So it's important to put it into a pinned Box to be able to take it out of the Option.
     future: Option<Pin<Box<Timeout<event_listener::EventListener>>>>,
     duration: Duration,
+    on_done: Option<OnDone>,
 }
 
 #[cfg(feature = "hanging_detection")]
@@ -175,6 +186,7 @@ impl Future for EventListener {
             match ready!(future.as_mut().poll(cx)) {
                 Ok(_) => {
                     self.future = None;
+                    self.done();
                     return Poll::Ready(());
                 }
                 Err(_) => {
@@ -201,3 +213,32 @@ impl Future for EventListener {
         Poll::Ready(())
     }
 }
+
+type OnDone = Box<dyn FnOnce() + Send + Sync>;
+
+impl EventListener {
+    /// Calls `on_done` once the listener has been notified, or when it's
+    /// dropped before that.
+    pub fn on_done(mut self, on_done: impl FnOnce() + Send + Sync + 'static) -> Self {
+        self.on_done = Some(match self.on_done.take() {
+            Some(previous) => Box::new(move || {
+                previous();
+                on_done();
+            }),
+            None => Box::new(on_done),
+        });
+        self
+    }
+
+    fn done(&mut self) {
+        if let Some(on_done) = self.on_done.take() {
+            on_done();
+        }
+    }
+}
+
+impl Drop for EventListener {
+    fn drop(&mut self) {
+        self.done();
+    }
+}
@@ -143,7 +143,7 @@ where
     }
 }
 
-struct TemporarySwapGuard<'c, T>(&'c RefCell<T>, ManuallyDrop<T>);
+pub(crate) struct TemporarySwapGuard<'c, T>(pub &'c RefCell<T>, pub ManuallyDrop<T>);
 
 impl<'c, T> Drop for TemporarySwapGuard<'c, T> {
     fn drop(&mut self) {
@@ -7,7 +7,7 @@ use std::{
 
 use indexmap::{map::Entry, IndexMap, IndexSet};
 
-use crate::{magic_any::HasherMut, util::StaticOrArc};
+use crate::{magic_any::HasherMut, util::StaticOrArc, TaskId};
 
 pub trait DynamicEqHash {
     fn as_any(&self) -> &dyn Any;
@@ -188,3 +188,15 @@ impl Display for InvalidationReasonSet {
         Ok(())
     }
 }
+
+/// Explains why a task has been executed again. It starts at the task that
+/// was invalidated directly, e.g. by an [Invalidator](crate::Invalidator), and
+/// follows the dependencies that were invalidated by its new execution.
+#[derive(Clone)]
+pub struct InvalidationProvenance {
+    /// The reason passed to the original invalidation, if any.
+    pub reason: Option<StaticOrArc<dyn InvalidationReason>>,
+    /// The tasks the invalidation has propagated through, starting at the
+    /// originally invalidated task and ending at the executed task.
+    pub path: Vec<TaskId>,
+}
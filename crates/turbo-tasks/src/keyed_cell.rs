@@ -0,0 +1,139 @@
+use std::{
+    borrow::Borrow,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::CellContent,
+    manager::{current_task, turbo_tasks},
+    registry,
+    task_input::SharedReference,
+    RawVc, ReadRef, TaskId, TurboTasksApi, Typed, ValueTypeId,
+};
+
+/// Identifies a keyed cell of a task. Keyed cells are addressed by the hash
+/// of a key instead of the order of creation, so readers can depend on
+/// individual entries of a map-like value.
+///
+/// Distinct keys can hash to the same id. The cell stores the full key next
+/// to the value, so such a collision is detected when reading and fails
+/// instead of returning the value of the other key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct KeyedCellId {
+    pub type_id: ValueTypeId,
+    pub key: u64,
+}
+
+impl KeyedCellId {
+    pub fn new<K: Hash + ?Sized>(type_id: ValueTypeId, key: &K) -> Self {
+        // The hash only needs to be stable within the process, as it's not
+        // used to address persisted cells.
+        #[allow(clippy::disallowed_types)]
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        key.hash(&mut hasher);
+        Self {
+            type_id,
+            key: hasher.finish(),
+        }
+    }
+}
+
+impl Display for KeyedCellId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}[{:016x}]",
+            registry::get_value_type(self.type_id).name,
+            self.key
+        )
+    }
+}
+
+/// The content of a keyed cell: the value together with the key it has been
+/// stored under.
+struct KeyedValue<K, T> {
+    key: K,
+    value: Arc<T>,
+}
+
+/// Stores `value` in the keyed cell of the current task identified by `key`.
+///
+/// Readers of the key via [read_key] are only invalidated when the value of
+/// this key changes. Keys that are not stored again during an execution are
+/// removed when the execution completes.
+pub fn cell_keyed<K, T>(key: &K, value: T) -> Result<()>
+where
+    K: Hash + Eq + ToOwned + ?Sized,
+    K::Owned: Send + Sync + 'static,
+    T: Typed + PartialEq + Send + Sync + 'static,
+{
+    let current_task = current_task("celling keyed turbo_tasks values");
+    let id = KeyedCellId::new(T::get_value_type_id(), key);
+    let tt = turbo_tasks();
+    // Keep the old content when the value didn't change, so readers are not
+    // invalidated. It still needs to be stored to keep the key alive.
+    let old_content = tt.read_own_task_keyed_cell(current_task, id)?;
+    let unchanged = old_content
+        .clone()
+        .try_cast::<KeyedValue<K::Owned, T>>()
+        .map_or(false, |old| old.key.borrow() == key && *old.value == value);
+    let content = if unchanged {
+        old_content
+    } else {
+        let entry = KeyedValue {
+            key: key.to_owned(),
+            value: Arc::new(value),
+        };
+        CellContent(Some(SharedReference(None, Arc::new(entry))))
+    };
+    tt.update_own_task_keyed_cell(current_task, id, content)
+}
+
+/// Reads the value that the task of `vc` stored under `key` via
+/// [cell_keyed]. The current task only depends on this key, changes to other
+/// keys don't invalidate it.
+///
+/// Returns `None` when no value is stored under `key`. Fails when the cell of
+/// `key` holds the value of a different key with the same hash.
+pub async fn read_key<K, T>(vc: impl Into<RawVc>, key: &K) -> Result<Option<ReadRef<T>>>
+where
+    K: Hash + Eq + ToOwned + ?Sized,
+    K::Owned: Send + Sync + 'static,
+    T: Typed + Send + Sync + 'static,
+{
+    let task = match vc.into().resolve().await? {
+        RawVc::TaskCell(task, _) => task,
+        RawVc::TaskOutput(_) => bail!("resolved Vc must point to a cell"),
+    };
+    let id = KeyedCellId::new(T::get_value_type_id(), key);
+    let tt = turbo_tasks();
+    let content = read_task_keyed_cell(&*tt, task, id).await?;
+    if content.0.is_none() {
+        return Ok(None);
+    }
+    let Some(entry) = content.try_cast::<KeyedValue<K::Owned, T>>() else {
+        bail!("keyed cell {id} of {task} has an unexpected type");
+    };
+    if entry.key.borrow() != key {
+        bail!("keyed cell {id} of {task} is used by another key with the same hash");
+    }
+    Ok(Some(ReadRef::new(entry.value.clone())))
+}
+
+async fn read_task_keyed_cell(
+    this: &dyn TurboTasksApi,
+    task: TaskId,
+    id: KeyedCellId,
+) -> Result<CellContent> {
+    loop {
+        match this.try_read_task_keyed_cell(task, id)? {
+            Ok(result) => return Ok(result),
+            Err(listener) => listener.await,
+        }
+    }
+}
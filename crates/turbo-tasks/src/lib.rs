@@ -45,6 +45,7 @@ mod id;
 mod id_factory;
 mod invalidation;
 mod join_iter_ext;
+mod keyed_cell;
 #[doc(hidden)]
 pub mod macro_helpers;
 mod magic_any;
@@ -55,6 +56,7 @@ mod nothing;
 mod once_map;
 pub mod persisted_graph;
 pub mod primitives;
+mod priority;
 mod raw_vc;
 mod read_ref;
 pub mod registry;
@@ -77,23 +79,27 @@ pub use id::{
     ValueTypeId,
 };
 pub use invalidation::{
-    DynamicEqHash, InvalidationReason, InvalidationReasonKind, InvalidationReasonSet,
+    DynamicEqHash, InvalidationProvenance, InvalidationReason, InvalidationReasonKind,
+    InvalidationReasonSet,
 };
 pub use join_iter_ext::{JoinIterExt, TryJoinIterExt};
+pub use keyed_cell::{cell_keyed, read_key, KeyedCellId};
 pub use manager::{
-    dynamic_call, emit, get_invalidator, mark_finished, mark_stateful, run_once,
-    run_once_with_reason, spawn_blocking, spawn_thread, trait_call, turbo_tasks, Invalidator,
+    cancellable, cancellation_point, dynamic_call, emit, get_invalidator, is_cancelled,
+    mark_finished, mark_session_dependent, mark_stateful, run_once, run_once_with_reason,
+    spawn_blocking, spawn_thread, trait_call, turbo_tasks, with_priority, Cancelled, Invalidator,
     StatsType, TaskIdProvider, TurboTasks, TurboTasksApi, TurboTasksBackendApi, TurboTasksCallApi,
     Unused, UpdateInfo,
 };
 pub use native_function::{NativeFunction, NativeFunctionVc};
 pub use nothing::{Nothing, NothingVc};
+pub use priority::TaskPriority;
 pub use raw_vc::{
     CellId, CollectiblesFuture, RawVc, ReadRawVcFuture, ResolveTypeError, TraitCast,
     TransparentValueCast, ValueCast,
 };
 pub use read_ref::ReadRef;
-pub use state::State;
+pub use state::{with_state_mapping, RestoreStateFn, State, StateContent, StateId, StateMapping};
 pub use task_input::{FromTaskInput, SharedReference, SharedValue, TaskInput};
 pub use trait_ref::{IntoTraitRef, TraitRef};
 pub use turbo_tasks_macros::{function, value, value_impl, value_trait, TaskInput};
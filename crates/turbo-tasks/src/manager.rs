@@ -29,14 +29,17 @@ use crate::{
     id::{BackendJobId, FunctionId, TraitTypeId},
     id_factory::IdFactory,
     invalidation::InvalidationReasonSet,
+    keyed_cell::KeyedCellId,
     primitives::RawVcSetVc,
     raw_vc::{CellId, RawVc},
     registry,
+    state::StateId,
     task_input::{SharedReference, TaskInput},
     timed_future::{self, TimedFuture},
     trace::TraceRawVcs,
     util::{FormatDuration, StaticOrArc},
-    Completion, CompletionVc, InvalidationReason, TaskId, ValueTraitVc, ValueTypeId,
+    Completion, CompletionVc, InvalidationProvenance, InvalidationReason, TaskId, TaskPriority,
+    ValueTraitVc, ValueTypeId,
 };
 
 pub trait TurboTasksCallApi: Sync + Send {
@@ -68,6 +71,10 @@ pub trait TurboTasksApi: TurboTasksCallApi + Sync + Send {
     fn invalidate(&self, task: TaskId);
     fn invalidate_with_reason(&self, task: TaskId, reason: StaticOrArc<dyn InvalidationReason>);
 
+    /// Returns why the latest execution of the task happened, when it was
+    /// caused by an invalidation.
+    fn get_invalidation_provenance(&self, task: TaskId) -> Option<InvalidationProvenance>;
+
     /// Eagerly notifies all tasks that were scheduled for notifications via
     /// `schedule_notify_tasks_set()`
     fn notify_scheduled_tasks(&self);
@@ -100,6 +107,12 @@ pub trait TurboTasksApi: TurboTasksCallApi + Sync + Send {
         index: CellId,
     ) -> Result<Result<CellContent, EventListener>>;
 
+    fn try_read_task_keyed_cell(
+        &self,
+        task: TaskId,
+        id: KeyedCellId,
+    ) -> Result<Result<CellContent, EventListener>>;
+
     fn read_task_collectibles(&self, task: TaskId, trait_id: TraitTypeId) -> RawVcSetVc;
 
     fn emit_collectible(&self, trait_type: TraitTypeId, collectible: RawVc);
@@ -116,7 +129,18 @@ pub trait TurboTasksApi: TurboTasksCallApi + Sync + Send {
 
     fn read_own_task_cell(&self, task: TaskId, index: CellId) -> Result<CellContent>;
     fn update_own_task_cell(&self, task: TaskId, index: CellId, content: CellContent);
+    fn read_own_task_keyed_cell(&self, task: TaskId, id: KeyedCellId) -> Result<CellContent>;
+    fn update_own_task_keyed_cell(
+        &self,
+        task: TaskId,
+        id: KeyedCellId,
+        content: CellContent,
+    ) -> Result<()>;
     fn mark_own_task_as_finished(&self, task: TaskId);
+    fn mark_own_task_as_session_dependent(&self, task: TaskId);
+    fn mark_state_as_changed(&self, state: StateId);
+    fn is_own_task_cancelled(&self, task: TaskId) -> bool;
+    fn listen_to_own_task_cancellation(&self, task: TaskId) -> Result<(), Option<EventListener>>;
 
     fn connect_task(&self, task: TaskId);
 
@@ -287,6 +311,9 @@ struct CurrentTaskState {
 
     // true, if the current task has state in cells
     stateful: bool,
+
+    /// The index of the next [State](crate::State) created by the current task
+    next_state_index: u32,
 }
 
 // TODO implement our own thread pool and make these thread locals instead
@@ -299,6 +326,13 @@ task_local! {
     static CURRENT_TASK_ID: TaskId;
 
     static CURRENT_TASK_STATE: RefCell<CurrentTaskState>;
+
+    /// Set during the execution of a strongly consistent once task, so all task
+    /// outputs are read strongly consistent. Collects the tasks that were read.
+    static STRONGLY_CONSISTENT_READS: RefCell<AutoSet<TaskId, BuildNoHashHasher<TaskId>>>;
+
+    /// The priority of root tasks spawned in a [with_priority] scope
+    static PRIORITY: TaskPriority;
 }
 
 impl<B: Backend + 'static> TurboTasks<B> {
@@ -347,13 +381,15 @@ impl<B: Backend + 'static> TurboTasks<B> {
         let id = self
             .backend
             .create_transient_task(TransientTaskType::Root(Box::new(functor)), self);
+        self.apply_current_priority(id);
         self.schedule(id);
         id
     }
 
-    // TODO make sure that all dependencies settle before reading them
     /// Creates a new root task, that is only executed once.
     /// Dependencies will not invalidate the task.
+    /// See [TurboTasks::spawn_strongly_consistent_once_task] when the task
+    /// needs to see a consistent snapshot.
     #[track_caller]
     pub fn spawn_once_task(
         &self,
@@ -362,10 +398,49 @@ impl<B: Backend + 'static> TurboTasks<B> {
         let id = self
             .backend
             .create_transient_task(TransientTaskType::Once(Box::pin(future)), self);
+        self.apply_current_priority(id);
+        self.schedule(id);
+        id
+    }
+
+    /// Creates a new root task, that observes a consistent snapshot. All task
+    /// outputs are read strongly consistent and the task is executed again
+    /// when it is invalidated before the execution has finished.
+    /// Dependencies will not invalidate the task after that.
+    #[track_caller]
+    pub fn spawn_strongly_consistent_once_task<Fut>(
+        &self,
+        functor: impl Fn() -> Fut + Sync + Send + 'static,
+    ) -> TaskId
+    where
+        Fut: Future<Output = Result<RawVc>> + Send + 'static,
+    {
+        let id = self.backend.create_transient_task(
+            TransientTaskType::StronglyConsistentOnce(Box::new(move || {
+                let future = functor();
+                Box::pin(
+                    STRONGLY_CONSISTENT_READS.scope(Default::default(), async move {
+                        let result = future.await?;
+                        settle_strongly_consistent_reads(&*turbo_tasks()).await?;
+                        Ok::<RawVc, anyhow::Error>(result)
+                    }),
+                ) as _
+            })),
+            self,
+        );
+        self.apply_current_priority(id);
         self.schedule(id);
         id
     }
 
+    /// Applies the priority of the surrounding [with_priority] scope to a new
+    /// root task.
+    fn apply_current_priority(&self, task: TaskId) {
+        if let Ok(priority) = PRIORITY.try_with(|priority| *priority) {
+            self.backend.raise_task_priority(task, priority, self);
+        }
+    }
+
     pub async fn run_once<T: TraceRawVcs + Send + 'static>(
         &self,
         future: impl Future<Output = Result<T>> + Send + 'static,
@@ -385,6 +460,40 @@ impl<B: Backend + 'static> TurboTasks<B> {
         Ok(rx.await?)
     }
 
+    /// Like [TurboTasks::run_once], but the result is computed from a
+    /// consistent snapshot, even when invalidations happen concurrently. The
+    /// future is created again for every execution that has been invalidated
+    /// before it finished.
+    pub async fn run_once_strongly_consistent<T, Fut>(
+        &self,
+        functor: impl Fn() -> Fut + Sync + Send + 'static,
+    ) -> Result<T>
+    where
+        T: TraceRawVcs + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let result = Arc::new(Mutex::new(None));
+        let task_id = self.spawn_strongly_consistent_once_task({
+            let result = result.clone();
+            move || {
+                let future = functor();
+                let result = result.clone();
+                async move {
+                    let value = future.await?;
+                    *result.lock().unwrap() = Some(value);
+                    Ok(CompletionVc::new().into())
+                }
+            }
+        });
+        // INVALIDATION: The task will not invalidate after it has finished, therefore
+        // we don't need to track a dependency
+        let raw_result = read_task_output_untracked(self, task_id, false).await?;
+        raw_result.into_read_untracked::<Completion>(self).await?;
+
+        let value = result.lock().unwrap().take();
+        value.ok_or_else(|| anyhow!("strongly consistent once task finished without a result"))
+    }
+
     /// Call a native function with arguments.
     /// All inputs must be resolved.
     pub(crate) fn native_call(&self, func: FunctionId, inputs: Vec<TaskInput>) -> RawVc {
@@ -463,7 +572,7 @@ impl<B: Backend + 'static> TurboTasks<B> {
                                 .await,
                         )
                     });
-                    if let Some((result, duration, instant)) = execution_future.await {
+                    if let Some((result, duration, instant, allocations)) = execution_future.await {
                         if cfg!(feature = "log_function_stats") && duration.as_millis() > 1000 {
                             println!(
                                 "{} took {}",
@@ -479,10 +588,15 @@ impl<B: Backend + 'static> TurboTasks<B> {
                             },
                         });
                         this.backend.task_execution_result(task_id, result, &*this);
-                        let stateful = this.finish_current_task_state();
-                        let reexecute = this
-                            .backend
-                            .task_execution_completed(task_id, duration, instant, stateful, &*this);
+                        let stateful = this.finish_current_task_state(task_id);
+                        let reexecute = this.backend.task_execution_completed(
+                            task_id,
+                            duration,
+                            instant,
+                            allocations,
+                            stateful,
+                            &*this,
+                        );
                         if !reexecute {
                             return false;
                         }
@@ -792,16 +906,17 @@ impl<B: Backend + 'static> TurboTasks<B> {
         );
     }
 
-    fn finish_current_task_state(&self) -> bool {
+    fn finish_current_task_state(&self, task: TaskId) -> bool {
         CURRENT_TASK_STATE.with(|cell| {
             let CurrentTaskState {
                 tasks_to_notify,
                 stateful,
+                ..
             } = &mut *cell.borrow_mut();
             let tasks = take(tasks_to_notify);
             if !tasks.is_empty() {
                 let _guard = trace_span!("finish_current_task_state").entered();
-                self.backend.invalidate_tasks(tasks, self);
+                self.backend.invalidate_dependent_tasks(task, tasks, self);
             }
             *stateful
         })
@@ -880,9 +995,13 @@ impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
     fn invalidate_with_reason(&self, task: TaskId, reason: StaticOrArc<dyn InvalidationReason>) {
         {
             let (_, reason_set) = &mut *self.aggregated_update.lock().unwrap();
-            reason_set.insert(reason);
+            reason_set.insert(reason.clone());
         }
-        self.backend.invalidate_task(task, self);
+        self.backend.invalidate_task_with_reason(task, reason, self);
+    }
+
+    fn get_invalidation_provenance(&self, task: TaskId) -> Option<InvalidationProvenance> {
+        self.backend.get_task_invalidation_provenance(task)
     }
 
     fn notify_scheduled_tasks(&self) {
@@ -894,7 +1013,10 @@ impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
             if tasks.is_empty() {
                 return;
             }
-            self.backend.invalidate_tasks(tasks, self);
+            match CURRENT_TASK_ID.try_with(|id| *id) {
+                Ok(task) => self.backend.invalidate_dependent_tasks(task, tasks, self),
+                Err(_) => self.backend.invalidate_tasks(tasks, self),
+            }
         });
     }
 
@@ -903,6 +1025,10 @@ impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
         task: TaskId,
         strongly_consistent: bool,
     ) -> Result<Result<RawVc, EventListener>> {
+        let strongly_consistent = strongly_consistent
+            || STRONGLY_CONSISTENT_READS
+                .try_with(|reads| reads.borrow_mut().insert(task))
+                .is_ok();
         self.backend.try_read_task_output(
             task,
             current_task("reading Vcs"),
@@ -946,6 +1072,15 @@ impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
             .try_read_own_task_cell_untracked(current_task, index, self)
     }
 
+    fn try_read_task_keyed_cell(
+        &self,
+        task: TaskId,
+        id: KeyedCellId,
+    ) -> Result<Result<CellContent, EventListener>> {
+        self.backend
+            .try_read_task_keyed_cell(task, id, current_task("reading keyed cells"), self)
+    }
+
     fn read_task_collectibles(&self, task: TaskId, trait_id: TraitTypeId) -> RawVcSetVc {
         self.backend.read_task_collectibles(
             task,
@@ -993,6 +1128,21 @@ impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
         self.backend.update_task_cell(task, index, content, self);
     }
 
+    fn read_own_task_keyed_cell(&self, task: TaskId, id: KeyedCellId) -> Result<CellContent> {
+        // INVALIDATION: don't need to track a dependency to itself
+        self.backend
+            .try_read_own_task_keyed_cell_untracked(task, id, self)
+    }
+
+    fn update_own_task_keyed_cell(
+        &self,
+        task: TaskId,
+        id: KeyedCellId,
+        content: CellContent,
+    ) -> Result<()> {
+        self.backend.update_task_keyed_cell(task, id, content, self)
+    }
+
     fn connect_task(&self, task: TaskId) {
         self.backend
             .connect_task(task, current_task("connecting task"), self);
@@ -1002,6 +1152,22 @@ impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
         self.backend.mark_own_task_as_finished(task, self);
     }
 
+    fn mark_own_task_as_session_dependent(&self, task: TaskId) {
+        self.backend.mark_own_task_as_session_dependent(task, self);
+    }
+
+    fn mark_state_as_changed(&self, state: StateId) {
+        self.backend.mark_state_as_changed(state, self);
+    }
+
+    fn is_own_task_cancelled(&self, task: TaskId) -> bool {
+        self.backend.is_own_task_cancelled(task, self)
+    }
+
+    fn listen_to_own_task_cancellation(&self, task: TaskId) -> Result<(), Option<EventListener>> {
+        self.backend.listen_to_own_task_cancellation(task, self)
+    }
+
     fn detached(
         &self,
         f: Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>,
@@ -1142,7 +1308,7 @@ impl<B: Backend + 'static> TaskIdProvider for TurboTasks<B> {
     }
 }
 
-fn current_task(from: &str) -> TaskId {
+pub(crate) fn current_task(from: &str) -> TaskId {
     match CURRENT_TASK_ID.try_with(|id| *id) {
         Ok(id) => id,
         Err(_) => panic!(
@@ -1330,6 +1496,10 @@ pub fn weak_turbo_tasks() -> Weak<dyn TurboTasksApi> {
     TURBO_TASKS.with(|arc| Arc::downgrade(arc))
 }
 
+pub(crate) fn try_weak_turbo_tasks() -> Option<Weak<dyn TurboTasksApi>> {
+    TURBO_TASKS.try_with(|arc| Arc::downgrade(arc)).ok()
+}
+
 pub fn with_turbo_tasks_for_testing<T>(
     tt: Arc<dyn TurboTasksApi>,
     current_task: TaskId,
@@ -1357,13 +1527,25 @@ pub fn current_task_for_testing() -> TaskId {
 /// based on external events.
 pub fn get_invalidator() -> Invalidator {
     let handle = Handle::current();
+    let task = current_task("turbo_tasks::get_invalidator()");
+    // Invalidations are only delivered within the current session, so the
+    // result can't be trusted after a restart.
+    with_turbo_tasks(|tt| tt.mark_own_task_as_session_dependent(task));
     Invalidator {
-        task: current_task("turbo_tasks::get_invalidator()"),
+        task,
         turbo_tasks: weak_turbo_tasks(),
         handle,
     }
 }
 
+/// Marks the current task as depending on state that doesn't survive a
+/// restart. A persistent backend will re-execute it in the next session.
+pub fn mark_session_dependent() {
+    with_turbo_tasks(|tt| {
+        tt.mark_own_task_as_session_dependent(current_task("turbo_tasks::mark_session_dependent()"))
+    });
+}
+
 /// Marks the current task as finished. This excludes it from waiting for
 /// strongly consistency.
 pub fn mark_finished() {
@@ -1372,6 +1554,53 @@ pub fn mark_finished() {
     });
 }
 
+/// The error returned when the current task execution has been cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("the task execution has been cancelled")]
+pub struct Cancelled;
+
+/// Returns true when the current execution of the task has been superseded by
+/// an invalidation. Its output and cells will be discarded, so long-running
+/// functions can stop early.
+pub fn is_cancelled() -> bool {
+    with_turbo_tasks(|tt| tt.is_own_task_cancelled(current_task("turbo_tasks::is_cancelled()")))
+}
+
+/// Returns an error when the current execution of the task has been cancelled.
+/// Use it between expensive steps as `cancellation_point()?`.
+pub fn cancellation_point() -> Result<(), Cancelled> {
+    if is_cancelled() {
+        Err(Cancelled)
+    } else {
+        Ok(())
+    }
+}
+
+/// Runs the future with the given priority. Root tasks spawned by it, e. g.
+/// via [run_once], and their child tasks are scheduled with this priority.
+pub async fn with_priority<T>(priority: TaskPriority, future: impl Future<Output = T>) -> T {
+    PRIORITY.scope(priority, future).await
+}
+
+/// Awaits the future, but stops waiting as soon as the current execution of
+/// the task has been cancelled.
+pub async fn cancellable<T>(future: impl Future<Output = T>) -> Result<T, Cancelled> {
+    let listener = with_turbo_tasks(|tt| {
+        tt.listen_to_own_task_cancellation(current_task("turbo_tasks::cancellable()"))
+    });
+    match listener {
+        Ok(()) => Err(Cancelled),
+        Err(None) => Ok(future.await),
+        Err(Some(listener)) => {
+            select! {
+                biased;
+                _ = listener => Err(Cancelled),
+                result = future => Ok(result),
+            }
+        }
+    }
+}
+
 /// Marks the current task as stateful. This prevents the tasks from being
 /// dropped without persisting the state.
 pub fn mark_stateful() {
@@ -1381,6 +1610,21 @@ pub fn mark_stateful() {
     })
 }
 
+/// Returns the id for a new [State](crate::State) of the current task. Ids are
+/// assigned in creation order, so they are stable when the task is executed
+/// again.
+pub(crate) fn next_state_id() -> StateId {
+    let task = current_task("creating a turbo_tasks::State");
+    CURRENT_TASK_STATE.with(|cell| {
+        let CurrentTaskState {
+            next_state_index, ..
+        } = &mut *cell.borrow_mut();
+        let index = *next_state_index;
+        *next_state_index += 1;
+        StateId { task, index }
+    })
+}
+
 /// Notifies scheduled tasks for execution.
 pub fn notify_scheduled_tasks() {
     with_turbo_tasks(|tt| tt.notify_scheduled_tasks())
@@ -1429,6 +1673,25 @@ pub(crate) async fn read_task_output(
     }
 }
 
+/// Waits until all tasks read by the current strongly consistent once task
+/// have settled. Changes of these tasks invalidate the once task, so it will be
+/// executed again. Repeats until no task needs to be waited for anymore.
+async fn settle_strongly_consistent_reads(this: &dyn TurboTasksApi) -> Result<()> {
+    loop {
+        let tasks = STRONGLY_CONSISTENT_READS.with(|reads| reads.borrow().clone());
+        let mut settled = true;
+        for task in tasks {
+            while let Err(listener) = this.try_read_task_output(task, true)? {
+                settled = false;
+                listener.await;
+            }
+        }
+        if settled {
+            return Ok(());
+        }
+    }
+}
+
 /// INVALIDATION: Be careful with this, it will not track dependencies, so
 /// using it could break cache invalidation.
 pub(crate) async fn read_task_output_untracked(
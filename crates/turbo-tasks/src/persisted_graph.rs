@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     backend::{CellContent, PersistentTaskType},
-    CellId, RawVc, TaskId,
+    CellId, RawVc, StateId, TaskId, TraitTypeId,
 };
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -24,6 +24,8 @@ pub struct TaskData {
     pub dependencies: Vec<RawVc>,
     pub cells: Vec<(CellId, TaskCell)>,
     pub output: RawVc,
+    /// Collectibles emitted by the task, with their emit count
+    pub collectibles: Vec<(TraitTypeId, RawVc, i32)>,
 }
 pub struct ReadTaskState {
     pub clean: bool,
@@ -32,6 +34,9 @@ pub struct ReadTaskState {
 
 pub struct PersistTaskState {
     pub externally_active: bool,
+    /// The task read state that doesn't survive a restart (e.g. it created an
+    /// invalidator), so it need to be re-executed in the next session.
+    pub session_dependent: bool,
 }
 
 /*
@@ -189,6 +194,14 @@ pub trait PersistedGraph: Sync + Send {
         api: &dyn PersistedGraphApi,
     ) -> Result<(Vec<TaskId>, Vec<TaskId>)>;
 
+    /// Stores the current values of states that have changed. States are
+    /// only known to the persisted graph after they have been serialized as
+    /// part of a task's cells, others can be ignored.
+    #[allow(unused_variables)]
+    fn persist_states(&self, states: Vec<StateId>, api: &dyn PersistedGraphApi) -> Result<()> {
+        Ok(())
+    }
+
     /// Stop operations
     #[allow(unused_variables)]
     fn stop(&self, api: &dyn PersistedGraphApi) -> Result<()> {
@@ -199,7 +212,9 @@ pub trait PersistedGraph: Sync + Send {
 pub trait PersistedGraphApi {
     fn get_or_create_task_type(&self, ty: PersistentTaskType) -> TaskId;
 
-    fn lookup_task_type(&self, id: TaskId) -> &PersistentTaskType;
+    /// Returns the task type of a task, or None when the task is not a
+    /// persistent task (e.g. a root task).
+    fn lookup_task_type(&self, id: TaskId) -> Option<&PersistentTaskType>;
 }
 
 /*
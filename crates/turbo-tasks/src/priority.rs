@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// The scheduling class of a task. Backends may run tasks of higher
+/// priorities first. Child tasks inherit the priority of their parent.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum TaskPriority {
+    /// Work nobody is waiting for, e. g. eager compilation.
+    Background,
+    #[default]
+    Normal,
+    /// Work a user is actively waiting for, e. g. an HTTP request.
+    Interactive,
+}
+
+impl TaskPriority {
+    pub const COUNT: usize = 3;
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            0 => TaskPriority::Background,
+            1 => TaskPriority::Normal,
+            2 => TaskPriority::Interactive,
+            _ => panic!("invalid task priority index {index}"),
+        }
+    }
+
+    /// Returns an index in `0..TaskPriority::COUNT`, higher priorities have
+    /// higher indices.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
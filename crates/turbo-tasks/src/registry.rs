@@ -145,3 +145,27 @@ pub fn get_trait(id: TraitTypeId) -> &'static TraitType {
 pub fn get_trait_type_global_name(id: TraitTypeId) -> &'static str {
     &TRAIT_TYPES.get(*id).unwrap().1
 }
+
+/// Returns the global names of all registered functions, value types and
+/// traits in a stable order.
+///
+/// Serialized data refers to these items by name, so a change in this list
+/// indicates that previously serialized data might no longer be readable.
+pub fn registered_global_names() -> Vec<String> {
+    let mut names = FUNCTIONS_BY_NAME
+        .iter()
+        .map(|e| format!("function {}", e.key()))
+        .chain(
+            VALUE_TYPES_BY_NAME
+                .iter()
+                .map(|e| format!("value {}", e.key())),
+        )
+        .chain(
+            TRAIT_TYPES_BY_NAME
+                .iter()
+                .map(|e| format!("trait {}", e.key())),
+        )
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
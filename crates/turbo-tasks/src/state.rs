@@ -1,17 +1,105 @@
 use std::{
+    cell::RefCell,
     fmt::Debug,
-    mem::take,
+    mem::{take, ManuallyDrop},
     ops::{Deref, DerefMut},
+    sync::{Arc, Weak},
 };
 
 use auto_hash_map::AutoSet;
 use parking_lot::{Mutex, MutexGuard};
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 
-use crate::{get_invalidator, mark_stateful, trace::TraceRawVcs, Invalidator};
+use crate::{
+    get_invalidator,
+    id::TemporarySwapGuard,
+    manager::{next_state_id, try_weak_turbo_tasks},
+    mark_stateful,
+    trace::TraceRawVcs,
+    Invalidator, TaskId, TurboTasksApi,
+};
+
+/// Identifies a [State] by the task that created it and the order of
+/// creation, so it's stable across executions and sessions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct StateId {
+    pub task: TaskId,
+    pub index: u32,
+}
+
+/// Type-erased access to the value of a [State], so a persisted graph can
+/// store it independently of the task that created it.
+pub trait StateContent: Send + Sync {
+    /// Locks the state and returns its current value.
+    fn serializable(&self) -> Box<dyn erased_serde::Serialize + '_>;
+}
+
+impl Serialize for dyn StateContent {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Serialize::serialize(&*self.serializable(), serializer)
+    }
+}
+
+/// Restores the value of a [State] from a deserializer.
+pub type RestoreStateFn<'a> = dyn FnMut(
+        &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Arc<dyn StateContent>, erased_serde::Error>
+    + 'a;
+
+/// Stores the values of [State]s while task data is (de)serialized.
+///
+/// A serialized [State] only contains its [StateId]. The value is stored
+/// separately, so it can be updated when the state changes without
+/// serializing the task data again.
+pub trait StateMapping {
+    /// A [State] has been serialized. `content` gives access to the value
+    /// now and when it changes later.
+    fn serialize_state(&self, id: StateId, content: Arc<dyn StateContent>);
+
+    /// Restores the value of a [State] by calling `restore` with a
+    /// deserializer for the stored value. Returns false when no value is
+    /// stored or it can't be restored.
+    fn deserialize_state(&self, id: StateId, restore: &mut RestoreStateFn<'_>) -> bool;
+}
+
+impl<M> StateMapping for &M
+where
+    M: StateMapping,
+{
+    fn serialize_state(&self, id: StateId, content: Arc<dyn StateContent>) {
+        (**self).serialize_state(id, content)
+    }
+
+    fn deserialize_state(&self, id: StateId, restore: &mut RestoreStateFn<'_>) -> bool {
+        (**self).deserialize_state(id, restore)
+    }
+}
+
+thread_local! {
+    static STATE_MAPPING: RefCell<Option<Box<dyn StateMapping>>> = RefCell::new(None);
+}
+
+pub fn with_state_mapping<'a, T, M>(mapping: M, func: impl FnOnce() -> T) -> T
+where
+    M: StateMapping + 'a,
+{
+    STATE_MAPPING.with(|cell| {
+        let dyn_box: Box<dyn StateMapping + 'a> = Box::new(mapping);
+        // SAFETY: We cast to 'static lifetime, but it's still safe since we remove the
+        // value again before the lifetime ends. So as long nobody copies the
+        // value it's safe. The thread_local is private, so nobody can use it
+        // except for this module.
+        let static_box: Box<dyn StateMapping + 'static> = unsafe { std::mem::transmute(dyn_box) };
+        let old = std::mem::replace(&mut *cell.borrow_mut(), Some(static_box));
+        let _swap_guard = TemporarySwapGuard(cell, ManuallyDrop::new(old));
+        func()
+    })
+}
 
 pub struct State<T> {
-    inner: Mutex<StateInner<T>>,
+    id: StateId,
+    turbo_tasks: Option<Weak<dyn TurboTasksApi>>,
+    inner: Arc<Mutex<StateInner<T>>>,
 }
 
 struct StateInner<T> {
@@ -19,8 +107,24 @@ struct StateInner<T> {
     invalidators: AutoSet<Invalidator>,
 }
 
+struct SerializableValue<'a, T>(MutexGuard<'a, StateInner<T>>);
+
+impl<T: Serialize> Serialize for SerializableValue<'_, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.value.serialize(serializer)
+    }
+}
+
+impl<T: Serialize + Send> StateContent for Mutex<StateInner<T>> {
+    fn serializable(&self) -> Box<dyn erased_serde::Serialize + '_> {
+        Box::new(SerializableValue(self.lock()))
+    }
+}
+
 pub struct StateRef<'a, T> {
-    inner: MutexGuard<'a, StateInner<T>>,
+    state: &'a State<T>,
+    /// Released in [Drop] before invalidating the readers.
+    inner: ManuallyDrop<MutexGuard<'a, StateInner<T>>>,
     mutated: bool,
 }
 
@@ -52,19 +156,50 @@ impl<T> PartialEq for State<T> {
 }
 impl<T> Eq for State<T> {}
 
-impl<T> Serialize for State<T> {
-    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
-        // For this to work at all we need to do more. Changing the the state need to
-        // invalidate the serialization of the task that contains the state. So we
-        // probably need to store the state outside of the task to be able to serialize
-        // it independent from the creating task.
-        panic!("State serialization is not implemented yet");
+impl<T: Serialize + Send + 'static> Serialize for State<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        STATE_MAPPING.with(|cell| {
+            if let Some(mapping) = cell.borrow().as_ref() {
+                mapping.serialize_state(self.id, self.inner.clone());
+            }
+        });
+        self.id.serialize(serializer)
     }
 }
 
-impl<'de, T> Deserialize<'de> for State<T> {
-    fn deserialize<D: Deserializer<'de>>(_deserializer: D) -> Result<Self, D::Error> {
-        panic!("State serialization is not implemented yet");
+impl<'de, T: Serialize + DeserializeOwned + Send + 'static> Deserialize<'de> for State<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = StateId::deserialize(deserializer)?;
+        let mut state = None;
+        let restored = STATE_MAPPING.with(|cell| {
+            let mapping = cell.borrow();
+            let Some(mapping) = mapping.as_ref() else {
+                return false;
+            };
+            mapping.deserialize_state(
+                id,
+                &mut |deserializer: &mut dyn erased_serde::Deserializer<'_>| {
+                    let value: T = erased_serde::deserialize(deserializer)?;
+                    let restored = State {
+                        id,
+                        turbo_tasks: try_weak_turbo_tasks(),
+                        inner: Arc::new(Mutex::new(StateInner {
+                            value,
+                            invalidators: AutoSet::new(),
+                        })),
+                    };
+                    let content: Arc<dyn StateContent> = restored.inner.clone();
+                    state = Some(restored);
+                    Ok(content)
+                },
+            )
+        });
+        match state {
+            Some(state) if restored => Ok(state),
+            _ => Err(serde::de::Error::custom(format!(
+                "the value of {id:?} is not available"
+            ))),
+        }
     }
 }
 
@@ -81,10 +216,28 @@ impl<T> State<T> {
     pub fn new(value: T) -> Self {
         mark_stateful();
         Self {
-            inner: Mutex::new(StateInner {
+            id: next_state_id(),
+            turbo_tasks: try_weak_turbo_tasks(),
+            inner: Arc::new(Mutex::new(StateInner {
                 value,
                 invalidators: AutoSet::new(),
-            }),
+            })),
+        }
+    }
+
+    pub fn id(&self) -> StateId {
+        self.id
+    }
+
+    /// Tells the backend that the value has changed, so it can be persisted.
+    fn mark_as_changed(&self) {
+        if let Some(turbo_tasks) = self
+            .turbo_tasks
+            .as_ref()
+            .and_then(|turbo_tasks| turbo_tasks.upgrade())
+            .or_else(|| try_weak_turbo_tasks().and_then(|turbo_tasks| turbo_tasks.upgrade()))
+        {
+            turbo_tasks.mark_state_as_changed(self.id);
         }
     }
 
@@ -96,7 +249,8 @@ impl<T> State<T> {
         let mut inner = self.inner.lock();
         inner.invalidators.insert(invalidator);
         StateRef {
-            inner,
+            state: self,
+            inner: ManuallyDrop::new(inner),
             mutated: false,
         }
     }
@@ -105,7 +259,8 @@ impl<T> State<T> {
     pub fn get_untracked(&self) -> StateRef<'_, T> {
         let inner = self.inner.lock();
         StateRef {
-            inner,
+            state: self,
+            inner: ManuallyDrop::new(inner),
             mutated: false,
         }
     }
@@ -118,6 +273,8 @@ impl<T> State<T> {
         for invalidator in take(&mut inner.invalidators) {
             invalidator.invalidate();
         }
+        drop(inner);
+        self.mark_as_changed();
     }
 
     /// Updates the current state with the `update` function. The `update`
@@ -132,6 +289,8 @@ impl<T> State<T> {
         for invalidator in take(&mut inner.invalidators) {
             invalidator.invalidate();
         }
+        drop(inner);
+        self.mark_as_changed();
     }
 }
 
@@ -147,6 +306,8 @@ impl<T: PartialEq> State<T> {
         for invalidator in take(&mut inner.invalidators) {
             invalidator.invalidate();
         }
+        drop(inner);
+        self.mark_as_changed();
     }
 }
 
@@ -167,10 +328,20 @@ impl<'a, T> DerefMut for StateRef<'a, T> {
 
 impl<'a, T> Drop for StateRef<'a, T> {
     fn drop(&mut self) {
+        let invalidators = if self.mutated {
+            take(&mut self.inner.invalidators)
+        } else {
+            AutoSet::new()
+        };
+        // Invalidators and the backend might read the state again, so the lock
+        // must be released first.
+        // SAFETY: `inner` is not used after this.
+        unsafe { ManuallyDrop::drop(&mut self.inner) };
         if self.mutated {
-            for invalidator in take(&mut self.inner.invalidators) {
+            for invalidator in invalidators {
                 invalidator.invalidate();
             }
+            self.state.mark_as_changed();
         }
     }
 }
@@ -8,6 +8,7 @@ use std::{
 
 use pin_project_lite::pin_project;
 use tokio::{task::futures::TaskLocalFuture, task_local};
+use turbo_tasks_malloc::{AllocationInfo, TurboMalloc};
 
 task_local! {
     static EXTRA_DURATION: Arc<Mutex<Duration>>;
@@ -19,6 +20,7 @@ pin_project! {
         #[pin]
         future: TaskLocalFuture<Arc<Mutex<Duration>>, F>,
         duration: Duration,
+        allocations: AllocationInfo,
     }
 }
 
@@ -29,6 +31,7 @@ impl<T, F: Future<Output = T>> TimedFuture<T, F> {
             future: EXTRA_DURATION.scope(cell.clone(), future),
             cell,
             duration: Duration::ZERO,
+            allocations: AllocationInfo::default(),
         }
     }
 }
@@ -38,12 +41,16 @@ pub fn add_duration(duration: Duration) {
 }
 
 impl<T, F: Future<Output = T>> Future for TimedFuture<T, F> {
-    type Output = (T, Duration, Instant);
+    type Output = (T, Duration, Instant, AllocationInfo);
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
         let start = Instant::now();
+        // Polls don't move between threads, so the allocations of this thread
+        // during the poll are the allocations of the future.
+        let allocation_counters = TurboMalloc::allocation_counters();
         let result = this.future.poll(cx);
+        *this.allocations += allocation_counters.until_now();
         let elapsed = start.elapsed();
         *this.duration += elapsed;
         match result {
@@ -51,6 +58,7 @@ impl<T, F: Future<Output = T>> Future for TimedFuture<T, F> {
                 r,
                 *this.duration + *this.cell.lock().unwrap(),
                 start + elapsed,
+                *this.allocations,
             )),
             Poll::Pending => Poll::Pending,
         }
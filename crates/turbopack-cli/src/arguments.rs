@@ -82,6 +82,13 @@ pub struct DevArguments {
     #[clap(long)]
     pub no_open: bool,
 
+    /// Persist computed results in `.turbopack/cache` of the application
+    /// directory, so the next start can reuse them. Experimental, can't be
+    /// combined with `--memory-limit` and doesn't serve the
+    /// `__turbo_tasks__` view.
+    #[clap(long, conflicts_with = "memory_limit")]
+    pub persistent_caching: bool,
+
     // ==
     // = Inherited options from next-dev, need revisit later.
     // ==
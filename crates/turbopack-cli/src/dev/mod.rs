@@ -4,7 +4,7 @@ use std::{
     future::{join, Future},
     io::{stdout, Write},
     net::{IpAddr, SocketAddr},
-    path::{PathBuf, MAIN_SEPARATOR},
+    path::{Path, PathBuf, MAIN_SEPARATOR},
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -13,12 +13,15 @@ use anyhow::{Context, Result};
 use dunce::canonicalize;
 use owo_colors::OwoColorize;
 use turbo_tasks::{
+    backend::Backend,
     util::{FormatBytes, FormatDuration},
-    StatsType, TransientInstance, TurboTasks, TurboTasksBackendApi, UpdateInfo, Value,
+    StatsType, TransientInstance, TurboTasks, TurboTasksApi, TurboTasksBackendApi, UpdateInfo,
+    Value,
 };
+use turbo_tasks_disk::DiskPersistedGraph;
 use turbo_tasks_fs::{DiskFileSystemVc, FileSystem, FileSystemVc};
 use turbo_tasks_malloc::TurboMalloc;
-use turbo_tasks_memory::MemoryBackend;
+use turbo_tasks_memory::{MemoryBackend, MemoryBackendWithPersistedGraph};
 use turbopack::evaluate_context::node_build_environment;
 use turbopack_cli_utils::issue::{ConsoleUiVc, LogOptions};
 use turbopack_core::{
@@ -53,7 +56,8 @@ pub enum EntryRequest {
 }
 
 pub struct TurbopackDevServerBuilder {
-    turbo_tasks: Arc<TurboTasks<MemoryBackend>>,
+    turbo_tasks: Arc<dyn TurboTasksApi>,
+    turbo_tasks_viz: Option<Arc<TurboTasks<MemoryBackend>>>,
     project_dir: String,
     root_dir: String,
     entry_requests: Vec<EntryRequest>,
@@ -70,12 +74,13 @@ pub struct TurbopackDevServerBuilder {
 
 impl TurbopackDevServerBuilder {
     pub fn new(
-        turbo_tasks: Arc<TurboTasks<MemoryBackend>>,
+        turbo_tasks: Arc<dyn TurboTasksApi>,
         project_dir: String,
         root_dir: String,
     ) -> TurbopackDevServerBuilder {
         TurbopackDevServerBuilder {
             turbo_tasks,
+            turbo_tasks_viz: None,
             project_dir,
             root_dir,
             entry_requests: vec![],
@@ -138,6 +143,15 @@ impl TurbopackDevServerBuilder {
         self
     }
 
+    /// Serves the task graph visualization under `/__turbo_tasks__/`.
+    pub fn turbo_tasks_viz(
+        mut self,
+        turbo_tasks: Arc<TurboTasks<MemoryBackend>>,
+    ) -> TurbopackDevServerBuilder {
+        self.turbo_tasks_viz = Some(turbo_tasks);
+        self
+    }
+
     pub fn issue_reporter(
         mut self,
         issue_reporter: Box<dyn IssueReporterProvider>,
@@ -193,6 +207,7 @@ impl TurbopackDevServerBuilder {
         let server = self.find_port(host, port, 10)?;
 
         let turbo_tasks = self.turbo_tasks;
+        let turbo_tasks_viz = self.turbo_tasks_viz;
         let project_dir = self.project_dir;
         let root_dir = self.root_dir;
         let eager_compile = self.eager_compile;
@@ -207,25 +222,27 @@ impl TurbopackDevServerBuilder {
             log_level: self.log_level,
         });
         let entry_requests = Arc::new(self.entry_requests);
-        let tasks = turbo_tasks.clone();
         let issue_provider = self.issue_reporter.unwrap_or_else(|| {
             // Initialize a ConsoleUi reporter if no custom reporter was provided
             Box::new(move || ConsoleUiVc::new(log_args.clone().into()).into())
         });
 
         let source = move || {
-            source(
+            let source = source(
                 root_dir.clone(),
                 project_dir.clone(),
                 entry_requests.clone().into(),
                 eager_compile,
-                turbo_tasks.clone().into(),
                 browserslist_query.clone(),
-            )
+            );
+            match &turbo_tasks_viz {
+                Some(turbo_tasks) => with_turbo_tasks_viz(source, turbo_tasks.clone().into()),
+                None => source,
+            }
         };
 
         let issue_reporter_arc = Arc::new(move || issue_provider.get_issue_reporter());
-        Ok(server.serve(tasks, source, issue_reporter_arc))
+        Ok(server.serve(turbo_tasks, source, issue_reporter_arc))
     }
 }
 
@@ -250,7 +267,6 @@ async fn source(
     project_dir: String,
     entry_requests: TransientInstance<Vec<EntryRequest>>,
     eager_compile: bool,
-    turbo_tasks: TransientInstance<TurboTasks<MemoryBackend>>,
     browserslist_query: String,
 ) -> Result<ContentSourceVc> {
     let output_fs = output_fs(&project_dir);
@@ -297,11 +313,6 @@ async fn source(
         eager_compile,
         &browserslist_query,
     );
-    let viz = turbo_tasks_viz::TurboTasksSource {
-        turbo_tasks: turbo_tasks.into(),
-    }
-    .cell()
-    .into();
     let static_source =
         StaticAssetsContentSourceVc::new(String::new(), project_path.join("public")).into();
     let main_source = CombinedContentSourceVc::new(vec![static_source, web_source]);
@@ -315,7 +326,6 @@ async fn source(
     let source = RouterContentSource {
         routes: vec![
             ("__turbopack__/".to_string(), introspect),
-            ("__turbo_tasks__/".to_string(), viz),
             ("__turbopack_sourcemap__/".to_string(), source_maps),
         ],
         fallback: main_source,
@@ -326,6 +336,24 @@ async fn source(
     Ok(source)
 }
 
+#[turbo_tasks::function]
+fn with_turbo_tasks_viz(
+    source: ContentSourceVc,
+    turbo_tasks: TransientInstance<TurboTasks<MemoryBackend>>,
+) -> ContentSourceVc {
+    let viz = turbo_tasks_viz::TurboTasksSource {
+        turbo_tasks: turbo_tasks.into(),
+    }
+    .cell()
+    .into();
+    RouterContentSource {
+        routes: vec![("__turbo_tasks__/".to_string(), viz)],
+        fallback: source,
+    }
+    .cell()
+    .into()
+}
+
 pub fn register() {
     turbopack::register();
     include!(concat!(env!("OUT_DIR"), "/register.rs"));
@@ -360,12 +388,49 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
         dir.clone()
     };
 
-    let tt = TurboTasks::new(MemoryBackend::new(
-        args.common
-            .memory_limit
-            .map_or(usize::MAX, |l| l * 1024 * 1024),
-    ));
+    if args.persistent_caching {
+        // The persisted graph backend doesn't implement garbage collection or
+        // the task introspection the `__turbo_tasks__` view needs
+        let cache_dir = Path::new(&dir).join(".turbopack/cache");
+        let tt = TurboTasks::new(MemoryBackendWithPersistedGraph::new(
+            DiskPersistedGraph::new(cache_dir, &executable_version())
+                .context("unable to open the persistent cache")?,
+        ));
+        run_server(args, start, dir, root_dir, tt, None).await
+    } else {
+        let tt = TurboTasks::new(MemoryBackend::new(
+            args.common
+                .memory_limit
+                .map_or(usize::MAX, |l| l * 1024 * 1024),
+        ));
+        let viz = tt.clone();
+        run_server(args, start, dir, root_dir, tt, Some(viz)).await
+    }
+}
+
+/// Identifies the build of the running executable. Data persisted by a
+/// different build is discarded, as any task implementation might have
+/// changed.
+fn executable_version() -> String {
+    let metadata = std::env::current_exe().and_then(|exe| exe.metadata());
+    let modified = metadata
+        .as_ref()
+        .ok()
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |modified| modified.as_nanos());
+    let len = metadata.map_or(0, |metadata| metadata.len());
+    format!("{}-{len}-{modified}", env!("CARGO_PKG_VERSION"))
+}
 
+async fn run_server<B: Backend + 'static>(
+    args: &DevArguments,
+    start: Instant,
+    dir: String,
+    root_dir: String,
+    tt: Arc<TurboTasks<B>>,
+    turbo_tasks_viz: Option<Arc<TurboTasks<MemoryBackend>>>,
+) -> Result<()> {
     let stats_type = match args.common.full_stats {
         true => StatsType::Full,
         false => StatsType::Essential,
@@ -373,8 +438,8 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
     tt.set_stats_type(stats_type);
 
     let tt_clone = tt.clone();
+    let tt_stop = tt.clone();
 
-    #[allow(unused_mut)]
     let mut server = TurbopackDevServerBuilder::new(tt, dir, root_dir)
         .entry_request(EntryRequest::Relative("src/index".into()))
         .eager_compile(args.eager_compile)
@@ -393,6 +458,10 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
         server = server.allow_retry(args.allow_retry);
     }
 
+    if let Some(turbo_tasks) = turbo_tasks_viz {
+        server = server.turbo_tasks_viz(turbo_tasks);
+    }
+
     let server = server.build().await?;
 
     {
@@ -489,7 +558,19 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
         }
     };
 
-    join!(stats_future, async { server.future.await.unwrap() }).await;
+    let server_future = async { server.future.await.unwrap() };
+    if args.persistent_caching {
+        // Stop gracefully on Ctrl-C, so everything computed so far is persisted
+        tokio::select! {
+            _ = join!(stats_future, server_future) => {}
+            _ = tokio::signal::ctrl_c() => {
+                println!("\x1b[2K{} - saving the persistent cache...", "event".purple());
+                tt_stop.stop_and_wait().await;
+            }
+        }
+    } else {
+        join!(stats_future, server_future).await;
+    }
 
     Ok(())
 }
@@ -497,7 +578,10 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
 #[cfg(feature = "profile")]
 // When profiling, exits the process when no new updates have been received for
 // a given timeout and there are no more tasks in progress.
-async fn profile_timeout<T>(tt: &TurboTasks<MemoryBackend>, future: impl Future<Output = T>) -> T {
+async fn profile_timeout<B: Backend + 'static, T>(
+    tt: &TurboTasks<B>,
+    future: impl Future<Output = T>,
+) -> T {
     /// How long to wait in between updates before force-exiting the process
     /// during profiling.
     const PROFILE_EXIT_TIMEOUT: Duration = Duration::from_secs(5);
@@ -516,8 +600,8 @@ async fn profile_timeout<T>(tt: &TurboTasks<MemoryBackend>, future: impl Future<
 }
 
 #[cfg(not(feature = "profile"))]
-fn profile_timeout<T>(
-    _tt: &TurboTasks<MemoryBackend>,
+fn profile_timeout<B: Backend + 'static, T>(
+    _tt: &TurboTasks<B>,
     future: impl Future<Output = T>,
 ) -> impl Future<Output = T> {
     future
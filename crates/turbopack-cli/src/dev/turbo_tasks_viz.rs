@@ -2,7 +2,9 @@ use std::{sync::Arc, time::Duration};
 
 use anyhow::Result;
 use mime::TEXT_HTML_UTF_8;
-use turbo_tasks::{get_invalidator, TurboTasks, TurboTasksBackendApi, Value};
+use turbo_tasks::{
+    backend::Backend, get_invalidator, TurboTasks, TurboTasksApi, TurboTasksBackendApi, Value,
+};
 use turbo_tasks_fs::File;
 use turbo_tasks_memory::{
     stats::{ReferenceType, Stats},
@@ -82,9 +84,10 @@ impl ContentSource for TurboTasksSource {
                             (include_unloaded || !info.unloaded) && (!active_only || info.active)
                         });
                     });
+                    let value_types = viz::table::create_value_type_table(&stats);
                     let tree = stats.treeify(ReferenceType::Dependency);
                     let table = viz::table::create_table(tree, tt.stats_type());
-                    viz::table::wrap_html(&table)
+                    viz::table::wrap_html(&format!("{table}{value_types}"))
                 } else {
                     return Ok(ContentSourceResultVc::need_data(Value::new(NeededData {
                         source: self_vc.into(),
@@ -96,6 +99,19 @@ impl ContentSource for TurboTasksSource {
                     })));
                 }
             }
+            "invalidations" => {
+                let b = tt.backend();
+                let mut executions = Vec::new();
+                b.with_all_cached_tasks(|task| {
+                    if let Some(provenance) = tt.get_invalidation_provenance(task) {
+                        executions.push((task, provenance));
+                    }
+                });
+                let list = viz::invalidations::create_list(executions, |task| {
+                    b.get_task_description(task)
+                });
+                viz::invalidations::wrap_html(&list)
+            }
             "reset" => {
                 let b = tt.backend();
                 b.with_all_cached_tasks(|task| {
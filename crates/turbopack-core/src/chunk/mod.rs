@@ -516,6 +516,9 @@ where
         let context = self.context;
 
         async move {
+            // Large graphs take a while to traverse, so stop early when the chunk has
+            // been invalidated in the meantime.
+            turbo_tasks::cancellation_point()?;
             let Some(chunk_item) = chunk_item else {
                 return Ok(vec![].into_iter().flatten());
             };
@@ -524,6 +524,9 @@ async fn find_package(
                 // For clippy -- This explicit deref is necessary
                 let root = &*root_vc.await?;
                 while context_value.is_inside(root) {
+                    // Walking up the directory tree can take many steps, so stop early when
+                    // the request has been invalidated in the meantime.
+                    turbo_tasks::cancellation_point()?;
                     for name in names.iter() {
                         let fs_path = context.join(name);
                         if let Some(fs_path) = dir_exists(fs_path, &mut references).await? {
@@ -1041,6 +1044,7 @@ async fn resolve_module_request(
     // "[baseUrl]/foo/bar" or "[baseUrl]/node_modules/foo/bar", and we'll need to
     // try both.
     for package_path in &result.packages {
+        turbo_tasks::cancellation_point()?;
         if is_match {
             results.push(resolve_into_folder(*package_path, options).await?);
         }
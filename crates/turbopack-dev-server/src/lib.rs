@@ -27,8 +27,8 @@ use hyper::{
 use socket2::{Domain, Protocol, Socket, Type};
 use tracing::{event, info_span, Instrument, Level, Span};
 use turbo_tasks::{
-    run_once_with_reason, trace::TraceRawVcs, util::FormatDuration, CollectiblesSource, RawVc,
-    TransientInstance, TransientValue, TurboTasksApi,
+    run_once_with_reason, trace::TraceRawVcs, util::FormatDuration, with_priority,
+    CollectiblesSource, RawVc, TaskPriority, TransientInstance, TransientValue, TurboTasksApi,
 };
 use turbopack_core::{
     error::PrettyPrintError,
@@ -222,7 +222,8 @@ impl DevServerBuilder {
                         .await
                     };
                     async move {
-                        match future.await {
+                        // Work for requests is scheduled before background work
+                        match with_priority(TaskPriority::Interactive, future).await {
                             Ok(r) => Ok::<_, hyper::http::Error>(r),
                             Err(e) => {
                                 println!(
@@ -92,6 +92,7 @@ impl EcmascriptDevChunkContentVc {
         )?;
 
         for (id, entry) in this.entries.await?.iter() {
+            turbo_tasks::cancellation_point()?;
             write!(code, "\n{}: ", StringifyJs(&id))?;
             code.push_code(&*entry.code.await?);
             write!(code, ",")?;
@@ -604,6 +604,9 @@ async fn gen_content_with_visitors(
         ..
     } = &*parsed
     {
+        // Applying the visitors and printing is expensive, so skip it when the module
+        // has changed again in the meantime.
+        turbo_tasks::cancellation_point()?;
         let mut program = program.clone();
 
         GLOBALS.set(globals, || {
@@ -341,6 +341,9 @@ async fn parse_content(
                 file_path: fs_path_vc,
             };
             for transform in transforms.iter() {
+                // Transforms are expensive, so stop early when the source has
+                // changed again in the meantime.
+                turbo_tasks::cancellation_point()?;
                 transform.apply(&mut parsed_program, &context).await?;
             }
 
@@ -270,19 +270,20 @@ fn node_file_trace_memory(#[case] input: CaseInput) {
 
 #[cfg(feature = "test_persistent_cache")]
 #[apply(test_cases)]
-fn node_file_trace_rocksdb(#[case] input: CaseInput) {
+fn node_file_trace_disk(#[case] input: CaseInput) {
+    use turbo_tasks_disk::DiskPersistedGraph;
     use turbo_tasks_memory::MemoryBackendWithPersistedGraph;
-    use turbo_tasks_rocksdb::RocksDbPersistedGraph;
 
     node_file_trace(
         input,
-        "rockdb",
+        "disk",
         false,
         2,
         240,
         |directory_path| {
             TurboTasks::new(MemoryBackendWithPersistedGraph::new(
-                RocksDbPersistedGraph::new(directory_path.join(".db")).unwrap(),
+                DiskPersistedGraph::new(directory_path.join(".db"), env!("CARGO_PKG_VERSION"))
+                    .unwrap(),
             ))
         },
         |_| {},
@@ -0,0 +1,158 @@
+use std::{collections::HashMap, str::FromStr};
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TokenParseError {
+    #[error("expected a token in the form <team>:<token>, got '{0}'")]
+    MissingSeparator(String),
+    #[error("team '{0}' may only contain alphanumeric characters, '-' and '_'")]
+    InvalidTeam(String),
+    #[error("token for team '{0}' is empty")]
+    EmptyToken(String),
+}
+
+/// A bearer token that grants access to a single team's artifacts.
+///
+/// Parsed from `<team>:<token>`. The team is used as the directory name
+/// that the team's artifacts are stored under, and is matched against both
+/// the `teamId` and `slug` query parameters sent by clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TeamToken {
+    pub team: String,
+    pub token: String,
+}
+
+impl FromStr for TeamToken {
+    type Err = TokenParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (team, token) = s
+            .split_once(':')
+            .ok_or_else(|| TokenParseError::MissingSeparator(s.to_string()))?;
+
+        if team.is_empty()
+            || !team
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(TokenParseError::InvalidTeam(team.to_string()));
+        }
+        if token.is_empty() {
+            return Err(TokenParseError::EmptyToken(team.to_string()));
+        }
+
+        Ok(Self {
+            team: team.to_string(),
+            token: token.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("missing or malformed Authorization header")]
+    MissingToken,
+    #[error("invalid token")]
+    InvalidToken,
+    #[error("token does not have access to team '{0}'")]
+    Forbidden(String),
+}
+
+#[derive(Debug, Default)]
+pub struct TokenStore {
+    teams_by_token: HashMap<String, String>,
+}
+
+impl TokenStore {
+    pub fn new(tokens: impl IntoIterator<Item = TeamToken>) -> Self {
+        Self {
+            teams_by_token: tokens
+                .into_iter()
+                .map(|TeamToken { team, token }| (token, team))
+                .collect(),
+        }
+    }
+
+    /// Resolves the team for a request given its `Authorization` header and
+    /// the team the client asked for, if any.
+    pub fn authorize(
+        &self,
+        authorization: Option<&str>,
+        requested_team: Option<&str>,
+    ) -> Result<&str, AuthError> {
+        let token = authorization
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .ok_or(AuthError::MissingToken)?;
+        let team = self
+            .teams_by_token
+            .get(token)
+            .ok_or(AuthError::InvalidToken)?;
+
+        match requested_team {
+            Some(requested_team) if requested_team != team => {
+                Err(AuthError::Forbidden(requested_team.to_string()))
+            }
+            _ => Ok(team),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_team_token() {
+        assert_eq!(
+            "team_a:secret:with:colons".parse(),
+            Ok(TeamToken {
+                team: "team_a".to_string(),
+                token: "secret:with:colons".to_string(),
+            })
+        );
+        assert_eq!(
+            "no-separator".parse::<TeamToken>(),
+            Err(TokenParseError::MissingSeparator(
+                "no-separator".to_string()
+            ))
+        );
+        assert_eq!(
+            "../etc:secret".parse::<TeamToken>(),
+            Err(TokenParseError::InvalidTeam("../etc".to_string()))
+        );
+        assert_eq!(
+            "team_a:".parse::<TeamToken>(),
+            Err(TokenParseError::EmptyToken("team_a".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_authorize() {
+        let tokens = TokenStore::new([
+            "team_a:token_a".parse().unwrap(),
+            "team_b:token_b".parse().unwrap(),
+        ]);
+
+        assert_eq!(tokens.authorize(Some("Bearer token_a"), None), Ok("team_a"));
+        assert_eq!(
+            tokens.authorize(Some("Bearer token_b"), Some("team_b")),
+            Ok("team_b")
+        );
+        assert_eq!(
+            tokens.authorize(Some("Bearer token_a"), Some("team_b")),
+            Err(AuthError::Forbidden("team_b".to_string()))
+        );
+        assert_eq!(
+            tokens.authorize(Some("Bearer nope"), None),
+            Err(AuthError::InvalidToken)
+        );
+        assert_eq!(
+            tokens.authorize(Some("token_a"), None),
+            Err(AuthError::MissingToken)
+        );
+        assert_eq!(tokens.authorize(None, None), Err(AuthError::MissingToken));
+    }
+}
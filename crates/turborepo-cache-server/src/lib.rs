@@ -0,0 +1,430 @@
+//! A self-hostable remote cache server that speaks the same `/v8/artifacts`
+//! protocol as the Vercel remote cache, backed by a local directory.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{
+    body::{Bytes, StreamBody},
+    extract::{DefaultBodyLimit, FromRequestParts, Path, Query, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE},
+        request::Parts,
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio_util::io::ReaderStream;
+use tracing::{error, info};
+use turbopath::AbsoluteSystemPathBuf;
+use turborepo_api_client::{AnalyticsEvent, CachingStatus, CachingStatusResponse};
+
+pub use crate::{
+    auth::{AuthError, TeamToken, TokenParseError, TokenStore},
+    store::{ArtifactMetadata, ArtifactStore, StoreError},
+};
+
+mod auth;
+mod store;
+
+pub const ARTIFACT_DURATION_HEADER: &str = "x-artifact-duration";
+pub const ARTIFACT_TAG_HEADER: &str = "x-artifact-tag";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+    #[error("artifact not found")]
+    NotFound,
+    #[error("invalid {0} header")]
+    InvalidHeader(&'static str),
+    #[error("invalid query parameters: {0}")]
+    InvalidQuery(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("background task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+/// Error body in the same shape as the Vercel API, so that clients can
+/// report it the same way.
+#[derive(Debug, Serialize)]
+struct ApiError {
+    code: &'static str,
+    message: String,
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            Error::Auth(AuthError::MissingToken | AuthError::InvalidToken) => {
+                (StatusCode::UNAUTHORIZED, "forbidden")
+            }
+            Error::Auth(AuthError::Forbidden(_)) => (StatusCode::FORBIDDEN, "forbidden"),
+            Error::Store(StoreError::InvalidHash(_))
+            | Error::InvalidHeader(_)
+            | Error::InvalidQuery(_) => (StatusCode::BAD_REQUEST, "bad_request"),
+            Error::Store(StoreError::TooLarge { .. }) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large")
+            }
+            Error::NotFound => (StatusCode::NOT_FOUND, "not_found"),
+            Error::Store(StoreError::Io(_) | StoreError::Metadata(_))
+            | Error::Io(_)
+            | Error::Join(_) => {
+                error!("{}", self);
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal_server_error")
+            }
+        };
+
+        (
+            status,
+            Json(ApiError {
+                code,
+                message: self.to_string(),
+            }),
+        )
+            .into_response()
+    }
+}
+
+pub struct AppState {
+    store: Arc<ArtifactStore>,
+    tokens: TokenStore,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TeamParams {
+    team_id: Option<String>,
+    slug: Option<String>,
+}
+
+/// The team that an authenticated request operates on.
+struct Team(String);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for Team {
+    type Rejection = Error;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<TeamParams>::from_request_parts(parts, state)
+            .await
+            .map_err(|err| Error::InvalidQuery(err.to_string()))?;
+        let authorization = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        let requested_team = params.team_id.as_deref().or(params.slug.as_deref());
+
+        let team = state.tokens.authorize(authorization, requested_team)?;
+        Ok(Team(team.to_string()))
+    }
+}
+
+pub fn app(store: ArtifactStore, tokens: TokenStore) -> Router {
+    let body_limit = match store.max_size() {
+        Some(max_size) => DefaultBodyLimit::max(max_size.try_into().unwrap_or(usize::MAX)),
+        None => DefaultBodyLimit::disable(),
+    };
+    let state = Arc::new(AppState {
+        store: Arc::new(store),
+        tokens,
+    });
+
+    Router::new()
+        .route("/v8/artifacts/status", get(get_caching_status))
+        .route("/v8/artifacts/events", post(record_events))
+        .route(
+            "/v8/artifacts/:hash",
+            get(get_artifact).head(artifact_exists).put(put_artifact),
+        )
+        .layer(body_limit)
+        .with_state(state)
+}
+
+async fn get_caching_status(_team: Team) -> Json<CachingStatusResponse> {
+    Json(CachingStatusResponse {
+        status: CachingStatus::Enabled,
+    })
+}
+
+async fn record_events(Team(team): Team, Json(events): Json<Vec<AnalyticsEvent>>) -> StatusCode {
+    for event in events {
+        info!(
+            team,
+            hash = event.hash,
+            source = ?event.source,
+            event = ?event.event,
+            duration = event.duration,
+            "cache event"
+        );
+    }
+
+    StatusCode::OK
+}
+
+async fn artifact_exists(
+    State(state): State<Arc<AppState>>,
+    Team(team): Team,
+    Path(hash): Path<String>,
+) -> Result<StatusCode, Error> {
+    if state.store.exists(&team, &hash)? {
+        Ok(StatusCode::OK)
+    } else {
+        Err(Error::NotFound)
+    }
+}
+
+async fn get_artifact(
+    State(state): State<Arc<AppState>>,
+    Team(team): Team,
+    Path(hash): Path<String>,
+) -> Result<Response, Error> {
+    let store = state.store.clone();
+    let (path, metadata) = tokio::task::spawn_blocking(move || store.get(&team, &hash))
+        .await??
+        .ok_or(Error::NotFound)?;
+
+    let file = match tokio::fs::File::open(&path).await {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Err(Error::NotFound),
+        Err(err) => return Err(err.into()),
+    };
+    let len = file.metadata().await?.len();
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/octet-stream"),
+    );
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(len));
+    if let Some(duration) = metadata.duration {
+        headers.insert(ARTIFACT_DURATION_HEADER, HeaderValue::from(duration));
+    }
+    if let Some(tag) = metadata.tag {
+        if let Ok(tag) = HeaderValue::from_str(&tag) {
+            headers.insert(ARTIFACT_TAG_HEADER, tag);
+        }
+    }
+
+    Ok((headers, StreamBody::new(ReaderStream::new(file))).into_response())
+}
+
+#[derive(Debug, Serialize)]
+struct PutArtifactResponse {
+    urls: Vec<String>,
+}
+
+async fn put_artifact(
+    State(state): State<Arc<AppState>>,
+    Team(team): Team,
+    Path(hash): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<PutArtifactResponse>, Error> {
+    let duration = headers
+        .get(ARTIFACT_DURATION_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .ok_or(Error::InvalidHeader(ARTIFACT_DURATION_HEADER))
+        })
+        .transpose()?;
+    let tag = headers
+        .get(ARTIFACT_TAG_HEADER)
+        .map(|value| {
+            value
+                .to_str()
+                .map(str::to_string)
+                .map_err(|_| Error::InvalidHeader(ARTIFACT_TAG_HEADER))
+        })
+        .transpose()?;
+
+    let url = format!("{}/{}", team, hash);
+    let store = state.store.clone();
+    tokio::task::spawn_blocking(move || {
+        store.put(&team, &hash, &body, &ArtifactMetadata { duration, tag })
+    })
+    .await??;
+
+    Ok(Json(PutArtifactResponse { urls: vec![url] }))
+}
+
+pub struct Config {
+    pub addr: SocketAddr,
+    pub dir: AbsoluteSystemPathBuf,
+    pub max_size: Option<u64>,
+    pub tokens: Vec<TeamToken>,
+}
+
+pub async fn serve(config: Config) -> Result<()> {
+    let store = ArtifactStore::open(config.dir, config.max_size)?;
+    let app = app(store, TokenStore::new(config.tokens));
+
+    info!("serving remote cache on {}", config.addr);
+    axum_server::bind(config.addr)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{Method, Request},
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn test_app(dir: &tempfile::TempDir) -> Result<Router> {
+        let store = ArtifactStore::open(
+            AbsoluteSystemPathBuf::new(dir.path().to_path_buf())?,
+            Some(1024),
+        )?;
+        let tokens = TokenStore::new(["team_a:token_a".parse()?, "team_b:token_b".parse()?]);
+        Ok(app(store, tokens))
+    }
+
+    fn request(method: Method, uri: &str, token: &str) -> axum::http::request::Builder {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(AUTHORIZATION, format!("Bearer {}", token))
+    }
+
+    #[tokio::test]
+    async fn test_artifact_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let app = test_app(&dir)?;
+
+        let response = app
+            .clone()
+            .oneshot(request(Method::HEAD, "/v8/artifacts/abc123", "token_a").body(Body::empty())?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(
+                request(Method::PUT, "/v8/artifacts/abc123?teamId=team_a", "token_a")
+                    .header(ARTIFACT_DURATION_HEADER, "150")
+                    .header(ARTIFACT_TAG_HEADER, "some-signature")
+                    .body(Body::from("artifact contents"))?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request(Method::HEAD, "/v8/artifacts/abc123", "token_a").body(Body::empty())?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(request(Method::GET, "/v8/artifacts/abc123", "token_a").body(Body::empty())?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ARTIFACT_DURATION_HEADER], "150");
+        assert_eq!(response.headers()[ARTIFACT_TAG_HEADER], "some-signature");
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        assert_eq!(&body[..], b"artifact contents");
+
+        // Artifacts are not shared between teams
+        let response = app
+            .oneshot(request(Method::GET, "/v8/artifacts/abc123", "token_b").body(Body::empty())?)
+            .await?;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_auth() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let app = test_app(&dir)?;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/v8/artifacts/status")
+                    .body(Body::empty())?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .clone()
+            .oneshot(
+                request(Method::GET, "/v8/artifacts/status?slug=team_b", "token_a")
+                    .body(Body::empty())?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .oneshot(
+                request(Method::GET, "/v8/artifacts/status?slug=team_a", "token_a")
+                    .body(Body::empty())?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        let status: CachingStatusResponse = serde_json::from_slice(&body)?;
+        assert!(matches!(status.status, CachingStatus::Enabled));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_events() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let app = test_app(&dir)?;
+
+        let response = app
+            .oneshot(
+                request(Method::POST, "/v8/artifacts/events", "token_a")
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        r#"[{"sessionId":"1234","source":"REMOTE","event":"HIT","hash":"abc123","duration":10}]"#,
+                    ))?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_artifacts() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let app = test_app(&dir)?;
+
+        let response = app
+            .oneshot(
+                request(Method::PUT, "/v8/artifacts/abc123", "token_a")
+                    .body(Body::from(vec![0; 2048]))?,
+            )
+            .await?;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        Ok(())
+    }
+}
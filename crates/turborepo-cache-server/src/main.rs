@@ -0,0 +1,65 @@
+use std::{
+    env,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use bytesize::ByteSize;
+use clap::Parser;
+use tracing_subscriber::EnvFilter;
+use turbopath::AbsoluteSystemPathBuf;
+use turborepo_cache_server::{serve, Config, TeamToken};
+
+/// A self-hosted remote cache for turbo
+#[derive(Parser, Debug)]
+#[clap(author, version, about)]
+struct Args {
+    /// Directory to store artifacts in
+    #[clap(long, env = "TURBO_CACHE_SERVER_DIR")]
+    dir: PathBuf,
+    /// Address to listen on
+    #[clap(long, env = "TURBO_CACHE_SERVER_HOST", default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    host: IpAddr,
+    /// Port to listen on
+    #[clap(long, env = "TURBO_CACHE_SERVER_PORT", default_value_t = 3000)]
+    port: u16,
+    /// Maximum total size of stored artifacts, e.g. `500MB` or `20GiB`.
+    /// Least recently used artifacts are evicted beyond this size.
+    #[clap(long, env = "TURBO_CACHE_SERVER_MAX_SIZE")]
+    max_size: Option<ByteSize>,
+    /// Access token for a team, in the form `<team>:<token>`. Can be passed
+    /// multiple times.
+    #[clap(
+        long = "token",
+        env = "TURBO_CACHE_SERVER_TOKENS",
+        value_delimiter = ',',
+        required = true
+    )]
+    tokens: Vec<TeamToken>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_env("TURBO_LOG_VERBOSITY")
+                .unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let args = Args::parse();
+    let dir = if args.dir.is_absolute() {
+        args.dir
+    } else {
+        env::current_dir()?.join(args.dir)
+    };
+
+    serve(Config {
+        addr: SocketAddr::new(args.host, args.port),
+        dir: AbsoluteSystemPathBuf::new(dir)?,
+        max_size: args.max_size.map(|size| size.as_u64()),
+        tokens: args.tokens,
+    })
+    .await
+}
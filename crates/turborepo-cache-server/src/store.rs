@@ -0,0 +1,478 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, Write},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use filetime::FileTime;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+use thiserror::Error;
+use tracing::{debug, warn};
+use turbopath::AbsoluteSystemPathBuf;
+
+const METADATA_EXTENSION: &str = "json";
+const TEMP_EXTENSION: &str = "tmp";
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("invalid artifact hash: {0}")]
+    InvalidHash(String),
+    #[error("artifact of {size} bytes exceeds the cache size limit of {max_size} bytes")]
+    TooLarge { size: u64, max_size: u64 },
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid artifact metadata: {0}")]
+    Metadata(#[from] serde_json::Error),
+}
+
+/// Metadata stored next to each artifact so that it can be handed back to
+/// clients verbatim on download.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactMetadata {
+    /// Value of the `x-artifact-duration` header, i.e. how long the task took
+    /// to produce this artifact, in milliseconds.
+    pub duration: Option<u64>,
+    /// Value of the `x-artifact-tag` header. This is an HMAC signature that
+    /// the server never inspects, it only passes it through.
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ArtifactKey {
+    team: String,
+    hash: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    size: u64,
+    last_access: u64,
+}
+
+/// In-memory index over the artifacts on disk, ordered by last access.
+#[derive(Debug, Default)]
+struct LruIndex {
+    entries: HashMap<ArtifactKey, Entry>,
+    by_access: BTreeMap<u64, ArtifactKey>,
+    clock: u64,
+    total_size: u64,
+}
+
+impl LruIndex {
+    fn touch(&mut self, key: &ArtifactKey) -> bool {
+        self.clock += 1;
+        let clock = self.clock;
+        let Some(entry) = self.entries.get_mut(key) else {
+            return false;
+        };
+        self.by_access.remove(&entry.last_access);
+        entry.last_access = clock;
+        self.by_access.insert(clock, key.clone());
+        true
+    }
+
+    fn insert(&mut self, key: ArtifactKey, size: u64) {
+        self.remove(&key);
+        self.clock += 1;
+        self.by_access.insert(self.clock, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                size,
+                last_access: self.clock,
+            },
+        );
+        self.total_size += size;
+    }
+
+    fn remove(&mut self, key: &ArtifactKey) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.by_access.remove(&entry.last_access);
+        self.total_size -= entry.size;
+        Some(entry)
+    }
+
+    fn pop_least_recently_used(&mut self) -> Option<(ArtifactKey, Entry)> {
+        let (_, key) = self.by_access.pop_first()?;
+        let entry = self
+            .entries
+            .remove(&key)
+            .expect("lru order and entries must be in sync");
+        self.total_size -= entry.size;
+        Some((key, entry))
+    }
+}
+
+/// A directory-backed artifact store, namespaced by team and bounded in size.
+///
+/// Artifacts live at `<root>/<team>/<hash>` with their metadata in
+/// `<root>/<team>/<hash>.json`. When `max_size` is set, the least recently
+/// accessed artifacts are evicted once the total size of all artifacts
+/// exceeds it. Access times are mirrored to the artifact's mtime so that the
+/// eviction order survives restarts.
+#[derive(Debug)]
+pub struct ArtifactStore {
+    root: AbsoluteSystemPathBuf,
+    max_size: Option<u64>,
+    index: Mutex<LruIndex>,
+}
+
+impl ArtifactStore {
+    /// Opens the store at `root`, creating it if necessary, and indexes any
+    /// artifacts that are already on disk.
+    pub fn open(root: AbsoluteSystemPathBuf, max_size: Option<u64>) -> Result<Self, StoreError> {
+        root.create_dir_all()?;
+
+        let mut existing = Vec::new();
+        for team_dir in fs::read_dir(root.as_path())? {
+            let team_dir = team_dir?;
+            if !team_dir.file_type()?.is_dir() {
+                continue;
+            }
+            let Some(team) = team_dir.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            for artifact in fs::read_dir(team_dir.path())? {
+                let artifact = artifact?;
+                let path = artifact.path();
+                match path.extension().and_then(|ext| ext.to_str()) {
+                    Some(TEMP_EXTENSION) => {
+                        // Left over from an interrupted upload
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    Some(_) => continue,
+                    None => {}
+                }
+                let Some(hash) = artifact.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                let metadata = artifact.metadata()?;
+                if !metadata.is_file() {
+                    continue;
+                }
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                existing.push((
+                    modified,
+                    ArtifactKey {
+                        team: team.clone(),
+                        hash,
+                    },
+                    metadata.len(),
+                ));
+            }
+        }
+
+        // Oldest first, so that the most recently used artifacts end up with
+        // the highest access counters.
+        existing.sort_by_key(|(modified, ..)| *modified);
+        let mut index = LruIndex::default();
+        for (_, key, size) in existing {
+            index.insert(key, size);
+        }
+        debug!(
+            "indexed {} artifacts ({} bytes) in {}",
+            index.entries.len(),
+            index.total_size,
+            root
+        );
+
+        let store = Self {
+            root,
+            max_size,
+            index: Mutex::new(index),
+        };
+        store.evict();
+
+        Ok(store)
+    }
+
+    /// Total size in bytes of all artifacts in the store
+    pub fn size(&self) -> u64 {
+        self.index.lock().unwrap().total_size
+    }
+
+    pub fn max_size(&self) -> Option<u64> {
+        self.max_size
+    }
+
+    pub fn exists(&self, team: &str, hash: &str) -> Result<bool, StoreError> {
+        let key = Self::key(team, hash)?;
+        let exists = self.index.lock().unwrap().entries.contains_key(&key);
+        Ok(exists)
+    }
+
+    /// Returns the path to the artifact body along with its metadata, marking
+    /// the artifact as recently used.
+    pub fn get(
+        &self,
+        team: &str,
+        hash: &str,
+    ) -> Result<Option<(AbsoluteSystemPathBuf, ArtifactMetadata)>, StoreError> {
+        let key = Self::key(team, hash)?;
+        if !self.index.lock().unwrap().touch(&key) {
+            return Ok(None);
+        }
+
+        let artifact_path = self.artifact_path(&key);
+        let metadata = match fs::read(self.metadata_path(&key).as_path()) {
+            Ok(contents) => serde_json::from_slice(&contents)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => ArtifactMetadata::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        if let Err(err) = filetime::set_file_mtime(&artifact_path, FileTime::now()) {
+            if err.kind() == io::ErrorKind::NotFound {
+                // Removed from underneath us, forget about it.
+                self.index.lock().unwrap().remove(&key);
+                return Ok(None);
+            }
+            warn!("failed to update access time of {}: {}", artifact_path, err);
+        }
+
+        Ok(Some((artifact_path, metadata)))
+    }
+
+    /// Stores an artifact, evicting least recently used artifacts if the store
+    /// grows beyond its size limit.
+    pub fn put(
+        &self,
+        team: &str,
+        hash: &str,
+        body: &[u8],
+        metadata: &ArtifactMetadata,
+    ) -> Result<(), StoreError> {
+        let key = Self::key(team, hash)?;
+        let size = body.len() as u64;
+        if let Some(max_size) = self.max_size {
+            if size > max_size {
+                return Err(StoreError::TooLarge { size, max_size });
+            }
+        }
+
+        let team_dir = self.root.join_component(&key.team);
+        team_dir.create_dir_all()?;
+
+        // Write to temporary files and rename them into place so that readers
+        // never observe a partially written artifact. Every upload gets its
+        // own temporary files, so concurrent uploads of a hash don't clash.
+        let temp_artifact = Self::write_temp(&team_dir, &key, body)?;
+        let temp_metadata = Self::write_temp(&team_dir, &key, &serde_json::to_vec(metadata)?)?;
+
+        // The files are moved into place under the same lock eviction removes
+        // them with, so an eviction can't delete them before they're indexed.
+        // The body goes first, so there's never metadata without one.
+        let mut index = self.index.lock().unwrap();
+        temp_artifact
+            .persist(self.artifact_path(&key))
+            .map_err(|err| err.error)?;
+        temp_metadata
+            .persist(self.metadata_path(&key))
+            .map_err(|err| err.error)?;
+        index.insert(key, size);
+        self.evict_locked(&mut index);
+
+        Ok(())
+    }
+
+    fn write_temp(
+        team_dir: &AbsoluteSystemPathBuf,
+        key: &ArtifactKey,
+        contents: &[u8],
+    ) -> Result<NamedTempFile, StoreError> {
+        // Temporary files are removed by `open` if an upload is interrupted
+        let mut file = tempfile::Builder::new()
+            .prefix(&key.hash)
+            .suffix(&format!(".{}", TEMP_EXTENSION))
+            .tempfile_in(team_dir)?;
+        file.write_all(contents)?;
+        Ok(file)
+    }
+
+    fn evict(&self) {
+        self.evict_locked(&mut self.index.lock().unwrap());
+    }
+
+    fn evict_locked(&self, index: &mut LruIndex) {
+        let Some(max_size) = self.max_size else {
+            return;
+        };
+        // Files are removed while holding the lock so that a concurrent upload
+        // of an evicted hash can't be deleted after it has been re-added.
+        while index.total_size > max_size {
+            let Some((key, entry)) = index.pop_least_recently_used() else {
+                break;
+            };
+            debug!(
+                "evicting {}/{} ({} bytes) from cache",
+                key.team, key.hash, entry.size
+            );
+            for path in [self.artifact_path(&key), self.metadata_path(&key)] {
+                if let Err(err) = fs::remove_file(&path) {
+                    if err.kind() != io::ErrorKind::NotFound {
+                        warn!("failed to evict {}: {}", path, err);
+                    }
+                }
+            }
+        }
+    }
+
+    fn key(team: &str, hash: &str) -> Result<ArtifactKey, StoreError> {
+        // Hashes come straight from the request path, so they must not be
+        // able to address anything outside of the team directory.
+        if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(StoreError::InvalidHash(hash.to_string()));
+        }
+        Ok(ArtifactKey {
+            team: team.to_string(),
+            hash: hash.to_string(),
+        })
+    }
+
+    fn artifact_path(&self, key: &ArtifactKey) -> AbsoluteSystemPathBuf {
+        self.root.join_components(&[&key.team, &key.hash])
+    }
+
+    fn metadata_path(&self, key: &ArtifactKey) -> AbsoluteSystemPathBuf {
+        self.root
+            .join_components(&[&key.team, &format!("{}.{}", key.hash, METADATA_EXTENSION)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn store(dir: &TempDir, max_size: Option<u64>) -> Result<ArtifactStore> {
+        let root = AbsoluteSystemPathBuf::new(dir.path().join("cache"))?;
+        Ok(ArtifactStore::open(root, max_size)?)
+    }
+
+    #[test]
+    fn test_put_and_get() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = store(&dir, None)?;
+        let metadata = ArtifactMetadata {
+            duration: Some(42),
+            tag: Some("signature".to_string()),
+        };
+
+        assert!(!store.exists("team_a", "abc123")?);
+        store.put("team_a", "abc123", b"artifact", &metadata)?;
+        assert!(store.exists("team_a", "abc123")?);
+        assert!(!store.exists("team_b", "abc123")?);
+
+        let (path, stored_metadata) = store.get("team_a", "abc123")?.unwrap();
+        assert_eq!(fs::read(path)?, b"artifact");
+        assert_eq!(stored_metadata, metadata);
+        assert!(store.get("team_b", "abc123")?.is_none());
+        assert_eq!(store.size(), 8);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_concurrent_puts_of_the_same_hash() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = store(&dir, Some(8))?;
+
+        std::thread::scope(|scope| {
+            let uploads: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        (0..20).try_for_each(|_| {
+                            store.put(
+                                "team_a",
+                                "abc123",
+                                b"artifact",
+                                &ArtifactMetadata::default(),
+                            )
+                        })
+                    })
+                })
+                .collect();
+            uploads
+                .into_iter()
+                .try_for_each(|upload| upload.join().unwrap())
+        })?;
+
+        assert_eq!(store.size(), 8);
+        let (path, _) = store.get("team_a", "abc123")?.unwrap();
+        assert_eq!(fs::read(path)?, b"artifact");
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(dir.path().join("cache/team_a"))?.count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_invalid_hashes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = store(&dir, None)?;
+
+        for hash in ["", "..", "../escape", "a/b", "abc.json"] {
+            assert!(matches!(
+                store.put("team_a", hash, b"", &ArtifactMetadata::default()),
+                Err(StoreError::InvalidHash(_))
+            ));
+            assert!(matches!(
+                store.get("team_a", hash),
+                Err(StoreError::InvalidHash(_))
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = store(&dir, Some(10))?;
+        let metadata = ArtifactMetadata::default();
+
+        store.put("team_a", "one", b"1111", &metadata)?;
+        store.put("team_b", "two", b"2222", &metadata)?;
+        // Reading `one` makes `two` the least recently used artifact
+        store.get("team_a", "one")?;
+        store.put("team_a", "three", b"3333", &metadata)?;
+
+        assert!(store.exists("team_a", "one")?);
+        assert!(!store.exists("team_b", "two")?);
+        assert!(store.exists("team_a", "three")?);
+        assert_eq!(store.size(), 8);
+        assert!(!dir.path().join("cache/team_b/two").exists());
+        assert!(!dir.path().join("cache/team_b/two.json").exists());
+
+        assert!(matches!(
+            store.put("team_a", "huge", &[0; 11], &metadata),
+            Err(StoreError::TooLarge { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reopen_indexes_existing_artifacts() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let metadata = ArtifactMetadata::default();
+        {
+            let store = store(&dir, None)?;
+            store.put("team_a", "one", b"1111", &metadata)?;
+            store.put("team_a", "two", b"2222", &metadata)?;
+        }
+        fs::write(dir.path().join("cache/team_a/three.tmp"), b"partial")?;
+
+        let store = store(&dir, Some(4))?;
+        assert_eq!(store.size(), 4);
+        assert!(!dir.path().join("cache/team_a/three.tmp").exists());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,308 @@
+//! Inspection and maintenance of the local filesystem cache.
+//!
+//! The layout matches the one written by the Go `fsCache`: each artifact is
+//! stored as `<hash>.tar.zst` (or `<hash>.tar` for older caches) next to a
+//! `<hash>-meta.json` metadata file.
+
+use std::{
+    fs, io,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
+
+use crate::CacheError;
+
+const COMPRESSED_EXTENSION: &str = ".tar.zst";
+const UNCOMPRESSED_EXTENSION: &str = ".tar";
+const META_SUFFIX: &str = "-meta.json";
+
+/// Returns the filesystem cache directory for the repository at `repo_root`.
+/// Relative overrides are resolved against the repository root, same as
+/// `--cache-dir` for `turbo run`.
+pub fn resolve_cache_dir(
+    repo_root: &AbsoluteSystemPath,
+    override_dir: Option<&str>,
+) -> AbsoluteSystemPathBuf {
+    match override_dir {
+        // Joining onto an absolute path replaces the repository root
+        Some(dir) => AbsoluteSystemPathBuf::new(repo_root.as_path().join(dir))
+            .expect("joined onto an absolute path"),
+        None => repo_root.join_components(&["node_modules", ".cache", "turbo"]),
+    }
+}
+
+/// The contents of `<hash>-meta.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheMetadata {
+    pub hash: String,
+    /// How long the task that produced the artifact took, in milliseconds
+    pub duration: u64,
+    /// When the artifact was last written or restored, in milliseconds since
+    /// the unix epoch. Not recorded by older versions of turbo.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_accessed: Option<u64>,
+    /// The task that produced the artifact, e.g. `apps/web#build`, using `//`
+    /// for the root package. Not recorded by older versions of turbo.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+}
+
+impl CacheMetadata {
+    pub fn read(path: &AbsoluteSystemPath) -> Result<Self, CacheError> {
+        let contents = fs::read(path.as_path())?;
+        Ok(serde_json::from_slice(&contents)?)
+    }
+}
+
+/// A single artifact in the filesystem cache
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub hash: String,
+    pub artifact_path: AbsoluteSystemPathBuf,
+    pub meta_path: Option<AbsoluteSystemPathBuf>,
+    /// Combined size of the artifact and its metadata, in bytes
+    pub size: u64,
+    pub duration: Option<Duration>,
+    pub created: SystemTime,
+    pub last_accessed: SystemTime,
+    pub task: Option<String>,
+}
+
+impl CacheEntry {
+    /// Time elapsed since the artifact was last used, as of `now`
+    pub fn idle_time(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.last_accessed).unwrap_or_default()
+    }
+
+    /// Removes the artifact and its metadata from the cache
+    pub fn remove(&self) -> Result<(), CacheError> {
+        remove_if_exists(self.artifact_path.as_absolute_path())?;
+        if let Some(meta_path) = &self.meta_path {
+            remove_if_exists(meta_path.as_absolute_path())?;
+        }
+        Ok(())
+    }
+}
+
+fn remove_if_exists(path: &AbsoluteSystemPath) -> Result<(), CacheError> {
+    match fs::remove_file(path.as_path()) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Lists every artifact in `cache_dir`, most recently used first. A missing
+/// cache directory is treated as empty.
+pub fn list_entries(cache_dir: &AbsoluteSystemPath) -> Result<Vec<CacheEntry>, CacheError> {
+    let dir = match fs::read_dir(cache_dir.as_path()) {
+        Ok(dir) => dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut entries = Vec::new();
+    for dir_entry in dir {
+        let dir_entry = dir_entry?;
+        let Some(file_name) = dir_entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let Some(hash) = file_name
+            .strip_suffix(COMPRESSED_EXTENSION)
+            .or_else(|| file_name.strip_suffix(UNCOMPRESSED_EXTENSION)) else {
+            continue;
+        };
+        let artifact_metadata = dir_entry.metadata()?;
+        if !artifact_metadata.is_file() {
+            continue;
+        }
+
+        let artifact_path = cache_dir.join_component(&file_name);
+        let meta_path = cache_dir.join_component(&format!("{}{}", hash, META_SUFFIX));
+        let (meta, meta_size) = match fs::metadata(meta_path.as_path()) {
+            Ok(meta_file) => (
+                CacheMetadata::read(meta_path.as_absolute_path()).ok(),
+                Some(meta_file.len()),
+            ),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (None, None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let created = artifact_metadata
+            .modified()
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let last_accessed = meta
+            .as_ref()
+            .and_then(|meta| meta.last_accessed)
+            .map(|millis| SystemTime::UNIX_EPOCH + Duration::from_millis(millis))
+            .unwrap_or(created);
+
+        entries.push(CacheEntry {
+            hash: hash.to_string(),
+            artifact_path,
+            meta_path: meta_size.map(|_| meta_path),
+            size: artifact_metadata.len() + meta_size.unwrap_or_default(),
+            duration: meta
+                .as_ref()
+                .map(|meta| Duration::from_millis(meta.duration)),
+            created,
+            last_accessed,
+            task: meta.and_then(|meta| meta.task),
+        });
+    }
+
+    entries.sort_by(|a, b| b.last_accessed.cmp(&a.last_accessed));
+    Ok(entries)
+}
+
+/// Selects the least recently used entries that need to be removed for the
+/// cache to fit within `max_size` bytes.
+pub fn entries_to_prune(entries: &[CacheEntry], max_size: u64) -> Vec<&CacheEntry> {
+    let mut by_last_access: Vec<_> = entries.iter().collect();
+    by_last_access.sort_by_key(|entry| entry.last_accessed);
+
+    let mut total_size: u64 = entries.iter().map(|entry| entry.size).sum();
+    by_last_access
+        .into_iter()
+        .take_while(|entry| {
+            if total_size <= max_size {
+                return false;
+            }
+            total_size -= entry.size;
+            true
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+
+    /// Writes an entry whose artifact is a placeholder, listing never reads
+    /// its contents
+    fn write_entry(
+        cache_dir: &AbsoluteSystemPath,
+        hash: &str,
+        last_accessed: Option<u64>,
+        task: Option<&str>,
+    ) -> Result<()> {
+        fs::write(
+            cache_dir.join_component(&format!("{}.tar.zst", hash)),
+            b"artifact",
+        )?;
+
+        let meta = CacheMetadata {
+            hash: hash.to_string(),
+            duration: 1500,
+            last_accessed,
+            task: task.map(|task| task.to_string()),
+        };
+        fs::write(
+            cache_dir.join_component(&format!("{}-meta.json", hash)),
+            serde_json::to_vec(&meta)?,
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_cache_dir() -> Result<()> {
+        let repo_root = AbsoluteSystemPathBuf::new(std::env::temp_dir().join("repo"))?;
+
+        assert_eq!(
+            resolve_cache_dir(repo_root.as_absolute_path(), None),
+            repo_root.join_components(&["node_modules", ".cache", "turbo"])
+        );
+        assert_eq!(
+            resolve_cache_dir(repo_root.as_absolute_path(), Some("custom/cache")),
+            repo_root.join_components(&["custom", "cache"])
+        );
+        let absolute = std::env::temp_dir().join("elsewhere");
+        assert_eq!(
+            resolve_cache_dir(repo_root.as_absolute_path(), absolute.to_str()),
+            AbsoluteSystemPathBuf::new(absolute)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_entries() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache_dir = AbsoluteSystemPathBuf::new(dir.path())?;
+
+        write_entry(
+            cache_dir.as_absolute_path(),
+            "older",
+            Some(1_000),
+            Some("apps/web#build"),
+        )?;
+        write_entry(cache_dir.as_absolute_path(), "newer", Some(2_000), None)?;
+        fs::write(dir.path().join("unrelated.txt"), b"ignored")?;
+
+        let entries = list_entries(cache_dir.as_absolute_path())?;
+        let hashes: Vec<_> = entries.iter().map(|entry| entry.hash.as_str()).collect();
+        assert_eq!(hashes, ["newer", "older"]);
+
+        let older = &entries[1];
+        assert_eq!(
+            older.last_accessed,
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1_000)
+        );
+        assert_eq!(older.duration, Some(Duration::from_millis(1500)));
+        assert_eq!(older.task.as_deref(), Some("apps/web#build"));
+        // Older versions of turbo don't record the task
+        assert_eq!(entries[0].task, None);
+
+        older.remove()?;
+        assert!(!dir.path().join("older.tar.zst").exists());
+        assert!(!dir.path().join("older-meta.json").exists());
+        assert_eq!(list_entries(cache_dir.as_absolute_path())?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_entries_missing_dir() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache_dir = AbsoluteSystemPathBuf::new(dir.path().join("missing"))?;
+
+        assert!(list_entries(cache_dir.as_absolute_path())?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_entries_to_prune() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cache_dir = AbsoluteSystemPathBuf::new(dir.path())?;
+
+        for (hash, last_accessed) in [("a", 3_000), ("b", 1_000), ("c", 2_000)] {
+            write_entry(
+                cache_dir.as_absolute_path(),
+                hash,
+                Some(last_accessed),
+                None,
+            )?;
+        }
+        let entries = list_entries(cache_dir.as_absolute_path())?;
+        let entry_size = entries[0].size;
+
+        let pruned = |max_size| {
+            entries_to_prune(&entries, max_size)
+                .into_iter()
+                .map(|entry| entry.hash.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert!(pruned(entry_size * 3).is_empty());
+        assert_eq!(pruned(entry_size * 2), ["b"]);
+        assert_eq!(pruned(entry_size * 2 - 1), ["b", "c"]);
+        assert_eq!(pruned(0), ["b", "c", "a"]);
+
+        Ok(())
+    }
+}
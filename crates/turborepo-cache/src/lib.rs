@@ -1 +1,26 @@
+#![cfg_attr(test, feature(assert_matches))]
+
+use thiserror::Error;
+use turbopath::PathError;
+
+pub mod fs;
+pub mod restore;
 pub mod signature_authentication;
+
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("path error: {0}")]
+    Path(#[from] PathError),
+    #[error("invalid cache metadata: {0}")]
+    Metadata(#[from] serde_json::Error),
+    #[error("invalid file path in artifact: {0}")]
+    InvalidFilePath(String),
+    #[error("{0} links outside of the restore directory")]
+    LinkOutsideOfDirectory(String),
+    #[error("too many levels of symbolic links resolving {0}")]
+    SymlinkLoop(String),
+    #[error("{0} has unsupported file type {1:?}")]
+    UnsupportedFileType(String, tar::EntryType),
+}
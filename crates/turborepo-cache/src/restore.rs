@@ -0,0 +1,735 @@
+//! Restoring artifacts into the repository.
+//!
+//! Artifacts may have been created on another machine, so the archive is
+//! treated as untrusted input: every entry is restored relative to an anchor
+//! directory and is rejected if it would end up outside of it. That covers
+//! traversal in entry names, absolute names, hard links, symlinks whose
+//! targets leave the anchor, and writes through symlinks (either restored
+//! earlier in the same archive or already on disk) that point elsewhere.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use tar::{Archive, Entry, EntryType};
+use turbopath::{AbsoluteSystemPath, AnchoredSystemPathBuf};
+
+use crate::CacheError;
+
+/// Upper bound on the number of symlinks followed while resolving a single
+/// path, mirroring `MAXSYMLINKS` on Linux.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Extracts the artifact read from `reader` into `anchor`, returning the
+/// restored paths in archive order.
+///
+/// `compressed` indicates a zstd-compressed tarball (`<hash>.tar.zst`) as
+/// opposed to a plain one (`<hash>.tar`).
+#[tracing::instrument(skip_all, fields(anchor = %anchor))]
+pub fn restore(
+    anchor: &AbsoluteSystemPath,
+    reader: impl Read,
+    compressed: bool,
+) -> Result<Vec<AnchoredSystemPathBuf>, CacheError> {
+    if compressed {
+        restore_archive(anchor, Archive::new(zstd::Decoder::new(reader)?))
+    } else {
+        restore_archive(anchor, Archive::new(reader))
+    }
+}
+
+fn restore_archive<R: Read>(
+    anchor: &AbsoluteSystemPath,
+    mut archive: Archive<R>,
+) -> Result<Vec<AnchoredSystemPathBuf>, CacheError> {
+    fs::create_dir_all(anchor.as_path())?;
+
+    let mut restored = Vec::new();
+    let mut symlinks = Vec::new();
+    let result = restore_entries(anchor, &mut archive, &mut restored, &mut symlinks);
+
+    // A symlink that was safe when it was created can be retargeted by a later
+    // entry replacing one of the directories its target passes through, so
+    // check them all again once we're done, even if restoring failed partway.
+    let checked = remove_escaping_symlinks(anchor, &symlinks);
+
+    result.and(checked).map(|_| restored)
+}
+
+fn restore_entries<R: Read>(
+    anchor: &AbsoluteSystemPath,
+    archive: &mut Archive<R>,
+    restored: &mut Vec<AnchoredSystemPathBuf>,
+    symlinks: &mut Vec<(AnchoredSystemPathBuf, PathBuf)>,
+) -> Result<(), CacheError> {
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = canonicalize_name(&entry.path_bytes())?;
+        match entry.header().entry_type() {
+            EntryType::Directory => restore_directory(anchor, &name)?,
+            EntryType::Regular => restore_regular(anchor, &name, &mut entry)?,
+            EntryType::Symlink => {
+                let location = restore_symlink(anchor, &name, &entry)?;
+                symlinks.push((name.clone(), location));
+            }
+            entry_type => {
+                return Err(CacheError::UnsupportedFileType(
+                    display_name(&name),
+                    entry_type,
+                ))
+            }
+        }
+        restored.push(name);
+    }
+
+    Ok(())
+}
+
+/// Removes restored symlinks that no longer resolve inside the anchor,
+/// returning the first such failure.
+///
+/// Links are checked at the location they were actually created at, which
+/// may differ from their name if they were restored through another link.
+/// Removing a link can change how others resolve, so keep going until a pass
+/// removes nothing.
+fn remove_escaping_symlinks(
+    anchor: &AbsoluteSystemPath,
+    symlinks: &[(AnchoredSystemPathBuf, PathBuf)],
+) -> Result<(), CacheError> {
+    let mut result = Ok(());
+    let mut removed = true;
+    while removed {
+        removed = false;
+        for (name, location) in symlinks {
+            if !fs::symlink_metadata(location).map_or(false, |m| m.file_type().is_symlink()) {
+                continue;
+            }
+            let relative = location
+                .strip_prefix(anchor.as_path())
+                .expect("symlinks are created inside the anchor");
+            if let Err(err) = resolve(anchor.as_path(), relative, &display_name(name)) {
+                fs::remove_file(location)?;
+                removed = true;
+                result = result.and(Err(err));
+            }
+        }
+    }
+    result
+}
+
+/// Validates an entry name and converts it to a path relative to the anchor.
+///
+/// Names are expected to be the unix-style relative paths `turbo` writes,
+/// with an optional trailing slash for directories. Anything containing
+/// empty, `.` or `..` components, or that is absolute, is rejected rather
+/// than normalized.
+fn canonicalize_name(raw: &[u8]) -> Result<AnchoredSystemPathBuf, CacheError> {
+    let invalid = || CacheError::InvalidFilePath(String::from_utf8_lossy(raw).to_string());
+
+    let name = std::str::from_utf8(raw).map_err(|_| invalid())?;
+    let name = name.strip_suffix('/').unwrap_or(name);
+
+    let mut path = PathBuf::new();
+    for segment in name.split('/') {
+        if segment.is_empty() || segment == "." || segment == ".." {
+            return Err(invalid());
+        }
+        // Backslashes are separators on Windows, and a segment like `C:` is a
+        // drive prefix, so make sure each segment is exactly one plain
+        // component on the current platform.
+        let mut components = Path::new(segment).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => path.push(segment),
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(AnchoredSystemPathBuf::try_from(path.as_path())?)
+}
+
+fn restore_directory(
+    anchor: &AbsoluteSystemPath,
+    name: &AnchoredSystemPathBuf,
+) -> Result<(), CacheError> {
+    let display = display_name(name);
+    // Existing directory symlinks inside the anchor are fine to create
+    // directories through, so resolve the whole path.
+    let directory = resolve(anchor.as_path(), &PathBuf::from(name.clone()), &display)?;
+    fs::create_dir_all(directory)?;
+    Ok(())
+}
+
+fn restore_regular<R: Read>(
+    anchor: &AbsoluteSystemPath,
+    name: &AnchoredSystemPathBuf,
+    entry: &mut Entry<R>,
+) -> Result<(), CacheError> {
+    let location = prepare_location(anchor, name)?;
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // Only the permission bits, the archive mustn't be able to create
+        // setuid, setgid or sticky files
+        options.mode(entry.header().mode()? & 0o777);
+    }
+    let mut file = options.open(location)?;
+    io::copy(entry, &mut file)?;
+    Ok(())
+}
+
+/// Restores a symlink, returning where it was created on disk.
+///
+/// Link targets are written verbatim, but only after checking that they
+/// resolve to somewhere inside the anchor.
+fn restore_symlink<R: Read>(
+    anchor: &AbsoluteSystemPath,
+    name: &AnchoredSystemPathBuf,
+    entry: &Entry<R>,
+) -> Result<PathBuf, CacheError> {
+    let display = display_name(name);
+    let target = entry
+        .link_name()?
+        .ok_or_else(|| CacheError::InvalidFilePath(display.clone()))?;
+
+    let location = prepare_location(anchor, name)?;
+    let parent = location.parent().expect("location is inside the anchor");
+    let resolved_target = resolve_from(anchor.as_path(), parent, &target, &display)?;
+
+    // Windows distinguishes between file and directory links. Targets that
+    // don't exist yet are restored as file links, same as the Go cache.
+    let link = AbsoluteSystemPath::new(&location)?;
+    if resolved_target.is_dir() {
+        link.symlink_to_dir(&target)?;
+    } else {
+        link.symlink_to_file(&target)?;
+    }
+
+    Ok(location)
+}
+
+/// Creates the parent directories for `name` and clears anything that is
+/// already at its location, returning that location.
+///
+/// The final component is never followed: restoring a file over an existing
+/// symlink replaces the link rather than writing through it.
+fn prepare_location(
+    anchor: &AbsoluteSystemPath,
+    name: &AnchoredSystemPathBuf,
+) -> Result<PathBuf, CacheError> {
+    let display = display_name(name);
+    let destination = anchor.resolve(name);
+    let relative_parent = destination
+        .as_path()
+        .parent()
+        .and_then(|parent| parent.strip_prefix(anchor.as_path()).ok())
+        .expect("name is a non-empty path inside the anchor");
+    let file_name = destination
+        .file_name()
+        .expect("name is a non-empty path inside the anchor");
+
+    let parent = resolve(anchor.as_path(), relative_parent, &display)?;
+    fs::create_dir_all(&parent)?;
+
+    let location = parent.join(file_name);
+    match fs::symlink_metadata(&location) {
+        Ok(metadata) if !metadata.is_dir() => fs::remove_file(&location)?,
+        _ => {}
+    }
+
+    Ok(location)
+}
+
+/// Resolves `path`, relative to `anchor`, to the location it refers to on
+/// disk. See [`resolve_from`].
+fn resolve(anchor: &Path, path: &Path, name: &str) -> Result<PathBuf, CacheError> {
+    resolve_from(anchor, anchor, path, name)
+}
+
+/// Resolves `path`, relative to `base`, to the location it refers to on disk
+/// by following any symlinks that already exist along the way. Components
+/// that don't exist yet are resolved lexically.
+///
+/// Fails if any intermediate step leaves `anchor`, even if a later `..`
+/// would bring it back, since the OS would have to traverse outside the
+/// anchor to get there.
+fn resolve_from(
+    anchor: &Path,
+    base: &Path,
+    path: &Path,
+    name: &str,
+) -> Result<PathBuf, CacheError> {
+    let mut hops = MAX_SYMLINK_HOPS;
+    resolve_inner(anchor, base.to_path_buf(), path, name, &mut hops)
+}
+
+fn resolve_inner(
+    anchor: &Path,
+    base: PathBuf,
+    path: &Path,
+    name: &str,
+    hops: &mut usize,
+) -> Result<PathBuf, CacheError> {
+    let escapes = || CacheError::LinkOutsideOfDirectory(name.to_string());
+
+    // Absolute targets are allowed as long as they point into the anchor.
+    let (mut current, path) = if path.has_root() {
+        let relative = path.strip_prefix(anchor).map_err(|_| escapes())?;
+        (anchor.to_path_buf(), relative)
+    } else {
+        (base, path)
+    };
+
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => return Err(escapes()),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if current == anchor {
+                    return Err(escapes());
+                }
+                current.pop();
+            }
+            Component::Normal(segment) => {
+                let next = current.join(segment);
+                match fs::symlink_metadata(&next) {
+                    Ok(metadata) if metadata.file_type().is_symlink() => {
+                        if *hops == 0 {
+                            return Err(CacheError::SymlinkLoop(name.to_string()));
+                        }
+                        *hops -= 1;
+                        let target = fs::read_link(&next)?;
+                        current = resolve_inner(anchor, current, &target, name, hops)?;
+                    }
+                    _ => current = next,
+                }
+            }
+        }
+    }
+
+    Ok(current)
+}
+
+fn display_name(name: &AnchoredSystemPathBuf) -> String {
+    name.to_unix()
+        .ok()
+        .and_then(|name| name.as_str().ok().map(str::to_string))
+        .unwrap_or_else(|| format!("{:?}", name))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{assert_matches::assert_matches, fs, io::Write};
+
+    use anyhow::Result;
+    use tar::{Builder, EntryType, Header};
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    enum Item<'a> {
+        File(&'a str),
+        Dir(&'a str),
+        Symlink(&'a str, &'a str),
+        HardLink(&'a str, &'a str),
+        Fifo(&'a str),
+    }
+
+    /// Builds an archive without any of the validation `tar::Builder`
+    /// applies to entry names, since the whole point is to produce names it
+    /// would refuse.
+    fn archive(items: &[Item]) -> Vec<u8> {
+        let mut builder = Builder::new(Vec::new());
+        for item in items {
+            let (name, entry_type, link, contents): (&str, _, _, &[u8]) = match *item {
+                Item::File(name) => (name, EntryType::Regular, None, b"contents"),
+                Item::Dir(name) => (name, EntryType::Directory, None, b""),
+                Item::Symlink(name, target) => (name, EntryType::Symlink, Some(target), b""),
+                Item::HardLink(name, target) => (name, EntryType::Link, Some(target), b""),
+                Item::Fifo(name) => (name, EntryType::Fifo, None, b""),
+            };
+            let mut header = Header::new_gnu();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            if let Some(link) = link {
+                header.as_old_mut().linkname[..link.len()].copy_from_slice(link.as_bytes());
+            }
+            header.set_entry_type(entry_type);
+            header.set_mode(if entry_type == EntryType::Directory {
+                0o755
+            } else {
+                0o644
+            });
+            header.set_size(contents.len() as u64);
+            header.set_cksum();
+            builder.append(&header, contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    /// A temporary directory containing the anchor `repo` next to a
+    /// `sentinel` file that must never be touched.
+    struct Sandbox {
+        _dir: TempDir,
+        root: PathBuf,
+        anchor: AbsoluteSystemPathBuf,
+    }
+
+    impl Sandbox {
+        fn new() -> Result<Self> {
+            let dir = tempfile::tempdir()?;
+            let root = dir.path().to_path_buf();
+            fs::write(root.join("sentinel"), "sentinel")?;
+            let anchor = AbsoluteSystemPathBuf::new(root.join("repo"))?;
+            Ok(Self {
+                _dir: dir,
+                root,
+                anchor,
+            })
+        }
+
+        fn restore(&self, items: &[Item]) -> Result<Vec<AnchoredSystemPathBuf>, CacheError> {
+            restore(
+                self.anchor.as_absolute_path(),
+                archive(items).as_slice(),
+                false,
+            )
+        }
+
+        fn path(&self, relative: &str) -> PathBuf {
+            self.anchor.as_path().join(relative)
+        }
+
+        /// Checks that nothing outside the anchor was created or modified,
+        /// and that no symlink left inside the anchor points out of it.
+        fn assert_contained(&self) {
+            let mut outside: Vec<_> = fs::read_dir(&self.root)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect();
+            outside.sort();
+            assert!(
+                outside == ["repo", "sentinel"] || outside == ["sentinel"],
+                "unexpected files next to the anchor: {:?}",
+                outside
+            );
+            assert_eq!(
+                fs::read_to_string(self.root.join("sentinel")).unwrap(),
+                "sentinel"
+            );
+
+            let Ok(anchor) = fs::canonicalize(self.anchor.as_path()) else {
+                return;
+            };
+            let mut pending = vec![anchor.clone()];
+            while let Some(dir) = pending.pop() {
+                for entry in fs::read_dir(&dir).unwrap() {
+                    let path = entry.unwrap().path();
+                    let metadata = fs::symlink_metadata(&path).unwrap();
+                    if metadata.file_type().is_symlink() {
+                        if let Ok(target) = fs::canonicalize(&path) {
+                            assert!(
+                                target.starts_with(&anchor),
+                                "{} links outside the anchor to {}",
+                                path.display(),
+                                target.display()
+                            );
+                        }
+                    } else if metadata.is_dir() {
+                        pending.push(path);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_restore_regular_files() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        let restored = sandbox.restore(&[
+            Item::Dir("dist/"),
+            Item::File("dist/index.js"),
+            Item::File("nested/without/dirs.txt"),
+        ])?;
+
+        assert_eq!(
+            restored,
+            vec![
+                AnchoredSystemPathBuf::from_raw("dist")?,
+                AnchoredSystemPathBuf::from_raw("dist/index.js")?,
+                AnchoredSystemPathBuf::from_raw("nested/without/dirs.txt")?,
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(sandbox.path("dist/index.js"))?,
+            "contents"
+        );
+        assert!(sandbox.path("nested/without/dirs.txt").is_file());
+        sandbox.assert_contained();
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_compressed() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        let mut encoder = zstd::Encoder::new(Vec::new(), 0)?;
+        encoder.write_all(&archive(&[Item::File("file.txt")]))?;
+        let compressed = encoder.finish()?;
+
+        restore(
+            sandbox.anchor.as_absolute_path(),
+            compressed.as_slice(),
+            true,
+        )?;
+        assert!(sandbox.path("file.txt").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_drops_special_mode_bits() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let sandbox = Sandbox::new()?;
+        let mut header = Header::new_gnu();
+        header.set_path("setuid")?;
+        header.set_mode(0o4755);
+        header.set_size(0);
+        header.set_cksum();
+        let mut builder = Builder::new(Vec::new());
+        builder.append(&header, &[][..])?;
+
+        restore(
+            sandbox.anchor.as_absolute_path(),
+            builder.into_inner()?.as_slice(),
+            false,
+        )?;
+        let mode = fs::metadata(sandbox.path("setuid"))?.permissions().mode();
+        assert_eq!(mode & 0o7000, 0);
+        assert_eq!(mode & 0o100, 0o100);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_through_directory_symlink_from_same_archive() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        let absolute_target = sandbox.path("dist");
+        sandbox.restore(&[
+            Item::Dir("dist/"),
+            Item::Symlink("out", "dist"),
+            Item::File("out/relative.txt"),
+            Item::Symlink("nested/up", "../dist"),
+            Item::File("nested/up/parent.txt"),
+            Item::Symlink("absolute", absolute_target.to_str().unwrap()),
+            Item::File("absolute/absolute.txt"),
+        ])?;
+
+        for file in ["relative.txt", "parent.txt", "absolute.txt"] {
+            assert!(sandbox.path("dist").join(file).is_file(), "{}", file);
+        }
+        assert_eq!(fs::read_link(sandbox.path("out"))?, Path::new("dist"));
+        sandbox.assert_contained();
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_replaces_existing_symlink() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        fs::create_dir_all(sandbox.anchor.as_path())?;
+        std::os::unix::fs::symlink("../sentinel", sandbox.path("file.txt"))?;
+
+        sandbox.restore(&[Item::File("file.txt")])?;
+
+        let metadata = fs::symlink_metadata(sandbox.path("file.txt"))?;
+        assert!(metadata.is_file());
+        sandbox.assert_contained();
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_dangling_symlink() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        sandbox.restore(&[Item::Symlink("link", "not-yet-built")])?;
+        assert_eq!(
+            fs::read_link(sandbox.path("link"))?,
+            Path::new("not-yet-built")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_malformed_names() -> Result<()> {
+        for name in [
+            "",
+            ".",
+            "..",
+            "/",
+            "../escape",
+            "/tmp/escape",
+            "./file",
+            "a/../../escape",
+            "a/./b",
+            "a//b",
+            "a/.",
+            "a/..",
+            "dist/../../../escape",
+        ] {
+            let sandbox = Sandbox::new()?;
+            let result = sandbox.restore(&[Item::File(name)]);
+            assert_matches!(result, Err(CacheError::InvalidFilePath(_)), "{:?}", name);
+            sandbox.assert_contained();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_escaping_symlinks() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        let outside = sandbox.root.join("sentinel");
+        let cases: &[&[Item]] = &[
+            &[Item::Symlink("link", "..")],
+            &[Item::Symlink("link", "../sentinel")],
+            &[Item::Symlink("a/b/link", "../../../sentinel")],
+            &[Item::Symlink("link", outside.to_str().unwrap())],
+            &[Item::Symlink("link", "/")],
+            // Leaves the anchor before coming back into it
+            &[Item::Symlink("link", "../repo/file")],
+            // Each link is fine on its own, but together they climb out
+            &[Item::Symlink("up", "."), Item::Symlink("link", "up/..")],
+            &[
+                Item::Dir("dist/"),
+                Item::Symlink("dist/up", ".."),
+                Item::Symlink("link", "dist/up/.."),
+            ],
+        ];
+
+        for items in cases {
+            let sandbox = Sandbox::new()?;
+            let result = sandbox.restore(items);
+            assert_matches!(result, Err(CacheError::LinkOutsideOfDirectory(_)));
+            sandbox.assert_contained();
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_retargeted_symlink() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        // `link` resolves inside the anchor while `step` doesn't exist yet,
+        // but points outside once `step` becomes a link to the anchor root.
+        let result = sandbox.restore(&[
+            Item::Dir("dist/"),
+            Item::Symlink("dist/up", ".."),
+            Item::Symlink("link", "step/.."),
+            Item::Symlink("step", "dist/up"),
+        ]);
+
+        assert_matches!(result, Err(CacheError::LinkOutsideOfDirectory(_)));
+        assert!(fs::symlink_metadata(sandbox.path("link")).is_err());
+        sandbox.assert_contained();
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_write_through_existing_symlink() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        fs::create_dir_all(sandbox.anchor.as_path())?;
+        std::os::unix::fs::symlink("..", sandbox.path("evil"))?;
+
+        let result = sandbox.restore(&[Item::File("evil/sentinel")]);
+        assert_matches!(result, Err(CacheError::LinkOutsideOfDirectory(_)));
+
+        let result = sandbox.restore(&[Item::Dir("evil/nested/")]);
+        assert_matches!(result, Err(CacheError::LinkOutsideOfDirectory(_)));
+
+        fs::remove_file(sandbox.path("evil"))?;
+        sandbox.assert_contained();
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_symlink_loops() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        let result = sandbox.restore(&[
+            Item::Symlink("a", "b"),
+            Item::Symlink("b", "a"),
+            Item::File("a/file"),
+        ]);
+        assert_matches!(result, Err(CacheError::SymlinkLoop(_)));
+        sandbox.assert_contained();
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_unsupported_file_types() -> Result<()> {
+        let sandbox = Sandbox::new()?;
+        let result = sandbox.restore(&[Item::File("file"), Item::HardLink("hard", "file")]);
+        assert_matches!(
+            result,
+            Err(CacheError::UnsupportedFileType(_, EntryType::Link))
+        );
+        assert!(!sandbox.path("hard").exists());
+
+        let result = sandbox.restore(&[Item::HardLink("hard", "../sentinel")]);
+        assert_matches!(
+            result,
+            Err(CacheError::UnsupportedFileType(_, EntryType::Link))
+        );
+
+        let result = sandbox.restore(&[Item::Fifo("fifo")]);
+        assert_matches!(
+            result,
+            Err(CacheError::UnsupportedFileType(_, EntryType::Fifo))
+        );
+        sandbox.assert_contained();
+        Ok(())
+    }
+
+    /// Restores every ordered triple of entries drawn from a pool of
+    /// hostile names and link targets, checking that whatever the outcome,
+    /// nothing escapes the anchor.
+    #[test]
+    fn test_corpus_never_escapes() -> Result<()> {
+        let names = ["a", "a/b", "link", "link/b", "../x", "a/../b", "/x"];
+        let targets = [
+            "..",
+            ".",
+            "a",
+            "link",
+            "../sentinel",
+            "a/..",
+            "link/..",
+            "/",
+        ];
+
+        let mut pool = Vec::new();
+        for name in names {
+            pool.push(Item::File(name));
+            pool.push(Item::Dir(name));
+            for target in targets {
+                pool.push(Item::Symlink(name, target));
+            }
+        }
+        // Keep the corpus to a reasonable size while still mixing every
+        // kind of entry.
+        let pool: Vec<_> = pool.into_iter().step_by(3).collect();
+
+        for first in &pool {
+            for second in &pool {
+                for third in pool.iter().step_by(5) {
+                    let sandbox = Sandbox::new()?;
+                    let items = [*first, *second, *third];
+                    // Errors are expected for most combinations; the
+                    // invariant is only that the sandbox stays intact.
+                    let _ = sandbox.restore(&items);
+                    sandbox.assert_contained();
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -3,11 +3,13 @@ use std::{
     io, mem,
     path::{Path, PathBuf},
     process,
+    time::Duration,
 };
 
 use anyhow::{anyhow, Result};
-use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueEnum};
-use clap_complete::{generate, Shell};
+use bytesize::ByteSize;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use dunce::canonicalize as fs_canonicalize;
 use serde::Serialize;
 use tracing::{debug, error};
@@ -16,7 +18,13 @@ use turbopath::AbsoluteSystemPathBuf;
 #[cfg(feature = "run-stub")]
 use crate::commands::run;
 use crate::{
-    commands::{bin, daemon, generate, link, login, logout, unlink, CommandBase},
+    commands::{
+        bin, boundaries, cache,
+        completion::{self, CompletionKind},
+        config, daemon, generate, link, login, logout, ls, query,
+        sbom::{self, SbomFormat},
+        unlink, watch, why, CommandBase,
+    },
     get_version,
     shim::{RepoMode, RepoState},
     tracing::TurboSubscriber,
@@ -182,6 +190,56 @@ pub enum DaemonCommand {
     Stop,
 }
 
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+pub enum QueryCommand {
+    /// List the workspaces that depend on a workspace
+    Dependents {
+        workspace: String,
+        /// Include workspaces that depend on it through other workspaces
+        #[clap(long)]
+        transitive: bool,
+    },
+    /// List the tasks `turbo run` would execute, in the order they can run,
+    /// without hashing or running them
+    Tasks {
+        #[clap(required = true)]
+        tasks: Vec<String>,
+        /// Use the given selector to specify package(s) to act as entry
+        /// points, the same as `turbo run --filter`
+        #[clap(short = 'F', long, action = ArgAction::Append)]
+        filter: Vec<String>,
+    },
+    /// Print the shortest chain of workspace dependencies from one workspace
+    /// to another
+    Path { from: String, to: String },
+}
+
+#[derive(Subcommand, Clone, Debug, PartialEq)]
+pub enum CacheCommand {
+    /// List the artifacts in the local cache
+    Ls,
+    /// Remove artifacts from the local cache
+    Clean {
+        /// Only remove artifacts that haven't been used for at least this
+        /// long, e.g. "7d" or "12h"
+        #[clap(long, value_parser = humantime::parse_duration)]
+        older_than: Option<Duration>,
+        /// Print the artifacts that would be removed without removing them
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Remove the least recently used artifacts from the local cache until it
+    /// fits within a maximum size
+    Prune {
+        /// Maximum size of the cache, e.g. "5GB" or "500MiB"
+        #[clap(long)]
+        max_size: ByteSize,
+        /// Print the artifacts that would be removed without removing them
+        #[clap(long)]
+        dry_run: bool,
+    },
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, ValueEnum)]
 pub enum LinkTarget {
     RemoteCache,
@@ -254,9 +312,37 @@ pub enum Command {
     // them as `{ "Bin": {} }` instead of as `"Bin"`.
     /// Get the path to the Turbo binary
     Bin {},
+    /// Check that workspaces only import the workspaces they depend on
+    #[serde(skip)]
+    Boundaries {},
+    /// Inspect and clean up the local filesystem cache
+    #[serde(skip)]
+    Cache {
+        /// Override the filesystem cache directory.
+        #[clap(long, global = true)]
+        cache_dir: Option<String>,
+        #[clap(subcommand)]
+        command: CacheCommand,
+    },
     /// Generate the autocompletion script for the specified shell
     #[serde(skip)]
     Completion { shell: Shell },
+    /// Lists completion candidates, used by the completion scripts
+    #[clap(hide = true)]
+    #[serde(skip)]
+    Complete {
+        kind: CompletionKind,
+        /// The word being completed
+        #[clap(default_value = "", allow_hyphen_values = true)]
+        current: String,
+    },
+    /// Print the resolved configuration and where each value came from
+    #[serde(skip)]
+    Config {
+        /// Pass --json to report the configuration in JSON format
+        #[clap(long)]
+        json: bool,
+    },
     /// Runs the Turborepo background daemon
     Daemon {
         /// Set the idle timeout for turbod
@@ -307,6 +393,13 @@ pub enum Command {
     },
     /// Logout to your Vercel account
     Logout {},
+    /// List the workspaces in the repository and how they depend on each other
+    #[serde(skip)]
+    Ls {
+        /// Pass --json to list the workspaces in JSON format
+        #[clap(long)]
+        json: bool,
+    },
     /// Prepare a subset of your monorepo.
     Prune {
         #[clap(long)]
@@ -317,6 +410,15 @@ pub enum Command {
         output_dir: String,
     },
 
+    /// Answer questions about the workspace and task graphs
+    #[serde(skip)]
+    Query {
+        /// Pass --json to report the answer in JSON format
+        #[clap(long, global = true)]
+        json: bool,
+        #[clap(subcommand)]
+        command: QueryCommand,
+    },
     /// Run tasks across projects in your monorepo
     ///
     /// By default, turbo executes tasks in topological order (i.e.
@@ -327,6 +429,17 @@ pub enum Command {
     ///
     /// Arguments passed after '--' will be passed through to the named tasks.
     Run(Box<RunArgs>),
+    /// Generate a software bill of materials for a workspace from the lockfile
+    #[serde(skip)]
+    Sbom {
+        /// The workspace to generate the bill of materials for, by name or
+        /// by path
+        #[clap(long)]
+        filter: String,
+        /// The document format
+        #[clap(long, value_enum, default_value_t = SbomFormat::Cyclonedx)]
+        format: SbomFormat,
+    },
     /// Unlink the current directory from your Vercel organization and disable
     /// Remote Caching
     Unlink {
@@ -334,6 +447,26 @@ pub enum Command {
         #[clap(long, value_enum, default_value_t = LinkTarget::RemoteCache)]
         target: LinkTarget,
     },
+    /// Explain why an external package is installed, and which version each
+    /// workspace resolves
+    #[serde(skip)]
+    Why {
+        /// The name of the external package
+        dependency: String,
+        /// Pass --json to report the dependency chains in JSON format
+        #[clap(long)]
+        json: bool,
+    },
+    /// Re-run tasks in the workspaces affected by file changes
+    ///
+    /// Tasks marked as `persistent` in turbo.json are started once and left
+    /// running rather than being restarted.
+    #[serde(skip)]
+    Watch {
+        /// The tasks to run
+        #[clap(required = true)]
+        tasks: Vec<String>,
+    },
 }
 
 #[derive(Parser, Clone, Debug, Default, Serialize, PartialEq)]
@@ -498,6 +631,13 @@ pub struct RunArgs {
     /// to identify which task produced a log.
     #[clap(long, value_enum)]
     pub log_prefix: Option<LogPrefix>,
+    /// Use "tui" for an interactive view of the tasks and their output.
+    /// Output is streamed instead when stdout isn't a terminal or turbo is
+    /// running in CI. (default stream)
+    #[cfg(feature = "run-stub")]
+    #[clap(long, value_enum)]
+    #[serde(skip)]
+    pub ui: Option<UiMode>,
     // NOTE: The following two are hidden because clap displays them in the help text incorrectly:
     // > Usage: turbo [OPTIONS] [TASKS]... [-- <FORWARDED_ARGS>...] [COMMAND]
     #[clap(hide = true)]
@@ -516,6 +656,17 @@ pub enum LogPrefix {
     None,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub enum UiMode {
+    /// Write each line of output prefixed with the task it came from
+    #[default]
+    #[serde(rename = "stream")]
+    Stream,
+    /// Show the tasks and the selected task's output in a full-screen view
+    #[serde(rename = "tui")]
+    Tui,
+}
+
 /// Runs the CLI by parsing arguments with clap, then either calling Rust code
 /// directly or returning a payload for the Go code to use.
 ///
@@ -605,6 +756,21 @@ pub async fn run(
 
             Ok(Payload::Rust(Ok(0)))
         }
+        Command::Cache { cache_dir, command } => {
+            let cache_dir = cache_dir.clone();
+            let command = command.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            cache::run(&base, cache_dir.as_deref(), &command)?;
+
+            Ok(Payload::Rust(Ok(0)))
+        }
+        Command::Config { json } => {
+            let json = *json;
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            config::run(&base, json)?;
+
+            Ok(Payload::Rust(Ok(0)))
+        }
         Command::Logout { .. } => {
             let mut base = CommandBase::new(cli_args, repo_root, version, ui)?;
             logout::logout(&mut base)?;
@@ -697,6 +863,12 @@ pub async fn run(
         }
         #[cfg(feature = "run-stub")]
         Command::Run(args) => {
+            // The profile is written out when the guard is dropped at the end of the run
+            let _profile_guard = args
+                .profile
+                .as_deref()
+                .map(|profile| logger.enable_chrome_tracing(profile))
+                .transpose()?;
             let base = CommandBase::new(cli_args, repo_root, version, ui)?;
             run::run(base).await?;
 
@@ -714,8 +886,60 @@ pub async fn run(
             let base = CommandBase::new(cli_args, repo_root, version, UI::new(true))?;
             Ok(Payload::Go(Box::new(base)))
         }
+        Command::Ls { json } => {
+            let json = *json;
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            ls::run(&base, json)?;
+
+            Ok(Payload::Rust(Ok(0)))
+        }
+        Command::Query { json, command } => {
+            let json = *json;
+            let command = command.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            query::run(&base, json, &command)?;
+
+            Ok(Payload::Rust(Ok(0)))
+        }
+        Command::Sbom { filter, format } => {
+            let filter = filter.clone();
+            let format = *format;
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            sbom::run(&base, &filter, format)?;
+
+            Ok(Payload::Rust(Ok(0)))
+        }
+        Command::Watch { tasks } => {
+            let tasks = tasks.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            watch::run(&base, &tasks).await?;
+
+            Ok(Payload::Rust(Ok(0)))
+        }
+        Command::Boundaries {} => {
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            let exit_code = boundaries::run(&base)?;
+
+            Ok(Payload::Rust(Ok(exit_code)))
+        }
+        Command::Why { dependency, json } => {
+            let dependency = dependency.clone();
+            let json = *json;
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            why::run(&base, &dependency, json)?;
+
+            Ok(Payload::Rust(Ok(0)))
+        }
         Command::Completion { shell } => {
-            generate(*shell, &mut Args::command(), "turbo", &mut io::stdout());
+            completion::script(*shell, &mut io::stdout())?;
+
+            Ok(Payload::Rust(Ok(0)))
+        }
+        Command::Complete { kind, current } => {
+            let kind = *kind;
+            let current = current.clone();
+            let base = CommandBase::new(cli_args, repo_root, version, ui)?;
+            completion::complete(&base, kind, &current);
 
             Ok(Payload::Rust(Ok(0)))
         }
@@ -724,7 +948,7 @@ pub async fn run(
 
 #[cfg(test)]
 mod test {
-    use std::path::PathBuf;
+    use std::{path::PathBuf, time::Duration};
 
     use clap::Parser;
     use itertools::Itertools;
@@ -779,8 +1003,15 @@ mod test {
     }
 
     use anyhow::Result;
-
-    use crate::cli::{Args, Command, DryRunMode, EnvMode, OutputLogsMode, RunArgs, Verbosity};
+    use bytesize::ByteSize;
+
+    use crate::{
+        cli::{
+            Args, CacheCommand, Command, DryRunMode, EnvMode, OutputLogsMode, QueryCommand,
+            RunArgs, Verbosity,
+        },
+        commands::{completion::CompletionKind, sbom::SbomFormat},
+    };
 
     #[test]
     fn test_parse_run() -> Result<()> {
@@ -1332,6 +1563,25 @@ mod test {
         Ok(())
     }
 
+    #[cfg(feature = "run-stub")]
+    #[test]
+    fn test_parse_ui() {
+        use crate::cli::UiMode;
+
+        assert_eq!(
+            Args::try_parse_from(["turbo", "run", "build", "--ui", "tui"]).unwrap(),
+            Args {
+                command: Some(Command::Run(Box::new(RunArgs {
+                    tasks: vec!["build".to_string()],
+                    ui: Some(UiMode::Tui),
+                    ..get_default_run_args()
+                }))),
+                ..Args::default()
+            }
+        );
+        assert!(Args::try_parse_from(["turbo", "run", "build", "--ui", "fancy"]).is_err());
+    }
+
     #[test]
     fn test_parse_bin() {
         assert_eq!(
@@ -1355,6 +1605,230 @@ mod test {
         .test();
     }
 
+    #[test]
+    fn test_parse_complete() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "complete", "filter", "...^web"]).unwrap(),
+            Args {
+                command: Some(Command::Complete {
+                    kind: CompletionKind::Filter,
+                    current: "...^web".to_string(),
+                }),
+                ..Args::default()
+            }
+        );
+        assert_eq!(
+            Args::try_parse_from(["turbo", "complete", "tasks"]).unwrap(),
+            Args {
+                command: Some(Command::Complete {
+                    kind: CompletionKind::Tasks,
+                    current: String::new(),
+                }),
+                ..Args::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_config() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "config"]).unwrap(),
+            Args {
+                command: Some(Command::Config { json: false }),
+                ..Args::default()
+            }
+        );
+        assert_eq!(
+            Args::try_parse_from(["turbo", "config", "--json", "--team", "my-team"]).unwrap(),
+            Args {
+                command: Some(Command::Config { json: true }),
+                team: Some("my-team".to_string()),
+                ..Args::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_ls() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "ls", "--json"]).unwrap(),
+            Args {
+                command: Some(Command::Ls { json: true }),
+                ..Args::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "query", "dependents", "ui", "--transitive"]).unwrap(),
+            Args {
+                command: Some(Command::Query {
+                    json: false,
+                    command: QueryCommand::Dependents {
+                        workspace: "ui".to_string(),
+                        transitive: true,
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+        assert_eq!(
+            Args::try_parse_from([
+                "turbo", "query", "tasks", "build", "lint", "--filter", "web...", "--json"
+            ])
+            .unwrap(),
+            Args {
+                command: Some(Command::Query {
+                    json: true,
+                    command: QueryCommand::Tasks {
+                        tasks: vec!["build".to_string(), "lint".to_string()],
+                        filter: vec!["web...".to_string()],
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+        assert_eq!(
+            Args::try_parse_from(["turbo", "query", "path", "web", "utils"]).unwrap(),
+            Args {
+                command: Some(Command::Query {
+                    json: false,
+                    command: QueryCommand::Path {
+                        from: "web".to_string(),
+                        to: "utils".to_string(),
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+        assert!(Args::try_parse_from(["turbo", "query", "tasks"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_boundaries() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "boundaries"]).unwrap(),
+            Args {
+                command: Some(Command::Boundaries {}),
+                ..Args::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_why() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "why", "react"]).unwrap(),
+            Args {
+                command: Some(Command::Why {
+                    dependency: "react".to_string(),
+                    json: false,
+                }),
+                ..Args::default()
+            }
+        );
+        assert!(Args::try_parse_from(["turbo", "why"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_sbom() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "sbom", "--filter=web"]).unwrap(),
+            Args {
+                command: Some(Command::Sbom {
+                    filter: "web".to_string(),
+                    format: SbomFormat::Cyclonedx,
+                }),
+                ..Args::default()
+            }
+        );
+        assert_eq!(
+            Args::try_parse_from(["turbo", "sbom", "--filter", "web", "--format", "spdx"]).unwrap(),
+            Args {
+                command: Some(Command::Sbom {
+                    filter: "web".to_string(),
+                    format: SbomFormat::Spdx,
+                }),
+                ..Args::default()
+            }
+        );
+        assert!(Args::try_parse_from(["turbo", "sbom"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        assert_eq!(
+            Args::try_parse_from(["turbo", "cache", "ls"]).unwrap(),
+            Args {
+                command: Some(Command::Cache {
+                    cache_dir: None,
+                    command: CacheCommand::Ls,
+                }),
+                ..Args::default()
+            }
+        );
+
+        let expected_clean = Args {
+            command: Some(Command::Cache {
+                cache_dir: Some("custom-cache".to_string()),
+                command: CacheCommand::Clean {
+                    older_than: Some(Duration::from_secs(7 * 24 * 60 * 60)),
+                    dry_run: true,
+                },
+            }),
+            ..Args::default()
+        };
+        assert_eq!(
+            Args::try_parse_from([
+                "turbo",
+                "cache",
+                "--cache-dir",
+                "custom-cache",
+                "clean",
+                "--older-than",
+                "7d",
+                "--dry-run"
+            ])
+            .unwrap(),
+            expected_clean
+        );
+        assert_eq!(
+            Args::try_parse_from([
+                "turbo",
+                "cache",
+                "clean",
+                "--dry-run",
+                "--older-than",
+                "7d",
+                "--cache-dir",
+                "custom-cache"
+            ])
+            .unwrap(),
+            expected_clean
+        );
+
+        assert_eq!(
+            Args::try_parse_from(["turbo", "cache", "prune", "--max-size", "5GB"]).unwrap(),
+            Args {
+                command: Some(Command::Cache {
+                    cache_dir: None,
+                    command: CacheCommand::Prune {
+                        max_size: ByteSize::gb(5),
+                        dry_run: false,
+                    },
+                }),
+                ..Args::default()
+            }
+        );
+
+        assert!(Args::try_parse_from(["turbo", "cache", "prune"]).is_err());
+        assert!(
+            Args::try_parse_from(["turbo", "cache", "clean", "--older-than", "a while"]).is_err()
+        );
+    }
+
     #[test]
     fn test_parse_login() {
         assert_eq!(
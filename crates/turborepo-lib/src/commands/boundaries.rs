@@ -0,0 +1,366 @@
+//! Checks that workspaces only import the workspaces they declare as
+//! dependencies, and none of the workspaces turbo.json restricts.
+
+use std::{collections::HashSet, fmt, fs, path::Path};
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::{
+    commands::CommandBase,
+    config::{Boundaries, TurboJson},
+    package_json::PackageJson,
+    run::{
+        package_graph::{PackageGraph, IGNORED_DIRECTORIES},
+        task_id::ROOT_PKG_NAME,
+    },
+    ui::{BOLD, GREY},
+};
+
+lazy_static! {
+    // Matches `from "x"`, `import "x"`, `require("x")` and `import("x")`
+    static ref IMPORT: Regex = Regex::new(
+        r#"(?:\bfrom\s*|^\s*import\s*|\b(?:require|import)\s*\(\s*)["']([^"'\n]+)["']"#
+    )
+    .unwrap();
+}
+
+const SOURCE_EXTENSIONS: &[&str] = &["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum ViolationKind {
+    Undeclared,
+    Private,
+    Tagged(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Violation {
+    /// Unix style path relative to the repository root
+    file: String,
+    line: usize,
+    workspace: String,
+    import: String,
+    kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Violation {
+            workspace, import, ..
+        } = self;
+        match &self.kind {
+            ViolationKind::Undeclared => write!(
+                f,
+                "\"{workspace}\" imports \"{import}\", which is not declared in its package.json"
+            ),
+            ViolationKind::Private => {
+                write!(f, "\"{workspace}\" imports private workspace \"{import}\"")
+            }
+            ViolationKind::Tagged(tag) => write!(
+                f,
+                "\"{workspace}\" imports \"{import}\", which is tagged \"{tag}\""
+            ),
+        }
+    }
+}
+
+/// Reports every boundary violation and returns the exit code
+pub fn run(base: &CommandBase) -> Result<i32> {
+    let package_json_path = base.repo_root.join_component("package.json");
+    let root_package_json = PackageJson::load(package_json_path.as_absolute_path())?;
+    let package_graph = PackageGraph::build_multi_package_graph(base, &root_package_json)?;
+    let turbo_json_path = base.repo_root.join_component("turbo.json");
+    let boundaries = if turbo_json_path.exists() {
+        TurboJson::load(turbo_json_path.as_absolute_path())?.boundaries
+    } else {
+        Boundaries::default()
+    };
+
+    let violations = check(base.repo_root.as_path(), &package_graph, &boundaries)?;
+    if violations.is_empty() {
+        println!(
+            "No boundary violations found in {} workspaces",
+            package_graph.len()
+        );
+        return Ok(0);
+    }
+
+    for violation in &violations {
+        println!(
+            "{} {}",
+            base.ui
+                .apply(GREY.apply_to(format!("{}:{}:", violation.file, violation.line))),
+            violation
+        );
+    }
+    println!();
+    println!(
+        "{}",
+        base.ui
+            .apply(BOLD.apply_to(format!("{} boundary violations found", violations.len())))
+    );
+
+    Ok(1)
+}
+
+fn check(
+    repo_root: &Path,
+    package_graph: &PackageGraph,
+    boundaries: &Boundaries,
+) -> Result<Vec<Violation>> {
+    let workspace_paths: HashSet<&str> = package_graph
+        .workspaces()
+        .map(|(_, info)| info.path.as_str())
+        .collect();
+
+    let mut violations = Vec::new();
+    for (name, info) in package_graph.workspaces() {
+        if name == ROOT_PKG_NAME {
+            continue;
+        }
+        let dependencies = package_graph.dependencies(name);
+
+        let mut files = Vec::new();
+        find_sources(
+            repo_root,
+            &info.path,
+            &info.path,
+            &workspace_paths,
+            &mut files,
+        );
+        for file in files {
+            let Ok(contents) = fs::read_to_string(repo_root.join(&file)) else {
+                continue;
+            };
+            for (line, import) in imports(&contents) {
+                if import == name.as_str() {
+                    continue;
+                }
+                let Some(imported) = package_graph.workspace(&import) else {
+                    continue;
+                };
+
+                let mut kinds = Vec::new();
+                if boundaries.restrict_private && imported.package_json.private {
+                    kinds.push(ViolationKind::Private);
+                }
+                if let Some(tags) = boundaries.tags.get(&import) {
+                    kinds.extend(
+                        tags.intersection(&boundaries.restricted_tags)
+                            .map(|tag| ViolationKind::Tagged(tag.clone())),
+                    );
+                }
+                if !dependencies.contains(import.as_str()) {
+                    kinds.push(ViolationKind::Undeclared);
+                }
+                violations.extend(kinds.into_iter().map(|kind| Violation {
+                    file: file.clone(),
+                    line,
+                    workspace: name.clone(),
+                    import: import.clone(),
+                    kind,
+                }));
+            }
+        }
+    }
+
+    violations.sort();
+    violations.dedup();
+    Ok(violations)
+}
+
+/// Collects the source files of the workspace at `workspace_path`, leaving
+/// out any workspaces nested inside of it
+fn find_sources(
+    repo_root: &Path,
+    workspace_path: &str,
+    directory: &str,
+    workspace_paths: &HashSet<&str>,
+    files: &mut Vec<String>,
+) {
+    let Ok(entries) = fs::read_dir(repo_root.join(directory)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        let path = match directory {
+            "" => file_name.to_string(),
+            directory => format!("{directory}/{file_name}"),
+        };
+
+        if file_type.is_dir() {
+            let nested_workspace =
+                path != workspace_path && workspace_paths.contains(path.as_str());
+            if !nested_workspace && !IGNORED_DIRECTORIES.contains(&file_name.as_ref()) {
+                find_sources(repo_root, workspace_path, &path, workspace_paths, files);
+            }
+        } else if file_type.is_file() {
+            let is_source = Path::new(file_name.as_ref())
+                .extension()
+                .map_or(false, |extension| {
+                    SOURCE_EXTENSIONS.contains(&extension.to_string_lossy().as_ref())
+                });
+            if is_source {
+                files.push(path);
+            }
+        }
+    }
+}
+
+/// The packages imported by a source file, along with the 1-indexed line
+/// each import is on. Relative imports are left out.
+fn imports(contents: &str) -> Vec<(usize, String)> {
+    let mut imports = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("//") || trimmed.starts_with('*') || trimmed.starts_with("/*") {
+            continue;
+        }
+        for captures in IMPORT.captures_iter(line) {
+            if let Some(package) = package_name(&captures[1]) {
+                imports.push((index + 1, package.to_string()));
+            }
+        }
+    }
+    imports
+}
+
+/// The package an import specifier refers to, e.g. `@scope/ui` for
+/// `@scope/ui/button`
+fn package_name(specifier: &str) -> Option<&str> {
+    if specifier.starts_with('.') || specifier.starts_with('/') || specifier.contains(':') {
+        return None;
+    }
+    let segments = if specifier.starts_with('@') { 2 } else { 1 };
+    let end = specifier
+        .match_indices('/')
+        .nth(segments - 1)
+        .map_or(specifier.len(), |(index, _)| index);
+    Some(&specifier[..end])
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::{get_version, ui::UI, Args};
+
+    #[test]
+    fn test_imports() {
+        let source = r#"import React from "react";
+import { Button } from "@repo/ui/button";
+import "./styles.css";
+// import { unused } from "commented-out";
+export * from 'utils';
+const lazy = await import("lazy");
+const fs = require("node:fs");
+import {
+  a,
+} from "multi-line";
+"#;
+        assert_eq!(
+            imports(source),
+            vec![
+                (1, "react".to_string()),
+                (2, "@repo/ui".to_string()),
+                (5, "utils".to_string()),
+                (6, "lazy".to_string()),
+                (10, "multi-line".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check() -> Result<()> {
+        let dir = TempDir::new()?;
+        let write = |path: &str, contents: &str| {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write(
+            "package.json",
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["apps/*", "packages/*"] }"#,
+        );
+        write(
+            "apps/web/package.json",
+            r#"{ "name": "web", "dependencies": { "ui": "*", "react": "^18.2.0" } }"#,
+        );
+        write(
+            "apps/web/src/index.tsx",
+            "import React from \"react\";\nimport { Button } from \"ui\";\nimport { format } from \
+             \"utils\";\nimport { key } from \"secrets\";\n",
+        );
+        write(
+            "apps/web/node_modules/ui/index.js",
+            "module.exports = require(\"utils\");\n",
+        );
+        write(
+            "packages/ui/package.json",
+            r#"{ "name": "ui", "private": true, "dependencies": { "utils": "*" } }"#,
+        );
+        write(
+            "packages/ui/button.ts",
+            "export { format } from \"utils\";\nexport * from \"ui/internal\";\n",
+        );
+        write("packages/utils/package.json", r#"{ "name": "utils" }"#);
+        write("packages/secrets/package.json", r#"{ "name": "secrets" }"#);
+
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        let root_package_json =
+            PackageJson::load(repo_root.join_component("package.json").as_absolute_path())?;
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+        let package_graph = PackageGraph::build_multi_package_graph(&base, &root_package_json)?;
+
+        let found = |boundaries: &Boundaries| -> Result<Vec<String>> {
+            Ok(check(dir.path(), &package_graph, boundaries)?
+                .into_iter()
+                .map(|violation| format!("{}:{}: {}", violation.file, violation.line, violation))
+                .collect())
+        };
+
+        assert_eq!(
+            found(&Boundaries::default())?,
+            vec![
+                "apps/web/src/index.tsx:3: \"web\" imports \"utils\", which is not declared in \
+                 its package.json",
+                "apps/web/src/index.tsx:4: \"web\" imports \"secrets\", which is not declared in \
+                 its package.json",
+            ]
+        );
+
+        let boundaries = Boundaries {
+            tags: [(
+                "secrets".to_string(),
+                BTreeSet::from(["internal".to_string()]),
+            )]
+            .into(),
+            restricted_tags: BTreeSet::from(["internal".to_string()]),
+            restrict_private: true,
+        };
+        assert_eq!(
+            found(&boundaries)?,
+            vec![
+                "apps/web/src/index.tsx:2: \"web\" imports private workspace \"ui\"",
+                "apps/web/src/index.tsx:3: \"web\" imports \"utils\", which is not declared in \
+                 its package.json",
+                "apps/web/src/index.tsx:4: \"web\" imports \"secrets\", which is not declared in \
+                 its package.json",
+                "apps/web/src/index.tsx:4: \"web\" imports \"secrets\", which is tagged \
+                 \"internal\"",
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use bytesize::ByteSize;
+use turbopath::AbsoluteSystemPath;
+use turborepo_cache::fs::{entries_to_prune, list_entries, resolve_cache_dir, CacheEntry};
+
+use crate::{
+    cli::CacheCommand,
+    commands::CommandBase,
+    ui::{BOLD, GREY},
+};
+
+pub fn run(base: &CommandBase, cache_dir: Option<&str>, command: &CacheCommand) -> Result<()> {
+    let cache_dir = resolve_cache_dir(base.repo_root.as_absolute_path(), cache_dir);
+
+    match command {
+        CacheCommand::Ls => ls(base, cache_dir.as_absolute_path()),
+        CacheCommand::Clean {
+            older_than,
+            dry_run,
+        } => clean(base, cache_dir.as_absolute_path(), *older_than, *dry_run),
+        CacheCommand::Prune { max_size, dry_run } => prune(
+            base,
+            cache_dir.as_absolute_path(),
+            max_size.as_u64(),
+            *dry_run,
+        ),
+    }
+}
+
+fn ls(base: &CommandBase, cache_dir: &AbsoluteSystemPath) -> Result<()> {
+    let entries = list_entries(cache_dir)?;
+    let total_size: u64 = entries.iter().map(|entry| entry.size).sum();
+    let now = SystemTime::now();
+
+    if !entries.is_empty() {
+        let hash_width = entries
+            .iter()
+            .map(|entry| entry.hash.len())
+            .max()
+            .unwrap_or_default();
+        println!(
+            "{}",
+            base.ui.apply(BOLD.apply_to(format!(
+                "{:<hash_width$}  {:>10}  {:>6}  {:>9}  TASK",
+                "HASH", "SIZE", "AGE", "LAST USED"
+            )))
+        );
+        for entry in &entries {
+            println!(
+                "{:<hash_width$}  {:>10}  {:>6}  {:>9}  {}",
+                entry.hash,
+                ByteSize(entry.size).to_string(),
+                format_age(now.duration_since(entry.created).unwrap_or_default()),
+                format_age(entry.idle_time(now)),
+                entry.task.as_deref().unwrap_or("-"),
+            );
+        }
+        println!();
+    }
+
+    println!(
+        "{}",
+        base.ui.apply(GREY.apply_to(format!(
+            "{} artifacts, {} in {}",
+            entries.len(),
+            ByteSize(total_size),
+            cache_dir
+        )))
+    );
+
+    Ok(())
+}
+
+fn clean(
+    base: &CommandBase,
+    cache_dir: &AbsoluteSystemPath,
+    older_than: Option<Duration>,
+    dry_run: bool,
+) -> Result<()> {
+    let entries = list_entries(cache_dir)?;
+    let now = SystemTime::now();
+    let to_remove: Vec<_> = entries
+        .iter()
+        .filter(|entry| older_than.map_or(true, |older_than| entry.idle_time(now) >= older_than))
+        .collect();
+
+    remove_entries(base, &to_remove, dry_run)
+}
+
+fn prune(
+    base: &CommandBase,
+    cache_dir: &AbsoluteSystemPath,
+    max_size: u64,
+    dry_run: bool,
+) -> Result<()> {
+    let entries = list_entries(cache_dir)?;
+    let to_remove = entries_to_prune(&entries, max_size);
+
+    remove_entries(base, &to_remove, dry_run)
+}
+
+fn remove_entries(base: &CommandBase, entries: &[&CacheEntry], dry_run: bool) -> Result<()> {
+    let mut freed = 0;
+    for entry in entries {
+        if dry_run {
+            println!("Would remove {} ({})", entry.hash, ByteSize(entry.size));
+        } else {
+            entry.remove()?;
+        }
+        freed += entry.size;
+    }
+
+    let summary = if dry_run {
+        format!(
+            ">>> Would remove {} artifacts, freeing {}",
+            entries.len(),
+            ByteSize(freed)
+        )
+    } else {
+        format!(
+            ">>> Removed {} artifacts, freed {}",
+            entries.len(),
+            ByteSize(freed)
+        )
+    };
+    println!("{}", base.ui.apply(GREY.apply_to(summary)));
+
+    Ok(())
+}
+
+/// Formats a duration using its largest unit, e.g. `45s`, `12m`, `5h` or `3d`
+fn format_age(age: Duration) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+
+    match age.as_secs() {
+        secs if secs < MINUTE => format!("{}s", secs),
+        secs if secs < HOUR => format!("{}m", secs / MINUTE),
+        secs if secs < DAY => format!("{}h", secs / HOUR),
+        secs => format!("{}d", secs / DAY),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use test_case::test_case;
+
+    use super::format_age;
+
+    #[test_case(0, "0s" ; "zero")]
+    #[test_case(59, "59s" ; "seconds")]
+    #[test_case(60 * 5 + 30, "5m" ; "minutes")]
+    #[test_case(60 * 60 * 23, "23h" ; "hours")]
+    #[test_case(60 * 60 * 24 * 8, "8d" ; "days")]
+    fn test_format_age(secs: u64, expected: &str) {
+        assert_eq!(format_age(Duration::from_secs(secs)), expected);
+    }
+}
@@ -0,0 +1,504 @@
+//! Shell completions that call back into turbo for the names it knows about:
+//! pipeline tasks for `turbo run`, and workspace names for `--filter`.
+//!
+//! The script printed by `turbo completion <shell>` is clap's static script
+//! followed by a small wrapper that shells out to the hidden `turbo complete
+//! <kind> <current word>` command, which prints one candidate per line.
+//!
+//! The hidden command can't be named `__complete`: clap's bash generator
+//! uses `__` to separate subcommand names.
+
+use std::{collections::BTreeSet, fs, io, path::Path, time::SystemTime};
+
+use anyhow::Result;
+use clap::{CommandFactory, ValueEnum};
+use clap_complete::{generate, Shell};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use turbopath::AbsoluteSystemPathBuf;
+
+use crate::{
+    commands::CommandBase,
+    config::RawTurboJson,
+    package_json::PackageJson,
+    package_manager::{Globs, PackageManager},
+    run::{
+        package_graph::PackageGraph,
+        task_id::{ROOT_PKG_NAME, TASK_DELIMITER},
+    },
+    Args,
+};
+
+/// Files whose changes can add or remove workspaces
+const WORKSPACE_CONFIG_FILES: &[&str] = &["package.json", "pnpm-workspace.yaml"];
+/// Syntax that can start a `--filter` selector, before the workspace part
+const FILTER_PREFIXES: &[&str] = &["!", "...", "^"];
+
+const BASH_DYNAMIC: &str = r#"
+_turbo_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    # `=` is a word break, so `--filter=web` arrives as `--filter`, `=`, `web`
+    if [[ "$cur" == "=" && "$prev" == "--filter" ]]; then
+        cur=""
+    elif [[ "$prev" == "=" ]]; then
+        prev="${COMP_WORDS[COMP_CWORD-2]}"
+    fi
+    if [[ "$prev" == "--filter" || "$prev" == "-F" ]]; then
+        COMPREPLY=($(compgen -W "$(turbo complete filter "$cur" 2>/dev/null)" -- "$cur"))
+        return 0
+    fi
+    if [[ "$cur" != -* ]]; then
+        local word
+        for word in "${COMP_WORDS[@]:1:COMP_CWORD-1}"; do
+            if [[ "$word" == "run" ]]; then
+                COMPREPLY=($(compgen -W "$(turbo complete tasks "$cur" 2>/dev/null)" -- "$cur"))
+                return 0
+            fi
+        done
+    fi
+    _turbo "$@"
+}
+complete -F _turbo_dynamic -o bashdefault -o default turbo
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_turbo_dynamic() {
+    local current="${words[CURRENT]}"
+    local -a candidates
+    if [[ "${words[CURRENT-1]}" == (--filter|-F) ]]; then
+        candidates=(${(f)"$(turbo complete filter "$current" 2>/dev/null)"})
+        compadd -Q -- $candidates
+        return
+    fi
+    if [[ "$current" == --filter=* ]]; then
+        candidates=(${(f)"$(turbo complete filter "${current#--filter=}" 2>/dev/null)"})
+        compadd -Q -P '--filter=' -- $candidates
+        return
+    fi
+    local run_index=${words[(I)run]}
+    if [[ "$current" != -* && $run_index -gt 1 && $run_index -lt $CURRENT ]]; then
+        candidates=(${(f)"$(turbo complete tasks "$current" 2>/dev/null)"})
+        compadd -- $candidates
+        return
+    fi
+    _turbo "$@"
+}
+compdef _turbo_dynamic turbo
+"#;
+
+const FISH_DYNAMIC: &str = r#"
+complete -c turbo -n "__fish_seen_subcommand_from run" -f -a "(turbo complete tasks (commandline -ct) 2>/dev/null)"
+complete -c turbo -n "__fish_seen_subcommand_from run" -s F -l filter -x -a "(turbo complete filter (commandline -ct | string replace -r -- '^--filter=' '') 2>/dev/null)"
+"#;
+
+/// What `turbo complete` should list
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CompletionKind {
+    /// Tasks from the pipeline in turbo.json
+    Tasks,
+    /// Workspace names
+    Packages,
+    /// `--filter` selectors
+    Filter,
+}
+
+/// Prints the completion script for `shell`.
+pub fn script(shell: Shell, writer: &mut impl io::Write) -> io::Result<()> {
+    generate(shell, &mut Args::command(), "turbo", writer);
+
+    let dynamic = match shell {
+        Shell::Bash => BASH_DYNAMIC,
+        Shell::Zsh => ZSH_DYNAMIC,
+        Shell::Fish => FISH_DYNAMIC,
+        _ => return Ok(()),
+    };
+    writer.write_all(dynamic.as_bytes())
+}
+
+/// Prints the candidates of `kind` that start with `current`, one per line.
+///
+/// Completions run on every keypress, so problems reading the repository are
+/// logged rather than reported and leave the list empty.
+pub fn complete(base: &CommandBase, kind: CompletionKind, current: &str) {
+    let candidates = match kind {
+        CompletionKind::Tasks => task_candidates(&pipeline_tasks(base), current),
+        CompletionKind::Packages => {
+            let workspaces = workspaces(base);
+            workspaces
+                .into_iter()
+                .map(|workspace| workspace.name)
+                .filter(|name| name.starts_with(current))
+                .collect()
+        }
+        CompletionKind::Filter => filter_candidates(&workspaces(base), current),
+    };
+
+    for candidate in candidates {
+        println!("{candidate}");
+    }
+}
+
+/// A workspace as listed in the completion cache
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    /// Unix style path relative to the repository root
+    pub path: String,
+}
+
+/// Writes the workspace list to the daemon's directory, so that completions
+/// don't have to walk the repository while the daemon is running.
+pub fn write_workspace_cache(base: &CommandBase) -> Result<()> {
+    let workspaces = discover_workspaces(base)?;
+    let cache = workspace_cache_path(base);
+    if let Some(parent) = cache.as_path().parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(cache.as_path(), serde_json::to_vec(&workspaces)?)?;
+    Ok(())
+}
+
+fn workspace_cache_path(base: &CommandBase) -> AbsoluteSystemPathBuf {
+    base.daemon_file_root().join_component("workspaces.json")
+}
+
+fn workspaces(base: &CommandBase) -> Vec<Workspace> {
+    if daemon_is_running(base) {
+        if let Some(workspaces) = read_workspace_cache(base) {
+            return workspaces;
+        }
+    }
+
+    discover_workspaces(base).unwrap_or_else(|err| {
+        debug!("unable to list workspaces: {}", err);
+        Vec::new()
+    })
+}
+
+fn daemon_is_running(base: &CommandBase) -> bool {
+    let pid_file = base.daemon_file_root().join_component("turbod.pid");
+    pidlock::Pidlock::new(pid_file.into()).get_owner().is_some()
+}
+
+/// Reads the cached workspace list, as long as nothing that defines the
+/// workspaces has changed since it was written: the root configuration, the
+/// workspaces' own `package.json`s, and the directories new workspaces would
+/// be added to.
+fn read_workspace_cache(base: &CommandBase) -> Option<Vec<Workspace>> {
+    let cache = workspace_cache_path(base);
+    let written_at = modified(cache.as_path())?;
+    let contents = fs::read(cache.as_path()).ok()?;
+    let workspaces: Vec<Workspace> = serde_json::from_slice(&contents).ok()?;
+
+    let config_files = WORKSPACE_CONFIG_FILES
+        .iter()
+        .map(|file| base.repo_root.as_path().join(file));
+    let glob_parents = workspace_globs(base)
+        .into_iter()
+        .flat_map(|globs| globs.inclusions)
+        .map(|glob| base.repo_root.as_path().join(glob_parent(&glob)));
+    let config_is_stale = config_files
+        .chain(glob_parents)
+        .any(|path| modified(&path).map_or(false, |modified| modified > written_at));
+
+    // Deleting a workspace's package.json removes the workspace, so unlike
+    // the root configuration a missing file counts as a change
+    let workspace_is_stale = workspaces.iter().any(|workspace| {
+        let directory = base.repo_root.as_path().join(&workspace.path);
+        let parent_is_stale = directory
+            .parent()
+            .and_then(modified)
+            .map_or(true, |modified| modified > written_at);
+        parent_is_stale
+            || modified(&directory.join("package.json"))
+                .map_or(true, |modified| modified > written_at)
+    });
+
+    if config_is_stale || workspace_is_stale {
+        return None;
+    }
+    Some(workspaces)
+}
+
+fn workspace_globs(base: &CommandBase) -> Option<Globs> {
+    let package_json_path = base.repo_root.join_component("package.json");
+    let root_package_json = PackageJson::load(package_json_path.as_absolute_path()).ok()?;
+    PackageManager::get_package_manager(base, Some(&root_package_json))
+        .ok()?
+        .get_workspace_globs(base.repo_root.as_path())
+        .ok()?
+}
+
+/// The directory part of a workspace glob, before its first pattern segment.
+/// Adding a workspace under it changes the directory's modification time.
+fn glob_parent(glob: &str) -> String {
+    glob.trim_start_matches("./")
+        .split('/')
+        .take_while(|segment| !segment.contains(['*', '?', '[', '{']))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Finds every workspace in the repository, leaving out the root
+fn discover_workspaces(base: &CommandBase) -> Result<Vec<Workspace>> {
+    let package_json_path = base.repo_root.join_component("package.json");
+    let root_package_json = PackageJson::load(package_json_path.as_absolute_path())?;
+    let package_graph = PackageGraph::build_multi_package_graph(base, &root_package_json)?;
+
+    let mut workspaces: Vec<_> = package_graph
+        .workspaces()
+        .filter(|(name, _)| name.as_str() != ROOT_PKG_NAME)
+        .map(|(name, info)| Workspace {
+            name: name.clone(),
+            path: info.path.clone(),
+        })
+        .collect();
+    workspaces.sort();
+    Ok(workspaces)
+}
+
+fn pipeline_tasks(base: &CommandBase) -> Vec<String> {
+    let turbo_json = base.repo_root.join_component("turbo.json");
+    match RawTurboJson::load(turbo_json.as_absolute_path()) {
+        Ok(turbo_json) => turbo_json
+            .pipeline
+            .map(|pipeline| pipeline.into_keys().collect())
+            .unwrap_or_default(),
+        Err(err) => {
+            debug!("unable to read turbo.json: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+/// Workspace specific tasks (`web#build`) are offered both as written and
+/// as the plain task name.
+fn task_candidates(pipeline_tasks: &[String], current: &str) -> BTreeSet<String> {
+    pipeline_tasks
+        .iter()
+        .flat_map(|task| {
+            let plain = task.split_once(TASK_DELIMITER).map(|(_, task)| task);
+            std::iter::once(task.as_str()).chain(plain)
+        })
+        .filter(|task| task.starts_with(current))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Completes a `--filter` selector: the leading `!`, `...` and `^` syntax,
+/// then a workspace name, a `{directory}` or a `./directory`.
+fn filter_candidates(workspaces: &[Workspace], current: &str) -> BTreeSet<String> {
+    let mut rest = current;
+    while let Some(prefix) = FILTER_PREFIXES
+        .iter()
+        .find(|prefix| rest.starts_with(**prefix))
+    {
+        rest = &rest[prefix.len()..];
+    }
+    let syntax = &current[..current.len() - rest.len()];
+
+    let mut candidates = BTreeSet::new();
+    if rest.is_empty() {
+        for prefix in FILTER_PREFIXES {
+            candidates.insert(format!("{syntax}{prefix}"));
+        }
+    }
+    for workspace in workspaces {
+        candidates.insert(format!("{syntax}{}", workspace.name));
+        candidates.insert(format!("{syntax}{{{}}}", workspace.path));
+        candidates.insert(format!("{syntax}./{}", workspace.path));
+        // `web...` selects the workspace along with its dependencies
+        if rest == workspace.name {
+            candidates.insert(format!("{syntax}{}...", workspace.name));
+        }
+    }
+
+    candidates.retain(|candidate| candidate.starts_with(current));
+    candidates
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use filetime::FileTime;
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::{get_version, ui::UI};
+
+    fn workspace(name: &str, path: &str) -> Workspace {
+        Workspace {
+            name: name.to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_task_candidates() {
+        let tasks = vec![
+            "build".to_string(),
+            "web#dev".to_string(),
+            "lint".to_string(),
+        ];
+
+        assert_eq!(
+            task_candidates(&tasks, "").into_iter().collect::<Vec<_>>(),
+            vec!["build", "dev", "lint", "web#dev"]
+        );
+        assert_eq!(
+            task_candidates(&tasks, "we")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["web#dev"]
+        );
+    }
+
+    #[test]
+    fn test_filter_candidates() {
+        let workspaces = vec![workspace("web", "apps/web"), workspace("ui", "packages/ui")];
+
+        assert_eq!(
+            filter_candidates(&workspaces, "...w")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["...web"]
+        );
+        assert_eq!(
+            filter_candidates(&workspaces, "web")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["web", "web..."]
+        );
+        assert_eq!(
+            filter_candidates(&workspaces, "!{p")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["!{packages/ui}"]
+        );
+        assert_eq!(
+            filter_candidates(&workspaces, "./a")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec!["./apps/web"]
+        );
+        let all = filter_candidates(&workspaces, "");
+        assert!(all.contains("..."));
+        assert!(all.contains("^"));
+        assert!(all.contains("ui"));
+    }
+
+    #[test]
+    fn test_discover_workspaces() -> Result<()> {
+        let dir = TempDir::new()?;
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        fs::write(
+            repo_root.join_component("package.json"),
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["apps/*", "packages/*"] }"#,
+        )?;
+        for (path, name) in [("apps/web", "web"), ("packages/ui", "ui")] {
+            let directory = dir.path().join(path);
+            fs::create_dir_all(&directory)?;
+            fs::write(
+                directory.join("package.json"),
+                format!(r#"{{ "name": "{name}" }}"#),
+            )?;
+        }
+        // Packages installed into node_modules aren't workspaces
+        let installed = dir.path().join("apps/web/node_modules/react");
+        fs::create_dir_all(&installed)?;
+        fs::write(installed.join("package.json"), r#"{ "name": "react" }"#)?;
+
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+        assert_eq!(
+            discover_workspaces(&base)?,
+            vec![workspace("ui", "packages/ui"), workspace("web", "apps/web")]
+        );
+
+        write_workspace_cache(&base)?;
+        assert_eq!(
+            read_workspace_cache(&base),
+            Some(vec![
+                workspace("ui", "packages/ui"),
+                workspace("web", "apps/web")
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspace_cache_staleness() -> Result<()> {
+        let dir = TempDir::new()?;
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        fs::write(
+            repo_root.join_component("package.json"),
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["apps/*"] }"#,
+        )?;
+        fs::create_dir_all(dir.path().join("apps/web"))?;
+        fs::write(
+            dir.path().join("apps/web/package.json"),
+            r#"{ "name": "web" }"#,
+        )?;
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+
+        // Backdate the repository so that any later write is newer than the cache
+        let past = FileTime::from_unix_time(FileTime::now().unix_seconds() - 60, 0);
+        let set_modified =
+            |path: &str, time: FileTime| filetime::set_file_mtime(dir.path().join(path), time);
+        for path in ["package.json", "apps", "apps/web", "apps/web/package.json"] {
+            set_modified(path, past)?;
+        }
+        write_workspace_cache(&base)?;
+        assert_eq!(
+            read_workspace_cache(&base),
+            Some(vec![workspace("web", "apps/web")])
+        );
+
+        // Renaming a workspace only touches its own package.json
+        fs::write(
+            dir.path().join("apps/web/package.json"),
+            r#"{ "name": "site" }"#,
+        )?;
+        assert_eq!(read_workspace_cache(&base), None);
+        write_workspace_cache(&base)?;
+        set_modified("apps/web/package.json", past)?;
+        assert_eq!(
+            read_workspace_cache(&base),
+            Some(vec![workspace("site", "apps/web")])
+        );
+
+        // Adding a workspace only touches the directory it's added to
+        fs::create_dir_all(dir.path().join("apps/docs"))?;
+        assert_eq!(read_workspace_cache(&base), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_script_wraps_static_completions() {
+        for (shell, wrapper) in [
+            (Shell::Bash, "complete -F _turbo_dynamic"),
+            (Shell::Zsh, "compdef _turbo_dynamic turbo"),
+            (Shell::Fish, "turbo complete tasks"),
+        ] {
+            let mut output = Vec::new();
+            script(shell, &mut output).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            assert!(output.contains(wrapper), "{shell} script missing wrapper");
+        }
+
+        let mut output = Vec::new();
+        script(Shell::Bash, &mut output).unwrap();
+        // the wrapper falls back to clap's generated function
+        assert!(String::from_utf8(output).unwrap().contains("_turbo() {"));
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    commands::CommandBase,
+    config::ConfigSource,
+    ui::{BOLD, GREY},
+};
+
+/// The effective configuration along with the layer each value came from
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigOutput<'a> {
+    api_url: Setting<'a, &'a str>,
+    login_url: Setting<'a, &'a str>,
+    team_slug: Setting<'a, Option<&'a str>>,
+    team_id: Setting<'a, Option<&'a str>>,
+    token: TokenSetting<'a>,
+    remote_cache_timeout: Setting<'a, u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Setting<'a, T> {
+    value: T,
+    #[serde(flatten)]
+    source: &'a ConfigSource,
+}
+
+/// The token is reported by presence only, its value is never printed
+#[derive(Debug, Serialize)]
+struct TokenSetting<'a> {
+    set: bool,
+    #[serde(flatten)]
+    source: &'a ConfigSource,
+}
+
+pub fn run(base: &CommandBase, json: bool) -> Result<()> {
+    let output = config_output(base)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let rows = [
+        (
+            "API URL",
+            output.api_url.value.to_string(),
+            output.api_url.source,
+        ),
+        (
+            "Login URL",
+            output.login_url.value.to_string(),
+            output.login_url.source,
+        ),
+        (
+            "Team slug",
+            output.team_slug.value.unwrap_or("-").to_string(),
+            output.team_slug.source,
+        ),
+        (
+            "Team ID",
+            output.team_id.value.unwrap_or("-").to_string(),
+            output.team_id.source,
+        ),
+        (
+            "Token",
+            if output.token.set { "set" } else { "not set" }.to_string(),
+            output.token.source,
+        ),
+        (
+            "Remote cache timeout",
+            format!("{}s", output.remote_cache_timeout.value),
+            output.remote_cache_timeout.source,
+        ),
+    ];
+
+    let name_width = rows.iter().map(|(name, ..)| name.len()).max().unwrap_or(0);
+    let value_width = rows
+        .iter()
+        .map(|(_, value, _)| value.len())
+        .max()
+        .unwrap_or(0);
+    println!(
+        "{}",
+        base.ui.apply(BOLD.apply_to(format!(
+            "{:<name_width$}  {:<value_width$}  SOURCE",
+            "SETTING", "VALUE"
+        )))
+    );
+    for (name, value, source) in &rows {
+        println!(
+            "{:<name_width$}  {:<value_width$}  {}",
+            name,
+            value,
+            base.ui.apply(GREY.apply_to(source.to_string()))
+        );
+    }
+
+    Ok(())
+}
+
+fn config_output(base: &CommandBase) -> Result<ConfigOutput<'_>> {
+    let repo_config = base.repo_config()?;
+    let user_config = base.user_config()?;
+    let client_config = base.client_config()?;
+    let repo_sources = repo_config.sources();
+
+    Ok(ConfigOutput {
+        api_url: Setting {
+            value: repo_config.api_url(),
+            source: &repo_sources.api_url,
+        },
+        login_url: Setting {
+            value: repo_config.login_url(),
+            source: &repo_sources.login_url,
+        },
+        team_slug: Setting {
+            value: repo_config.team_slug(),
+            source: &repo_sources.team_slug,
+        },
+        team_id: Setting {
+            value: repo_config.team_id(),
+            source: &repo_sources.team_id,
+        },
+        token: TokenSetting {
+            set: user_config.token().is_some(),
+            source: user_config.token_source(),
+        },
+        remote_cache_timeout: Setting {
+            value: client_config.remote_cache_timeout(),
+            source: client_config.remote_cache_timeout_source(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::NamedTempFile;
+    use tokio::sync::OnceCell;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::{
+        config::{ClientConfigLoader, RepoConfigLoader, UserConfigLoader},
+        ui::UI,
+        Args,
+    };
+
+    #[test]
+    fn test_config_output_hides_token() {
+        let user_config_file = NamedTempFile::new().unwrap();
+        fs::write(user_config_file.path(), r#"{ "token": "super-secret" }"#).unwrap();
+        let repo_config_file = NamedTempFile::new().unwrap();
+        fs::write(repo_config_file.path(), r#"{ "teamid": "team_123" }"#).unwrap();
+
+        let base = CommandBase {
+            repo_root: Default::default(),
+            ui: UI::new(true),
+            client_config: OnceCell::from(
+                ClientConfigLoader::new()
+                    .with_remote_cache_timeout(Some(5))
+                    .load()
+                    .unwrap(),
+            ),
+            user_config: OnceCell::from(
+                UserConfigLoader::new(user_config_file.path().to_path_buf())
+                    .with_environment(Some(Default::default()))
+                    .load()
+                    .unwrap(),
+            ),
+            repo_config: OnceCell::from(
+                RepoConfigLoader::new(AbsoluteSystemPathBuf::new(repo_config_file.path()).unwrap())
+                    .with_environment(Some(Default::default()))
+                    .load()
+                    .unwrap(),
+            ),
+            args: Args::default(),
+            version: "",
+        };
+
+        let output = serde_json::to_value(config_output(&base).unwrap()).unwrap();
+        assert!(!output.to_string().contains("super-secret"));
+        assert_eq!(
+            output["token"],
+            serde_json::json!({
+                "set": true,
+                "source": "file",
+                "path": user_config_file.path(),
+            })
+        );
+        assert_eq!(
+            output["teamId"],
+            serde_json::json!({
+                "value": "team_123",
+                "source": "file",
+                "path": repo_config_file.path(),
+            })
+        );
+        assert_eq!(
+            output["apiUrl"],
+            serde_json::json!({ "value": "https://vercel.com/api", "source": "default" })
+        );
+        assert_eq!(
+            output["remoteCacheTimeout"],
+            serde_json::json!({
+                "value": 5,
+                "source": "flag",
+                "name": "--remote-cache-timeout",
+            })
+        );
+    }
+}
@@ -5,7 +5,7 @@ use time::{format_description, OffsetDateTime};
 use tracing::{trace, warn};
 use turbopath::AbsoluteSystemPathBuf;
 
-use super::CommandBase;
+use super::{completion, CommandBase};
 use crate::{
     cli::DaemonCommand,
     daemon::{endpoint::SocketOpenError, CloseReason, DaemonConnector, DaemonError},
@@ -103,6 +103,11 @@ pub async fn daemon_server(
         tracing::error!("failed to set file logger: {}", e);
     }
 
+    // completions read the workspace list from here while the daemon is up
+    if let Err(e) = completion::write_workspace_cache(base) {
+        tracing::debug!("failed to cache workspaces: {}", e);
+    }
+
     let timeout = go_parse_duration::parse_duration(idle_time)
         .map_err(|_| DaemonError::InvalidTimeout(idle_time.to_owned()))
         .map(|d| Duration::from_nanos(d as u64))?;
@@ -23,7 +23,7 @@ use crate::ui::CYAN;
 use crate::{
     cli::LinkTarget,
     commands::CommandBase,
-    config::{SpacesJson, TurboJson},
+    config::RawTurboJson,
     ui::{BOLD, GREY, UNDERLINE},
 };
 
@@ -425,24 +425,7 @@ fn add_space_id_to_turbo_json(base: &CommandBase, space_id: &str) -> Result<()>
         return Err(anyhow!("turbo.json not found."));
     }
 
-    let turbo_json_file = File::open(&turbo_json_path)?;
-    let mut turbo_json: TurboJson = serde_json::from_reader(turbo_json_file)?;
-    match turbo_json.experimental_spaces {
-        Some(mut spaces_config) => {
-            spaces_config.id = Some(space_id.to_string());
-            turbo_json.experimental_spaces = Some(spaces_config);
-        }
-        None => {
-            turbo_json.experimental_spaces = Some(SpacesJson {
-                id: Some(space_id.to_string()),
-                other: None,
-            });
-        }
-    }
-
-    // write turbo_json back to file
-    let config_file = File::create(&turbo_json_path)?;
-    serde_json::to_writer_pretty(&config_file, &turbo_json)?;
+    RawTurboJson::set_space_id(turbo_json_path.as_absolute_path(), space_id)?;
 
     Ok(())
 }
@@ -459,7 +442,7 @@ mod test {
     use crate::{
         cli::LinkTarget,
         commands::{link, CommandBase},
-        config::{ClientConfigLoader, RepoConfigLoader, TurboJson, UserConfigLoader},
+        config::{ClientConfigLoader, RawTurboJson, RepoConfigLoader, UserConfigLoader},
         ui::UI,
         Args,
     };
@@ -554,7 +537,7 @@ mod test {
 
         fs::write(
             turbo_json_file.as_path(),
-            r#"{ "globalEnv": [], "pipeline": {} }"#,
+            "{\n  // Shared settings\n  \"globalEnv\": [],\n  \"pipeline\": {}\n}\n",
         )
         .unwrap();
 
@@ -565,11 +548,13 @@ mod test {
         handle.abort();
 
         // verify space id is added to turbo.json
-        let turbo_json_file = fs::File::open(&turbo_json_file).unwrap();
-        let turbo_json: TurboJson = serde_json::from_reader(turbo_json_file).unwrap();
+        let turbo_json = RawTurboJson::load(turbo_json_file.as_absolute_path()).unwrap();
         assert_eq!(
             turbo_json.experimental_spaces.unwrap().id.unwrap(),
             vercel_api_mock::EXPECTED_SPACE_ID
         );
+        // the rest of the file is left as it was
+        let contents = fs::read_to_string(turbo_json_file.as_path()).unwrap();
+        assert!(contents.contains("// Shared settings"));
     }
 }
@@ -0,0 +1,163 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    commands::CommandBase,
+    package_json::PackageJson,
+    package_manager::PackageManager,
+    run::{package_graph::PackageGraph, task_id::ROOT_PKG_NAME},
+    ui::{BOLD, GREY},
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LsOutput<'a> {
+    package_manager: PackageManager,
+    workspaces: Vec<WorkspaceOutput<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct WorkspaceOutput<'a> {
+    name: &'a str,
+    path: &'a str,
+    dependencies: BTreeSet<&'a str>,
+    dependents: BTreeSet<&'a str>,
+}
+
+pub fn run(base: &CommandBase, json: bool) -> Result<()> {
+    let package_json_path = base.repo_root.join_component("package.json");
+    let root_package_json = PackageJson::load(package_json_path.as_absolute_path())?;
+    let package_manager = PackageManager::get_package_manager(base, Some(&root_package_json))?;
+    let package_graph = PackageGraph::build_multi_package_graph(base, &root_package_json)?;
+    let output = ls_output(package_manager, &package_graph);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} workspaces {}",
+        output.workspaces.len(),
+        base.ui
+            .apply(GREY.apply_to(format!("({})", output.package_manager)))
+    );
+    if output.workspaces.is_empty() {
+        return Ok(());
+    }
+
+    let join = |names: &BTreeSet<&str>| {
+        if names.is_empty() {
+            "-".to_string()
+        } else {
+            names.iter().copied().collect::<Vec<_>>().join(", ")
+        }
+    };
+    let rows: Vec<_> = output
+        .workspaces
+        .iter()
+        .map(|workspace| {
+            (
+                workspace.name,
+                workspace.path,
+                join(&workspace.dependencies),
+                join(&workspace.dependents),
+            )
+        })
+        .collect();
+    let name_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+    let path_width = rows.iter().map(|row| row.1.len()).max().unwrap_or(0);
+    let dependencies_width = rows
+        .iter()
+        .map(|row| row.2.len())
+        .max()
+        .unwrap_or(0)
+        .max("DEPENDENCIES".len());
+
+    println!();
+    println!(
+        "{}",
+        base.ui.apply(BOLD.apply_to(format!(
+            "{:<name_width$}  {:<path_width$}  {:<dependencies_width$}  DEPENDENTS",
+            "NAME", "PATH", "DEPENDENCIES"
+        )))
+    );
+    for (name, path, dependencies, dependents) in &rows {
+        println!(
+            "{:<name_width$}  {:<path_width$}  {:<dependencies_width$}  {}",
+            name, path, dependencies, dependents
+        );
+    }
+
+    Ok(())
+}
+
+fn ls_output(package_manager: PackageManager, package_graph: &PackageGraph) -> LsOutput<'_> {
+    let workspaces = package_graph
+        .workspaces()
+        .filter(|(name, _)| name.as_str() != ROOT_PKG_NAME)
+        .map(|(name, info)| WorkspaceOutput {
+            name,
+            path: &info.path,
+            dependencies: package_graph.dependencies(name),
+            dependents: package_graph.dependents(name),
+        })
+        .collect();
+
+    LsOutput {
+        package_manager,
+        workspaces,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use serde_json::json;
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::{get_version, ui::UI, Args};
+
+    #[test]
+    fn test_ls_output() -> Result<()> {
+        let dir = TempDir::new()?;
+        let write = |path: &str, contents: &str| {
+            let directory = dir.path().join(path);
+            fs::create_dir_all(&directory).unwrap();
+            fs::write(directory.join("package.json"), contents).unwrap();
+        };
+        write(
+            "",
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["apps/*", "packages/*"] }"#,
+        );
+        write(
+            "apps/web",
+            r#"{ "name": "web", "dependencies": { "ui": "*" } }"#,
+        );
+        write("packages/ui", r#"{ "name": "ui" }"#);
+
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        let root_package_json =
+            PackageJson::load(repo_root.join_component("package.json").as_absolute_path())?;
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+        let package_graph = PackageGraph::build_multi_package_graph(&base, &root_package_json)?;
+
+        assert_eq!(
+            serde_json::to_value(ls_output(PackageManager::Npm, &package_graph))?,
+            json!({
+                "packageManager": "npm",
+                "workspaces": [
+                    { "name": "ui", "path": "packages/ui", "dependencies": [], "dependents": ["web"] },
+                    { "name": "web", "path": "apps/web", "dependencies": ["ui"], "dependents": [] },
+                ],
+            })
+        );
+
+        Ok(())
+    }
+}
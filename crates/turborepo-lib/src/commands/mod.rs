@@ -16,13 +16,22 @@ use crate::{
 };
 
 pub(crate) mod bin;
+pub(crate) mod boundaries;
+pub(crate) mod cache;
+pub(crate) mod completion;
+pub(crate) mod config;
 pub(crate) mod daemon;
 pub(crate) mod generate;
 pub(crate) mod link;
 pub(crate) mod login;
 pub(crate) mod logout;
+pub(crate) mod ls;
+pub(crate) mod query;
 pub(crate) mod run;
+pub(crate) mod sbom;
 pub(crate) mod unlink;
+pub(crate) mod watch;
+pub(crate) mod why;
 
 #[derive(Debug)]
 pub struct CommandBase {
@@ -141,6 +150,10 @@ impl CommandBase {
         &self.args
     }
 
+    pub fn version(&self) -> &'static str {
+        self.version
+    }
+
     pub fn api_client(&mut self) -> Result<APIClient> {
         let repo_config = self.repo_config()?;
         let client_config = self.client_config()?;
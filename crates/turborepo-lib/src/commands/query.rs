@@ -0,0 +1,252 @@
+use std::collections::{BTreeSet, HashSet};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+use crate::{
+    cli::QueryCommand,
+    commands::CommandBase,
+    config::TurboJson,
+    opts::ScopeOpts,
+    package_json::PackageJson,
+    run::{
+        package_graph::PackageGraph,
+        scope,
+        task_graph::TaskGraph,
+        task_id::{get_package_task_from_id, root_task_id, ROOT_PKG_NAME},
+    },
+    ui::GREY,
+};
+
+#[derive(Debug, Serialize)]
+struct TaskOutput<'a> {
+    #[serde(rename = "taskId")]
+    task_id: &'a str,
+    package: String,
+    task: String,
+    command: Option<&'a str>,
+    dependencies: BTreeSet<&'a str>,
+}
+
+pub fn run(base: &CommandBase, json: bool, command: &QueryCommand) -> Result<()> {
+    let package_json_path = base.repo_root.join_component("package.json");
+    let root_package_json = PackageJson::load(package_json_path.as_absolute_path())?;
+    let package_graph = PackageGraph::build_multi_package_graph(base, &root_package_json)?;
+
+    match command {
+        QueryCommand::Dependents {
+            workspace,
+            transitive,
+        } => {
+            let dependents = dependents(&package_graph, workspace, *transitive)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&dependents)?);
+            } else {
+                for dependent in dependents {
+                    println!("{dependent}");
+                }
+            }
+        }
+        QueryCommand::Tasks { tasks, filter } => {
+            let turbo_json = TurboJson::load(
+                base.repo_root
+                    .join_component("turbo.json")
+                    .as_absolute_path(),
+            )?;
+            let task_graph = task_graph(base, &package_graph, &turbo_json, tasks, filter)?;
+            let tasks = task_output(&package_graph, &task_graph);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&tasks)?);
+            } else {
+                for task in tasks {
+                    println!(
+                        "{}  {}",
+                        task.task_id,
+                        base.ui
+                            .apply(GREY.apply_to(task.command.unwrap_or("<NONEXISTENT>")))
+                    );
+                }
+            }
+        }
+        QueryCommand::Path { from, to } => {
+            for workspace in [from, to] {
+                if package_graph.workspace(workspace).is_none() {
+                    return Err(anyhow!("No workspace named \"{workspace}\""));
+                }
+            }
+            let path = package_graph.shortest_path(from, to);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&path)?);
+            } else {
+                match path {
+                    Some(path) => println!("{}", path.join(" -> ")),
+                    None => println!("{from} does not depend on {to}"),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn dependents<'a>(
+    package_graph: &'a PackageGraph,
+    workspace: &str,
+    transitive: bool,
+) -> Result<BTreeSet<&'a str>> {
+    if package_graph.workspace(workspace).is_none() {
+        return Err(anyhow!("No workspace named \"{workspace}\""));
+    }
+    Ok(if transitive {
+        package_graph.transitive_dependents(workspace)
+    } else {
+        package_graph.dependents(workspace)
+    })
+}
+
+/// Builds the task graph `turbo run <tasks> --filter <filter>` would execute
+fn task_graph(
+    base: &CommandBase,
+    package_graph: &PackageGraph,
+    turbo_json: &TurboJson,
+    tasks: &[String],
+    filter: &[String],
+) -> Result<TaskGraph> {
+    let scope_opts = ScopeOpts {
+        filter_patterns: filter.to_vec(),
+    };
+    let mut packages: HashSet<String> = scope::resolve_packages(&scope_opts, base, package_graph)?;
+    // Root tasks only run when nothing was filtered out, the same as a run
+    if packages.len() == package_graph.len()
+        && tasks
+            .iter()
+            .any(|task| turbo_json.pipeline.contains_key(&root_task_id(task)))
+    {
+        packages.insert(ROOT_PKG_NAME.to_string());
+    }
+
+    TaskGraph::build(package_graph, &turbo_json.pipeline, tasks, &packages)
+}
+
+fn task_output<'a>(
+    package_graph: &'a PackageGraph,
+    task_graph: &'a TaskGraph,
+) -> Vec<TaskOutput<'a>> {
+    task_graph
+        .tasks()
+        .into_iter()
+        .map(|task_id| {
+            let (package, task) = get_package_task_from_id(task_id);
+            let command = package_graph
+                .workspace(&package)
+                .and_then(|info| info.package_json.script(&task));
+            TaskOutput {
+                task_id,
+                package,
+                task,
+                command,
+                dependencies: task_graph.dependencies(task_id),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::{get_version, ui::UI, Args};
+
+    fn setup() -> Result<(TempDir, CommandBase, PackageGraph)> {
+        let dir = TempDir::new()?;
+        let write = |path: &str, contents: &str| {
+            let directory = dir.path().join(path);
+            fs::create_dir_all(&directory).unwrap();
+            fs::write(directory.join("package.json"), contents).unwrap();
+        };
+        write(
+            "",
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["apps/*", "packages/*"] }"#,
+        );
+        write(
+            "apps/web",
+            r#"{ "name": "web", "dependencies": { "ui": "*" }, "scripts": { "build": "next build" } }"#,
+        );
+        write(
+            "apps/docs",
+            r#"{ "name": "docs", "dependencies": { "ui": "*" } }"#,
+        );
+        write(
+            "packages/ui",
+            r#"{ "name": "ui", "dependencies": { "utils": "*" }, "scripts": { "build": "tsc" } }"#,
+        );
+        write("packages/utils", r#"{ "name": "utils" }"#);
+        fs::write(
+            dir.path().join("turbo.json"),
+            r#"{ "pipeline": { "build": { "dependsOn": ["^build"] }, "//#build": {} } }"#,
+        )?;
+
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        let root_package_json =
+            PackageJson::load(repo_root.join_component("package.json").as_absolute_path())?;
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+        let package_graph = PackageGraph::build_multi_package_graph(&base, &root_package_json)?;
+        Ok((dir, base, package_graph))
+    }
+
+    #[test]
+    fn test_dependents() -> Result<()> {
+        let (_dir, _base, package_graph) = setup()?;
+        assert_eq!(
+            dependents(&package_graph, "utils", false)?,
+            BTreeSet::from(["ui"])
+        );
+        assert_eq!(
+            dependents(&package_graph, "utils", true)?,
+            BTreeSet::from(["docs", "ui", "web"])
+        );
+        assert!(dependents(&package_graph, "missing", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tasks() -> Result<()> {
+        let (_dir, base, package_graph) = setup()?;
+        let turbo_json = TurboJson::load(
+            base.repo_root
+                .join_component("turbo.json")
+                .as_absolute_path(),
+        )?;
+
+        let graph = task_graph(
+            &base,
+            &package_graph,
+            &turbo_json,
+            &["build".to_string()],
+            &["web".to_string()],
+        )?;
+        let tasks = task_output(&package_graph, &graph);
+        let ids: Vec<_> = tasks.iter().map(|task| task.task_id).collect();
+        assert_eq!(ids, vec!["utils#build", "ui#build", "web#build"]);
+        assert_eq!(tasks[1].command, Some("tsc"));
+        assert_eq!(tasks[0].command, None);
+        assert_eq!(tasks[2].dependencies, BTreeSet::from(["ui#build"]));
+
+        // Without a filter the root task is included as well
+        let graph = task_graph(
+            &base,
+            &package_graph,
+            &turbo_json,
+            &["build".to_string()],
+            &[],
+        )?;
+        assert!(graph.tasks().contains(&"//#build"));
+        assert_eq!(graph.tasks().len(), 5);
+
+        Ok(())
+    }
+}
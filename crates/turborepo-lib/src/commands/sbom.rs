@@ -0,0 +1,815 @@
+//! Software bill of materials generation for a workspace.
+//!
+//! The document is built from the package graph and the lockfile alone, so it
+//! doesn't need `node_modules` or network access. It lists the workspace, the
+//! internal workspaces it depends on, and every package the lockfile resolves
+//! for them, along with the dependency edges between all of these.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use turborepo_lockfiles::Lockfile;
+
+use crate::{
+    commands::CommandBase, package_json::PackageJson, package_manager::PackageManager,
+    run::package_graph::PackageGraph,
+};
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SbomFormat {
+    /// CycloneDX 1.4 JSON
+    #[default]
+    Cyclonedx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+pub fn run(base: &CommandBase, filter: &str, format: SbomFormat) -> Result<()> {
+    let package_json_path = base.repo_root.join_component("package.json");
+    let root_package_json = PackageJson::load(package_json_path.as_absolute_path())?;
+    let package_manager = PackageManager::get_package_manager(base, Some(&root_package_json))?;
+    let package_graph = PackageGraph::build_multi_package_graph(base, &root_package_json)?;
+    let target = find_workspace(&package_graph, filter)?;
+
+    let inventory = package_manager
+        .with_lockfile(base.repo_root.as_absolute_path(), |lockfile| {
+            Inventory::collect(&package_graph, lockfile, &package_manager, target)
+        })?;
+
+    let created = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    let document = match format {
+        SbomFormat::Cyclonedx => {
+            serde_json::to_string_pretty(&cyclonedx(&inventory, base.version(), &created))?
+        }
+        SbomFormat::Spdx => {
+            serde_json::to_string_pretty(&spdx(&inventory, base.version(), &created))?
+        }
+    };
+    println!("{document}");
+
+    Ok(())
+}
+
+/// Finds the workspace selected by `filter`, either by package name or by
+/// its directory relative to the repository root
+fn find_workspace<'a>(package_graph: &'a PackageGraph, filter: &str) -> Result<&'a str> {
+    if let Some((name, _)) = package_graph.workspaces().find(|(name, _)| *name == filter) {
+        return Ok(name);
+    }
+
+    let path = filter.trim_start_matches("./").trim_end_matches('/');
+    package_graph
+        .workspaces()
+        .find(|(_, info)| info.path == path)
+        .map(|(name, _)| name.as_str())
+        .ok_or_else(|| anyhow!("No workspace found matching \"{filter}\""))
+}
+
+/// Everything that ends up in the document, independent of its format
+#[derive(Debug)]
+struct Inventory {
+    /// The selected workspace followed by the workspaces it depends on
+    workspaces: Vec<WorkspaceComponent>,
+    /// Packages from the lockfile, keyed by lockfile key
+    packages: BTreeMap<String, PackageComponent>,
+}
+
+#[derive(Debug)]
+struct WorkspaceComponent {
+    name: String,
+    version: Option<String>,
+    path: String,
+    workspace_dependencies: BTreeSet<String>,
+    package_dependencies: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+struct PackageComponent {
+    name: String,
+    version: String,
+    resolved: Option<String>,
+    integrity: Option<String>,
+    checksum: Option<String>,
+    dependencies: BTreeSet<String>,
+}
+
+impl Inventory {
+    fn collect(
+        package_graph: &PackageGraph,
+        lockfile: &dyn Lockfile,
+        package_manager: &PackageManager,
+        target: &str,
+    ) -> Result<Self> {
+        let names = std::iter::once(target)
+            .chain(package_graph.transitive_dependencies(target))
+            .collect::<Vec<_>>();
+
+        let mut workspaces = Vec::new();
+        let mut packages = BTreeMap::new();
+        for name in names {
+            let info = package_graph
+                .workspace(name)
+                .ok_or_else(|| anyhow!("workspace {name} is missing from the package graph"))?;
+            let workspace_path = package_manager.lockfile_workspace_path(&info.path);
+
+            let mut package_dependencies = BTreeSet::new();
+            for (dependency, specifier) in package_graph.external_dependencies(name) {
+                match lockfile.resolve_package(workspace_path, dependency, specifier)? {
+                    Some(package) => {
+                        package_dependencies.insert(package.key.clone());
+                        collect_package(lockfile, workspace_path, package, &mut packages)?;
+                    }
+                    None => debug!("{dependency}@{specifier} of {name} is not in the lockfile"),
+                }
+            }
+
+            workspaces.push(WorkspaceComponent {
+                name: name.to_string(),
+                version: info.package_json.version.clone(),
+                path: info.path.clone(),
+                workspace_dependencies: package_graph
+                    .dependencies(name)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect(),
+                package_dependencies,
+            });
+        }
+
+        Ok(Self {
+            workspaces,
+            packages,
+        })
+    }
+}
+
+/// Adds `package` and everything it depends on to `packages`
+fn collect_package(
+    lockfile: &dyn Lockfile,
+    workspace_path: &str,
+    package: turborepo_lockfiles::Package,
+    packages: &mut BTreeMap<String, PackageComponent>,
+) -> Result<()> {
+    let mut queue = vec![package];
+    while let Some(package) = queue.pop() {
+        if packages.contains_key(&package.key) {
+            continue;
+        }
+
+        let metadata = lockfile.package_metadata(&package.key)?.unwrap_or_default();
+        let mut dependencies = BTreeSet::new();
+        for (name, specifier) in lockfile.all_dependencies(&package.key)?.unwrap_or_default() {
+            if let Some(dependency) = lockfile.resolve_package(workspace_path, &name, &specifier)? {
+                dependencies.insert(dependency.key.clone());
+                queue.push(dependency);
+            }
+        }
+
+        packages.insert(
+            package.key,
+            PackageComponent {
+                name: metadata.name,
+                version: package.version,
+                resolved: metadata.resolved,
+                integrity: metadata.integrity,
+                checksum: metadata.checksum,
+                dependencies,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+impl PackageComponent {
+    fn purl(&self) -> String {
+        format!(
+            "pkg:npm/{}@{}",
+            self.name.replacen('@', "%40", 1),
+            self.version
+        )
+    }
+
+    /// The resolved location, if it's somewhere the package can be downloaded
+    /// from. Berry records locators rather than URLs.
+    fn download_url(&self) -> Option<&str> {
+        self.resolved
+            .as_deref()
+            .filter(|resolved| resolved.starts_with("https://") || resolved.starts_with("http://"))
+    }
+
+    /// The tarball hashes from the Subresource Integrity string as
+    /// (algorithm, hex digest) pairs
+    fn hashes(&self) -> Vec<(&'static str, String)> {
+        let Some(integrity) = &self.integrity else {
+            return Vec::new();
+        };
+        integrity
+            .split_whitespace()
+            .filter_map(|hash| {
+                let (algorithm, digest) = hash.split_once('-')?;
+                let algorithm = match algorithm {
+                    "sha1" => "SHA1",
+                    "sha256" => "SHA256",
+                    "sha384" => "SHA384",
+                    "sha512" => "SHA512",
+                    _ => return None,
+                };
+                let digest = base64::engine::general_purpose::STANDARD
+                    .decode(digest)
+                    .ok()?;
+                Some((algorithm, hex::encode(digest)))
+            })
+            .collect()
+    }
+}
+
+fn workspace_ref(workspace: &WorkspaceComponent) -> String {
+    if workspace.path.is_empty() {
+        "workspace:.".to_string()
+    } else {
+        format!("workspace:{}", workspace.path)
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDx {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    version: u32,
+    metadata: CycloneDxMetadata,
+    components: Vec<CycloneDxComponent>,
+    dependencies: Vec<CycloneDxDependency>,
+}
+
+#[derive(Debug, Serialize)]
+struct CycloneDxMetadata {
+    timestamp: String,
+    tools: Vec<CycloneDxTool>,
+    component: CycloneDxComponent,
+}
+
+#[derive(Debug, Serialize)]
+struct CycloneDxTool {
+    vendor: &'static str,
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDxComponent {
+    #[serde(rename = "type")]
+    component_type: &'static str,
+    #[serde(rename = "bom-ref")]
+    bom_ref: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purl: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hashes: Vec<CycloneDxHash>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_references: Vec<CycloneDxReference>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<CycloneDxProperty>,
+}
+
+#[derive(Debug, Serialize)]
+struct CycloneDxHash {
+    alg: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CycloneDxReference {
+    #[serde(rename = "type")]
+    reference_type: &'static str,
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct CycloneDxProperty {
+    name: &'static str,
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CycloneDxDependency {
+    #[serde(rename = "ref")]
+    dependency_ref: String,
+    depends_on: Vec<String>,
+}
+
+fn cyclonedx(inventory: &Inventory, version: &'static str, created: &str) -> CycloneDx {
+    let workspace_component = |workspace: &WorkspaceComponent, component_type| CycloneDxComponent {
+        component_type,
+        bom_ref: workspace_ref(workspace),
+        name: workspace.name.clone(),
+        version: workspace.version.clone(),
+        purl: None,
+        hashes: Vec::new(),
+        external_references: Vec::new(),
+        properties: Vec::new(),
+    };
+
+    let mut workspaces = inventory.workspaces.iter();
+    let target = workspaces
+        .next()
+        .expect("the selected workspace is always collected");
+    let mut components: Vec<_> = workspaces
+        .map(|workspace| workspace_component(workspace, "library"))
+        .collect();
+    components.extend(inventory.packages.iter().map(|(key, package)| {
+        CycloneDxComponent {
+            component_type: "library",
+            bom_ref: key.clone(),
+            name: package.name.clone(),
+            version: Some(package.version.clone()),
+            purl: Some(package.purl()),
+            hashes: package
+                .hashes()
+                .into_iter()
+                .map(|(algorithm, content)| CycloneDxHash {
+                    // CycloneDX spells algorithms with a dash, e.g. SHA-512
+                    alg: algorithm.replacen("SHA", "SHA-", 1),
+                    content,
+                })
+                .collect(),
+            external_references: package
+                .download_url()
+                .map(|url| CycloneDxReference {
+                    reference_type: "distribution",
+                    url: url.to_string(),
+                })
+                .into_iter()
+                .collect(),
+            properties: package
+                .checksum
+                .as_ref()
+                .map(|checksum| CycloneDxProperty {
+                    name: "yarn:checksum",
+                    value: checksum.clone(),
+                })
+                .into_iter()
+                .collect(),
+        }
+    }));
+
+    let workspace_refs: BTreeMap<&str, String> = inventory
+        .workspaces
+        .iter()
+        .map(|workspace| (workspace.name.as_str(), workspace_ref(workspace)))
+        .collect();
+    let mut dependencies: Vec<_> = inventory
+        .workspaces
+        .iter()
+        .map(|workspace| CycloneDxDependency {
+            dependency_ref: workspace_ref(workspace),
+            depends_on: workspace
+                .workspace_dependencies
+                .iter()
+                .map(|name| workspace_refs[name.as_str()].clone())
+                .chain(workspace.package_dependencies.iter().cloned())
+                .collect(),
+        })
+        .collect();
+    dependencies.extend(
+        inventory
+            .packages
+            .iter()
+            .map(|(key, package)| CycloneDxDependency {
+                dependency_ref: key.clone(),
+                depends_on: package.dependencies.iter().cloned().collect(),
+            }),
+    );
+
+    CycloneDx {
+        bom_format: "CycloneDX",
+        spec_version: "1.4",
+        version: 1,
+        metadata: CycloneDxMetadata {
+            timestamp: created.to_string(),
+            tools: vec![CycloneDxTool {
+                vendor: "Vercel",
+                name: "turbo",
+                version,
+            }],
+            component: workspace_component(target, "application"),
+        },
+        components,
+        dependencies,
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Spdx {
+    spdx_version: &'static str,
+    data_license: &'static str,
+    #[serde(rename = "SPDXID")]
+    spdx_id: &'static str,
+    name: String,
+    document_namespace: String,
+    creation_info: SpdxCreationInfo,
+    packages: Vec<SpdxPackage>,
+    relationships: Vec<SpdxRelationship>,
+}
+
+#[derive(Debug, Serialize)]
+struct SpdxCreationInfo {
+    created: String,
+    creators: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxPackage {
+    name: String,
+    #[serde(rename = "SPDXID")]
+    spdx_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version_info: Option<String>,
+    download_location: String,
+    files_analyzed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    checksums: Vec<SpdxChecksum>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_refs: Vec<SpdxExternalRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxChecksum {
+    algorithm: &'static str,
+    checksum_value: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxExternalRef {
+    reference_category: &'static str,
+    reference_type: &'static str,
+    reference_locator: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxRelationship {
+    spdx_element_id: String,
+    relationship_type: &'static str,
+    related_spdx_element: String,
+}
+
+const NOASSERTION: &str = "NOASSERTION";
+
+fn spdx(inventory: &Inventory, version: &'static str, created: &str) -> Spdx {
+    // SPDX identifiers only allow letters, numbers, `.` and `-`, so elements
+    // are numbered rather than named after their lockfile key
+    let workspace_ids: BTreeMap<&str, String> = inventory
+        .workspaces
+        .iter()
+        .enumerate()
+        .map(|(i, workspace)| (workspace.name.as_str(), format!("SPDXRef-Workspace-{i}")))
+        .collect();
+    let package_ids: BTreeMap<&str, String> = inventory
+        .packages
+        .keys()
+        .enumerate()
+        .map(|(i, key)| (key.as_str(), format!("SPDXRef-Package-{i}")))
+        .collect();
+
+    let mut packages: Vec<_> = inventory
+        .workspaces
+        .iter()
+        .map(|workspace| SpdxPackage {
+            name: workspace.name.clone(),
+            spdx_id: workspace_ids[workspace.name.as_str()].clone(),
+            version_info: workspace.version.clone(),
+            download_location: NOASSERTION.to_string(),
+            files_analyzed: false,
+            checksums: Vec::new(),
+            external_refs: Vec::new(),
+            comment: Some(format!("Workspace at {}", workspace_ref(workspace))),
+        })
+        .collect();
+    packages.extend(inventory.packages.iter().map(|(key, package)| {
+        SpdxPackage {
+            name: package.name.clone(),
+            spdx_id: package_ids[key.as_str()].clone(),
+            version_info: Some(package.version.clone()),
+            download_location: package.download_url().unwrap_or(NOASSERTION).to_string(),
+            files_analyzed: false,
+            checksums: package
+                .hashes()
+                .into_iter()
+                .map(|(algorithm, checksum_value)| SpdxChecksum {
+                    algorithm,
+                    checksum_value,
+                })
+                .collect(),
+            external_refs: vec![SpdxExternalRef {
+                reference_category: "PACKAGE-MANAGER",
+                reference_type: "purl",
+                reference_locator: package.purl(),
+            }],
+            comment: package
+                .checksum
+                .as_ref()
+                .map(|checksum| format!("yarn cache checksum {checksum}")),
+        }
+    }));
+
+    let target = &inventory.workspaces[0];
+    let mut relationships = vec![SpdxRelationship {
+        spdx_element_id: "SPDXRef-DOCUMENT".to_string(),
+        relationship_type: "DESCRIBES",
+        related_spdx_element: workspace_ids[target.name.as_str()].clone(),
+    }];
+    for workspace in &inventory.workspaces {
+        let id = &workspace_ids[workspace.name.as_str()];
+        let related = workspace
+            .workspace_dependencies
+            .iter()
+            .map(|name| &workspace_ids[name.as_str()])
+            .chain(
+                workspace
+                    .package_dependencies
+                    .iter()
+                    .map(|key| &package_ids[key.as_str()]),
+            );
+        relationships.extend(related.map(|related| SpdxRelationship {
+            spdx_element_id: id.clone(),
+            relationship_type: "DEPENDS_ON",
+            related_spdx_element: related.clone(),
+        }));
+    }
+    for (key, package) in &inventory.packages {
+        let id = &package_ids[key.as_str()];
+        relationships.extend(
+            package
+                .dependencies
+                .iter()
+                .map(|dependency| SpdxRelationship {
+                    spdx_element_id: id.clone(),
+                    relationship_type: "DEPENDS_ON",
+                    related_spdx_element: package_ids[dependency.as_str()].clone(),
+                }),
+        );
+    }
+
+    Spdx {
+        spdx_version: "SPDX-2.3",
+        data_license: "CC0-1.0",
+        spdx_id: "SPDXRef-DOCUMENT",
+        name: target.name.clone(),
+        document_namespace: document_namespace(inventory, created),
+        creation_info: SpdxCreationInfo {
+            created: created.to_string(),
+            creators: vec![format!("Tool: turbo-{version}")],
+        },
+        packages,
+        relationships,
+    }
+}
+
+/// A URI that is unique to this document's contents and creation time
+fn document_namespace(inventory: &Inventory, created: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(created);
+    for workspace in &inventory.workspaces {
+        hasher.update(&workspace.name);
+    }
+    for (key, package) in &inventory.packages {
+        hasher.update(key);
+        hasher.update(&package.version);
+    }
+    let target = &inventory.workspaces[0];
+    format!(
+        "https://spdx.org/spdxdocs/turbo-{}-{}",
+        target.name.trim_start_matches('@').replace('/', "-"),
+        hex::encode(hasher.finalize())
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use serde_json::json;
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::{get_version, ui::UI, Args};
+
+    const LOCKFILE: &str = r#"{
+  "name": "monorepo",
+  "lockfileVersion": 3,
+  "requires": true,
+  "packages": {
+    "": { "name": "monorepo", "workspaces": ["apps/*", "packages/*"] },
+    "apps/web": {
+      "name": "web",
+      "version": "1.0.0",
+      "dependencies": { "lodash": "^4.17.21", "ui": "*" }
+    },
+    "packages/ui": {
+      "name": "ui",
+      "version": "0.1.0",
+      "dependencies": { "@scope/tokens": "^1.0.0" }
+    },
+    "node_modules/web": { "resolved": "apps/web", "link": true },
+    "node_modules/ui": { "resolved": "packages/ui", "link": true },
+    "node_modules/lodash": {
+      "version": "4.17.21",
+      "resolved": "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz",
+      "integrity": "sha512-v2kDEe57lecTulaDIuNTPy3Ry4gLGJ6Z1O3vE1krgXZNrsQ+LFTGHVxVjcXPs17LhbZVGedAJv8XZ1tvj5FvSg=="
+    },
+    "node_modules/@scope/tokens": {
+      "version": "1.0.0",
+      "resolved": "https://registry.npmjs.org/@scope/tokens/-/tokens-1.0.0.tgz",
+      "dependencies": { "js-tokens": "^4.0.0" }
+    },
+    "node_modules/@scope/tokens/node_modules/js-tokens": {
+      "version": "4.0.0",
+      "resolved": "https://registry.npmjs.org/js-tokens/-/js-tokens-4.0.0.tgz"
+    }
+  }
+}"#;
+
+    fn write(dir: &TempDir, path: &str, contents: &str) {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn collect_inventory(filter: &str) -> Result<Inventory> {
+        let dir = TempDir::new()?;
+        write(
+            &dir,
+            "package.json",
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["apps/*", "packages/*"] }"#,
+        );
+        write(
+            &dir,
+            "apps/web/package.json",
+            r#"{ "name": "web", "version": "1.0.0", "dependencies": { "lodash": "^4.17.21", "ui": "*" } }"#,
+        );
+        write(
+            &dir,
+            "packages/ui/package.json",
+            r#"{ "name": "ui", "version": "0.1.0", "dependencies": { "@scope/tokens": "^1.0.0" } }"#,
+        );
+        write(&dir, "package-lock.json", LOCKFILE);
+
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        let root_package_json =
+            PackageJson::load(repo_root.join_component("package.json").as_absolute_path())?;
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+        let package_manager = PackageManager::get_package_manager(&base, Some(&root_package_json))?;
+        let package_graph = PackageGraph::build_multi_package_graph(&base, &root_package_json)?;
+        let target = find_workspace(&package_graph, filter)?;
+        package_manager.with_lockfile(base.repo_root.as_absolute_path(), |lockfile| {
+            Inventory::collect(&package_graph, lockfile, &package_manager, target)
+        })
+    }
+
+    #[test]
+    fn test_inventory_follows_workspace_dependencies() -> Result<()> {
+        let inventory = collect_inventory("./apps/web")?;
+        assert_eq!(
+            inventory
+                .workspaces
+                .iter()
+                .map(|workspace| workspace.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["web", "ui"]
+        );
+        assert_eq!(
+            inventory.packages.keys().collect::<Vec<_>>(),
+            vec![
+                "node_modules/@scope/tokens",
+                "node_modules/@scope/tokens/node_modules/js-tokens",
+                "node_modules/lodash",
+            ]
+        );
+
+        // Workspaces that web doesn't depend on are left out
+        let inventory = collect_inventory("ui")?;
+        assert_eq!(inventory.workspaces.len(), 1);
+        assert!(!inventory.packages.contains_key("node_modules/lodash"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cyclonedx() -> Result<()> {
+        let document = serde_json::to_value(cyclonedx(
+            &collect_inventory("web")?,
+            "1.9.0",
+            "2023-04-01T00:00:00Z",
+        ))?;
+
+        assert_eq!(document["bomFormat"], "CycloneDX");
+        assert_eq!(document["specVersion"], "1.4");
+        assert_eq!(
+            document["metadata"]["component"],
+            json!({
+                "type": "application",
+                "bom-ref": "workspace:apps/web",
+                "name": "web",
+                "version": "1.0.0",
+            })
+        );
+        let lodash = document["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|component| component["name"] == "lodash")
+            .unwrap();
+        assert_eq!(lodash["purl"], "pkg:npm/lodash@4.17.21");
+        assert_eq!(lodash["hashes"][0]["alg"], "SHA-512");
+        assert!(lodash["hashes"][0]["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("bf690311"));
+        assert_eq!(
+            lodash["externalReferences"][0]["url"],
+            "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz"
+        );
+        let tokens = document["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|component| component["bom-ref"] == "node_modules/@scope/tokens")
+            .unwrap();
+        assert_eq!(tokens["purl"], "pkg:npm/%40scope/tokens@1.0.0");
+        assert_eq!(
+            document["dependencies"][0],
+            json!({
+                "ref": "workspace:apps/web",
+                "dependsOn": ["workspace:packages/ui", "node_modules/lodash"],
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spdx() -> Result<()> {
+        let document = serde_json::to_value(spdx(
+            &collect_inventory("web")?,
+            "1.9.0",
+            "2023-04-01T00:00:00Z",
+        ))?;
+
+        assert_eq!(document["spdxVersion"], "SPDX-2.3");
+        assert_eq!(
+            document["creationInfo"]["creators"],
+            json!(["Tool: turbo-1.9.0"])
+        );
+        assert!(document["documentNamespace"]
+            .as_str()
+            .unwrap()
+            .starts_with("https://spdx.org/spdxdocs/turbo-web-"));
+        let packages = document["packages"].as_array().unwrap();
+        assert_eq!(packages.len(), 5);
+        assert_eq!(packages[0]["SPDXID"], "SPDXRef-Workspace-0");
+        assert_eq!(packages[0]["downloadLocation"], "NOASSERTION");
+        let lodash = packages
+            .iter()
+            .find(|package| package["name"] == "lodash")
+            .unwrap();
+        assert_eq!(lodash["checksums"][0]["algorithm"], "SHA512");
+        assert_eq!(
+            lodash["externalRefs"][0]["referenceLocator"],
+            "pkg:npm/lodash@4.17.21"
+        );
+
+        let relationships = document["relationships"].as_array().unwrap();
+        assert_eq!(
+            relationships[0],
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": "SPDXRef-Workspace-0",
+            })
+        );
+        // web -> ui, web -> lodash, ui -> tokens, tokens -> js-tokens
+        assert_eq!(relationships.len(), 5);
+
+        Ok(())
+    }
+}
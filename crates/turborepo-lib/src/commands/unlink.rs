@@ -1,8 +1,6 @@
-use std::fs::File;
-
 use anyhow::{Context, Result};
 
-use crate::{cli::LinkTarget, commands::CommandBase, config::TurboJson, ui::GREY};
+use crate::{cli::LinkTarget, commands::CommandBase, config::RawTurboJson, ui::GREY};
 
 enum UnlinkSpacesResult {
     Unlinked,
@@ -55,8 +53,8 @@ pub fn unlink(base: &mut CommandBase, target: LinkTarget) -> Result<()> {
 fn remove_spaces_from_turbo_json(base: &CommandBase) -> Result<UnlinkSpacesResult> {
     let turbo_json_path = base.repo_root.join_component("turbo.json");
 
-    let turbo_json_file = File::open(&turbo_json_path).context("unable to open turbo.json file")?;
-    let mut turbo_json: TurboJson = serde_json::from_reader(turbo_json_file)?;
+    let turbo_json = RawTurboJson::load(turbo_json_path.as_absolute_path())
+        .context("unable to read turbo.json file")?;
     let has_spaces_id = turbo_json
         .experimental_spaces
         .unwrap_or_default()
@@ -64,11 +62,7 @@ fn remove_spaces_from_turbo_json(base: &CommandBase) -> Result<UnlinkSpacesResul
         .is_some();
     // remove the spaces config
     // TODO: in the future unlink should possible just remove the spaces id
-    turbo_json.experimental_spaces = None;
-
-    // write turbo_json back to file
-    let config_file = File::create(&turbo_json_path)?;
-    serde_json::to_writer_pretty(&config_file, &turbo_json)?;
+    RawTurboJson::remove_spaces(turbo_json_path.as_absolute_path())?;
 
     match has_spaces_id {
         true => Ok(UnlinkSpacesResult::Unlinked),
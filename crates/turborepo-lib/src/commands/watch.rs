@@ -0,0 +1,625 @@
+//! `turbo watch`: re-run tasks as files in the repository change.
+//!
+//! Watch doesn't schedule tasks itself, it drives `turbo run`. Changed files
+//! are mapped to the workspaces that contain them, and each batch of changes
+//! runs the requested tasks with `--filter=...<workspace>`, which selects
+//! those workspaces along with everything that depends on them. Tasks whose
+//! inputs didn't change within that scope are cache hits, so only the
+//! affected tasks actually execute.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    env,
+    fmt::Debug,
+    fs, io, mem,
+    path::{Component, Path, PathBuf},
+    process::ExitStatus,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use command_group::{AsyncCommandGroup, AsyncGroupChild};
+use futures::{Stream, StreamExt};
+use globwatch::{GlobWatcher, StopSource, WatchConfig};
+use itertools::Itertools;
+use notify::{event::CreateKind, Event, EventKind, RecommendedWatcher};
+use tokio::{
+    process::Command,
+    select,
+    signal::ctrl_c,
+    time::{sleep_until, timeout, Instant},
+};
+use tracing::{debug, warn};
+use turbopath::AbsoluteSystemPath;
+
+use crate::{
+    commands::CommandBase,
+    config::RawTurboJson,
+    daemon::DaemonConnector,
+    package_json::PackageJson,
+    run::package_graph::{PackageGraph, IGNORED_DIRECTORIES},
+    ui::{BOLD, GREY},
+};
+
+/// How long to wait for the filesystem to go quiet before starting a run, so
+/// that bursts of changes (saving many files, switching branches) coalesce
+/// into a single run.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+/// How long a cancelled run gets to shut down its tasks before it's killed.
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+pub async fn run(base: &CommandBase, tasks: &[String]) -> Result<()> {
+    let repo_root = base.repo_root.as_path().to_path_buf();
+    let turbo_json_path = base.repo_root.join_component("turbo.json");
+    let pipeline = Pipeline::load(turbo_json_path.as_absolute_path());
+    let workspaces = Workspaces::new(base)?;
+
+    // Persistent tasks (dev servers, watchers) are started once and left
+    // running, the rest are re-run as files change.
+    let (persistent_tasks, tasks): (Vec<_>, Vec<_>) = tasks
+        .iter()
+        .cloned()
+        .partition(|task| pipeline.is_persistent(task));
+
+    // Each run talks to the daemon to skip restoring outputs that are already
+    // on disk. Start it up front rather than paying for it on the first run.
+    let connector = DaemonConnector {
+        can_start_server: true,
+        can_kill_server: true,
+        pid_file: base.daemon_file_root().join_component("turbod.pid"),
+        sock_file: base.daemon_file_root().join_component("turbod.sock"),
+    };
+    if let Err(err) = connector.connect().await {
+        warn!("unable to start the daemon, runs will be slower: {}", err);
+    }
+
+    let (watcher, config) =
+        GlobWatcher::new(base.daemon_file_root().join_component("watch-flush").into())?;
+    let stop_source = StopSource::new();
+    let events = watcher.into_stream(stop_source.token());
+
+    // The root is watched on its own so new top level directories are
+    // noticed, and everything below it except the ignored directories is
+    // watched recursively.
+    config
+        .include_path(&repo_root)
+        .await
+        .map_err(|err| anyhow!("unable to watch {}: {:?}", repo_root.display(), err))?;
+    for entry in fs::read_dir(&repo_root)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            watch_directory(&config, &repo_root, &entry.path()).await;
+        }
+    }
+
+    let mut session = Session {
+        base,
+        repo_root,
+        pipeline,
+        workspaces,
+        tasks,
+        persistent_tasks,
+        current: None,
+        persistent: None,
+    };
+    let result = session.watch(events, &config).await;
+    // Whatever ended the session, the runs it started mustn't outlive it
+    let stopped = session.stop().await;
+
+    result.and(stopped)
+}
+
+/// A watch session along with the runs it has in flight
+struct Session<'a> {
+    base: &'a CommandBase,
+    repo_root: PathBuf,
+    pipeline: Pipeline,
+    workspaces: Workspaces,
+    tasks: Vec<String>,
+    persistent_tasks: Vec<String>,
+    current: Option<InFlightRun>,
+    persistent: Option<AsyncGroupChild>,
+}
+
+impl Session<'_> {
+    /// Runs the tasks as files change, until the watcher stops or the user
+    /// interrupts it. Runs that are still going when this returns are left to
+    /// [Session::stop].
+    async fn watch<E: Debug, T: Debug>(
+        &mut self,
+        mut events: impl Stream<Item = Result<Result<Event, E>, T>> + Unpin,
+        config: &WatchConfig<RecommendedWatcher>,
+    ) -> Result<()> {
+        let base = self.base;
+        if !self.tasks.is_empty() {
+            self.current = Some(start_run(base, &self.tasks, ChangeSet::everything())?);
+        } else if !self.persistent_tasks.is_empty() {
+            self.persistent = Some(start_persistent(base, &self.persistent_tasks)?);
+        }
+
+        let mut pending = ChangeSet::default();
+        let mut deadline = None;
+        loop {
+            select! {
+                event = events.next() => {
+                    let event = match event {
+                        Some(Ok(event)) => event
+                            .map_err(|err| anyhow!("file watcher failed: {:?}", err))?,
+                        Some(Err(err)) => {
+                            warn!("file watcher stopped: {:?}", err);
+                            break;
+                        }
+                        None => {
+                            warn!("file watcher stopped");
+                            break;
+                        }
+                    };
+                    if let EventKind::Create(CreateKind::Folder) = event.kind {
+                        for path in event
+                            .paths
+                            .iter()
+                            .filter(|p| p.parent() == Some(&self.repo_root))
+                        {
+                            watch_directory(config, &self.repo_root, path).await;
+                        }
+                    }
+
+                    if self.workspaces.affected_by(&event.paths) {
+                        match Workspaces::new(base) {
+                            Ok(updated) => self.workspaces = updated,
+                            Err(err) => warn!("unable to update the workspaces: {}", err),
+                        }
+                    }
+                    let changes = self.workspaces.changes(&event.paths, &self.pipeline);
+                    if !changes.is_empty() && !self.tasks.is_empty() {
+                        debug!("changes detected: {:?}", changes);
+                        pending.merge(changes);
+                        deadline = Some(Instant::now() + DEBOUNCE);
+                    }
+                }
+                _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    deadline = None;
+                    // The in-flight run is superseded. It may have been
+                    // cancelled before getting to everything it was started
+                    // for, so fold its changes into the new run.
+                    if let Some(mut run) = self.current.take() {
+                        stop(&mut run.child).await?;
+                        pending.merge(run.changes);
+                    }
+                    self.current = Some(start_run(base, &self.tasks, mem::take(&mut pending))?);
+                }
+                status = wait(self.current.as_mut().map(|run| &mut run.child)) => {
+                    self.current = None;
+                    report(base, "Run", status?);
+                    if self.persistent.is_none() && !self.persistent_tasks.is_empty() {
+                        self.persistent = Some(start_persistent(base, &self.persistent_tasks)?);
+                    }
+                }
+                status = wait(self.persistent.as_mut()) => {
+                    self.persistent = None;
+                    report(base, "Persistent tasks", status?);
+                    // Persistent tasks are never restarted, so don't start
+                    // them again after the next run.
+                    self.persistent_tasks.clear();
+                }
+                _ = ctrl_c() => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops the in-flight run and the persistent tasks, if any
+    async fn stop(&mut self) -> Result<()> {
+        let current = match self.current.take() {
+            Some(mut run) => stop(&mut run.child).await,
+            None => Ok(()),
+        };
+        let persistent = match self.persistent.take() {
+            Some(mut child) => stop(&mut child).await,
+            None => Ok(()),
+        };
+
+        current.and(persistent)
+    }
+}
+
+async fn watch_directory(config: &WatchConfig<RecommendedWatcher>, repo_root: &Path, path: &Path) {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return;
+    };
+    if IGNORED_DIRECTORIES.contains(&name) {
+        return;
+    }
+    if let Err(err) = config.include(repo_root, name).await {
+        warn!("unable to watch {}: {:?}", path.display(), err);
+    }
+}
+
+/// An in-flight `turbo run` along with the changes it was started for
+struct InFlightRun {
+    child: AsyncGroupChild,
+    changes: ChangeSet,
+}
+
+fn start_run(base: &CommandBase, tasks: &[String], changes: ChangeSet) -> Result<InFlightRun> {
+    let scope = if changes.global {
+        "all workspaces".to_string()
+    } else {
+        format!("{} and dependents", changes.workspaces.iter().join(", "))
+    };
+    println!(
+        "{}",
+        base.ui
+            .apply(GREY.apply_to(format!("• Running {} in {}", tasks.join(", "), scope)))
+    );
+
+    let child = turbo_run(base, tasks, &changes.filters())?;
+    Ok(InFlightRun { child, changes })
+}
+
+fn start_persistent(base: &CommandBase, tasks: &[String]) -> Result<AsyncGroupChild> {
+    println!(
+        "{}",
+        base.ui.apply(GREY.apply_to(format!(
+            "• Starting persistent tasks {}, these won't be restarted on changes",
+            tasks.join(", ")
+        )))
+    );
+    turbo_run(base, tasks, &[])
+}
+
+fn turbo_run(base: &CommandBase, tasks: &[String], filters: &[String]) -> Result<AsyncGroupChild> {
+    // Each run gets its own process group so that cancelling it takes any
+    // stragglers along with it.
+    Ok(Command::new(env::current_exe()?)
+        .arg("run")
+        .args(tasks)
+        .args(filters)
+        .current_dir(base.repo_root.as_path())
+        .group_spawn()?)
+}
+
+/// Waits for `child` to exit, or forever if there is no child.
+async fn wait(child: Option<&mut AsyncGroupChild>) -> io::Result<ExitStatus> {
+    match child {
+        Some(child) => child.wait().await,
+        None => futures::future::pending().await,
+    }
+}
+
+/// Interrupts a run, giving it a chance to shut down its own tasks before
+/// killing its process group.
+async fn stop(child: &mut AsyncGroupChild) -> Result<()> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // The run leads its own process group, so a negative pid interrupts
+        // the tasks it started as well.
+        // SAFETY: libc::kill only sends a signal, same as in `spawn_child`
+        unsafe {
+            libc::kill(-(pid as i32), libc::SIGINT);
+        }
+    }
+    #[cfg(unix)]
+    let stopped = timeout(STOP_GRACE_PERIOD, child.wait()).await.is_ok();
+    #[cfg(not(unix))]
+    let stopped = false;
+
+    if !stopped {
+        child.kill().ok();
+        child.wait().await?;
+    }
+    Ok(())
+}
+
+fn report(base: &CommandBase, what: &str, status: ExitStatus) {
+    let message = if status.success() {
+        format!("• {} finished, waiting for changes", what)
+    } else {
+        format!("• {} failed ({}), waiting for changes", what, status)
+    };
+    println!("{}", base.ui.apply(BOLD.apply_to(message)));
+}
+
+/// Workspaces affected by a batch of file changes
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct ChangeSet {
+    /// A file outside of any workspace changed, which may affect every
+    /// workspace
+    global: bool,
+    workspaces: BTreeSet<String>,
+}
+
+impl ChangeSet {
+    fn everything() -> Self {
+        Self {
+            global: true,
+            ..Default::default()
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        !self.global && self.workspaces.is_empty()
+    }
+
+    fn merge(&mut self, other: ChangeSet) {
+        self.global |= other.global;
+        self.workspaces.extend(other.workspaces);
+    }
+
+    /// `--filter` arguments selecting the changed workspaces and their
+    /// dependents
+    fn filters(&self) -> Vec<String> {
+        if self.global {
+            return Vec::new();
+        }
+        self.workspaces
+            .iter()
+            .map(|workspace| format!("--filter=...{}", workspace))
+            .collect()
+    }
+}
+
+/// The parts of the root `turbo.json` pipeline that watch cares about
+#[derive(Debug, Default)]
+struct Pipeline {
+    pipeline: HashMap<String, PipelineTask>,
+}
+
+#[derive(Debug, Default)]
+struct PipelineTask {
+    outputs: Option<Vec<String>>,
+    persistent: bool,
+}
+
+impl From<RawTurboJson> for Pipeline {
+    fn from(turbo_json: RawTurboJson) -> Self {
+        let pipeline = turbo_json
+            .pipeline
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, definition)| {
+                let task = PipelineTask {
+                    outputs: definition.outputs,
+                    persistent: definition.persistent.unwrap_or_default(),
+                };
+                (name, task)
+            })
+            .collect();
+        Self { pipeline }
+    }
+}
+
+impl Pipeline {
+    fn load(path: &AbsoluteSystemPath) -> Self {
+        match RawTurboJson::load(path) {
+            Ok(turbo_json) => turbo_json.into(),
+            Err(err) => {
+                warn!(
+                    "unable to read {}, all tasks will be re-run on change: {}",
+                    path, err
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Whether `task` is marked `persistent`, either for every workspace or
+    /// for a specific one.
+    fn is_persistent(&self, task: &str) -> bool {
+        self.pipeline.iter().any(|(name, definition)| {
+            definition.persistent
+                && (name == task
+                    || name
+                        .rsplit_once('#')
+                        .map_or(false, |(_, name)| name == task))
+        })
+    }
+
+    /// Whether `path`, relative to its workspace, is matched by any task's
+    /// `outputs`. Runs write their outputs (or restore them from the cache),
+    /// and those writes must not trigger another run.
+    fn is_output(&self, path: &Path) -> bool {
+        let path = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .join("/");
+        self.pipeline
+            .values()
+            .filter_map(|definition| definition.outputs.as_ref())
+            .flatten()
+            .filter(|glob| !glob.starts_with('!'))
+            .any(|glob| {
+                glob_match::glob_match(glob, &path)
+                    || glob
+                        .strip_suffix("/**")
+                        .map_or(false, |directory| directory == path)
+            })
+    }
+}
+
+struct Workspaces {
+    repo_root: PathBuf,
+    /// Workspace names by their directory, empty for single package repos
+    directories: HashMap<PathBuf, String>,
+}
+
+impl Workspaces {
+    fn new(base: &CommandBase) -> Result<Self> {
+        let package_json_path = base.repo_root.join_component("package.json");
+        let root_package_json = PackageJson::load(package_json_path.as_absolute_path())?;
+        let package_graph = PackageGraph::build_multi_package_graph(base, &root_package_json)?;
+
+        let repo_root = base.repo_root.as_path().to_path_buf();
+        let directories = package_graph
+            .workspaces()
+            .filter(|(_, info)| !info.path.is_empty())
+            .map(|(name, info)| (repo_root.join(&info.path), name.clone()))
+            .collect();
+        Ok(Self {
+            repo_root,
+            directories,
+        })
+    }
+
+    /// Whether `paths` include a workspace manifest, so workspaces may have
+    /// been added, removed or renamed.
+    fn affected_by(&self, paths: &[PathBuf]) -> bool {
+        paths.iter().any(|path| {
+            path.ends_with("package.json")
+                && path
+                    .strip_prefix(&self.repo_root)
+                    .map_or(false, |relative| !is_ignored(relative))
+        })
+    }
+
+    /// Maps changed files to the workspaces they affect, skipping files that
+    /// are task outputs or in ignored directories.
+    fn changes(&self, paths: &[PathBuf], pipeline: &Pipeline) -> ChangeSet {
+        let mut changes = ChangeSet::default();
+        for path in paths {
+            let Ok(relative) = path.strip_prefix(&self.repo_root) else {
+                continue;
+            };
+            if is_ignored(relative) {
+                continue;
+            }
+
+            match self.workspace_for(path) {
+                Some((directory, name)) => {
+                    let within = path
+                        .strip_prefix(directory)
+                        .expect("directory is an ancestor");
+                    if !pipeline.is_output(within) {
+                        changes.workspaces.insert(name.to_string());
+                    }
+                }
+                None => {
+                    if !pipeline.is_output(relative) {
+                        changes.global = true;
+                    }
+                }
+            }
+        }
+        changes
+    }
+
+    /// Finds the workspace containing `path`, returning its directory and
+    /// name.
+    fn workspace_for<'a>(&self, path: &'a Path) -> Option<(&'a Path, &str)> {
+        path.ancestors()
+            .take_while(|directory| *directory != self.repo_root)
+            .find_map(|directory| Some((directory, self.directories.get(directory)?.as_str())))
+    }
+}
+
+/// Whether `path`, relative to the repository root, is inside an ignored
+/// directory
+fn is_ignored(path: &Path) -> bool {
+    path.components().any(|component| {
+        matches!(component, Component::Normal(name)
+            if IGNORED_DIRECTORIES.iter().any(|ignored| name == *ignored))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use anyhow::Result;
+    use tempfile::tempdir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{ChangeSet, Pipeline, Workspaces};
+
+    fn pipeline() -> Pipeline {
+        let dir = tempdir().unwrap();
+        let path = AbsoluteSystemPathBuf::new(dir.path().join("turbo.json")).unwrap();
+        fs::write(
+            path.as_path(),
+            r#"{
+                // Comments and trailing commas are allowed
+                "pipeline": {
+                    "build": { "outputs": ["dist/**", "!dist/cache/**"] },
+                    "test": { "dependsOn": ["build"] },
+                    "dev": { "persistent": true, "cache": false },
+                    "web#start": { "persistent": true },
+                }
+            }"#,
+        )
+        .unwrap();
+        Pipeline::load(path.as_absolute_path())
+    }
+
+    #[test]
+    fn test_persistent_tasks() {
+        let pipeline = pipeline();
+        assert!(pipeline.is_persistent("dev"));
+        assert!(pipeline.is_persistent("start"));
+        assert!(pipeline.is_persistent("web#start"));
+        assert!(!pipeline.is_persistent("build"));
+        assert!(!pipeline.is_persistent("lint"));
+    }
+
+    #[test]
+    fn test_changes() -> Result<()> {
+        let dir = tempdir()?;
+        let repo_root = dir.path().canonicalize()?;
+        let workspaces = Workspaces {
+            repo_root: repo_root.clone(),
+            directories: [("packages/ui", "ui"), ("apps/web", "web")]
+                .into_iter()
+                .map(|(directory, name)| (repo_root.join(directory), name.to_string()))
+                .collect(),
+        };
+        let pipeline = pipeline();
+        let changes = |paths: &[&str]| {
+            let paths: Vec<PathBuf> = paths.iter().map(|path| repo_root.join(path)).collect();
+            workspaces.changes(&paths, &pipeline)
+        };
+
+        assert_eq!(
+            changes(&["packages/ui/src/button.tsx", "apps/web/package.json"]),
+            ChangeSet {
+                global: false,
+                workspaces: ["ui".to_string(), "web".to_string()].into(),
+            }
+        );
+        assert_eq!(changes(&["scripts/tool/index.js"]), ChangeSet::everything());
+        assert!(changes(&[
+            "packages/ui/dist",
+            "packages/ui/dist/index.js",
+            "packages/ui/node_modules/react/index.js",
+            ".git/index",
+            "/outside/the/repo",
+        ])
+        .is_empty());
+        // Excluded outputs are still outputs of some other build
+        assert!(changes(&["packages/ui/dist/cache/entry"]).is_empty());
+
+        assert!(workspaces.affected_by(&[repo_root.join("apps/docs/package.json")]));
+        assert!(!workspaces.affected_by(&[repo_root.join("apps/web/src/package.ts")]));
+        assert!(
+            !workspaces.affected_by(&[repo_root.join("apps/web/node_modules/react/package.json")])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut changes = ChangeSet::default();
+        changes.workspaces.insert("web".to_string());
+        changes.merge(ChangeSet {
+            global: false,
+            workspaces: ["@acme/ui".to_string()].into(),
+        });
+        assert_eq!(
+            changes.filters(),
+            vec!["--filter=...@acme/ui", "--filter=...web"]
+        );
+
+        changes.merge(ChangeSet::everything());
+        assert!(changes.filters().is_empty());
+    }
+}
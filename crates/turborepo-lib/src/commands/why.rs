@@ -0,0 +1,571 @@
+//! Explains why an external package is installed by listing the chains of
+//! dependencies that lead to it from each workspace, as resolved by the
+//! lockfile. The number of chains grows exponentially with the depth of the
+//! graph, so only the shortest chain through each direct dependency is listed.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use anyhow::Result;
+use serde::Serialize;
+use turborepo_lockfiles::{Lockfile, Package};
+
+use crate::{
+    commands::CommandBase,
+    package_json::PackageJson,
+    package_manager::PackageManager,
+    run::package_graph::PackageGraph,
+    ui::{BOLD, GREY, YELLOW},
+};
+
+/// A resolved package along a dependency chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Hop {
+    name: String,
+    version: String,
+    key: String,
+}
+
+#[derive(Debug, Serialize)]
+struct WorkspaceChains {
+    workspace: String,
+    /// The shortest chain through each dependency of the workspace that leads
+    /// to the package being explained
+    chains: Vec<Vec<Hop>>,
+    /// How many chains lead to the package in total
+    total_chains: u64,
+    versions: BTreeSet<String>,
+}
+
+pub fn run(base: &CommandBase, dependency: &str, json: bool) -> Result<()> {
+    let package_json_path = base.repo_root.join_component("package.json");
+    let root_package_json = PackageJson::load(package_json_path.as_absolute_path())?;
+    let package_manager = PackageManager::get_package_manager(base, Some(&root_package_json))?;
+    let package_graph = PackageGraph::build_multi_package_graph(base, &root_package_json)?;
+
+    let workspaces = package_manager
+        .with_lockfile(base.repo_root.as_absolute_path(), |lockfile| {
+            explain(&package_graph, lockfile, &package_manager, dependency)
+        })?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&workspaces)?);
+        return Ok(());
+    }
+
+    if workspaces.is_empty() {
+        println!("No workspace depends on {dependency}");
+        return Ok(());
+    }
+
+    for workspace in &workspaces {
+        println!("{}", base.ui.apply(BOLD.apply_to(&workspace.workspace)));
+        for chain in &workspace.chains {
+            let hops: Vec<_> = chain
+                .iter()
+                .map(|hop| {
+                    format!(
+                        "{}@{} {}",
+                        hop.name,
+                        hop.version,
+                        base.ui.apply(GREY.apply_to(format!("({})", hop.key)))
+                    )
+                })
+                .collect();
+            println!("  {}", hops.join(" -> "));
+        }
+        let more = workspace
+            .total_chains
+            .saturating_sub(workspace.chains.len() as u64);
+        if more > 0 {
+            println!(
+                "  {}",
+                base.ui
+                    .apply(GREY.apply_to(format!("... and {more} more chains")))
+            );
+        }
+    }
+
+    let conflicts = version_conflicts(&workspaces);
+    if !conflicts.is_empty() {
+        println!();
+        println!(
+            "{}",
+            base.ui.apply(YELLOW.apply_to(format!(
+                "{dependency} resolves to different versions across workspaces:"
+            )))
+        );
+        for (version, workspaces) in conflicts {
+            println!("  {version}: {}", workspaces.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the chains leading to `dependency` for every workspace that
+/// depends on it
+fn explain(
+    package_graph: &PackageGraph,
+    lockfile: &dyn Lockfile,
+    package_manager: &PackageManager,
+    dependency: &str,
+) -> Result<Vec<WorkspaceChains>> {
+    let mut workspaces = Vec::new();
+    for (name, info) in package_graph.workspaces() {
+        let workspace_path = package_manager.lockfile_workspace_path(&info.path);
+        let mut graph = DependencyGraph::new(lockfile, workspace_path);
+
+        let mut roots = Vec::new();
+        for (dependency_name, specifier) in package_graph.external_dependencies(name) {
+            if let Some(package) =
+                lockfile.resolve_package(workspace_path, dependency_name, specifier)?
+            {
+                roots.push(package);
+            }
+        }
+        graph.explore(&roots)?;
+
+        if let Some(chains) = graph.chains_to(name, &roots, dependency)? {
+            workspaces.push(chains);
+        }
+    }
+
+    Ok(workspaces)
+}
+
+/// Maps each resolved version to the workspaces that resolve it, if not every
+/// workspace agrees on a single version
+fn version_conflicts(workspaces: &[WorkspaceChains]) -> BTreeMap<&str, Vec<&str>> {
+    let mut by_version: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for workspace in workspaces {
+        for version in &workspace.versions {
+            by_version
+                .entry(version.as_str())
+                .or_default()
+                .push(workspace.workspace.as_str());
+        }
+    }
+    if by_version.len() < 2 {
+        by_version.clear();
+    }
+    by_version
+}
+
+/// The packages reachable from a workspace, keyed by lockfile key
+struct DependencyGraph<'a> {
+    lockfile: &'a dyn Lockfile,
+    workspace_path: &'a str,
+    versions: HashMap<String, String>,
+    dependencies: HashMap<String, Vec<String>>,
+}
+
+impl<'a> DependencyGraph<'a> {
+    fn new(lockfile: &'a dyn Lockfile, workspace_path: &'a str) -> Self {
+        Self {
+            lockfile,
+            workspace_path,
+            versions: HashMap::new(),
+            dependencies: HashMap::new(),
+        }
+    }
+
+    fn explore(&mut self, roots: &[Package]) -> Result<()> {
+        let mut stack = roots.to_vec();
+        while let Some(package) = stack.pop() {
+            if self.dependencies.contains_key(&package.key) {
+                continue;
+            }
+            let mut dependencies = Vec::new();
+            let all_dependencies = self
+                .lockfile
+                .all_dependencies(&package.key)?
+                .unwrap_or_default();
+            for (name, specifier) in all_dependencies {
+                if let Some(dependency) =
+                    self.lockfile
+                        .resolve_package(self.workspace_path, &name, &specifier)?
+                {
+                    dependencies.push(dependency.key.clone());
+                    stack.push(dependency);
+                }
+            }
+            dependencies.sort();
+            self.versions.insert(package.key.clone(), package.version);
+            self.dependencies.insert(package.key, dependencies);
+        }
+        Ok(())
+    }
+
+    /// The shortest chain from each of `roots` of `workspace` to a package
+    /// named `name`, along with the number of chains and the versions they
+    /// lead to. Chains stop at the first package named `name`.
+    fn chains_to(
+        &self,
+        workspace: &str,
+        roots: &[Package],
+        name: &str,
+    ) -> Result<Option<WorkspaceChains>> {
+        let mut names = HashMap::new();
+        for key in self.dependencies.keys() {
+            let metadata = self.lockfile.package_metadata(key)?.unwrap_or_default();
+            names.insert(key.as_str(), metadata.name);
+        }
+        let targets: HashSet<&str> = names
+            .iter()
+            .filter(|(_, package_name)| package_name.as_str() == name)
+            .map(|(key, _)| *key)
+            .collect();
+
+        let mut roots: Vec<_> = roots.iter().map(|root| root.key.as_str()).collect();
+        roots.sort();
+        roots.dedup();
+        let mut chains = Vec::new();
+        let mut total_chains = 0u64;
+        let mut counts = HashMap::new();
+        let mut versions = BTreeSet::new();
+        for root in roots {
+            let Some(chain) = self.shortest_chain(root, &targets) else {
+                continue;
+            };
+            let count = self.count_chains(root, &targets, &mut counts, &mut HashSet::new());
+            total_chains = total_chains.saturating_add(count.max(1));
+            versions.extend(
+                self.reachable(root, &targets)
+                    .into_iter()
+                    .map(|target| self.versions[target].clone()),
+            );
+            chains.push(chain);
+        }
+        if chains.is_empty() {
+            return Ok(None);
+        }
+
+        let chains = chains
+            .into_iter()
+            .map(|chain| {
+                chain
+                    .into_iter()
+                    .map(|key| Hop {
+                        name: names[key].clone(),
+                        version: self.versions[key].clone(),
+                        key: key.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Some(WorkspaceChains {
+            workspace: workspace.to_string(),
+            chains,
+            total_chains,
+            versions,
+        }))
+    }
+
+    /// A breadth first search from `root` to the closest of `targets`
+    fn shortest_chain<'b>(
+        &'b self,
+        root: &'b str,
+        targets: &HashSet<&str>,
+    ) -> Option<Vec<&'b str>> {
+        let mut previous = HashMap::new();
+        let mut queue = VecDeque::from([root]);
+        while let Some(key) = queue.pop_front() {
+            if targets.contains(key) {
+                let mut chain = vec![key];
+                while let Some(&parent) = previous.get(chain[chain.len() - 1]) {
+                    chain.push(parent);
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+            for dependency in &self.dependencies[key] {
+                let dependency = dependency.as_str();
+                if dependency != root && !previous.contains_key(dependency) {
+                    previous.insert(dependency, key);
+                    queue.push_back(dependency);
+                }
+            }
+        }
+        None
+    }
+
+    /// The number of chains from `key` to any of `targets`, memoized in
+    /// `counts`. Cycles are only followed once, so cyclic graphs may be
+    /// undercounted.
+    fn count_chains<'b>(
+        &'b self,
+        key: &'b str,
+        targets: &HashSet<&str>,
+        counts: &mut HashMap<&'b str, u64>,
+        visiting: &mut HashSet<&'b str>,
+    ) -> u64 {
+        if targets.contains(key) {
+            return 1;
+        }
+        if let Some(&count) = counts.get(key) {
+            return count;
+        }
+        if !visiting.insert(key) {
+            return 0;
+        }
+        let count = self.dependencies[key]
+            .iter()
+            .fold(0u64, |count, dependency| {
+                count.saturating_add(self.count_chains(dependency, targets, counts, visiting))
+            });
+        visiting.remove(key);
+        counts.insert(key, count);
+        count
+    }
+
+    /// The `targets` reachable from `root` without passing through another
+    /// target
+    fn reachable<'b>(&'b self, root: &'b str, targets: &HashSet<&str>) -> Vec<&'b str> {
+        let mut seen = HashSet::from([root]);
+        let mut stack = vec![root];
+        let mut found = Vec::new();
+        while let Some(key) = stack.pop() {
+            if targets.contains(key) {
+                found.push(key);
+                continue;
+            }
+            for dependency in &self.dependencies[key] {
+                if seen.insert(dependency.as_str()) {
+                    stack.push(dependency.as_str());
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+    use turborepo_lockfiles::NpmLockfile;
+
+    use super::*;
+    use crate::{get_version, ui::UI, Args};
+
+    const NPM_LOCKFILE: &str = r#"{
+  "name": "npm-why",
+  "lockfileVersion": 3,
+  "requires": true,
+  "packages": {
+    "": { "name": "npm-why", "workspaces": ["apps/*", "packages/*"] },
+    "apps/web": {
+      "name": "web",
+      "dependencies": { "next": "^13.0.0", "react": "^18.2.0" }
+    },
+    "packages/ui": {
+      "name": "ui",
+      "dependencies": { "react": "^17.0.2" }
+    },
+    "node_modules/web": { "resolved": "apps/web", "link": true },
+    "node_modules/ui": { "resolved": "packages/ui", "link": true },
+    "node_modules/next": {
+      "version": "13.0.0",
+      "dependencies": { "react": "^18.2.0" }
+    },
+    "node_modules/react": { "version": "18.2.0" },
+    "packages/ui/node_modules/react": { "version": "17.0.2" }
+  }
+}"#;
+
+    const BERRY_LOCKFILE: &str = r#"__metadata:
+  version: 6
+  cacheKey: 8
+
+"berry-why@workspace:.":
+  version: 0.0.0-use.local
+  resolution: "berry-why@workspace:."
+  languageName: unknown
+  linkType: soft
+
+"next@npm:^13.0.0":
+  version: 13.0.0
+  resolution: "next@npm:13.0.0"
+  dependencies:
+    react: ^18.2.0
+  languageName: node
+  linkType: hard
+
+"react@npm:^17.0.2":
+  version: 17.0.2
+  resolution: "react@npm:17.0.2"
+  languageName: node
+  linkType: hard
+
+"react@npm:^18.2.0":
+  version: 18.2.0
+  resolution: "react@npm:18.2.0"
+  languageName: node
+  linkType: hard
+
+"ui@workspace:packages/ui":
+  version: 0.0.0-use.local
+  resolution: "ui@workspace:packages/ui"
+  dependencies:
+    react: ^17.0.2
+  languageName: unknown
+  linkType: soft
+
+"web@workspace:apps/web":
+  version: 0.0.0-use.local
+  resolution: "web@workspace:apps/web"
+  dependencies:
+    next: ^13.0.0
+    react: ^18.2.0
+  languageName: unknown
+  linkType: soft
+"#;
+
+    fn explain_fixture(
+        package_manager: &str,
+        lockfile_name: &str,
+        lockfile: &str,
+    ) -> Result<Vec<WorkspaceChains>> {
+        let dir = TempDir::new()?;
+        let write = |path: &str, contents: &str| {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write(
+            "package.json",
+            &format!(
+                r#"{{ "name": "{package_manager}-why", "packageManager": "{package_manager}", "workspaces": ["apps/*", "packages/*"] }}"#
+            ),
+        );
+        write(
+            "apps/web/package.json",
+            r#"{ "name": "web", "dependencies": { "next": "^13.0.0", "react": "^18.2.0" } }"#,
+        );
+        write(
+            "packages/ui/package.json",
+            r#"{ "name": "ui", "dependencies": { "react": "^17.0.2" } }"#,
+        );
+        write(lockfile_name, lockfile);
+
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        let root_package_json =
+            PackageJson::load(repo_root.join_component("package.json").as_absolute_path())?;
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+        let package_manager = PackageManager::get_package_manager(&base, Some(&root_package_json))?;
+        let package_graph = PackageGraph::build_multi_package_graph(&base, &root_package_json)?;
+        package_manager.with_lockfile(base.repo_root.as_absolute_path(), |lockfile| {
+            explain(&package_graph, lockfile, &package_manager, "react")
+        })
+    }
+
+    fn summarize(workspaces: &[WorkspaceChains]) -> Vec<(String, Vec<Vec<String>>)> {
+        workspaces
+            .iter()
+            .map(|workspace| {
+                (
+                    workspace.workspace.clone(),
+                    workspace
+                        .chains
+                        .iter()
+                        .map(|chain| {
+                            chain
+                                .iter()
+                                .map(|hop| format!("{}@{}", hop.name, hop.version))
+                                .collect()
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn expected() -> Vec<(String, Vec<Vec<String>>)> {
+        vec![
+            ("ui".to_string(), vec![vec!["react@17.0.2".to_string()]]),
+            (
+                "web".to_string(),
+                vec![
+                    vec!["next@13.0.0".to_string(), "react@18.2.0".to_string()],
+                    vec!["react@18.2.0".to_string()],
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_explain_npm() -> Result<()> {
+        let workspaces = explain_fixture("npm@8.19.4", "package-lock.json", NPM_LOCKFILE)?;
+        assert_eq!(summarize(&workspaces), expected());
+        assert_eq!(
+            workspaces[0].chains[0][0].key,
+            "packages/ui/node_modules/react"
+        );
+        assert_eq!(
+            version_conflicts(&workspaces),
+            BTreeMap::from([("17.0.2", vec!["ui"]), ("18.2.0", vec!["web"])])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_berry() -> Result<()> {
+        let workspaces = explain_fixture("yarn@3.5.0", "yarn.lock", BERRY_LOCKFILE)?;
+        assert_eq!(summarize(&workspaces), expected());
+        assert_eq!(workspaces[1].chains[1][0].key, "react@npm:18.2.0");
+        assert_eq!(version_conflicts(&workspaces).len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_counts_chains_instead_of_listing_them() -> Result<()> {
+        // Every level doubles the number of chains leading to react
+        const LEVELS: usize = 40;
+        let mut packages = vec![r#""": { "name": "deep" }"#.to_string()];
+        for level in 0..LEVELS {
+            let next = match level + 1 {
+                LEVELS => "react".to_string(),
+                next => format!("l{next}"),
+            };
+            packages.push(format!(
+                r#""node_modules/l{level}": {{ "version": "1.0.0", "dependencies": {{ "l{level}a": "1.0.0", "l{level}b": "1.0.0" }} }}"#
+            ));
+            for side in ["a", "b"] {
+                packages.push(format!(
+                    r#""node_modules/l{level}{side}": {{ "version": "1.0.0", "dependencies": {{ "{next}": "*" }} }}"#
+                ));
+            }
+        }
+        packages.push(r#""node_modules/react": { "version": "18.2.0" }"#.to_string());
+        let lockfile = NpmLockfile::load(
+            format!(
+                r#"{{ "lockfileVersion": 3, "packages": {{ {} }} }}"#,
+                packages.join(",")
+            )
+            .as_bytes(),
+        )?;
+
+        let roots = vec![lockfile.resolve_package("", "l0", "1.0.0")?.unwrap()];
+        let mut graph = DependencyGraph::new(&lockfile, "");
+        graph.explore(&roots)?;
+        let chains = graph.chains_to("deep", &roots, "react")?.unwrap();
+        assert_eq!(chains.chains.len(), 1);
+        assert_eq!(chains.chains[0].len(), 2 * LEVELS + 1);
+        assert_eq!(chains.total_chains, 1 << LEVELS);
+        assert_eq!(chains.versions, BTreeSet::from(["18.2.0".to_string()]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_conflict_for_single_version() {
+        let workspace = |name: &str| WorkspaceChains {
+            workspace: name.to_string(),
+            chains: Vec::new(),
+            total_chains: 0,
+            versions: BTreeSet::from(["18.2.0".to_string()]),
+        };
+        assert!(version_conflicts(&[workspace("web"), workspace("docs")]).is_empty());
+    }
+}
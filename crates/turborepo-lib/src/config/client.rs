@@ -4,11 +4,14 @@ use anyhow::Result;
 use config::{Config, ConfigError, Environment};
 use serde::{Deserialize, Serialize};
 
+use super::source::{find_env_var, ConfigSource};
+
 const DEFAULT_TIMEOUT: u64 = 20;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ClientConfig {
     config: ClientConfigValue,
+    remote_cache_timeout_source: ConfigSource,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -26,6 +29,11 @@ impl ClientConfig {
     pub fn remote_cache_timeout(&self) -> u64 {
         self.config.remote_cache_timeout
     }
+
+    /// Where the effective remote cache timeout came from
+    pub fn remote_cache_timeout_source(&self) -> &ConfigSource {
+        &self.remote_cache_timeout_source
+    }
 }
 
 impl ClientConfigLoader {
@@ -55,6 +63,16 @@ impl ClientConfigLoader {
             environment,
         } = self;
 
+        let remote_cache_timeout_source = if remote_cache_timeout.is_some() {
+            ConfigSource::flag("--remote-cache-timeout")
+        } else if let Some(name) =
+            find_env_var(environment.as_ref(), &["TURBO_REMOTE_CACHE_TIMEOUT"])
+        {
+            ConfigSource::env(name)
+        } else {
+            ConfigSource::Default
+        };
+
         let config_attempt: Result<ClientConfigValue, ConfigError> = Config::builder()
             .set_default("remote_cache_timeout", DEFAULT_TIMEOUT)?
             .add_source(Environment::with_prefix("turbo").source(environment))
@@ -68,8 +86,12 @@ impl ClientConfigLoader {
                 config: ClientConfigValue {
                     remote_cache_timeout: DEFAULT_TIMEOUT,
                 },
+                remote_cache_timeout_source: ConfigSource::Default,
+            }),
+            Ok(config) => Ok(ClientConfig {
+                config,
+                remote_cache_timeout_source,
             }),
-            Ok(config) => Ok(ClientConfig { config }),
         }
     }
 }
@@ -107,6 +129,10 @@ mod test {
             .load()?;
 
         assert_eq!(config.remote_cache_timeout(), arg_value);
+        assert_eq!(
+            config.remote_cache_timeout_source(),
+            &ConfigSource::flag("--remote-cache-timeout")
+        );
 
         Ok(())
     }
@@ -126,6 +152,10 @@ mod test {
             config.remote_cache_timeout(),
             env_value.parse::<u64>().unwrap()
         );
+        assert_eq!(
+            config.remote_cache_timeout_source(),
+            &ConfigSource::env("TURBO_REMOTE_CACHE_TIMEOUT")
+        );
 
         Ok(())
     }
@@ -1,6 +1,8 @@
 mod client;
 mod env;
 mod repo;
+pub(crate) mod schema;
+mod source;
 mod turbo;
 mod user;
 
@@ -19,7 +21,11 @@ use dirs_next::data_local_dir as config_dir;
 pub use env::MappedEnvironment;
 pub use repo::{get_repo_config_path, RepoConfig, RepoConfigLoader};
 use serde::Serialize;
-pub use turbo::{SpacesJson, TurboJson};
+pub use source::ConfigSource;
+pub use turbo::{
+    Boundaries, RawBoundaries, RawOutputMode, RawRemoteCache, RawTaskDefinition, RawTurboJson,
+    SpacesJson, TurboJson, TurboJsonError,
+};
 pub use user::{UserConfig, UserConfigLoader};
 
 pub fn default_user_config_path() -> Result<PathBuf> {
@@ -5,7 +5,10 @@ use config::Config;
 use serde::{Deserialize, Serialize};
 use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 
-use super::{write_to_disk, MappedEnvironment};
+use super::{
+    source::{find_env_var, ConfigSource},
+    write_to_disk, MappedEnvironment,
+};
 
 const DEFAULT_API_URL: &str = "https://vercel.com/api";
 const DEFAULT_LOGIN_URL: &str = "https://vercel.com";
@@ -14,9 +17,19 @@ const DEFAULT_LOGIN_URL: &str = "https://vercel.com";
 pub struct RepoConfig {
     disk_config: RepoConfigValue,
     config: RepoConfigValue,
+    sources: RepoConfigSources,
     path: AbsoluteSystemPathBuf,
 }
 
+/// Where each of the effective repo config values came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoConfigSources {
+    pub api_url: ConfigSource,
+    pub login_url: ConfigSource,
+    pub team_slug: ConfigSource,
+    pub team_id: ConfigSource,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
 struct RepoConfigValue {
     #[serde(rename = "apiurl")]
@@ -62,6 +75,10 @@ impl RepoConfig {
         self.config.team_id.as_deref()
     }
 
+    pub fn sources(&self) -> &RepoConfigSources {
+        &self.sources
+    }
+
     /// Sets the team id and clears the team slug, since it may have been from
     /// an old team
     #[allow(dead_code)]
@@ -70,6 +87,9 @@ impl RepoConfig {
         self.config.team_slug = None;
         self.disk_config.team_id = team_id.clone();
         self.config.team_id = team_id;
+        let file_source = ConfigSource::file(self.path.as_path());
+        self.sources.team_slug = file_source.clone();
+        self.sources.team_id = file_source;
         self.write_to_disk()
     }
 
@@ -136,9 +156,47 @@ impl RepoConfigLoader {
             .build()?;
 
         let has_team_slug_override = team_slug.is_some();
+        let disk_config: RepoConfigValue = raw_disk_config.clone().try_deserialize()?;
+
+        // Sources are resolved in the same order as the layers below: flags
+        // trump environment variables, which trump the config file.
+        let source_for = |flag: Option<&str>, env_vars: &[&str], on_disk: bool| {
+            if let Some(flag) = flag {
+                ConfigSource::flag(flag)
+            } else if let Some(name) = find_env_var(environment.as_ref(), env_vars) {
+                ConfigSource::env(name)
+            } else if on_disk {
+                ConfigSource::file(path.as_path())
+            } else {
+                ConfigSource::Default
+            }
+        };
+        let mut sources = RepoConfigSources {
+            api_url: source_for(
+                api.is_some().then_some("--api"),
+                &["TURBO_API", "TURBO_APIURL"],
+                disk_config.api_url.is_some(),
+            ),
+            login_url: source_for(
+                login.is_some().then_some("--login"),
+                &["TURBO_LOGIN", "TURBO_LOGINURL"],
+                disk_config.login_url.is_some(),
+            ),
+            team_slug: source_for(
+                has_team_slug_override.then_some("--team"),
+                &["TURBO_TEAM", "TURBO_TEAMSLUG"],
+                disk_config.team_slug.is_some(),
+            ),
+            // --team clears the team id below, leaving it unset
+            team_id: if has_team_slug_override {
+                ConfigSource::Default
+            } else {
+                source_for(None, &["TURBO_TEAMID"], disk_config.team_id.is_some())
+            },
+        };
 
         let mut config: RepoConfigValue = Config::builder()
-            .add_source(raw_disk_config.clone())
+            .add_source(raw_disk_config)
             .add_source(
                 MappedEnvironment::with_prefix("turbo")
                     .source(environment)
@@ -153,8 +211,6 @@ impl RepoConfigLoader {
             .build()?
             .try_deserialize()?;
 
-        let disk_config: RepoConfigValue = raw_disk_config.try_deserialize()?;
-
         // If teamid was passed via command line flag we ignore team slug as it
         // might not match.
         if has_team_slug_override {
@@ -164,11 +220,13 @@ impl RepoConfigLoader {
         // We don't set this above because it's specific to team_id
         if let Ok(vercel_artifacts_owner) = env::var("VERCEL_ARTIFACTS_OWNER") {
             config.team_id = Some(vercel_artifacts_owner);
+            sources.team_id = ConfigSource::env("VERCEL_ARTIFACTS_OWNER");
         }
 
         Ok(RepoConfig {
             disk_config,
             config,
+            sources,
             path,
         })
     }
@@ -290,4 +348,47 @@ mod test {
         assert_eq!(config.team_slug(), Some(team_slug));
         Ok(())
     }
+
+    #[test]
+    fn test_repo_config_sources() -> Result<()> {
+        let mut config_file = NamedTempFile::new()?;
+        let config_path = AbsoluteSystemPathBuf::new(config_file.path())?;
+        writeln!(
+            &mut config_file,
+            "{{\"teamslug\": \"other-team\", \"loginurl\": \"http://my-login\"}}"
+        )?;
+        let config = RepoConfigLoader::new(config_path)
+            .with_api(Some("http://my-api".into()))
+            .with_environment({
+                let mut env = HashMap::new();
+                env.insert("TURBO_TEAM".into(), "my-team".into());
+                Some(env)
+            })
+            .load()?;
+
+        let sources = config.sources();
+        assert_eq!(sources.api_url, ConfigSource::flag("--api"));
+        assert_eq!(sources.login_url, ConfigSource::file(config_file.path()));
+        assert_eq!(sources.team_slug, ConfigSource::env("TURBO_TEAM"));
+        assert_eq!(sources.team_id, ConfigSource::Default);
+
+        // --team clears the team id from every other source
+        let mut config_file = NamedTempFile::new()?;
+        let config_path = AbsoluteSystemPathBuf::new(config_file.path())?;
+        writeln!(&mut config_file, "{{\"teamid\": \"123\"}}")?;
+        let config = RepoConfigLoader::new(config_path)
+            .with_team_slug(Some("my-team".into()))
+            .with_environment({
+                let mut env = HashMap::new();
+                env.insert("TURBO_TEAMID".into(), "456".into());
+                Some(env)
+            })
+            .load()?;
+
+        let sources = config.sources();
+        assert_eq!(config.team_id(), None);
+        assert_eq!(sources.team_slug, ConfigSource::flag("--team"));
+        assert_eq!(sources.team_id, ConfigSource::Default);
+        Ok(())
+    }
 }
@@ -0,0 +1,440 @@
+//! A small JSON schema model for the configuration files that turbo reads.
+//!
+//! Config types describe themselves through [`JsonSchema`], usually by being
+//! declared with [`json_schema_struct!`]. The same description is used to
+//! validate a parsed file, producing diagnostics that point at the offending
+//! source, and to generate the `schema.json` that editors use, so the two
+//! can't drift apart.
+
+use std::collections::{BTreeMap, HashSet};
+
+use jsonc_parser::{
+    ast::Value,
+    common::{Range, Ranged},
+};
+use serde_json::json;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schema {
+    Boolean,
+    String,
+    Integer { minimum: Option<i64> },
+    Enum(&'static [&'static str]),
+    Array(Box<Schema>),
+    // An object with a fixed set of keys
+    Object(Vec<Property>),
+    // An object with arbitrary keys that all share a schema
+    Map(Box<Schema>),
+    OneOf(Vec<Schema>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: &'static str,
+    pub description: Option<String>,
+    pub schema: Schema,
+}
+
+/// A problem found in a config file, along with the byte range of the source
+/// text it refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub range: Range,
+}
+
+pub trait JsonSchema {
+    fn schema() -> Schema;
+}
+
+/// Declares a struct whose fields map onto the keys of a JSON object, and
+/// derives its [`JsonSchema`] from the declaration. Each field must be an
+/// `Option` and carry a `#[serde(rename = "...")]` with its key; its doc
+/// comment becomes the description in the generated schema.
+macro_rules! json_schema_struct {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[doc = $doc:literal])*
+                #[serde(rename = $key:literal)]
+                $field_vis:vis $field:ident: $ty:ty,
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        $vis struct $name {
+            $(
+                $(#[doc = $doc])*
+                #[serde(rename = $key, skip_serializing_if = "Option::is_none")]
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl $crate::config::schema::JsonSchema for $name {
+            fn schema() -> $crate::config::schema::Schema {
+                $crate::config::schema::Schema::Object(vec![
+                    $(
+                        $crate::config::schema::Property {
+                            name: $key,
+                            description: $crate::config::schema::description(&[$($doc),*]),
+                            schema: <$ty as $crate::config::schema::JsonSchema>::schema(),
+                        },
+                    )*
+                ])
+            }
+        }
+    };
+}
+
+pub(crate) use json_schema_struct;
+
+/// Joins the lines of a doc comment into a schema description
+pub fn description(lines: &[&str]) -> Option<String> {
+    if lines.is_empty() {
+        return None;
+    }
+
+    Some(
+        lines
+            .iter()
+            .map(|line| line.strip_prefix(' ').unwrap_or(line))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+impl JsonSchema for String {
+    fn schema() -> Schema {
+        Schema::String
+    }
+}
+
+impl JsonSchema for bool {
+    fn schema() -> Schema {
+        Schema::Boolean
+    }
+}
+
+impl JsonSchema for u32 {
+    fn schema() -> Schema {
+        Schema::Integer { minimum: Some(0) }
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Option<T> {
+    fn schema() -> Schema {
+        T::schema()
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Vec<T> {
+    fn schema() -> Schema {
+        Schema::Array(Box::new(T::schema()))
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for BTreeMap<String, T> {
+    fn schema() -> Schema {
+        Schema::Map(Box::new(T::schema()))
+    }
+}
+
+impl Schema {
+    /// Renders this schema as a JSON schema (draft 7) document
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Schema::Boolean => json!({ "type": "boolean" }),
+            Schema::String => json!({ "type": "string" }),
+            Schema::Integer { minimum: None } => json!({ "type": "integer" }),
+            Schema::Integer {
+                minimum: Some(minimum),
+            } => json!({ "type": "integer", "minimum": minimum }),
+            Schema::Enum(values) => json!({ "type": "string", "enum": values }),
+            Schema::Array(items) => json!({ "type": "array", "items": items.to_json() }),
+            Schema::Object(properties) => {
+                let properties: serde_json::Map<_, _> = properties
+                    .iter()
+                    .map(|property| {
+                        let mut schema = property.schema.to_json();
+                        if let (Some(description), Some(schema)) =
+                            (&property.description, schema.as_object_mut())
+                        {
+                            schema.insert("description".into(), description.as_str().into());
+                        }
+                        (property.name.to_owned(), schema)
+                    })
+                    .collect();
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "additionalProperties": false,
+                })
+            }
+            Schema::Map(values) => json!({
+                "type": "object",
+                "additionalProperties": values.to_json(),
+            }),
+            Schema::OneOf(variants) => json!({
+                "oneOf": variants.iter().map(Schema::to_json).collect::<Vec<_>>(),
+            }),
+        }
+    }
+
+    /// Checks a parsed value against this schema, recording every problem
+    /// found rather than stopping at the first one
+    pub fn validate(&self, value: &Value, diagnostics: &mut Vec<Diagnostic>) {
+        match (self, value) {
+            (Schema::Boolean, Value::BooleanLit(_)) | (Schema::String, Value::StringLit(_)) => {}
+            (Schema::Integer { minimum }, Value::NumberLit(number)) => {
+                match number.value.parse::<i64>() {
+                    Ok(n) if minimum.map_or(true, |minimum| n >= minimum) => {}
+                    _ => diagnostics.push(Diagnostic {
+                        message: format!("expected {}, found {}", self.expected(), number.value),
+                        range: number.range,
+                    }),
+                }
+            }
+            (Schema::Enum(values), Value::StringLit(string)) => {
+                if !values.contains(&string.value.as_ref()) {
+                    let mut message = format!(
+                        "\"{}\" is not a valid value, expected {}",
+                        string.value,
+                        self.expected()
+                    );
+                    if let Some(suggestion) = did_you_mean(&string.value, values.iter().copied()) {
+                        message.push_str(&format!(", did you mean \"{}\"?", suggestion));
+                    }
+                    diagnostics.push(Diagnostic {
+                        message,
+                        range: string.range,
+                    });
+                }
+            }
+            (Schema::Array(items), Value::Array(array)) => {
+                for element in &array.elements {
+                    items.validate(element, diagnostics);
+                }
+            }
+            (Schema::Object(properties), Value::Object(object)) => {
+                let mut seen = HashSet::new();
+                for prop in &object.properties {
+                    let name = prop.name.as_str();
+                    if !seen.insert(name) {
+                        diagnostics.push(Diagnostic {
+                            message: format!("duplicate key \"{}\"", name),
+                            range: *prop.name.range(),
+                        });
+                        continue;
+                    }
+
+                    match properties.iter().find(|property| property.name == name) {
+                        // A null value is treated the same as leaving the key out
+                        Some(_) if matches!(prop.value, Value::NullKeyword(_)) => {}
+                        Some(property) => property.schema.validate(&prop.value, diagnostics),
+                        None => {
+                            let mut message = format!("unknown key \"{}\"", name);
+                            if let Some(suggestion) =
+                                did_you_mean(name, properties.iter().map(|property| property.name))
+                            {
+                                message.push_str(&format!(", did you mean \"{}\"?", suggestion));
+                            }
+                            diagnostics.push(Diagnostic {
+                                message,
+                                range: *prop.name.range(),
+                            });
+                        }
+                    }
+                }
+            }
+            (Schema::Map(values), Value::Object(object)) => {
+                let mut seen = HashSet::new();
+                for prop in &object.properties {
+                    if !seen.insert(prop.name.as_str()) {
+                        diagnostics.push(Diagnostic {
+                            message: format!("duplicate key \"{}\"", prop.name.as_str()),
+                            range: *prop.name.range(),
+                        });
+                    } else if !matches!(prop.value, Value::NullKeyword(_)) {
+                        values.validate(&prop.value, diagnostics);
+                    }
+                }
+            }
+            (Schema::OneOf(variants), value) => {
+                // Validate against the variant that has the same shape as the
+                // value, so that mistakes inside it are reported precisely
+                match variants.iter().find(|variant| variant.accepts_kind(value)) {
+                    Some(variant) => variant.validate(value, diagnostics),
+                    None => diagnostics.push(self.type_mismatch(value)),
+                }
+            }
+            (_, value) => diagnostics.push(self.type_mismatch(value)),
+        }
+    }
+
+    fn accepts_kind(&self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (Schema::Boolean, Value::BooleanLit(_))
+                | (Schema::String | Schema::Enum(_), Value::StringLit(_))
+                | (Schema::Integer { .. }, Value::NumberLit(_))
+                | (Schema::Array(_), Value::Array(_))
+                | (Schema::Object(_) | Schema::Map(_), Value::Object(_))
+        )
+    }
+
+    fn type_mismatch(&self, value: &Value) -> Diagnostic {
+        let found = match value {
+            Value::StringLit(_) => "a string",
+            Value::NumberLit(_) => "a number",
+            Value::BooleanLit(_) => "a boolean",
+            Value::Object(_) => "an object",
+            Value::Array(_) => "an array",
+            Value::NullKeyword(_) => "null",
+        };
+        Diagnostic {
+            message: format!("expected {}, found {}", self.expected(), found),
+            range: *value.range(),
+        }
+    }
+
+    fn expected(&self) -> String {
+        match self {
+            Schema::Boolean => "a boolean".to_owned(),
+            Schema::String => "a string".to_owned(),
+            Schema::Integer { minimum: Some(0) } => "a non-negative integer".to_owned(),
+            Schema::Integer {
+                minimum: Some(minimum),
+            } => {
+                format!("an integer of at least {}", minimum)
+            }
+            Schema::Integer { minimum: None } => "an integer".to_owned(),
+            Schema::Enum(values) => format!(
+                "one of {}",
+                values
+                    .iter()
+                    .map(|value| format!("\"{}\"", value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Schema::Array(_) => "an array".to_owned(),
+            Schema::Object(_) | Schema::Map(_) => "an object".to_owned(),
+            Schema::OneOf(variants) => variants
+                .iter()
+                .map(Schema::expected)
+                .collect::<Vec<_>>()
+                .join(" or "),
+        }
+    }
+}
+
+/// Finds the candidate closest to `name`, as long as it's close enough to
+/// plausibly be what was meant
+fn did_you_mean<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    candidates
+        .map(|candidate| {
+            (
+                strsim::damerau_levenshtein(&name, &candidate.to_lowercase()),
+                candidate,
+            )
+        })
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod test {
+    use jsonc_parser::{parse_to_ast, CollectOptions, ParseOptions};
+    use test_case::test_case;
+
+    use super::{did_you_mean, Diagnostic, Schema};
+
+    fn validate(schema: &Schema, text: &str) -> Vec<Diagnostic> {
+        let ast = parse_to_ast(text, &CollectOptions::default(), &ParseOptions::default())
+            .unwrap()
+            .value
+            .unwrap();
+        let mut diagnostics = Vec::new();
+        schema.validate(&ast, &mut diagnostics);
+        diagnostics
+    }
+
+    #[test_case("dependOn", Some("dependsOn") ; "missing letter")]
+    #[test_case("passthroughEnv", Some("passThroughEnv") ; "case")]
+    #[test_case("outptus", Some("outputs") ; "transposed")]
+    #[test_case("somethingElse", None ; "unrelated")]
+    fn test_did_you_mean(name: &str, expected: Option<&str>) {
+        let candidates = ["dependsOn", "passThroughEnv", "outputs", "inputs"];
+        assert_eq!(did_you_mean(name, candidates.into_iter()), expected);
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let schema = Schema::Object(vec![
+            super::Property {
+                name: "count",
+                description: None,
+                schema: Schema::Integer { minimum: Some(0) },
+            },
+            super::Property {
+                name: "mode",
+                description: None,
+                schema: Schema::Enum(&["full", "none"]),
+            },
+        ]);
+        let text = r#"{ "count": -1, "mode": "ful", "cuont": 1, "count": 2 }"#;
+
+        let messages: Vec<_> = validate(&schema, text)
+            .into_iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.message,
+                    &text[diagnostic.range.start..diagnostic.range.end],
+                )
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                ("expected a non-negative integer, found -1".to_owned(), "-1"),
+                (
+                    "\"ful\" is not a valid value, expected one of \"full\", \"none\", did you \
+                     mean \"full\"?"
+                        .to_owned(),
+                    "\"ful\""
+                ),
+                (
+                    "unknown key \"cuont\", did you mean \"count\"?".to_owned(),
+                    "\"cuont\""
+                ),
+                ("duplicate key \"count\"".to_owned(), "\"count\""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_one_of_validates_matching_variant() {
+        let schema = Schema::OneOf(vec![
+            Schema::Integer { minimum: Some(0) },
+            Schema::Array(Box::new(Schema::String)),
+        ]);
+
+        assert_eq!(validate(&schema, "3"), vec![]);
+        assert_eq!(
+            validate(&schema, "[1]")
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect::<Vec<_>>(),
+            vec!["expected a string, found a number"]
+        );
+        assert_eq!(
+            validate(&schema, "true")
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect::<Vec<_>>(),
+            vec!["expected a non-negative integer or an array, found a boolean"]
+        );
+    }
+}
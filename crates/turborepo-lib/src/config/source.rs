@@ -0,0 +1,102 @@
+use std::{collections::HashMap, env, fmt, path::PathBuf};
+
+use serde::Serialize;
+
+/// The configuration layer that an effective setting was resolved from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "source", rename_all = "camelCase")]
+pub enum ConfigSource {
+    /// Nothing set the value, so the built-in default is used
+    Default,
+    /// The value was read from a config file
+    File { path: PathBuf },
+    /// The value was read from an environment variable
+    Env { name: String },
+    /// The value was passed as a command line flag
+    Flag { name: String },
+}
+
+impl ConfigSource {
+    pub(crate) fn file(path: impl Into<PathBuf>) -> Self {
+        Self::File { path: path.into() }
+    }
+
+    pub(crate) fn env(name: impl Into<String>) -> Self {
+        Self::Env { name: name.into() }
+    }
+
+    pub(crate) fn flag(name: impl Into<String>) -> Self {
+        Self::Flag { name: name.into() }
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File { path } => write!(f, "file {}", path.display()),
+            ConfigSource::Env { name } => write!(f, "env {name}"),
+            ConfigSource::Flag { name } => write!(f, "flag {name}"),
+        }
+    }
+}
+
+/// Returns the name of the first of `names` that is set, looking in
+/// `environment` if one was given and in the process environment otherwise.
+///
+/// Names are matched case-insensitively, the same way `config::Environment`
+/// matches its prefix.
+pub(crate) fn find_env_var(
+    environment: Option<&HashMap<String, String>>,
+    names: &[&str],
+) -> Option<String> {
+    let set_vars: Vec<String> = match environment {
+        Some(environment) => environment.keys().cloned().collect(),
+        None => env::vars_os()
+            .filter_map(|(key, _)| key.into_string().ok())
+            .collect(),
+    };
+
+    names.iter().find_map(|name| {
+        set_vars
+            .iter()
+            .find(|var| var.eq_ignore_ascii_case(name))
+            .cloned()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_env_var_respects_order() {
+        let environment: HashMap<String, String> = [
+            ("TURBO_TOKEN".to_string(), "a".to_string()),
+            ("vercel_artifacts_token".to_string(), "b".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            find_env_var(
+                Some(&environment),
+                &["VERCEL_ARTIFACTS_TOKEN", "TURBO_TOKEN"]
+            ),
+            Some("vercel_artifacts_token".to_string())
+        );
+        assert_eq!(find_env_var(Some(&environment), &["TURBO_API"]), None);
+    }
+
+    #[test]
+    fn test_source_serialization() {
+        assert_eq!(
+            serde_json::to_value(ConfigSource::env("TURBO_API")).unwrap(),
+            serde_json::json!({ "source": "env", "name": "TURBO_API" })
+        );
+        assert_eq!(
+            serde_json::to_value(ConfigSource::Default).unwrap(),
+            serde_json::json!({ "source": "default" })
+        );
+    }
+}
@@ -1,23 +1,802 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+};
+
+use jsonc_parser::{
+    ast::{Object, ObjectProp, Value},
+    common::{Range, Ranged},
+    parse_to_ast,
+    tokens::{Token, TokenAndRange},
+    CollectOptions, ParseOptions,
+};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use turbopath::AbsoluteSystemPath;
 
-use crate::{opts::RemoteCacheOpts, run::pipeline::Pipeline};
+use crate::{
+    config::schema::{json_schema_struct, Diagnostic, JsonSchema, Schema},
+    opts::RemoteCacheOpts,
+    run::pipeline::{BookkeepingTaskDefinition, Pipeline, RawTaskRetries},
+};
 
-#[derive(Serialize, Deserialize, Debug, Default)]
-#[serde(rename_all = "camelCase")]
-pub struct SpacesJson {
-    pub id: Option<String>,
-    #[serde(flatten)]
-    pub other: Option<serde_json::Value>,
+pub const SCHEMA_URL: &str = "https://turbo.build/schema.json";
+
+json_schema_struct! {
+    #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+    pub struct SpacesJson {
+        /// The id of the space that runs are reported to
+        #[serde(rename = "id")]
+        pub id: Option<String>,
+    }
+}
+
+json_schema_struct! {
+    #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+    pub struct RawBoundaries {
+        /// Tags to attach to workspaces, keyed by workspace name
+        #[serde(rename = "tags")]
+        pub tags: Option<BTreeMap<String, Vec<String>>>,
+        /// Workspaces with any of these tags may not be imported by other workspaces
+        #[serde(rename = "restrictedTags")]
+        pub restricted_tags: Option<Vec<String>>,
+        /// Whether workspaces marked `"private": true` may not be imported by other
+        /// workspaces
+        #[serde(rename = "restrictPrivate")]
+        pub restrict_private: Option<bool>,
+    }
+}
+
+json_schema_struct! {
+    #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+    pub struct RawRemoteCache {
+        /// The team id to use for remote caching
+        #[serde(rename = "teamId")]
+        pub team_id: Option<String>,
+        /// Indicates if signature verification is enabled for requests to the remote cache.
+        /// When `true`, artifacts are signed using the value of the environment variable
+        /// `TURBO_REMOTE_CACHE_SIGNATURE_KEY`, and downloaded artifacts with an invalid or
+        /// missing signature are rejected.
+        #[serde(rename = "signature")]
+        pub signature: Option<bool>,
+    }
+}
+
+// RawOutputMode is the value of `outputMode` as it's written in turbo.json
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RawOutputMode {
+    Full,
+    None,
+    HashOnly,
+    NewOnly,
+    ErrorsOnly,
+}
+
+impl JsonSchema for RawOutputMode {
+    fn schema() -> Schema {
+        Schema::Enum(&["full", "none", "hash-only", "new-only", "errors-only"])
+    }
+}
+
+json_schema_struct! {
+    #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+    pub struct RawTaskDefinition {
+        /// The set of glob patterns indicating a task's cacheable filesystem outputs.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#outputs
+        #[serde(rename = "outputs")]
+        pub outputs: Option<Vec<String>>,
+        /// Whether or not to cache the outputs of the task.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#cache
+        #[serde(rename = "cache")]
+        pub cache: Option<bool>,
+        /// The list of tasks that this task depends on. Items prefixed with a ^ refer to the
+        /// task in the package's topological dependencies.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#dependson
+        #[serde(rename = "dependsOn")]
+        pub depends_on: Option<Vec<String>>,
+        /// The set of glob patterns to consider as inputs to this task. Changes to files
+        /// covered by these globs will cause a cache miss.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#inputs
+        #[serde(rename = "inputs")]
+        pub inputs: Option<Vec<String>>,
+        /// Output mode for the task.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/command-line-reference#--output-logs
+        #[serde(rename = "outputMode")]
+        pub output_mode: Option<RawOutputMode>,
+        /// Indicates whether the task exits or not. Persistent tasks are long-running, and
+        /// other tasks cannot depend on them.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#persistent
+        #[serde(rename = "persistent")]
+        pub persistent: Option<bool>,
+        /// A list of environment variables that this task depends on.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#env
+        #[serde(rename = "env")]
+        pub env: Option<Vec<String>>,
+        /// An allowlist of environment variables that should be made available in this
+        /// task's environment, but should not contribute to the task's cache key.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#passThroughEnv
+        #[serde(rename = "passThroughEnv")]
+        pub pass_through_env: Option<Vec<String>>,
+        /// Deprecated, use `passThroughEnv` instead.
+        #[serde(rename = "experimentalPassThroughEnv")]
+        pub experimental_pass_through_env: Option<Vec<String>>,
+        /// A priority-ordered array of workspace-anchored Unix-style paths to `.env` files
+        /// to include in the task hash.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#dotEnv
+        #[serde(rename = "dotEnv")]
+        pub dot_env: Option<Vec<String>>,
+        /// How long a single run of the task may take before it is killed, along with any
+        /// processes it started. Accepts a duration such as "90s" or "10m".
+        #[serde(rename = "timeout")]
+        pub timeout: Option<String>,
+        /// How many more times to run the task if it fails. Either a number of retries, or
+        /// an object that also sets how long to wait between attempts.
+        #[serde(rename = "retries")]
+        pub retries: Option<RawTaskRetries>,
+    }
+}
+
+json_schema_struct! {
+    #[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+    pub struct RawTurboJson {
+        /// The JSON schema that describes this file
+        #[serde(rename = "$schema")]
+        pub schema: Option<String>,
+        /// Tells turbo to extend the root `turbo.json` with the keys provided in this
+        /// Workspace Config. Only available in Workspace Configs, and currently only the
+        /// "//" value is allowed.
+        #[serde(rename = "extends")]
+        pub extends: Option<Vec<String>>,
+        /// A list of globs to include in the set of implicit global hash dependencies.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#globaldependencies
+        #[serde(rename = "globalDependencies")]
+        pub global_dependencies: Option<Vec<String>>,
+        /// A list of environment variables for implicit global hash dependencies.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#globalenv
+        #[serde(rename = "globalEnv")]
+        pub global_env: Option<Vec<String>>,
+        /// An allowlist of environment variables that should be made available to all
+        /// tasks, but should not contribute to their cache keys.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#globalPassThroughEnv
+        #[serde(rename = "globalPassThroughEnv")]
+        pub global_pass_through_env: Option<Vec<String>>,
+        /// Deprecated, use `globalPassThroughEnv` instead.
+        #[serde(rename = "experimentalGlobalPassThroughEnv")]
+        pub experimental_global_pass_through_env: Option<Vec<String>>,
+        /// A priority-ordered array of repository-anchored Unix-style paths to `.env` files
+        /// to include in the global hash.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#globalDotEnv
+        #[serde(rename = "globalDotEnv")]
+        pub global_dot_env: Option<Vec<String>>,
+        /// An object representing the task dependency graph of your project, keyed by
+        /// task name.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/reference/configuration#pipeline
+        #[serde(rename = "pipeline")]
+        pub pipeline: Option<BTreeMap<String, RawTaskDefinition>>,
+        /// Configuration options that control how turbo interfaces with the remote cache.
+        ///
+        /// Documentation: https://turbo.build/repo/docs/core-concepts/remote-caching
+        #[serde(rename = "remoteCache")]
+        pub remote_cache: Option<RawRemoteCache>,
+        /// Configuration for reporting runs to a space
+        #[serde(rename = "experimentalSpaces")]
+        pub experimental_spaces: Option<SpacesJson>,
+        /// Rules that `turbo boundaries` checks imports between workspaces against
+        #[serde(rename = "boundaries")]
+        pub boundaries: Option<RawBoundaries>,
+    }
 }
 
 #[derive(Serialize, Deserialize, Default, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct TurboJson {
-    #[serde(flatten)]
-    other: serde_json::Value,
     pub(crate) remote_cache_opts: Option<RemoteCacheOpts>,
     pub space_id: Option<String>,
     pub pipeline: Pipeline,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub experimental_spaces: Option<SpacesJson>,
+    #[serde(default)]
+    pub boundaries: Boundaries,
+}
+
+/// The resolved `boundaries` section of turbo.json
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Boundaries {
+    pub tags: BTreeMap<String, BTreeSet<String>>,
+    pub restricted_tags: BTreeSet<String>,
+    pub restrict_private: bool,
+}
+
+impl From<RawBoundaries> for Boundaries {
+    fn from(raw: RawBoundaries) -> Self {
+        Boundaries {
+            tags: raw
+                .tags
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(workspace, tags)| (workspace, tags.into_iter().collect()))
+                .collect(),
+            restricted_tags: raw
+                .restricted_tags
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            restrict_private: raw.restrict_private.unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TurboJsonError {
+    #[error("unable to read {path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("unable to write {path}: {source}")]
+    Write {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(
+        "{}",
+        .diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n\n")
+    )]
+    Invalid { diagnostics: Vec<SourceDiagnostic> },
+}
+
+/// A diagnostic that has been resolved against the file it was found in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceDiagnostic {
+    pub path: String,
+    pub message: String,
+    // 1-indexed, with the column counted in characters
+    pub line: usize,
+    pub column: usize,
+    source_line: String,
+    width: usize,
+}
+
+impl SourceDiagnostic {
+    fn new(path: &str, text: &str, diagnostic: Diagnostic) -> Self {
+        let start = diagnostic.range.start.min(text.len());
+        let end = diagnostic.range.end.clamp(start, text.len());
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+        let source_line = text[line_start..line_end].trim_end_matches('\r');
+
+        Self {
+            path: path.to_owned(),
+            message: diagnostic.message,
+            line: text[..start].matches('\n').count() + 1,
+            column: text[line_start..start].chars().count() + 1,
+            source_line: source_line.to_owned(),
+            width: text[start..end.min(line_end)].chars().count().max(1),
+        }
+    }
+}
+
+impl fmt::Display for SourceDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(
+            f,
+            "{}:{}:{}: {}",
+            self.path, self.line, self.column, self.message
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.source_line)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(self.column - 1),
+            "^".repeat(self.width)
+        )
+    }
+}
+
+impl RawTurboJson {
+    /// Reads a turbo.json, which may contain comments and trailing commas
+    pub fn load(path: &AbsoluteSystemPath) -> Result<RawTurboJson, TurboJsonError> {
+        let text = read(path)?;
+        parse(&text)
+            .map(|(raw, _)| raw)
+            .map_err(|diagnostics| invalid(path, &text, diagnostics))
+    }
+
+    /// Generates the JSON schema that describes turbo.json
+    pub fn json_schema() -> serde_json::Value {
+        let mut schema = Self::schema().to_json();
+        let root = schema
+            .as_object_mut()
+            .expect("turbo.json schema is an object");
+        root.insert(
+            "$schema".into(),
+            "http://json-schema.org/draft-07/schema#".into(),
+        );
+        root.insert("$id".into(), SCHEMA_URL.into());
+        root.insert("title".into(), "turbo.json".into());
+        schema
+    }
+
+    /// Sets `experimentalSpaces.id` in the turbo.json at `path`. Only that
+    /// value is rewritten, so comments and formatting are kept.
+    pub fn set_space_id(path: &AbsoluteSystemPath, space_id: &str) -> Result<(), TurboJsonError> {
+        let text = read(path)?;
+        let edited = with_space_id(&text, space_id)
+            .map_err(|diagnostics| invalid(path, &text, diagnostics))?;
+        write(path, &edited)
+    }
+
+    /// Removes `experimentalSpaces` from the turbo.json at `path`, keeping the
+    /// comments and formatting of everything else
+    pub fn remove_spaces(path: &AbsoluteSystemPath) -> Result<(), TurboJsonError> {
+        let text = read(path)?;
+        let edited =
+            without_spaces(&text).map_err(|diagnostics| invalid(path, &text, diagnostics))?;
+        write(path, &edited)
+    }
+}
+
+impl TurboJson {
+    /// Reads and validates a turbo.json, resolving its task definitions
+    #[tracing::instrument(skip_all, fields(path = %path))]
+    pub fn load(path: &AbsoluteSystemPath) -> Result<TurboJson, TurboJsonError> {
+        let text = read(path)?;
+        let (raw, ast) = parse(&text).map_err(|diagnostics| invalid(path, &text, diagnostics))?;
+
+        let mut pipeline = Pipeline::new();
+        let mut diagnostics = Vec::new();
+        for (name, task) in raw.pipeline.unwrap_or_default() {
+            match BookkeepingTaskDefinition::try_from(task) {
+                Ok(task) => {
+                    pipeline.insert(name, task);
+                }
+                Err(err) => {
+                    let task = ast
+                        .as_ref()
+                        .and_then(|ast| find_property(ast, "pipeline"))
+                        .and_then(|pipeline| find_property(pipeline, &name));
+                    let range = task
+                        .and_then(|task| find_property(task, err.key()))
+                        .or(task)
+                        .map_or(Range::new(0, 0), |value| *value.range());
+                    diagnostics.push(Diagnostic {
+                        message: err.to_string(),
+                        range,
+                    });
+                }
+            }
+        }
+        if !diagnostics.is_empty() {
+            return Err(invalid(path, &text, diagnostics));
+        }
+
+        Ok(TurboJson {
+            remote_cache_opts: raw.remote_cache.map(|remote_cache| {
+                RemoteCacheOpts::new(
+                    remote_cache.team_id.unwrap_or_default(),
+                    remote_cache.signature.unwrap_or_default(),
+                )
+            }),
+            space_id: raw.experimental_spaces.and_then(|spaces| spaces.id),
+            pipeline,
+            boundaries: raw.boundaries.map(Boundaries::from).unwrap_or_default(),
+        })
+    }
+}
+
+fn read(path: &AbsoluteSystemPath) -> Result<String, TurboJsonError> {
+    fs::read_to_string(path.as_path()).map_err(|source| TurboJsonError::Io {
+        path: path.to_string(),
+        source,
+    })
+}
+
+fn write(path: &AbsoluteSystemPath, text: &str) -> Result<(), TurboJsonError> {
+    fs::write(path.as_path(), text).map_err(|source| TurboJsonError::Write {
+        path: path.to_string(),
+        source,
+    })
+}
+
+fn invalid(path: &AbsoluteSystemPath, text: &str, diagnostics: Vec<Diagnostic>) -> TurboJsonError {
+    let path = path.to_string();
+    TurboJsonError::Invalid {
+        diagnostics: diagnostics
+            .into_iter()
+            .map(|diagnostic| SourceDiagnostic::new(&path, text, diagnostic))
+            .collect(),
+    }
+}
+
+fn parse_options() -> ParseOptions {
+    ParseOptions {
+        allow_comments: true,
+        allow_trailing_commas: true,
+        allow_loose_object_property_names: false,
+    }
+}
+
+fn parse(text: &str) -> Result<(RawTurboJson, Option<Value>), Vec<Diagnostic>> {
+    let ast = parse_to_ast(text, &CollectOptions::default(), &parse_options())
+        .map_err(|err| {
+            vec![Diagnostic {
+                message: err.message,
+                range: err.range,
+            }]
+        })?
+        .value;
+
+    let Some(value) = &ast else {
+        return Err(vec![Diagnostic {
+            message: "expected an object, found an empty file".to_owned(),
+            range: Range::new(0, 0),
+        }]);
+    };
+
+    let mut diagnostics = Vec::new();
+    RawTurboJson::schema().validate(value, &mut diagnostics);
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+
+    // The schema has already been checked, so this can only fail if the
+    // schema and the types have drifted apart
+    let raw = serde_json::from_value(value.clone().into()).map_err(|err| {
+        vec![Diagnostic {
+            message: err.to_string(),
+            range: *value.range(),
+        }]
+    })?;
+
+    Ok((raw, ast))
+}
+
+fn find_property<'a, 'b>(value: &'b Value<'a>, name: &str) -> Option<&'b Value<'a>> {
+    match value {
+        Value::Object(object) => object_property(object, name).map(|property| &property.value),
+        _ => None,
+    }
+}
+
+fn object_property<'a, 'b>(object: &'b Object<'a>, name: &str) -> Option<&'b ObjectProp<'a>> {
+    object
+        .properties
+        .iter()
+        .find(|property| property.name.as_str() == name)
+}
+
+/// Parses `text` and returns its root object
+fn parse_object(text: &str) -> Result<Object, Vec<Diagnostic>> {
+    match parse(text)? {
+        (_, Some(Value::Object(object))) => Ok(object),
+        (_, value) => Err(vec![Diagnostic {
+            message: "expected an object".to_owned(),
+            range: value.map_or(Range::new(0, 0), |value| *value.range()),
+        }]),
+    }
+}
+
+/// Returns `text` with `experimentalSpaces.id` set to `space_id`
+fn with_space_id(text: &str, space_id: &str) -> Result<String, Vec<Diagnostic>> {
+    let root = parse_object(text)?;
+    let id = serde_json::to_string(space_id).expect("strings can be serialized");
+    let spaces = format!("{{ \"id\": {id} }}");
+    Ok(match object_property(&root, "experimentalSpaces") {
+        Some(ObjectProp {
+            value: Value::Object(spaces),
+            ..
+        }) => match object_property(spaces, "id") {
+            Some(property) => splice(text, *property.value.range(), &id),
+            None => insert_property(text, spaces, "id", &id),
+        },
+        Some(property) => splice(text, *property.value.range(), &spaces),
+        None => insert_property(text, &root, "experimentalSpaces", &spaces),
+    })
+}
+
+/// Returns `text` without the `experimentalSpaces` property
+fn without_spaces(text: &str) -> Result<String, Vec<Diagnostic>> {
+    let root = parse_object(text)?;
+    let Some(property) = object_property(&root, "experimentalSpaces") else {
+        return Ok(text.to_owned());
+    };
+
+    // Remove the comma that separates the property from its neighbours too
+    let collect = CollectOptions {
+        comments: false,
+        tokens: true,
+    };
+    let tokens = parse_to_ast(text, &collect, &parse_options())
+        .ok()
+        .and_then(|result| result.tokens)
+        .unwrap_or_default();
+    let is_comma = |token: &&TokenAndRange| matches!(token.token, Token::Comma);
+    let next = tokens
+        .iter()
+        .find(|token| token.range.start >= property.range.end)
+        .filter(is_comma);
+    let previous = tokens
+        .iter()
+        .rev()
+        .find(|token| token.range.end <= property.range.start)
+        .filter(is_comma);
+    let range = match (previous, next) {
+        (_, Some(comma)) => {
+            let rest = &text[comma.range.end..];
+            let spaces = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            Range::new(property.range.start, comma.range.end + spaces)
+        }
+        (Some(comma), None) => Range::new(comma.range.start, property.range.end),
+        (None, None) => property.range,
+    };
+    Ok(splice(text, whole_lines(text, range), ""))
+}
+
+/// Returns `text` with `"name": value` added as the last property of `object`
+fn insert_property(text: &str, object: &Object, name: &str, value: &str) -> String {
+    let name = serde_json::to_string(name).expect("strings can be serialized");
+    let Some(last) = object.properties.last() else {
+        let start = object.range.start + 1;
+        return splice(text, Range::new(start, start), &format!(" {name}: {value} "));
+    };
+    // Match the indentation of the last property when it's on its own line
+    let indentation = &text[line_start(text, last.range.start)..last.range.start];
+    let separator = if indentation.trim().is_empty() {
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        format!("{newline}{indentation}")
+    } else {
+        " ".to_owned()
+    };
+    let end = last.range.end;
+    splice(
+        text,
+        Range::new(end, end),
+        &format!(",{separator}{name}: {value}"),
+    )
+}
+
+/// Extends `range` to the lines it's on when nothing else is on them
+fn whole_lines(text: &str, range: Range) -> Range {
+    let start = line_start(text, range.start);
+    let end = text[range.end..]
+        .find('\n')
+        .map_or(text.len(), |i| range.end + i + 1);
+    if text[start..range.start].trim().is_empty() && text[range.end..end].trim().is_empty() {
+        Range::new(start, end)
+    } else {
+        range
+    }
+}
+
+fn line_start(text: &str, position: usize) -> usize {
+    text[..position].rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn splice(text: &str, range: Range, replacement: &str) -> String {
+    format!(
+        "{}{replacement}{}",
+        &text[..range.start],
+        &text[range.end..]
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeSet, fs};
+
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::{
+        with_space_id, without_spaces, RawOutputMode, RawTurboJson, TurboJson, TurboJsonError,
+    };
+
+    fn write_turbo_json(contents: &str) -> (TempDir, AbsoluteSystemPathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = AbsoluteSystemPathBuf::new(dir.path().join("turbo.json")).unwrap();
+        fs::write(path.as_path(), contents).unwrap();
+        (dir, path)
+    }
+
+    fn load_err(contents: &str) -> String {
+        let (_dir, path) = write_turbo_json(contents);
+        match TurboJson::load(path.as_absolute_path()) {
+            Err(err @ TurboJsonError::Invalid { .. }) => {
+                err.to_string().replace(&path.to_string(), "turbo.json")
+            }
+            other => panic!("expected invalid turbo.json, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_accepts_comments_and_trailing_commas() {
+        let (_dir, path) = write_turbo_json(
+            r#"{
+  // the build task
+  "pipeline": {
+    "build": {
+      "dependsOn": ["^build"],
+      "outputs": ["dist/**", "!dist/cache/**",],
+      "outputMode": "new-only",
+    },
+    /* tests are never cached */
+    "test": { "cache": false, "timeout": "5m", "retries": 2 },
+  },
+  "remoteCache": { "teamId": "team_abc", "signature": true },
+  "experimentalSpaces": { "id": "space_123" },
+  "boundaries": { "tags": { "secrets": ["internal"] }, "restrictedTags": ["internal"] },
+}"#,
+        );
+
+        let turbo_json = TurboJson::load(path.as_absolute_path()).unwrap();
+        assert_eq!(turbo_json.space_id.as_deref(), Some("space_123"));
+        assert!(turbo_json.remote_cache_opts.is_some());
+        assert_eq!(
+            turbo_json.boundaries.tags["secrets"],
+            BTreeSet::from(["internal".to_string()])
+        );
+        assert!(!turbo_json.boundaries.restrict_private);
+        let mut tasks: Vec<_> = turbo_json.pipeline.keys().cloned().collect();
+        tasks.sort();
+        assert_eq!(tasks, vec!["build", "test"]);
+    }
+
+    #[test]
+    fn test_unknown_key_points_at_span() {
+        let err = load_err(
+            r#"{
+  "pipeline": {
+    "build": {
+      "dependOn": ["^build"]
+    }
+  }
+}"#,
+        );
+
+        assert_eq!(
+            err,
+            r#"turbo.json:4:7: unknown key "dependOn", did you mean "dependsOn"?
+  |
+4 |       "dependOn": ["^build"]
+  |       ^^^^^^^^^^"#
+        );
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let err = load_err(
+            r#"{
+  "globalEnv": "CI",
+  "pipeline": { "lint": { "outputMode": "errors" } },
+  "pipline": {}
+}"#,
+        );
+
+        assert_eq!(
+            err,
+            r#"turbo.json:2:16: expected an array, found a string
+  |
+2 |   "globalEnv": "CI",
+  |                ^^^^
+
+turbo.json:3:41: "errors" is not a valid value, expected one of "full", "none", "hash-only", "new-only", "errors-only"
+  |
+3 |   "pipeline": { "lint": { "outputMode": "errors" } },
+  |                                         ^^^^^^^^
+
+turbo.json:4:3: unknown key "pipline", did you mean "pipeline"?
+  |
+4 |   "pipline": {}
+  |   ^^^^^^^^^"#
+        );
+    }
+
+    #[test]
+    fn test_task_definition_errors_point_at_key() {
+        let err = load_err(
+            r#"{
+  "pipeline": {
+    "test": { "timeout": "ten minutes" }
+  }
+}"#,
+        );
+
+        assert_eq!(
+            err,
+            r#"turbo.json:3:26: "timeout" must be a positive duration such as "90s" or "10m", found "ten minutes"
+  |
+3 |     "test": { "timeout": "ten minutes" }
+  |                          ^^^^^^^^^^^^^"#
+        );
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let err = load_err("{\n  \"pipeline\": {\n    \"build\": }\n}");
+        assert_eq!(
+            err,
+            r#"turbo.json:3:14: Unexpected close brace
+  |
+3 |     "build": }
+  |              ^"#
+        );
+    }
+
+    #[test]
+    fn test_output_modes_match_schema() {
+        use super::JsonSchema;
+
+        let super::Schema::Enum(values) = RawOutputMode::schema() else {
+            panic!("outputMode should be an enum");
+        };
+        for value in values {
+            let mode: RawOutputMode = serde_json::from_value(serde_json::json!(value)).unwrap();
+            assert_eq!(
+                serde_json::to_value(mode).unwrap(),
+                serde_json::json!(value)
+            );
+        }
+    }
+
+    #[test]
+    fn test_space_id_edits_keep_comments() {
+        let unlinked = "{\n  // Shared settings\n  \"pipeline\": {},\n}\n";
+        let linked = with_space_id(unlinked, "space").unwrap();
+        assert_eq!(
+            linked,
+            "{\n  // Shared settings\n  \"pipeline\": {},\n  \"experimentalSpaces\": { \"id\": \
+             \"space\" },\n}\n"
+        );
+        assert_eq!(
+            with_space_id(&linked, "other").unwrap(),
+            linked.replace("\"space\"", "\"other\"")
+        );
+        assert_eq!(without_spaces(&linked).unwrap(), unlinked);
+        assert_eq!(without_spaces(unlinked).unwrap(), unlinked);
+
+        let inline = r#"{ "experimentalSpaces": {}, "pipeline": {} }"#;
+        assert_eq!(
+            with_space_id(inline, "space").unwrap(),
+            r#"{ "experimentalSpaces": { "id": "space" }, "pipeline": {} }"#
+        );
+        assert_eq!(without_spaces(inline).unwrap(), r#"{ "pipeline": {} }"#);
+    }
+
+    #[test]
+    fn test_published_schema_is_up_to_date() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/schema.json");
+        let generated = format!(
+            "{}\n",
+            serde_json::to_string_pretty(&RawTurboJson::json_schema()).unwrap()
+        );
+        if std::env::var_os("UPDATE_SCHEMA").is_some() {
+            fs::write(path, &generated).unwrap();
+        }
+
+        let published = fs::read_to_string(path).unwrap_or_default();
+        assert!(
+            published == generated,
+            "schema.json is out of date, regenerate it with `UPDATE_SCHEMA=1 cargo test -p \
+             turborepo-lib test_published_schema_is_up_to_date`"
+        );
+    }
 }
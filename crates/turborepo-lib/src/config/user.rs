@@ -4,7 +4,10 @@ use anyhow::Result;
 use config::{Config, Environment};
 use serde::{Deserialize, Serialize};
 
-use super::write_to_disk;
+use super::{
+    source::{find_env_var, ConfigSource},
+    write_to_disk,
+};
 
 // Inner struct that matches the config file schema
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq, Default)]
@@ -19,6 +22,7 @@ pub struct UserConfig {
     // environment variables or command line flags.
     disk_config: UserConfigValue,
     config: UserConfigValue,
+    token_source: ConfigSource,
     path: PathBuf,
 }
 
@@ -36,10 +40,16 @@ impl UserConfig {
         self.config.token.as_deref()
     }
 
+    /// Where the effective token came from
+    pub fn token_source(&self) -> &ConfigSource {
+        &self.token_source
+    }
+
     /// Set token and sync the changes to disk
     pub fn set_token(&mut self, token: Option<String>) -> Result<()> {
         self.disk_config.token = token.clone();
         self.config.token = token;
+        self.token_source = ConfigSource::file(&self.path);
         self.write_to_disk()
     }
 
@@ -89,20 +99,34 @@ impl UserConfigLoader {
                     .required(false),
             )
             .build()?;
+        let disk_config: UserConfigValue = raw_disk_config.clone().try_deserialize()?;
+
+        // VERCEL_ARTIFACTS_TOKEN is layered after TURBO_TOKEN, so it wins
+        let token_source = if token.is_some() {
+            ConfigSource::flag("--token")
+        } else if let Some(name) = find_env_var(
+            environment.as_ref(),
+            &["VERCEL_ARTIFACTS_TOKEN", "TURBO_TOKEN"],
+        ) {
+            ConfigSource::env(name)
+        } else if disk_config.token.is_some() {
+            ConfigSource::file(&path)
+        } else {
+            ConfigSource::Default
+        };
 
         let config = Config::builder()
-            .add_source(raw_disk_config.clone())
+            .add_source(raw_disk_config)
             .add_source(Environment::with_prefix("TURBO").source(environment.clone()))
             .add_source(Environment::with_prefix("VERCEL_ARTIFACTS").source(environment))
             .set_override_option("token", token)?
             .build()?
             .try_deserialize()?;
 
-        let disk_config: UserConfigValue = raw_disk_config.try_deserialize()?;
-
         Ok(UserConfig {
             disk_config,
             config,
+            token_source,
             path,
         })
     }
@@ -139,9 +163,14 @@ mod test {
             UserConfigLoader::new(config_file.path().to_path_buf()).with_token(Some("bar".into()));
         let config = loader.load()?;
         assert_eq!(config.token(), Some("bar"));
+        assert_eq!(config.token_source(), &ConfigSource::flag("--token"));
         config.write_to_disk()?;
         let new_config = UserConfigLoader::new(config_file.path().to_path_buf()).load()?;
         assert_eq!(new_config.token(), Some("foo"));
+        assert_eq!(
+            new_config.token_source(),
+            &ConfigSource::file(config_file.path())
+        );
         Ok(())
     }
 
@@ -165,6 +194,7 @@ mod test {
                 .load()?;
 
             assert_eq!(config.token(), Some(env_var_value.as_str()));
+            assert_eq!(config.token_source(), &ConfigSource::env(env_var));
         }
 
         Ok(())
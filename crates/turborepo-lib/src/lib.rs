@@ -21,7 +21,7 @@ use ::tracing::error;
 use anyhow::Result;
 pub use child::spawn_child;
 
-pub use crate::{cli::Args, execution_state::ExecutionState};
+pub use crate::{cli::Args, config::RawTurboJson, execution_state::ExecutionState};
 use crate::{commands::CommandBase, package_manager::PackageManager};
 
 /// The payload from running main, if the program can complete without using Go
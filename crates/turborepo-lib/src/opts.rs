@@ -3,7 +3,7 @@ use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    cli::{Command, DryRunMode, EnvMode, LogPrefix, RunArgs},
+    cli::{Command, DryRunMode, EnvMode, LogPrefix, RunArgs, UiMode},
     daemon::{DaemonClient, DaemonConnector},
     Args,
 };
@@ -42,6 +42,12 @@ pub struct RemoteCacheOpts {
     signature: bool,
 }
 
+impl RemoteCacheOpts {
+    pub fn new(team_id: String, signature: bool) -> Self {
+        Self { team_id, signature }
+    }
+}
+
 impl<'a> TryFrom<&'a Args> for Opts<'a> {
     type Error = anyhow::Error;
 
@@ -55,7 +61,7 @@ impl<'a> TryFrom<&'a Args> for Opts<'a> {
         Ok(Self {
             run_opts,
             cache_opts,
-            scope_opts: ScopeOpts::default(),
+            scope_opts: ScopeOpts::from(run_args.as_ref()),
             runcache_opts: RunCacheOpts::default(),
         })
     }
@@ -83,6 +89,7 @@ pub struct RunOpts<'a> {
     pub(crate) no_daemon: bool,
     pub(crate) single_package: bool,
     log_prefix: Option<LogPrefix>,
+    pub(crate) ui_mode: UiMode,
     summarize: Option<Option<bool>>,
     pub(crate) experimental_space_id: Option<String>,
 }
@@ -109,6 +116,11 @@ impl<'a> TryFrom<&'a RunArgs> for RunOpts<'a> {
         Ok(Self {
             tasks: args.tasks.as_slice(),
             log_prefix: args.log_prefix,
+            #[cfg(feature = "run-stub")]
+            ui_mode: args.ui.unwrap_or_default(),
+            // Only the Rust run has an interactive view
+            #[cfg(not(feature = "run-stub"))]
+            ui_mode: UiMode::Stream,
             summarize: args.summarize,
             experimental_space_id: args.experimental_space_id.clone(),
             env_mode: args.env_mode,
@@ -152,4 +164,15 @@ fn parse_concurrency(concurrency_raw: &str) -> Result<u32> {
 }
 
 #[derive(Debug, Default)]
-pub struct ScopeOpts {}
+pub struct ScopeOpts {
+    /// `--filter` selectors, with `--scope` values treated as name selectors
+    pub filter_patterns: Vec<String>,
+}
+
+impl<'a> From<&'a RunArgs> for ScopeOpts {
+    fn from(args: &'a RunArgs) -> Self {
+        ScopeOpts {
+            filter_patterns: args.filter.iter().chain(&args.scope).cloned().collect(),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use turbopath::AbsoluteSystemPath;
@@ -5,7 +7,16 @@ use turbopath::AbsoluteSystemPath;
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct PackageJson {
+    pub name: Option<String>,
+    pub version: Option<String>,
     pub package_manager: Option<String>,
+    #[serde(default)]
+    pub private: bool,
+    pub dependencies: Option<BTreeMap<String, String>>,
+    pub dev_dependencies: Option<BTreeMap<String, String>>,
+    pub optional_dependencies: Option<BTreeMap<String, String>>,
+    pub peer_dependencies: Option<BTreeMap<String, String>>,
+    pub scripts: Option<BTreeMap<String, String>>,
 }
 
 impl PackageJson {
@@ -14,4 +25,22 @@ impl PackageJson {
         let package_json: PackageJson = serde_json::from_str(&contents)?;
         Ok(package_json)
     }
+
+    /// Every dependency a package manager installs for this package, keyed by
+    /// name. Peer dependencies are left out as they're provided by whoever
+    /// depends on this package.
+    pub fn all_dependencies(&self) -> impl Iterator<Item = (&String, &String)> {
+        [
+            &self.dev_dependencies,
+            &self.optional_dependencies,
+            &self.dependencies,
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+    }
+
+    pub fn script(&self, name: &str) -> Option<&str> {
+        self.scripts.as_ref()?.get(name).map(String::as_str)
+    }
 }
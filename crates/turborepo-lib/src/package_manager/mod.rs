@@ -11,6 +11,8 @@ use anyhow::{anyhow, Result};
 use itertools::Itertools;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use turbopath::AbsoluteSystemPath;
+use turborepo_lockfiles::{BerryLockfile, BerryManifest, Lockfile, LockfileData, NpmLockfile};
 
 use crate::{
     commands::CommandBase,
@@ -161,6 +163,43 @@ impl PackageManager {
         }))
     }
 
+    /// Reads the repository's lockfile and passes it to `f`. The lockfile is
+    /// only lent out because berry lockfiles borrow from the parsed file.
+    pub fn with_lockfile<T>(
+        &self,
+        repo_root: &AbsoluteSystemPath,
+        f: impl FnOnce(&dyn Lockfile) -> Result<T>,
+    ) -> Result<T> {
+        match self {
+            PackageManager::Npm => {
+                let contents = fs::read(repo_root.join_component(npm::LOCKFILE).as_path())?;
+                let lockfile = NpmLockfile::load(&contents)?;
+                f(&lockfile)
+            }
+            PackageManager::Berry => {
+                let contents = fs::read(repo_root.join_component(yarn::LOCKFILE).as_path())?;
+                let data = LockfileData::from_bytes(&contents)?;
+                // Resolutions in the root package.json change how packages resolve
+                let package_json = fs::read(repo_root.join_component("package.json").as_path())?;
+                let manifest: BerryManifest = serde_json::from_slice(&package_json)?;
+                let lockfile = BerryLockfile::new(&data, Some(&manifest))?;
+                f(&lockfile)
+            }
+            PackageManager::Pnpm | PackageManager::Pnpm6 | PackageManager::Yarn => {
+                Err(anyhow!("reading {} lockfiles is not supported yet", self))
+            }
+        }
+    }
+
+    /// The path lockfiles use to refer to the workspace at `path`, which is
+    /// relative to the repository root and empty for the root workspace
+    pub fn lockfile_workspace_path<'a>(&self, path: &'a str) -> &'a str {
+        match self {
+            PackageManager::Berry if path.is_empty() => ".",
+            _ => path,
+        }
+    }
+
     pub fn get_package_manager(base: &CommandBase, pkg: Option<&PackageJson>) -> Result<Self> {
         // We don't surface errors for `read_package_manager` as we can fall back to
         // `detect_package_manager`
@@ -337,6 +376,7 @@ mod tests {
     fn test_read_package_manager() -> Result<()> {
         let mut package_json = PackageJson {
             package_manager: Some("npm@8.19.4".to_string()),
+            ..Default::default()
         };
         let package_manager = PackageManager::read_package_manager(&package_json)?;
         assert_eq!(package_manager, Some(PackageManager::Npm));
@@ -5,7 +5,11 @@ use turbopath::AbsoluteSystemPath;
 
 use crate::{
     config::TurboJson,
-    run::pipeline::{Pipeline, TaskDefinition},
+    package_json::PackageJson,
+    run::{
+        pipeline::{Pipeline, TaskDefinition},
+        task_id::ROOT_PKG_NAME,
+    },
 };
 
 pub struct CompleteGraph<'run> {
@@ -42,18 +46,39 @@ impl<'run> CompleteGraph<'run> {
         }
     }
 
+    #[tracing::instrument(skip(self))]
     pub fn get_turbo_config_from_workspace(
         &self,
-        _workspace_name: &str,
+        workspace_name: &str,
         _is_single_package: bool,
     ) -> Result<TurboJson> {
-        // TODO
-        Ok(TurboJson::default())
+        // TODO: workspace configs, and synthesizing a config for single package
+        // repos that don't have a turbo.json
+        if workspace_name != ROOT_PKG_NAME {
+            return Ok(TurboJson::default());
+        }
+
+        Ok(TurboJson::load(
+            self.repo_root
+                .join_component("turbo.json")
+                .as_absolute_path(),
+        )?)
     }
 }
 
-#[derive(Default)]
-pub struct WorkspaceCatalog {}
+/// The workspaces in the repository, by package name
+#[derive(Debug, Default)]
+pub struct WorkspaceCatalog {
+    pub workspaces: BTreeMap<String, WorkspaceInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceInfo {
+    pub package_json: PackageJson,
+    /// Unix style path of the workspace directory relative to the repository
+    /// root, empty for the root workspace
+    pub path: String,
+}
 
 #[derive(Default)]
 pub struct TaskHashTracker {}
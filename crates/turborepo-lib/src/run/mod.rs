@@ -1,10 +1,11 @@
 #![allow(dead_code)]
 
 mod graph;
-mod package_graph;
+pub(crate) mod package_graph;
 pub mod pipeline;
-mod scope;
-mod task_id;
+pub(crate) mod scope;
+pub(crate) mod task_graph;
+pub(crate) mod task_id;
 
 use anyhow::{Context as ErrorContext, Result};
 use graph::CompleteGraph;
@@ -16,7 +17,8 @@ use crate::{
     manager::Manager,
     opts::Opts,
     package_json::PackageJson,
-    run::{package_graph::PackageGraph, task_id::ROOT_PKG_NAME},
+    run::{package_graph::PackageGraph, task_graph::TaskGraph, task_id::ROOT_PKG_NAME},
+    ui::{RunOutput, TaskEvent},
 };
 
 #[derive(Debug)]
@@ -39,6 +41,7 @@ impl Run {
         Ok(self.base.args().try_into()?)
     }
 
+    #[tracing::instrument(skip_all)]
     pub async fn run(&mut self) -> Result<()> {
         let _start_at = std::time::Instant::now();
         let package_json_path = self.base.repo_root.join_component("package.json");
@@ -52,7 +55,7 @@ impl Run {
         let pkg_dep_graph = if opts.run_opts.single_package {
             PackageGraph::build_single_package_graph(root_package_json)?
         } else {
-            PackageGraph::build_multi_package_graph(&self.base.repo_root, &root_package_json)?
+            PackageGraph::build_multi_package_graph(&self.base, &root_package_json)?
         };
         // There's some warning handling code in Go that I'm ignoring
 
@@ -105,6 +108,17 @@ impl Run {
             }
         }
 
+        let task_graph = TaskGraph::build(&pkg_dep_graph, pipeline, targets, &filtered_pkgs)?;
+
+        // Tasks are still executed by the Go code, so there's only the plan to report
+        let mut output = RunOutput::new(&self.base.ui, opts.run_opts.ui_mode);
+        for task_id in task_graph.tasks() {
+            output.send(TaskEvent::Planned {
+                task_id: task_id.to_string(),
+            })?;
+        }
+        output.finish()?;
+
         Ok(())
     }
 }
@@ -140,6 +154,10 @@ mod test {
 
         // Add package.json
         fs::write(repo_root.join_component("package.json"), "{}")?;
+        fs::write(
+            repo_root.join_component("turbo.json"),
+            r#"{ "pipeline": {} }"#,
+        )?;
 
         let base = CommandBase::new(args, repo_root, get_version(), ui)?;
         let mut run = Run::new(base);
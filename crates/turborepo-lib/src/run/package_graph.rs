@@ -1,41 +1,455 @@
-use std::rc::Rc;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    fs,
+    path::{Component, Path},
+    rc::Rc,
+};
 
-use anyhow::Result;
-use turbopath::AbsoluteSystemPathBuf;
+use anyhow::{anyhow, bail, Result};
+use node_semver::{Range, Version};
+use petgraph::{graph::NodeIndex, Direction};
+use tracing::debug;
+use turbopath::{AbsoluteSystemPath, AbsoluteSystemPathBuf};
 
-use crate::{package_json::PackageJson, run::graph::WorkspaceCatalog};
+use crate::{
+    commands::CommandBase,
+    package_json::PackageJson,
+    package_manager::{Globs, PackageManager},
+    run::{
+        graph::{WorkspaceCatalog, WorkspaceInfo},
+        task_id::ROOT_PKG_NAME,
+    },
+};
+
+/// Directories that never contain workspaces or their sources
+pub const IGNORED_DIRECTORIES: &[&str] = &["node_modules", ".git", ".turbo"];
 
 pub struct PackageGraph {
+    /// Edges point from a workspace to the workspaces it depends on and are
+    /// weighted by the version specifier of the dependency
     pub workspace_graph: Rc<petgraph::Graph<String, String>>,
     pub workspace_infos: Rc<WorkspaceCatalog>,
+    node_lookup: HashMap<String, NodeIndex>,
 }
 
 impl PackageGraph {
-    pub fn build_single_package_graph(_root_package_json: PackageJson) -> Result<PackageGraph> {
-        // TODO
-        Ok(PackageGraph {
-            workspace_graph: Rc::new(petgraph::Graph::new()),
-            workspace_infos: Rc::new(WorkspaceCatalog::default()),
-        })
+    #[tracing::instrument(skip_all)]
+    pub fn build_single_package_graph(root_package_json: PackageJson) -> Result<PackageGraph> {
+        let mut workspaces = BTreeMap::new();
+        workspaces.insert(
+            ROOT_PKG_NAME.to_string(),
+            WorkspaceInfo {
+                package_json: root_package_json,
+                path: String::new(),
+            },
+        );
+        Ok(Self::from_workspaces(workspaces))
     }
 
+    #[tracing::instrument(skip_all)]
     pub fn build_multi_package_graph(
-        _repo_root: &AbsoluteSystemPathBuf,
-        _root_package_json: &PackageJson,
+        base: &CommandBase,
+        root_package_json: &PackageJson,
     ) -> Result<PackageGraph> {
-        // TODO
-        Ok(PackageGraph {
-            workspace_graph: Rc::new(petgraph::Graph::new()),
-            workspace_infos: Rc::new(WorkspaceCatalog::default()),
-        })
+        let mut workspaces = BTreeMap::new();
+        workspaces.insert(
+            ROOT_PKG_NAME.to_string(),
+            WorkspaceInfo {
+                package_json: root_package_json.clone(),
+                path: String::new(),
+            },
+        );
+
+        // Without a package manager there is nothing that links workspaces, so
+        // the repository only consists of the root
+        let package_manager =
+            match PackageManager::get_package_manager(base, Some(root_package_json)) {
+                Ok(package_manager) => package_manager,
+                Err(err) => {
+                    debug!("not discovering workspaces: {err}");
+                    return Ok(Self::from_workspaces(workspaces));
+                }
+            };
+        if let Some(globs) = package_manager.get_workspace_globs(base.repo_root.as_path())? {
+            walk_workspaces(
+                base.repo_root.as_absolute_path(),
+                base.repo_root.as_absolute_path(),
+                &globs,
+                &mut workspaces,
+            )?;
+        }
+
+        Ok(Self::from_workspaces(workspaces))
+    }
+
+    fn from_workspaces(workspaces: BTreeMap<String, WorkspaceInfo>) -> PackageGraph {
+        let mut graph = petgraph::Graph::new();
+        let node_lookup: HashMap<String, NodeIndex> = workspaces
+            .keys()
+            .map(|name| (name.clone(), graph.add_node(name.clone())))
+            .collect();
+
+        for (name, info) in &workspaces {
+            for (dependency, specifier) in info.package_json.all_dependencies() {
+                let Some(dependency_info) = workspaces.get(dependency) else {
+                    continue;
+                };
+                if is_workspace_reference(info, dependency_info, specifier) {
+                    graph.update_edge(
+                        node_lookup[name],
+                        node_lookup[dependency],
+                        specifier.clone(),
+                    );
+                }
+            }
+        }
+
+        PackageGraph {
+            workspace_graph: Rc::new(graph),
+            workspace_infos: Rc::new(WorkspaceCatalog { workspaces }),
+            node_lookup,
+        }
     }
 
+    #[tracing::instrument(skip_all)]
     pub fn validate(&self) -> Result<()> {
-        // TODO
-        Ok(())
+        petgraph::algo::toposort(self.workspace_graph.as_ref(), None)
+            .map(|_| ())
+            .map_err(|cycle| {
+                anyhow!(
+                    "cyclic dependency detected involving {}",
+                    self.workspace_graph[cycle.node_id()]
+                )
+            })
     }
 
+    /// The number of workspaces, not counting the root
     pub fn len(&self) -> usize {
-        self.workspace_graph.node_count()
+        self.workspace_graph.node_count() - 1
+    }
+
+    pub fn workspace(&self, name: &str) -> Option<&WorkspaceInfo> {
+        self.workspace_infos.workspaces.get(name)
+    }
+
+    pub fn workspaces(&self) -> impl Iterator<Item = (&String, &WorkspaceInfo)> {
+        self.workspace_infos.workspaces.iter()
+    }
+
+    /// The workspaces that `name` depends on directly
+    pub fn dependencies(&self, name: &str) -> BTreeSet<&str> {
+        self.neighbors(name, Direction::Outgoing)
+    }
+
+    /// The workspaces that depend on `name` directly
+    pub fn dependents(&self, name: &str) -> BTreeSet<&str> {
+        self.neighbors(name, Direction::Incoming)
+    }
+
+    /// Every workspace that `name` depends on, directly or through other
+    /// workspaces
+    pub fn transitive_dependencies(&self, name: &str) -> BTreeSet<&str> {
+        self.transitive_neighbors(name, Direction::Outgoing)
+    }
+
+    /// Every workspace that depends on `name`, directly or through other
+    /// workspaces
+    pub fn transitive_dependents(&self, name: &str) -> BTreeSet<&str> {
+        self.transitive_neighbors(name, Direction::Incoming)
+    }
+
+    /// The shortest chain of workspace dependencies leading from `from` to
+    /// `to`, including both ends
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<Vec<&str>> {
+        let start = *self.node_lookup.get(from)?;
+        let goal = *self.node_lookup.get(to)?;
+        let (_, path) = petgraph::algo::astar(
+            self.workspace_graph.as_ref(),
+            start,
+            |node| node == goal,
+            |_| 1,
+            |_| 0,
+        )?;
+        Some(
+            path.into_iter()
+                .map(|node| self.workspace_graph[node].as_str())
+                .collect(),
+        )
+    }
+
+    /// The dependencies of `name` that aren't satisfied by a workspace in the
+    /// repository, keyed by package name
+    pub fn external_dependencies(&self, name: &str) -> BTreeMap<&str, &str> {
+        let Some(info) = self.workspace(name) else {
+            return BTreeMap::new();
+        };
+        let internal = self.dependencies(name);
+        info.package_json
+            .all_dependencies()
+            .filter(|(dependency, _)| !internal.contains(dependency.as_str()))
+            .map(|(dependency, specifier)| (dependency.as_str(), specifier.as_str()))
+            .collect()
+    }
+
+    fn transitive_neighbors(&self, name: &str, direction: Direction) -> BTreeSet<&str> {
+        let mut seen = BTreeSet::new();
+        let mut queue: VecDeque<&str> = self.neighbors(name, direction).into_iter().collect();
+        while let Some(workspace) = queue.pop_front() {
+            if seen.insert(workspace) {
+                queue.extend(self.neighbors(workspace, direction));
+            }
+        }
+        seen.remove(name);
+        seen
+    }
+
+    fn neighbors(&self, name: &str, direction: Direction) -> BTreeSet<&str> {
+        let Some(node) = self.node_lookup.get(name) else {
+            return BTreeSet::new();
+        };
+        self.workspace_graph
+            .neighbors_directed(*node, direction)
+            .map(|neighbor| self.workspace_graph[neighbor].as_str())
+            .collect()
+    }
+}
+
+/// Decides whether `specifier` in `dependent` refers to the `dependency`
+/// workspace rather than a published package of the same name. This matches
+/// the rules package managers use to link workspaces.
+fn is_workspace_reference(
+    dependent: &WorkspaceInfo,
+    dependency: &WorkspaceInfo,
+    specifier: &str,
+) -> bool {
+    if specifier.starts_with("workspace:") {
+        return true;
+    }
+    if let Some(path) = specifier
+        .strip_prefix("file:")
+        .or_else(|| specifier.strip_prefix("link:"))
+    {
+        return normalize(&format!("{}/{}", dependent.path, path)) == dependency.path;
+    }
+    if specifier == "*" {
+        return true;
+    }
+
+    let Some(version) = dependency.package_json.version.as_deref() else {
+        return false;
+    };
+    match (Range::parse(specifier), Version::parse(version)) {
+        (Ok(range), Ok(version)) => range.satisfies(&version),
+        // Specifiers that aren't a range, e.g. git urls or npm aliases,
+        // always point outside the repository
+        _ => false,
+    }
+}
+
+/// Resolves `.` and `..` segments of a unix style relative path
+fn normalize(path: &str) -> String {
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+fn walk_workspaces(
+    repo_root: &AbsoluteSystemPath,
+    directory: &AbsoluteSystemPath,
+    globs: &Globs,
+    workspaces: &mut BTreeMap<String, WorkspaceInfo>,
+) -> Result<()> {
+    let Ok(entries) = fs::read_dir(directory.as_path()) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let is_dir = entry
+            .file_type()
+            .map_or(false, |file_type| file_type.is_dir());
+        let file_name = entry.file_name();
+        if !is_dir
+            || IGNORED_DIRECTORIES
+                .iter()
+                .any(|ignored| file_name == *ignored)
+        {
+            continue;
+        }
+        let Ok(child) = AbsoluteSystemPathBuf::new(entry.path()) else {
+            continue;
+        };
+
+        let package_json_path = child.join_component("package.json");
+        let is_workspace = package_json_path.exists()
+            && globs
+                .test(
+                    repo_root.as_path().to_path_buf(),
+                    child.as_path().to_path_buf(),
+                )
+                .unwrap_or(false);
+        if is_workspace {
+            let path = unix_path(repo_root.as_path(), child.as_path());
+            let package_json = PackageJson::load(package_json_path.as_absolute_path())?;
+            let Some(name) = package_json.name.clone() else {
+                bail!("{path}/package.json is missing the \"name\" field");
+            };
+            if let Some(existing) = workspaces.get(&name) {
+                bail!(
+                    "Failed to add workspace \"{name}\" from {path}, it already exists at {}",
+                    existing.path
+                );
+            }
+            workspaces.insert(name, WorkspaceInfo { package_json, path });
+        }
+
+        walk_workspaces(repo_root, child.as_absolute_path(), globs, workspaces)?;
+    }
+
+    Ok(())
+}
+
+fn unix_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{get_version, ui::UI, Args};
+
+    fn write_workspace(dir: &TempDir, path: &str, package_json: &str) {
+        let directory = dir.path().join(path);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("package.json"), package_json).unwrap();
+    }
+
+    fn build(dir: &TempDir) -> Result<PackageGraph> {
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        let root_package_json =
+            PackageJson::load(repo_root.join_component("package.json").as_absolute_path())?;
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+        PackageGraph::build_multi_package_graph(&base, &root_package_json)
+    }
+
+    #[test]
+    fn test_build_multi_package_graph() -> Result<()> {
+        let dir = TempDir::new()?;
+        write_workspace(
+            &dir,
+            "",
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["apps/*", "packages/*"] }"#,
+        );
+        write_workspace(
+            &dir,
+            "apps/web",
+            r#"{ "name": "web", "dependencies": { "ui": "*", "react": "^18.2.0" } }"#,
+        );
+        write_workspace(
+            &dir,
+            "apps/docs",
+            r#"{ "name": "docs", "dependencies": { "ui": "^2.0.0" } }"#,
+        );
+        write_workspace(
+            &dir,
+            "packages/ui",
+            r#"{ "name": "ui", "version": "1.0.0", "dependencies": { "utils": "workspace:*" } }"#,
+        );
+        write_workspace(&dir, "packages/utils", r#"{ "name": "utils" }"#);
+        write_workspace(
+            &dir,
+            "apps/web/node_modules/react",
+            r#"{ "name": "react" }"#,
+        );
+
+        let graph = build(&dir)?;
+        graph.validate()?;
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.workspace("ui").unwrap().path, "packages/ui");
+        assert_eq!(graph.dependencies("web"), BTreeSet::from(["ui"]));
+        assert_eq!(graph.dependents("ui"), BTreeSet::from(["web"]));
+        assert_eq!(
+            graph.transitive_dependencies("web"),
+            BTreeSet::from(["ui", "utils"])
+        );
+        assert_eq!(
+            graph.transitive_dependents("utils"),
+            BTreeSet::from(["ui", "web"])
+        );
+        assert_eq!(
+            graph.shortest_path("web", "utils"),
+            Some(vec!["web", "ui", "utils"])
+        );
+        assert_eq!(graph.shortest_path("utils", "web"), None);
+        // docs asks for a version of ui that the workspace doesn't satisfy
+        assert!(graph.dependencies("docs").is_empty());
+        assert_eq!(
+            graph.external_dependencies("docs"),
+            BTreeMap::from([("ui", "^2.0.0")])
+        );
+        assert_eq!(
+            graph.external_dependencies("web"),
+            BTreeMap::from([("react", "^18.2.0")])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_detects_cycles() -> Result<()> {
+        let dir = TempDir::new()?;
+        write_workspace(
+            &dir,
+            "",
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["packages/*"] }"#,
+        );
+        write_workspace(
+            &dir,
+            "packages/a",
+            r#"{ "name": "a", "dependencies": { "b": "*" } }"#,
+        );
+        write_workspace(
+            &dir,
+            "packages/b",
+            r#"{ "name": "b", "dependencies": { "a": "file:../a" } }"#,
+        );
+
+        let graph = build(&dir)?;
+        assert_eq!(graph.dependencies("b"), BTreeSet::from(["a"]));
+        assert!(graph.validate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_workspace_names() {
+        let dir = TempDir::new().unwrap();
+        write_workspace(
+            &dir,
+            "",
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["packages/*"] }"#,
+        );
+        write_workspace(&dir, "packages/a", r#"{ "name": "a" }"#);
+        write_workspace(&dir, "packages/b", r#"{ "name": "a" }"#);
+
+        assert!(build(&dir).is_err());
     }
 }
@@ -1,6 +1,19 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::config::{
+    schema::{JsonSchema, Property, Schema},
+    RawOutputMode, RawTaskDefinition,
+};
+
+const ENV_PIPELINE_DELIMITER: &str = "$";
+const TOPOLOGICAL_PIPELINE_DELIMITER: &str = "^";
 
 pub type Pipeline = HashMap<String, BookkeepingTaskDefinition>;
 
@@ -12,6 +25,20 @@ pub struct BookkeepingTaskDefinition {
     task_definition: TaskDefinitionHashable,
 }
 
+impl BookkeepingTaskDefinition {
+    /// Tasks that must complete in the workspace's dependencies first, written
+    /// as `^task` in `dependsOn`
+    pub fn topological_dependencies(&self) -> &[String] {
+        &self.task_definition.topological_dependencies
+    }
+
+    /// Tasks in the same workspace, or `workspace#task`, that must complete
+    /// first
+    pub fn task_dependencies(&self) -> &[String] {
+        &self.task_definition.task_dependencies
+    }
+}
+
 // A list of config fields in a task definition that are considered
 // experimental. We keep these separated so we can compute a global hash without
 // these.
@@ -60,6 +87,8 @@ struct TaskDefinitionHashable {
     inputs: Vec<String>,
     output_mode: TaskOutputMode,
     persistent: bool,
+    timeout: Option<Duration>,
+    retries: TaskRetries,
 }
 
 // task_definition is a representation of the configFile pipeline for further
@@ -97,4 +126,319 @@ pub struct TaskDefinition {
     // Persistent indicates whether the Task is expected to exit or not
     // Tasks marked Persistent do not exit (e.g. --watch mode or dev servers)
     persistent: bool,
+
+    // Timeout is how long a single attempt of the Task may run before its
+    // process group is killed
+    timeout: Option<Duration>,
+
+    // Retries determines how many times a failed Task is re-run before the
+    // failure is reported
+    retries: TaskRetries,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TaskDefinitionError {
+    #[error("\"timeout\" must be a positive duration such as \"90s\" or \"10m\", found \"{0}\"")]
+    InvalidTimeout(String),
+    #[error("\"retries.backoff\" must be a duration such as \"500ms\" or \"5s\", found \"{0}\"")]
+    InvalidBackoff(String),
+    #[error(
+        "You specified \"{value}\" in the \"{key}\" key. You should not prefix your environment \
+         variables with \"$\""
+    )]
+    PrefixedEnvVar { key: &'static str, value: String },
+}
+
+impl TaskDefinitionError {
+    // key returns the task definition key that caused the error
+    pub fn key(&self) -> &'static str {
+        match self {
+            TaskDefinitionError::InvalidTimeout(_) => "timeout",
+            TaskDefinitionError::InvalidBackoff(_) => "retries",
+            TaskDefinitionError::PrefixedEnvVar { key, .. } => key,
+        }
+    }
+}
+
+// TaskRetries configures how many more times a task is run after it fails,
+// and how long to wait before each retry
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskRetries {
+    pub count: u32,
+    pub backoff: Duration,
+}
+
+// RawTaskRetries accepts either a bare count (`"retries": 2`) or an object
+// with a count and a backoff (`"retries": {"count": 2, "backoff": "1s"}`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RawTaskRetries {
+    Count(u32),
+    Config {
+        #[serde(default)]
+        count: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        backoff: Option<String>,
+    },
+}
+
+impl JsonSchema for RawTaskRetries {
+    fn schema() -> Schema {
+        Schema::OneOf(vec![
+            u32::schema(),
+            Schema::Object(vec![
+                Property {
+                    name: "count",
+                    description: Some("How many more times to run the task if it fails".into()),
+                    schema: u32::schema(),
+                },
+                Property {
+                    name: "backoff",
+                    description: Some(
+                        "How long to wait before each retry, e.g. \"500ms\" or \"5s\"".into(),
+                    ),
+                    schema: String::schema(),
+                },
+            ]),
+        ])
+    }
+}
+
+impl TryFrom<RawTaskRetries> for TaskRetries {
+    type Error = TaskDefinitionError;
+
+    fn try_from(raw: RawTaskRetries) -> Result<Self, Self::Error> {
+        match raw {
+            RawTaskRetries::Count(count) => Ok(TaskRetries {
+                count,
+                backoff: Duration::ZERO,
+            }),
+            RawTaskRetries::Config { count, backoff } => {
+                let backoff = backoff
+                    .map(|backoff| {
+                        parse_duration(&backoff).ok_or(TaskDefinitionError::InvalidBackoff(backoff))
+                    })
+                    .transpose()?
+                    .unwrap_or_default();
+                Ok(TaskRetries { count, backoff })
+            }
+        }
+    }
+}
+
+impl From<RawOutputMode> for TaskOutputMode {
+    fn from(raw: RawOutputMode) -> Self {
+        match raw {
+            RawOutputMode::Full => TaskOutputMode::FullTaskOutput,
+            RawOutputMode::None => TaskOutputMode::NoTaskOutput,
+            RawOutputMode::HashOnly => TaskOutputMode::HashTaskOutput,
+            RawOutputMode::NewOnly => TaskOutputMode::NewTaskOutput,
+            RawOutputMode::ErrorsOnly => TaskOutputMode::ErrorTaskOutput,
+        }
+    }
+}
+
+// Resolves a task definition as written in turbo.json, keeping track of which
+// fields were actually present so that definitions can be merged later
+impl TryFrom<RawTaskDefinition> for BookkeepingTaskDefinition {
+    type Error = TaskDefinitionError;
+
+    fn try_from(raw: RawTaskDefinition) -> Result<Self, Self::Error> {
+        let mut btd = BookkeepingTaskDefinition::default();
+        let mut define = |field: &str| btd.defined_fields.insert(field.to_owned());
+        let task_definition = &mut btd.task_definition;
+
+        if let Some(outputs) = raw.outputs {
+            define("Outputs");
+            for glob in outputs {
+                match glob.strip_prefix('!') {
+                    Some(exclusion) => task_definition
+                        .outputs
+                        .exclusions
+                        .push(exclusion.to_owned()),
+                    None => task_definition.outputs.inclusions.push(glob),
+                }
+            }
+            task_definition.outputs.inclusions.sort();
+            task_definition.outputs.exclusions.sort();
+        }
+
+        task_definition.should_cache = raw.cache.unwrap_or(true);
+        if raw.cache.is_some() {
+            define("Cache");
+        }
+
+        let mut env_var_dependencies = HashSet::new();
+        if let Some(depends_on) = raw.depends_on {
+            define("DependsOn");
+            for dependency in depends_on {
+                if let Some(env_var) = dependency.strip_prefix(ENV_PIPELINE_DELIMITER) {
+                    warn!(
+                        "[DEPRECATED] Declaring an environment variable in \"dependsOn\" is \
+                         deprecated, found {}. Use the \"env\" key or use `npx @turbo/codemod \
+                         migrate-env-var-dependencies`.",
+                        dependency
+                    );
+                    define("Env");
+                    env_var_dependencies.insert(env_var.to_owned());
+                } else if let Some(topological) =
+                    dependency.strip_prefix(TOPOLOGICAL_PIPELINE_DELIMITER)
+                {
+                    task_definition
+                        .topological_dependencies
+                        .push(topological.to_owned());
+                } else {
+                    task_definition.task_dependencies.push(dependency);
+                }
+            }
+            task_definition.topological_dependencies.sort();
+            task_definition.task_dependencies.sort();
+        }
+
+        if let Some(env) = raw.env {
+            define("Env");
+            env_var_dependencies.extend(gather_env_vars(env, "env")?);
+        }
+        task_definition.env_var_dependencies = env_var_dependencies.into_iter().collect();
+        task_definition.env_var_dependencies.sort();
+
+        if let Some(inputs) = raw.inputs {
+            define("Inputs");
+            task_definition.inputs = inputs;
+        }
+
+        if let Some(output_mode) = raw.output_mode {
+            define("OutputMode");
+            task_definition.output_mode = output_mode.into();
+        }
+
+        if let Some(persistent) = raw.persistent {
+            define("Persistent");
+            task_definition.persistent = persistent;
+        }
+
+        if let Some(timeout) = raw.timeout {
+            define("Timeout");
+            task_definition.timeout = Some(parse_timeout(&timeout)?);
+        }
+
+        if let Some(retries) = raw.retries {
+            define("Retries");
+            task_definition.retries = retries.try_into()?;
+        }
+
+        if let Some(pass_through_env) = raw.pass_through_env {
+            btd.experimental_fields.insert("PassThroughEnv".to_owned());
+            let mut pass_through_env = gather_env_vars(pass_through_env, "passThroughEnv")?;
+            pass_through_env.sort();
+            btd.experimental.passthrough_env = pass_through_env;
+        }
+
+        Ok(btd)
+    }
+}
+
+// gather_env_vars rejects env vars that still use the deprecated `$` prefix
+fn gather_env_vars(
+    vars: Vec<String>,
+    key: &'static str,
+) -> Result<Vec<String>, TaskDefinitionError> {
+    vars.into_iter()
+        .map(|value| {
+            if value.starts_with(ENV_PIPELINE_DELIMITER) {
+                Err(TaskDefinitionError::PrefixedEnvVar { key, value })
+            } else {
+                Ok(value)
+            }
+        })
+        .collect()
+}
+
+// parse_timeout validates the `timeout` key of a task definition, which uses
+// the same duration syntax as Go's time.ParseDuration
+pub fn parse_timeout(timeout: &str) -> Result<Duration, TaskDefinitionError> {
+    parse_duration(timeout)
+        .filter(|timeout| !timeout.is_zero())
+        .ok_or_else(|| TaskDefinitionError::InvalidTimeout(timeout.to_owned()))
+}
+
+fn parse_duration(duration: &str) -> Option<Duration> {
+    let nanos = go_parse_duration::parse_duration(duration).ok()?;
+    u64::try_from(nanos).ok().map(Duration::from_nanos)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use test_case::test_case;
+
+    use super::{
+        parse_timeout, BookkeepingTaskDefinition, RawTaskRetries, TaskDefinitionError, TaskRetries,
+    };
+    use crate::config::RawTaskDefinition;
+
+    #[test_case("90s", Ok(Duration::from_secs(90)) ; "seconds")]
+    #[test_case("1m30s", Ok(Duration::from_secs(90)) ; "compound")]
+    #[test_case("0s", Err(TaskDefinitionError::InvalidTimeout("0s".into())) ; "zero")]
+    #[test_case("-5m", Err(TaskDefinitionError::InvalidTimeout("-5m".into())) ; "negative")]
+    #[test_case("10 minutes", Err(TaskDefinitionError::InvalidTimeout("10 minutes".into())) ; "invalid")]
+    fn test_parse_timeout(timeout: &str, expected: Result<Duration, TaskDefinitionError>) {
+        assert_eq!(parse_timeout(timeout), expected);
+    }
+
+    #[test_case("2", Ok(TaskRetries { count: 2, backoff: Duration::ZERO }) ; "count")]
+    #[test_case(r#"{"count": 3, "backoff": "5s"}"#, Ok(TaskRetries { count: 3, backoff: Duration::from_secs(5) }) ; "config")]
+    #[test_case(r#"{"count": 1}"#, Ok(TaskRetries { count: 1, backoff: Duration::ZERO }) ; "config without backoff")]
+    #[test_case(r#"{"count": 1, "backoff": "soon"}"#, Err(TaskDefinitionError::InvalidBackoff("soon".into())) ; "invalid backoff")]
+    fn test_task_retries(raw: &str, expected: Result<TaskRetries, TaskDefinitionError>) {
+        let raw: RawTaskRetries = serde_json::from_str(raw).unwrap();
+        assert_eq!(TaskRetries::try_from(raw), expected);
+    }
+
+    #[test]
+    fn test_task_definition_from_raw() {
+        let raw: RawTaskDefinition = serde_json::from_str(
+            r#"{
+                "dependsOn": ["^build", "codegen", "$LEGACY_VAR"],
+                "outputs": ["dist/**", "!dist/cache/**"],
+                "env": ["NODE_ENV"],
+                "retries": 1
+            }"#,
+        )
+        .unwrap();
+
+        let btd = BookkeepingTaskDefinition::try_from(raw).unwrap();
+        let mut defined_fields: Vec<_> = btd.defined_fields.iter().map(String::as_str).collect();
+        defined_fields.sort();
+        assert_eq!(
+            defined_fields,
+            vec!["DependsOn", "Env", "Outputs", "Retries"]
+        );
+        let task = &btd.task_definition;
+        assert!(task.should_cache);
+        assert_eq!(task.topological_dependencies, vec!["build"]);
+        assert_eq!(task.task_dependencies, vec!["codegen"]);
+        assert_eq!(task.env_var_dependencies, vec!["LEGACY_VAR", "NODE_ENV"]);
+        assert_eq!(task.outputs.inclusions, vec!["dist/**"]);
+        assert_eq!(task.outputs.exclusions, vec!["dist/cache/**"]);
+        assert_eq!(task.retries.count, 1);
+    }
+
+    #[test]
+    fn test_prefixed_env_var_is_rejected() {
+        let raw: RawTaskDefinition = serde_json::from_str(r#"{ "env": ["$A"] }"#).unwrap();
+        assert_eq!(
+            BookkeepingTaskDefinition::try_from(raw).unwrap_err(),
+            TaskDefinitionError::PrefixedEnvVar {
+                key: "env",
+                value: "$A".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_negative_retries_are_rejected() {
+        assert!(serde_json::from_str::<RawTaskRetries>("-1").is_err());
+    }
 }
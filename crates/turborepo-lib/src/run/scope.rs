@@ -1,15 +1,293 @@
 use std::collections::HashSet;
 
-use anyhow::Result;
-use tracing::warn;
+use anyhow::{anyhow, bail, Result};
+use glob_match::glob_match;
+use lazy_static::lazy_static;
+use regex::Regex;
 
-use crate::{commands::CommandBase, opts::ScopeOpts, run::package_graph};
+use crate::{
+    commands::CommandBase,
+    opts::ScopeOpts,
+    run::{package_graph::PackageGraph, task_id::ROOT_PKG_NAME},
+};
 
+lazy_static! {
+    // name, then {directory}, then [git range], each of them optional
+    static ref SELECTOR: Regex = Regex::new(
+        r"^(?P<name>[^.](?:[^{}\[\]]*[^{}\[\].])?)?(?P<directory>\{[^}]*\})?(?P<commits>(?:\.{3})?\[[^\]]+\])?$"
+    )
+    .unwrap();
+}
+
+/// A single `--filter` selector, e.g. `...^web` or `./packages/*`
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TargetSelector {
+    pub include_dependencies: bool,
+    pub include_dependents: bool,
+    pub exclude: bool,
+    pub exclude_self: bool,
+    pub name_pattern: String,
+    pub parent_dir: Option<String>,
+    pub git_range: Option<String>,
+}
+
+impl TargetSelector {
+    pub fn parse(raw: &str) -> Result<Self> {
+        let mut selector = TargetSelector::default();
+        let mut rest = raw;
+
+        if let Some(stripped) = rest.strip_prefix('!') {
+            selector.exclude = true;
+            rest = stripped;
+        }
+        if let Some(stripped) = rest.strip_prefix("...") {
+            selector.include_dependents = true;
+            rest = stripped;
+            if let Some(stripped) = rest.strip_prefix('^') {
+                selector.exclude_self = true;
+                rest = stripped;
+            }
+        }
+        if let Some(stripped) = rest.strip_suffix("...") {
+            selector.include_dependencies = true;
+            rest = stripped;
+            if let Some(stripped) = rest.strip_suffix('^') {
+                selector.exclude_self = true;
+                rest = stripped;
+            }
+        }
+
+        if rest.is_empty() {
+            bail!("invalid filter \"{raw}\": no workspace selected");
+        }
+
+        match SELECTOR.captures(rest) {
+            Some(captures) => {
+                selector.name_pattern = captures
+                    .name("name")
+                    .map(|name| name.as_str().to_string())
+                    .unwrap_or_default();
+                selector.parent_dir = captures.name("directory").map(|directory| {
+                    let directory = directory.as_str();
+                    directory[1..directory.len() - 1].to_string()
+                });
+                selector.git_range = captures
+                    .name("commits")
+                    .map(|commits| commits.as_str().to_string());
+            }
+            None if rest.starts_with('.') => selector.parent_dir = Some(rest.to_string()),
+            None => selector.name_pattern = rest.to_string(),
+        }
+
+        Ok(selector)
+    }
+
+    /// The workspaces this selector picks out, before exclusion is applied
+    fn evaluate<'a>(&self, package_graph: &'a PackageGraph) -> Result<HashSet<&'a str>> {
+        if let Some(git_range) = &self.git_range {
+            bail!("filtering by changed files ({git_range}) is not supported yet");
+        }
+
+        let parent_dir = self.parent_dir.as_deref().map(normalize_dir);
+        let matched: HashSet<&str> = package_graph
+            .workspaces()
+            .filter(|(name, info)| {
+                let name_matches = match self.name_pattern.as_str() {
+                    "" => name.as_str() != ROOT_PKG_NAME,
+                    ROOT_PKG_NAME => name.as_str() == ROOT_PKG_NAME,
+                    pattern => name.as_str() != ROOT_PKG_NAME && glob_match(pattern, name),
+                };
+                let dir_matches = parent_dir.as_deref().map_or(true, |dir| {
+                    dir == info.path || (!dir.is_empty() && glob_match(dir, &info.path))
+                });
+                name_matches && dir_matches
+            })
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        if matched.is_empty() && parent_dir.is_none() && !self.name_pattern.contains('*') {
+            bail!(
+                "No package found with name '{}' in workspace",
+                self.name_pattern
+            );
+        }
+
+        let mut selected = HashSet::new();
+        for name in &matched {
+            if !self.exclude_self {
+                selected.insert(*name);
+            }
+            if self.include_dependencies {
+                selected.extend(package_graph.transitive_dependencies(name));
+            }
+            if self.include_dependents {
+                selected.extend(package_graph.transitive_dependents(name));
+            }
+        }
+
+        Ok(selected)
+    }
+}
+
+/// Turns a directory selector into the unix style repository relative paths
+/// that workspaces are stored with
+fn normalize_dir(dir: &str) -> String {
+    let dir = dir.trim_start_matches("./").trim_end_matches('/');
+    match dir {
+        "." => String::new(),
+        dir => dir.to_string(),
+    }
+}
+
+#[tracing::instrument(skip_all)]
 pub fn resolve_packages(
-    _opts: &ScopeOpts,
+    opts: &ScopeOpts,
     _base: &CommandBase,
-    _ctx: &package_graph::PackageGraph,
+    ctx: &PackageGraph,
 ) -> Result<HashSet<String>> {
-    warn!("resolve packages not implemented yet");
-    Ok(HashSet::new())
+    let all_workspaces = || {
+        ctx.workspaces()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| *name != ROOT_PKG_NAME)
+            .collect::<HashSet<_>>()
+    };
+    if opts.filter_patterns.is_empty() {
+        return Ok(all_workspaces().into_iter().map(str::to_string).collect());
+    }
+
+    let selectors = opts
+        .filter_patterns
+        .iter()
+        .map(|pattern| {
+            TargetSelector::parse(pattern).map_err(|err| anyhow!("invalid filter: {err}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let (excludes, includes): (Vec<_>, Vec<_>) =
+        selectors.iter().partition(|selector| selector.exclude);
+
+    let mut packages = if includes.is_empty() {
+        all_workspaces()
+    } else {
+        let mut packages = HashSet::new();
+        for selector in includes {
+            packages.extend(selector.evaluate(ctx)?);
+        }
+        packages
+    };
+    for selector in excludes {
+        for excluded in selector.evaluate(ctx)? {
+            packages.remove(excluded);
+        }
+    }
+
+    Ok(packages.into_iter().map(str::to_string).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::{get_version, package_json::PackageJson, ui::UI, Args};
+
+    #[test]
+    fn test_parse_target_selector() {
+        assert_eq!(
+            TargetSelector::parse("...^web").unwrap(),
+            TargetSelector {
+                include_dependents: true,
+                exclude_self: true,
+                name_pattern: "web".to_string(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            TargetSelector::parse("!@scope/*...").unwrap(),
+            TargetSelector {
+                exclude: true,
+                include_dependencies: true,
+                name_pattern: "@scope/*".to_string(),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            TargetSelector::parse("./apps/*").unwrap(),
+            TargetSelector {
+                parent_dir: Some("./apps/*".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            TargetSelector::parse("web{./apps/web}[main]").unwrap(),
+            TargetSelector {
+                name_pattern: "web".to_string(),
+                parent_dir: Some("./apps/web".to_string()),
+                git_range: Some("[main]".to_string()),
+                ..Default::default()
+            }
+        );
+        assert!(TargetSelector::parse("...").is_err());
+    }
+
+    fn resolve(patterns: &[&str]) -> Result<Vec<String>> {
+        let dir = TempDir::new()?;
+        let write = |path: &str, contents: &str| {
+            let directory = dir.path().join(path);
+            fs::create_dir_all(&directory).unwrap();
+            fs::write(directory.join("package.json"), contents).unwrap();
+        };
+        write(
+            "",
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["apps/*", "packages/*"] }"#,
+        );
+        write(
+            "apps/web",
+            r#"{ "name": "web", "dependencies": { "ui": "*" } }"#,
+        );
+        write(
+            "apps/docs",
+            r#"{ "name": "docs", "dependencies": { "ui": "*" } }"#,
+        );
+        write(
+            "packages/ui",
+            r#"{ "name": "ui", "dependencies": { "utils": "*" } }"#,
+        );
+        write("packages/utils", r#"{ "name": "utils" }"#);
+
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        let root_package_json =
+            PackageJson::load(repo_root.join_component("package.json").as_absolute_path())?;
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+        let package_graph = PackageGraph::build_multi_package_graph(&base, &root_package_json)?;
+        let opts = ScopeOpts {
+            filter_patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+        };
+
+        let mut packages: Vec<_> = resolve_packages(&opts, &base, &package_graph)?
+            .into_iter()
+            .collect();
+        packages.sort();
+        Ok(packages)
+    }
+
+    #[test]
+    fn test_resolve_packages() -> Result<()> {
+        assert_eq!(resolve(&[])?, vec!["docs", "ui", "utils", "web"]);
+        assert_eq!(resolve(&["web"])?, vec!["web"]);
+        assert_eq!(resolve(&["web..."])?, vec!["ui", "utils", "web"]);
+        assert_eq!(resolve(&["web^..."])?, vec!["ui", "utils"]);
+        assert_eq!(resolve(&["...ui"])?, vec!["docs", "ui", "web"]);
+        assert_eq!(resolve(&["...^ui"])?, vec!["docs", "web"]);
+        assert_eq!(resolve(&["./apps/*"])?, vec!["docs", "web"]);
+        assert_eq!(resolve(&["{packages/ui}"])?, vec!["ui"]);
+        assert_eq!(resolve(&["!docs"])?, vec!["ui", "utils", "web"]);
+        assert_eq!(resolve(&["...utils", "!ui"])?, vec!["docs", "utils", "web"]);
+        assert!(resolve(&["missing"]).is_err());
+        assert!(resolve(&["web[main]"]).is_err());
+
+        Ok(())
+    }
 }
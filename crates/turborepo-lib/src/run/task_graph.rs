@@ -0,0 +1,246 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use anyhow::{anyhow, bail, Result};
+use petgraph::{graph::NodeIndex, Direction};
+
+use crate::run::{
+    package_graph::PackageGraph,
+    pipeline::{BookkeepingTaskDefinition, Pipeline},
+    task_id::{
+        get_package_task_from_id, get_task_id, is_package_task, strip_package_name, ROOT_PKG_NAME,
+    },
+};
+
+/// The tasks a run would execute. Edges point from a task to the tasks that
+/// have to finish before it can start.
+pub struct TaskGraph {
+    graph: petgraph::Graph<String, ()>,
+    node_lookup: HashMap<String, NodeIndex>,
+}
+
+impl TaskGraph {
+    #[tracing::instrument(skip_all)]
+    pub fn build(
+        package_graph: &PackageGraph,
+        pipeline: &Pipeline,
+        tasks: &[String],
+        packages: &HashSet<String>,
+    ) -> Result<Self> {
+        let mut task_graph = TaskGraph {
+            graph: petgraph::Graph::new(),
+            node_lookup: HashMap::new(),
+        };
+
+        let mut packages: Vec<_> = packages.iter().collect();
+        packages.sort();
+        let mut queue = Vec::new();
+        for task in tasks {
+            let defined = pipeline.keys().any(|key| strip_package_name(key) == *task);
+            if !defined && !is_package_task(task) {
+                bail!("Could not find the following tasks in project: {task}");
+            }
+            for package in &packages {
+                if task_definition(pipeline, package, task).is_some() {
+                    queue.push(get_task_id(package, task));
+                }
+            }
+        }
+
+        let mut visited = HashSet::new();
+        while let Some(task_id) = queue.pop() {
+            if !visited.insert(task_id.clone()) {
+                continue;
+            }
+            let (package, task) = get_package_task_from_id(&task_id);
+            let definition = task_definition(pipeline, &package, &task).ok_or_else(|| {
+                anyhow!("Could not find \"{task}\" in root turbo.json or \"{package}\" workspace")
+            })?;
+
+            let node = task_graph.add_task(&task_id);
+            for dependency in definition.topological_dependencies() {
+                for dependency_package in package_graph.dependencies(&package) {
+                    let dependency_id = get_task_id(dependency_package, dependency);
+                    let dependency_node = task_graph.add_task(&dependency_id);
+                    task_graph.graph.update_edge(node, dependency_node, ());
+                    queue.push(dependency_id);
+                }
+            }
+            for dependency in definition.task_dependencies() {
+                let dependency_id = get_task_id(&package, dependency);
+                let dependency_node = task_graph.add_task(&dependency_id);
+                task_graph.graph.update_edge(node, dependency_node, ());
+                queue.push(dependency_id);
+            }
+        }
+
+        if let Err(cycle) = petgraph::algo::toposort(&task_graph.graph, None) {
+            bail!(
+                "Invalid task dependency graph: cyclic dependency detected involving {}",
+                task_graph.graph[cycle.node_id()]
+            );
+        }
+
+        Ok(task_graph)
+    }
+
+    /// Every task id, with each task listed after the tasks it depends on
+    pub fn tasks(&self) -> Vec<&str> {
+        let mut sorted = petgraph::algo::toposort(&self.graph, None)
+            .expect("task graph is validated to be acyclic when built");
+        sorted.reverse();
+        sorted
+            .into_iter()
+            .map(|node| self.graph[node].as_str())
+            .collect()
+    }
+
+    /// The tasks that have to finish before `task_id` starts
+    pub fn dependencies(&self, task_id: &str) -> BTreeSet<&str> {
+        let Some(node) = self.node_lookup.get(task_id) else {
+            return BTreeSet::new();
+        };
+        self.graph
+            .neighbors_directed(*node, Direction::Outgoing)
+            .map(|dependency| self.graph[dependency].as_str())
+            .collect()
+    }
+
+    fn add_task(&mut self, task_id: &str) -> NodeIndex {
+        if let Some(node) = self.node_lookup.get(task_id) {
+            return *node;
+        }
+        let node = self.graph.add_node(task_id.to_string());
+        self.node_lookup.insert(task_id.to_string(), node);
+        node
+    }
+}
+
+/// Looks up the definition of `task` in `package`, preferring a
+/// `package#task` entry over the generic one. Root tasks are only run when
+/// they're declared as `//#task`.
+fn task_definition<'a>(
+    pipeline: &'a Pipeline,
+    package: &str,
+    task: &str,
+) -> Option<&'a BookkeepingTaskDefinition> {
+    pipeline
+        .get(&get_task_id(package, task))
+        .or_else(|| match package {
+            ROOT_PKG_NAME => None,
+            _ => pipeline.get(task),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use tempfile::TempDir;
+    use turbopath::AbsoluteSystemPathBuf;
+
+    use super::*;
+    use crate::{
+        commands::CommandBase, config::TurboJson, get_version, package_json::PackageJson, ui::UI,
+        Args,
+    };
+
+    fn build(tasks: &[&str], packages: &[&str]) -> Result<Vec<(String, Vec<String>)>> {
+        let dir = TempDir::new()?;
+        let write = |path: &str, contents: &str| {
+            let directory = dir.path().join(path);
+            fs::create_dir_all(&directory).unwrap();
+            fs::write(directory.join("package.json"), contents).unwrap();
+        };
+        write(
+            "",
+            r#"{ "packageManager": "npm@8.19.4", "workspaces": ["apps/*", "packages/*"] }"#,
+        );
+        write(
+            "apps/web",
+            r#"{ "name": "web", "dependencies": { "ui": "*" } }"#,
+        );
+        write("packages/ui", r#"{ "name": "ui" }"#);
+        fs::write(
+            dir.path().join("turbo.json"),
+            r#"{
+                "pipeline": {
+                    "build": { "dependsOn": ["^build", "codegen"] },
+                    "codegen": {},
+                    "web#deploy": { "dependsOn": ["build", "//#check"] },
+                    "//#check": {}
+                }
+            }"#,
+        )?;
+
+        let repo_root = AbsoluteSystemPathBuf::new(dir.path())?;
+        let root_package_json =
+            PackageJson::load(repo_root.join_component("package.json").as_absolute_path())?;
+        let turbo_json =
+            TurboJson::load(repo_root.join_component("turbo.json").as_absolute_path())?;
+        let base = CommandBase::new(Args::default(), repo_root, get_version(), UI::new(true))?;
+        let package_graph = PackageGraph::build_multi_package_graph(&base, &root_package_json)?;
+
+        let task_graph = TaskGraph::build(
+            &package_graph,
+            &turbo_json.pipeline,
+            &tasks
+                .iter()
+                .map(|task| task.to_string())
+                .collect::<Vec<_>>(),
+            &packages.iter().map(|package| package.to_string()).collect(),
+        )?;
+        let tasks = task_graph
+            .tasks()
+            .into_iter()
+            .map(|task| {
+                (
+                    task.to_string(),
+                    task_graph
+                        .dependencies(task)
+                        .into_iter()
+                        .map(str::to_string)
+                        .collect(),
+                )
+            })
+            .collect();
+        Ok(tasks)
+    }
+
+    #[test]
+    fn test_build_follows_dependencies() -> Result<()> {
+        let tasks = build(&["build"], &["web"])?;
+        let ids: Vec<_> = tasks.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids.len(), 4);
+        assert_eq!(ids.last(), Some(&"web#build"));
+        let web_build = &tasks.last().unwrap().1;
+        assert_eq!(web_build, &vec!["ui#build", "web#codegen"]);
+        // Dependencies always come first
+        assert!(
+            ids.iter().position(|id| *id == "ui#codegen")
+                < ids.iter().position(|id| *id == "ui#build")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_package_tasks() -> Result<()> {
+        let tasks = build(&["deploy"], &["web", "ui"])?;
+        let ids: BTreeSet<_> = tasks.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(
+            ids,
+            BTreeSet::from([
+                "//#check",
+                "ui#build",
+                "ui#codegen",
+                "web#build",
+                "web#codegen",
+                "web#deploy"
+            ])
+        );
+
+        assert!(build(&["missing"], &["web"]).is_err());
+
+        Ok(())
+    }
+}
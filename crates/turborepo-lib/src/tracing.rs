@@ -40,9 +40,19 @@ type DaemonLog = tracing_subscriber::fmt::Layer<
 
 type Layered = tracing_subscriber::layer::Layered<StdOutLog, Registry>;
 
+#[cfg(feature = "run-stub")]
+type DaemonLayered =
+    tracing_subscriber::layer::Layered<reload::Layer<Option<DaemonLog>, Layered>, Layered>;
+
+#[cfg(feature = "run-stub")]
+type ProfileLayer = tracing_chrome::ChromeLayer<DaemonLayered>;
+
 pub struct TurboSubscriber {
     update: Handle<Option<DaemonLog>, Layered>,
 
+    #[cfg(feature = "run-stub")]
+    profile_update: Handle<Option<ProfileLayer>, DaemonLayered>,
+
     /// The non-blocking file logger only continues to log while this guard is
     /// held. We keep it here so that it doesn't get dropped.
     guard: Mutex<Option<WorkerGuard>>,
@@ -95,6 +105,14 @@ impl TurboSubscriber {
 
         let registry = Registry::default().with(stdout).with(logrotate);
 
+        // like the daemon logger, the `--profile` trace is only enabled on
+        // request
+        #[cfg(feature = "run-stub")]
+        let (registry, profile_update) = {
+            let (profile, profile_update) = reload::Layer::new(Option::<ProfileLayer>::None);
+            (registry.with(profile), profile_update)
+        };
+
         #[cfg(feature = "tracing-chrome")]
         let (registry, chrome_guard) = {
             let (chrome_layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
@@ -107,6 +125,8 @@ impl TurboSubscriber {
 
         Self {
             update,
+            #[cfg(feature = "run-stub")]
+            profile_update,
             guard: Mutex::new(None),
             #[cfg(feature = "tracing-chrome")]
             chrome_guard,
@@ -128,6 +148,44 @@ impl TurboSubscriber {
 
         Ok(())
     }
+
+    /// Starts recording spans as a Chrome trace, which is written to `path`
+    /// once the returned guard is dropped.
+    ///
+    /// The file can be opened in `chrome://tracing` or Perfetto.
+    #[cfg(feature = "run-stub")]
+    pub fn enable_chrome_tracing(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<ChromeTraceGuard, Error> {
+        let (layer, flush_guard) = tracing_chrome::ChromeLayerBuilder::new()
+            .file(path)
+            .include_args(true)
+            .build();
+        self.profile_update.reload(Some(layer))?;
+
+        Ok(ChromeTraceGuard {
+            update: self.profile_update.clone(),
+            _flush_guard: flush_guard,
+        })
+    }
+}
+
+/// Writes the recorded Chrome trace to disk when dropped.
+#[cfg(feature = "run-stub")]
+pub struct ChromeTraceGuard {
+    update: Handle<Option<ProfileLayer>, DaemonLayered>,
+    // Dropped after the layer is disabled, finishing the file
+    _flush_guard: tracing_chrome::FlushGuard,
+}
+
+#[cfg(feature = "run-stub")]
+impl Drop for ChromeTraceGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.update.reload(None) {
+            tracing::warn!("failed to disable chrome tracing: {}", e);
+        }
+    }
 }
 
 /// The formatter for TURBOREPO
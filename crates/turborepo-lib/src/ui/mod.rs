@@ -1,8 +1,13 @@
+mod output;
+mod tui;
+
 use std::{borrow::Cow, env, f64::consts::PI, time::Duration};
 
 use console::{Style, StyledObject};
 use indicatif::{ProgressBar, ProgressStyle};
 use lazy_static::lazy_static;
+pub use output::{CacheStatus, RunOutput, StreamOutput, TaskEvent};
+pub use tui::Tui;
 
 pub fn start_spinner(message: &str) -> ProgressBar {
     let pb = ProgressBar::new_spinner();
@@ -110,6 +115,7 @@ lazy_static! {
     pub static ref CYAN: Style = Style::new().cyan();
     pub static ref BOLD: Style = Style::new().bold();
     pub static ref MAGENTA: Style = Style::new().magenta();
+    pub static ref YELLOW: Style = Style::new().yellow();
     pub static ref UNDERLINE: Style = Style::new().underlined();
 }
 
@@ -0,0 +1,222 @@
+//! Reports the progress and output of a run's tasks, either as lines
+//! prefixed with the task they came from or in the terminal UI.
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    time::Duration,
+};
+
+use anyhow::Result;
+use console::Style;
+use is_terminal::IsTerminal;
+use lazy_static::lazy_static;
+use tracing::debug;
+
+use crate::{
+    cli::UiMode,
+    ui::{tui::Tui, UI},
+};
+
+lazy_static! {
+    // Prefixes are colored in turn, so neighbouring tasks are easy to tell apart
+    static ref PREFIX_STYLES: [Style; 5] = [
+        Style::new().cyan(),
+        Style::new().magenta(),
+        Style::new().green(),
+        Style::new().yellow(),
+        Style::new().blue(),
+    ];
+}
+
+// Tasks only start once they're executed by the Rust code
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    /// Caching is turned off for the task
+    Disabled,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskEvent {
+    /// The task is part of the run and waiting on its dependencies
+    Planned {
+        task_id: String,
+    },
+    Started {
+        task_id: String,
+        cache: CacheStatus,
+    },
+    /// A line the task printed, or replayed from the cache
+    Output {
+        task_id: String,
+        line: String,
+    },
+    Finished {
+        task_id: String,
+        success: bool,
+        duration: Duration,
+    },
+}
+
+pub enum RunOutput {
+    Stream(StreamOutput<io::Stdout>),
+    Tui(Tui),
+}
+
+impl RunOutput {
+    /// Shows the terminal UI if it was asked for, unless stdout isn't a
+    /// terminal or we're running in CI, where output is streamed instead
+    pub fn new(ui: &UI, mode: UiMode) -> Self {
+        if should_use_tui(ui, mode, io::stdout().is_terminal()) {
+            match Tui::start() {
+                Ok(tui) => return RunOutput::Tui(tui),
+                Err(err) => debug!("failed to start the terminal UI, streaming output: {err}"),
+            }
+        }
+        RunOutput::Stream(StreamOutput::new(io::stdout(), ui.should_strip_ansi))
+    }
+
+    pub fn send(&mut self, event: TaskEvent) -> Result<()> {
+        match self {
+            RunOutput::Stream(output) => output.send(event),
+            RunOutput::Tui(tui) => {
+                tui.send(event);
+                Ok(())
+            }
+        }
+    }
+
+    /// Called once every task has finished
+    pub fn finish(self) -> Result<()> {
+        match self {
+            RunOutput::Stream(mut output) => Ok(output.writer.flush()?),
+            RunOutput::Tui(tui) => tui.finish(),
+        }
+    }
+}
+
+fn should_use_tui(ui: &UI, mode: UiMode, is_terminal: bool) -> bool {
+    mode == UiMode::Tui && is_terminal && !ui.is_ci()
+}
+
+/// Writes each line of task output prefixed with the task it came from
+pub struct StreamOutput<W> {
+    writer: W,
+    ui: UI,
+    prefixes: HashMap<String, String>,
+}
+
+impl StreamOutput<io::Stdout> {
+    pub fn stdout() -> Self {
+        Self::new(io::stdout(), UI::infer().should_strip_ansi)
+    }
+}
+
+impl<W: Write> StreamOutput<W> {
+    pub fn new(writer: W, should_strip_ansi: bool) -> Self {
+        Self {
+            writer,
+            ui: UI::new(should_strip_ansi),
+            prefixes: HashMap::new(),
+        }
+    }
+
+    pub fn send(&mut self, event: TaskEvent) -> Result<()> {
+        match event {
+            TaskEvent::Planned { .. } => {}
+            TaskEvent::Started { task_id, cache } => {
+                let message = match cache {
+                    CacheStatus::Hit => "cache hit, replaying output",
+                    CacheStatus::Miss => "cache miss, executing",
+                    CacheStatus::Disabled => "cache bypass, force executing",
+                };
+                let prefix = self.prefix(&task_id);
+                writeln!(self.writer, "{prefix} {message}")?;
+            }
+            TaskEvent::Output { task_id, line } => {
+                let prefix = self.prefix(&task_id);
+                writeln!(self.writer, "{prefix} {line}")?;
+            }
+            TaskEvent::Finished {
+                task_id, success, ..
+            } => {
+                if !success {
+                    let prefix = self.prefix(&task_id);
+                    writeln!(self.writer, "{prefix} ERROR: command finished with error")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn prefix(&mut self, task_id: &str) -> String {
+        let next = self.prefixes.len();
+        let ui = &self.ui;
+        self.prefixes
+            .entry(task_id.to_string())
+            .or_insert_with(|| {
+                let style = &PREFIX_STYLES[next % PREFIX_STYLES.len()];
+                ui.apply(style.apply_to(format!("{task_id}:"))).to_string()
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stream_output() -> Result<()> {
+        let mut output = StreamOutput::new(Vec::new(), true);
+        let events = [
+            TaskEvent::Planned {
+                task_id: "ui#build".to_string(),
+            },
+            TaskEvent::Started {
+                task_id: "ui#build".to_string(),
+                cache: CacheStatus::Miss,
+            },
+            TaskEvent::Output {
+                task_id: "ui#build".to_string(),
+                line: "> tsc".to_string(),
+            },
+            TaskEvent::Finished {
+                task_id: "ui#build".to_string(),
+                success: false,
+                duration: Duration::from_secs(1),
+            },
+        ];
+        for event in events {
+            output.send(event)?;
+        }
+
+        assert_eq!(
+            String::from_utf8(output.writer)?,
+            "ui#build: cache miss, executing\nui#build: > tsc\nui#build: ERROR: command finished \
+             with error\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefixes_are_colored_in_turn() {
+        let mut output = StreamOutput::new(Vec::new(), false);
+        assert_eq!(output.prefix("ui#build"), "\u{1b}[36mui#build:\u{1b}[0m");
+        assert_eq!(output.prefix("web#build"), "\u{1b}[35mweb#build:\u{1b}[0m");
+        assert_eq!(output.prefix("ui#build"), "\u{1b}[36mui#build:\u{1b}[0m");
+    }
+
+    #[test]
+    fn test_falls_back_to_streaming() {
+        let ui = UI::new(false);
+        let in_ci = std::env::var_os("CI").is_some();
+        assert_eq!(should_use_tui(&ui, UiMode::Tui, true), !in_ci);
+        assert!(!should_use_tui(&ui, UiMode::Tui, false));
+        assert!(!should_use_tui(&ui, UiMode::Stream, true));
+    }
+}
@@ -0,0 +1,627 @@
+//! A full-screen view of a run: a list of tasks on the left and the output of
+//! the selected task on the right.
+
+use std::{
+    collections::HashMap,
+    io::{self, Stdout},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+
+use crate::ui::output::{CacheStatus, StreamOutput, TaskEvent};
+
+/// How often the screen is redrawn while nothing else is happening, which
+/// keeps the durations of running tasks ticking
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How many lines PageUp and PageDown scroll the output by
+const SCROLL_STEP: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TaskStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug)]
+struct TaskState {
+    id: String,
+    status: TaskStatus,
+    cache: Option<CacheStatus>,
+    started_at: Option<Instant>,
+    duration: Option<Duration>,
+    output: Vec<String>,
+    pinned: bool,
+}
+
+impl TaskState {
+    fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            status: TaskStatus::Pending,
+            cache: None,
+            started_at: None,
+            duration: None,
+            output: Vec::new(),
+            pinned: false,
+        }
+    }
+
+    fn elapsed(&self) -> Option<Duration> {
+        self.duration
+            .or_else(|| self.started_at.map(|started_at| started_at.elapsed()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Continue,
+    Interrupt,
+}
+
+/// Everything the terminal UI shows, kept apart from the terminal so it can
+/// be driven by tests
+#[derive(Debug, Default)]
+struct App {
+    tasks: Vec<TaskState>,
+    lookup: HashMap<String, usize>,
+    selected: Option<String>,
+    search: String,
+    searching: bool,
+    /// How many lines the output has been scrolled up from the bottom
+    scroll: usize,
+}
+
+impl App {
+    fn task_mut(&mut self, task_id: &str) -> &mut TaskState {
+        let index = match self.lookup.get(task_id) {
+            Some(index) => *index,
+            None => {
+                self.tasks.push(TaskState::new(task_id));
+                self.lookup
+                    .insert(task_id.to_string(), self.tasks.len() - 1);
+                self.tasks.len() - 1
+            }
+        };
+        &mut self.tasks[index]
+    }
+
+    fn selected_task(&self) -> Option<&TaskState> {
+        let index = self.lookup.get(self.selected.as_deref()?)?;
+        Some(&self.tasks[*index])
+    }
+
+    fn handle_event(&mut self, event: TaskEvent) {
+        match event {
+            TaskEvent::Planned { task_id } => {
+                self.task_mut(&task_id);
+            }
+            TaskEvent::Started { task_id, cache } => {
+                let task = self.task_mut(&task_id);
+                task.status = TaskStatus::Running;
+                task.cache = Some(cache);
+                task.started_at = Some(Instant::now());
+                if self.selected.is_none() {
+                    self.selected = Some(task_id);
+                }
+            }
+            TaskEvent::Output { task_id, line } => {
+                let line = console::strip_ansi_codes(&line).into_owned();
+                self.task_mut(&task_id).output.push(line);
+            }
+            TaskEvent::Finished {
+                task_id,
+                success,
+                duration,
+            } => {
+                let task = self.task_mut(&task_id);
+                task.duration = Some(duration);
+                if success {
+                    task.status = TaskStatus::Succeeded;
+                } else {
+                    // Failed tasks are pinned so they don't get lost among the others
+                    task.status = TaskStatus::Failed;
+                    task.pinned = true;
+                }
+            }
+        }
+        self.ensure_selection();
+    }
+
+    /// The tasks in the order they're listed, pinned tasks first and limited
+    /// to the ones matching the search
+    fn visible(&self) -> Vec<&TaskState> {
+        let search = self.search.to_lowercase();
+        let matching = self
+            .tasks
+            .iter()
+            .filter(|task| task.id.to_lowercase().contains(&search));
+        let (mut pinned, unpinned): (Vec<_>, Vec<_>) = matching.partition(|task| task.pinned);
+        pinned.extend(unpinned);
+        pinned
+    }
+
+    fn selected_index(&self) -> Option<usize> {
+        let selected = self.selected.as_deref()?;
+        self.visible().iter().position(|task| task.id == selected)
+    }
+
+    fn select(&mut self, index: usize) {
+        let id = self.visible().get(index).map(|task| task.id.clone());
+        if id.is_some() && id != self.selected {
+            self.selected = id;
+            self.scroll = 0;
+        }
+    }
+
+    /// Moves the selection onto a listed task if it isn't on one
+    fn ensure_selection(&mut self) {
+        if self.selected_index().is_none() {
+            self.selected = None;
+            self.select(0);
+        }
+    }
+
+    fn move_selection(&mut self, offset: isize) {
+        let visible = self.visible().len();
+        if visible == 0 {
+            return;
+        }
+        let current = self.selected_index().unwrap_or(0) as isize;
+        let index = (current + offset).clamp(0, visible as isize - 1);
+        self.select(index as usize);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if key.kind != KeyEventKind::Press {
+            return Action::Continue;
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Action::Interrupt;
+        }
+
+        if self.searching {
+            match key.code {
+                KeyCode::Char(c) => self.search.push(c),
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.searching = false;
+                }
+                _ => {}
+            }
+            self.ensure_selection();
+            return Action::Continue;
+        }
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Home | KeyCode::Char('g') => self.select(0),
+            KeyCode::End | KeyCode::Char('G') => {
+                self.select(self.visible().len().saturating_sub(1))
+            }
+            KeyCode::PageUp => {
+                let lines = self.selected_task().map_or(0, |task| task.output.len());
+                self.scroll = (self.scroll + SCROLL_STEP).min(lines);
+            }
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(SCROLL_STEP),
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Esc => {
+                self.search.clear();
+                self.ensure_selection();
+            }
+            KeyCode::Char('p') => {
+                if let Some(selected) = self.selected.clone() {
+                    let task = self.task_mut(&selected);
+                    task.pinned = !task.pinned;
+                }
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+}
+
+/// Handle to the terminal UI, which is drawn from its own thread
+pub struct Tui {
+    sender: Sender<Message>,
+    thread: Option<JoinHandle<Result<App>>>,
+}
+
+enum Message {
+    Event(TaskEvent),
+    Done,
+}
+
+impl Tui {
+    /// Switches the terminal over to the UI
+    pub fn start() -> Result<Self> {
+        enable_raw_mode()?;
+        let terminal = (|| {
+            let mut stdout = io::stdout();
+            execute!(stdout, EnterAlternateScreen)?;
+            Terminal::new(CrosstermBackend::new(stdout))
+        })();
+        let terminal = match terminal {
+            Ok(terminal) => terminal,
+            Err(err) => {
+                disable_raw_mode()?;
+                return Err(err.into());
+            }
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let thread = thread::spawn(move || run(terminal, receiver));
+
+        Ok(Self {
+            sender,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn send(&self, event: TaskEvent) {
+        // The UI is already gone if the run was interrupted
+        let _ = self.sender.send(Message::Event(event));
+    }
+
+    /// Gives the terminal back and prints the output of the tasks that failed,
+    /// since it would otherwise disappear along with the UI
+    pub fn finish(mut self) -> Result<()> {
+        let app = self.stop()?;
+        let mut output = StreamOutput::stdout();
+        for task in app
+            .tasks
+            .iter()
+            .filter(|task| task.status == TaskStatus::Failed)
+        {
+            for line in &task.output {
+                output.send(TaskEvent::Output {
+                    task_id: task.id.clone(),
+                    line: line.clone(),
+                })?;
+            }
+            output.send(TaskEvent::Finished {
+                task_id: task.id.clone(),
+                success: false,
+                duration: task.duration.unwrap_or_default(),
+            })?;
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<App> {
+        let _ = self.sender.send(Message::Done);
+        let thread = self
+            .thread
+            .take()
+            .ok_or_else(|| anyhow!("terminal UI was already stopped"))?;
+        thread
+            .join()
+            .map_err(|_| anyhow!("terminal UI thread panicked"))?
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            let _ = self.stop();
+        }
+    }
+}
+
+fn run(
+    mut terminal: Terminal<CrosstermBackend<Stdout>>,
+    receiver: Receiver<Message>,
+) -> Result<App> {
+    let result = event_loop(&mut terminal, &receiver);
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    let (app, action) = result?;
+    if action == Action::Interrupt {
+        interrupt();
+    }
+    Ok(app)
+}
+
+fn event_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    receiver: &Receiver<Message>,
+) -> Result<(App, Action)> {
+    let mut app = App::default();
+    loop {
+        loop {
+            match receiver.try_recv() {
+                Ok(Message::Event(event)) => app.handle_event(event),
+                Ok(Message::Done) | Err(TryRecvError::Disconnected) => {
+                    return Ok((app, Action::Continue))
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        terminal.draw(|frame| render(frame, &app))?;
+
+        if event::poll(FRAME_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                if app.handle_key(key) == Action::Interrupt {
+                    return Ok((app, Action::Interrupt));
+                }
+            }
+        }
+    }
+}
+
+/// Raw mode stops the terminal from turning Ctrl-C into a SIGINT, so send
+/// it ourselves to stop the run the way it normally would be
+fn interrupt() {
+    #[cfg(unix)]
+    // SAFETY: libc::kill only sends a signal, 0 targets our own process group
+    unsafe {
+        libc::kill(0, libc::SIGINT);
+    }
+}
+
+fn render<B: Backend>(frame: &mut Frame<B>, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(frame.size());
+
+    let visible = app.visible();
+    let list_width = visible
+        .iter()
+        .map(|task| task.id.len() as u16 + 20)
+        .max()
+        .unwrap_or(20)
+        .min(rows[0].width / 2);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(list_width), Constraint::Min(1)])
+        .split(rows[0]);
+
+    let items: Vec<_> = visible.iter().map(|task| task_item(task)).collect();
+    let mut list_state = ListState::default();
+    list_state.select(app.selected_index());
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Tasks"))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, columns[0], &mut list_state);
+
+    let output_block = Block::default()
+        .borders(Borders::ALL)
+        .title(app.selected.as_deref().unwrap_or("Output"));
+    let output = app.selected_task().map_or(&[][..], |task| &task.output);
+    let height = columns[1].height.saturating_sub(2) as usize;
+    let top = output
+        .len()
+        .saturating_sub(height)
+        .saturating_sub(app.scroll);
+    let lines: Vec<_> = output
+        .iter()
+        .map(|line| Spans::from(line.as_str()))
+        .collect();
+    let paragraph = Paragraph::new(lines)
+        .block(output_block)
+        .scroll((top as u16, 0));
+    frame.render_widget(paragraph, columns[1]);
+
+    let footer = if app.searching {
+        format!("/{}", app.search)
+    } else if !app.search.is_empty() {
+        format!("filter: {}  esc clear", app.search)
+    } else {
+        "↑/↓ select  / search  p pin  pgup/pgdn scroll  ctrl-c stop".to_string()
+    };
+    frame.render_widget(
+        Paragraph::new(Span::styled(
+            footer,
+            Style::default().add_modifier(Modifier::DIM),
+        )),
+        rows[1],
+    );
+}
+
+fn task_item(task: &TaskState) -> ListItem<'static> {
+    let (symbol, color) = match task.status {
+        TaskStatus::Pending => ("·", Color::DarkGray),
+        TaskStatus::Running => ("▶", Color::Cyan),
+        TaskStatus::Succeeded => ("✓", Color::Green),
+        TaskStatus::Failed => ("✗", Color::Red),
+    };
+    let mut id_style = Style::default();
+    if task.pinned {
+        id_style = id_style.add_modifier(Modifier::BOLD);
+    }
+    let cache = match task.cache {
+        Some(CacheStatus::Hit) => "HIT",
+        Some(CacheStatus::Miss) => "MISS",
+        Some(CacheStatus::Disabled) | None => "",
+    };
+    let duration = task.elapsed().map(format_duration).unwrap_or_default();
+
+    ListItem::new(Spans::from(vec![
+        Span::styled(format!("{symbol} "), Style::default().fg(color)),
+        Span::styled(task.id.clone(), id_style),
+        Span::styled(
+            format!(" {cache:>4} {duration:>7}"),
+            Style::default().add_modifier(Modifier::DIM),
+        ),
+    ]))
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds < 60.0 {
+        format!("{seconds:.1}s")
+    } else {
+        let seconds = duration.as_secs();
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn app(tasks: &[&str]) -> App {
+        let mut app = App::default();
+        for task in tasks {
+            app.handle_event(TaskEvent::Planned {
+                task_id: task.to_string(),
+            });
+        }
+        app
+    }
+
+    fn listed(app: &App) -> Vec<&str> {
+        app.visible().iter().map(|task| task.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut app = app(&["ui#build", "web#build", "docs#build"]);
+        assert_eq!(app.selected.as_deref(), Some("ui#build"));
+
+        app.handle_key(key(KeyCode::Down));
+        app.handle_key(key(KeyCode::Char('j')));
+        app.handle_key(key(KeyCode::Down));
+        assert_eq!(app.selected.as_deref(), Some("docs#build"));
+        app.handle_key(key(KeyCode::Char('k')));
+        assert_eq!(app.selected.as_deref(), Some("web#build"));
+        app.handle_key(key(KeyCode::Home));
+        assert_eq!(app.selected.as_deref(), Some("ui#build"));
+
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Action::Interrupt
+        );
+    }
+
+    #[test]
+    fn test_search() {
+        let mut app = app(&["ui#build", "web#build", "web#test"]);
+        for code in [
+            KeyCode::Char('/'),
+            KeyCode::Char('W'),
+            KeyCode::Char('e'),
+            KeyCode::Char('b'),
+        ] {
+            app.handle_key(key(code));
+        }
+        assert_eq!(listed(&app), vec!["web#build", "web#test"]);
+        assert_eq!(app.selected.as_deref(), Some("web#build"));
+
+        // Keys go to the search until it's confirmed
+        app.handle_key(key(KeyCode::Char('j')));
+        assert!(listed(&app).is_empty());
+        app.handle_key(key(KeyCode::Backspace));
+        app.handle_key(key(KeyCode::Enter));
+        app.handle_key(key(KeyCode::Char('j')));
+        assert_eq!(app.selected.as_deref(), Some("web#test"));
+
+        app.handle_key(key(KeyCode::Esc));
+        assert_eq!(listed(&app).len(), 3);
+        assert_eq!(app.selected.as_deref(), Some("web#test"));
+    }
+
+    #[test]
+    fn test_failed_tasks_are_pinned() {
+        let mut app = app(&["ui#build", "web#build", "docs#build"]);
+        app.handle_event(TaskEvent::Started {
+            task_id: "docs#build".to_string(),
+            cache: CacheStatus::Miss,
+        });
+        app.handle_event(TaskEvent::Output {
+            task_id: "docs#build".to_string(),
+            line: "\u{1b}[31merror\u{1b}[0m: missing page".to_string(),
+        });
+        app.handle_event(TaskEvent::Finished {
+            task_id: "docs#build".to_string(),
+            success: false,
+            duration: Duration::from_secs(3),
+        });
+        assert_eq!(listed(&app), vec!["docs#build", "ui#build", "web#build"]);
+        assert_eq!(app.tasks[2].output, vec!["error: missing page"]);
+
+        // Pinning is a toggle, and pinned tasks keep the order of the run
+        app.handle_key(key(KeyCode::End));
+        app.handle_key(key(KeyCode::Char('p')));
+        assert_eq!(listed(&app), vec!["web#build", "docs#build", "ui#build"]);
+        app.handle_key(key(KeyCode::Char('p')));
+        assert_eq!(listed(&app), vec!["docs#build", "ui#build", "web#build"]);
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let mut app = app(&["ui#build", "web#build"]);
+        app.handle_event(TaskEvent::Started {
+            task_id: "ui#build".to_string(),
+            cache: CacheStatus::Hit,
+        });
+        for line in ["> tsc", "done"] {
+            app.handle_event(TaskEvent::Output {
+                task_id: "ui#build".to_string(),
+                line: line.to_string(),
+            });
+        }
+        app.handle_event(TaskEvent::Finished {
+            task_id: "ui#build".to_string(),
+            success: true,
+            duration: Duration::from_millis(1500),
+        });
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 8))?;
+        terminal.draw(|frame| render(frame, &app))?;
+        let buffer = terminal.backend().buffer();
+        let screen: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer.get(x, y).symbol.as_str())
+                    .collect()
+            })
+            .collect();
+
+        assert!(screen[1].contains("✓ ui#build  HIT    1.5s"));
+        assert!(screen[2].contains("· web#build"));
+        assert!(screen[1].contains("│> tsc"));
+        assert!(screen[2].contains("│done"));
+        assert!(screen[7].starts_with("↑/↓ select"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(300)), "0.3s");
+        assert_eq!(format_duration(Duration::from_secs(75)), "1m 15s");
+    }
+}
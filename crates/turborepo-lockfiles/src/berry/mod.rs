@@ -17,7 +17,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use self::resolution::{parse_resolution, Resolution};
-use super::Lockfile;
+use super::{Lockfile, PackageMetadata};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -432,6 +432,22 @@ impl<'a> Lockfile for BerryLockfile<'a> {
         // For each dependency we need to check if there's an override
         Ok(Some(map))
     }
+
+    fn package_metadata(&self, key: &str) -> Result<Option<PackageMetadata>, crate::Error> {
+        let locator =
+            Locator::try_from(key).map_err(|_| crate::Error::InvalidPackageKey(key.to_string()))?;
+
+        let Some(package) = self.locator_package.get(&locator) else {
+            return Ok(None);
+        };
+
+        Ok(Some(PackageMetadata {
+            name: locator.ident.to_string(),
+            resolved: Some(package.resolution.clone()),
+            integrity: None,
+            checksum: package.checksum.clone(),
+        }))
+    }
 }
 
 impl LockfileData {
@@ -570,6 +586,33 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_package_metadata() {
+        let data: LockfileData =
+            serde_yaml::from_str(include_str!("../../fixtures/berry.lock")).unwrap();
+        let lockfile = BerryLockfile::new(&data, None).unwrap();
+
+        let metadata = lockfile
+            .package_metadata("@babel/code-frame@npm:7.18.6")
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.name, "@babel/code-frame");
+        assert_eq!(
+            metadata.resolved.as_deref(),
+            Some("@babel/code-frame@npm:7.18.6")
+        );
+        assert_eq!(metadata.integrity, None);
+        assert!(metadata.checksum.unwrap().starts_with("195e2be3172d"));
+        assert_eq!(
+            lockfile.package_metadata("missing@npm:1.0.0").unwrap(),
+            None
+        );
+        assert!(matches!(
+            lockfile.package_metadata("no-reference"),
+            Err(crate::Error::InvalidPackageKey(_))
+        ));
+    }
+
     #[test]
     fn test_package_extension_detection() {
         let data: LockfileData =
@@ -6,6 +6,8 @@ pub enum Error {
     MissingWorkspace(String),
     #[error("No lockfile entry found for '{0}'")]
     MissingPackage(String),
+    #[error("Invalid package key '{0}'")]
+    InvalidPackageKey(String),
     #[error("Missing version from non-workspace package: '{0}'")]
     MissingVersion(String),
     #[error("Unable to convert from json: {0}")]
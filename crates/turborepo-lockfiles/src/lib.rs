@@ -17,6 +17,21 @@ pub struct Package {
     pub version: String,
 }
 
+/// What a lockfile records about a resolved package beyond its version
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct PackageMetadata {
+    pub name: String,
+    /// Where the package was fetched from, a tarball URL for npm and a
+    /// locator for berry
+    pub resolved: Option<String>,
+    /// Subresource Integrity string of the published tarball, e.g.
+    /// `sha512-...`
+    pub integrity: Option<String>,
+    /// Berry's checksum of the package's cache archive. Unlike `integrity`
+    /// this isn't a hash of the published tarball.
+    pub checksum: Option<String>,
+}
+
 // This trait will only be used when migrating the Go lockfile implementations
 // to Rust. Once the migration is complete we will leverage petgraph for doing
 // our graph calculations.
@@ -32,9 +47,12 @@ pub trait Lockfile {
     // Given a lockfile key return all (prod/dev/optional) dependencies of that
     // package
     fn all_dependencies(&self, key: &str) -> Result<Option<HashMap<String, String>>, Error>;
+    // Given a lockfile key return the package's name along with where it was
+    // resolved from and how to verify it
+    fn package_metadata(&self, key: &str) -> Result<Option<PackageMetadata>, Error>;
 }
 
-pub fn all_transitive_closures<L: Lockfile + Sync>(
+pub fn all_transitive_closures<L: Lockfile + Sync + ?Sized>(
     lockfile: &L,
     workspaces: HashMap<String, HashMap<String, String>>,
 ) -> Result<HashMap<String, HashSet<Package>>, Error> {
@@ -48,7 +66,7 @@ pub fn all_transitive_closures<L: Lockfile + Sync>(
 }
 
 // this should get replaced by petgraph in the future :)
-pub fn transitive_closure<L: Lockfile>(
+pub fn transitive_closure<L: Lockfile + ?Sized>(
     lockfile: &L,
     workspace_path: &str,
     unresolved_deps: HashMap<String, String>,
@@ -64,7 +82,7 @@ pub fn transitive_closure<L: Lockfile>(
     Ok(transitive_deps)
 }
 
-fn transitive_closure_helper<L: Lockfile>(
+fn transitive_closure_helper<L: Lockfile + ?Sized>(
     lockfile: &L,
     workspace_path: &str,
     unresolved_deps: HashMap<String, impl AsRef<str>>,
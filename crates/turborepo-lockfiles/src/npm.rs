@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use super::{Error, Lockfile, Package};
+use super::{Error, Lockfile, Package, PackageMetadata};
 
 type Map<K, V> = std::collections::BTreeMap<K, V>;
 
@@ -99,6 +99,33 @@ impl Lockfile for NpmLockfile {
             })
             .transpose()
     }
+
+    fn package_metadata(&self, key: &str) -> Result<Option<PackageMetadata>, Error> {
+        let Some(pkg) = self.packages.get(key) else {
+            return Ok(None);
+        };
+        // Aliased packages record the real package name, otherwise the name is
+        // the last node_modules segment of the key
+        let name = match pkg.other.get("name").and_then(Value::as_str) {
+            Some(name) => name.to_string(),
+            None => key
+                .rsplit_once("node_modules/")
+                .map_or(key, |(_, name)| name)
+                .to_string(),
+        };
+        let integrity = pkg
+            .other
+            .get("integrity")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        Ok(Some(PackageMetadata {
+            name,
+            resolved: pkg.resolved.clone(),
+            integrity,
+            checksum: None,
+        }))
+    }
 }
 
 impl NpmLockfile {
@@ -311,6 +338,34 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_package_metadata() -> Result<(), Error> {
+        let lockfile = NpmLockfile::load(include_bytes!("../fixtures/npm-lock.json"))?;
+        assert_eq!(
+            lockfile.package_metadata("node_modules/lodash")?,
+            Some(PackageMetadata {
+                name: "lodash".into(),
+                resolved: Some("https://registry.npmjs.org/lodash/-/lodash-3.10.1.tgz".into()),
+                integrity: Some(
+                    "sha512-9mDDwqVIma6OZX79ZlDACZl8sBm0TEnkf99zV3iMA4GzkIT/9hiqP5mY0HoT1iNLCrKc/\
+                     R1HByV+yJfRWVJryQ=="
+                        .into()
+                ),
+                checksum: None,
+            })
+        );
+        assert_eq!(
+            lockfile
+                .package_metadata(
+                    "node_modules/@babel/generator/node_modules/@jridgewell/gen-mapping"
+                )?
+                .map(|metadata| metadata.name),
+            Some("@jridgewell/gen-mapping".into())
+        );
+        assert_eq!(lockfile.package_metadata("node_modules/missing")?, None);
+        Ok(())
+    }
+
     #[test]
     fn test_all_dependencies() -> Result<(), Error> {
         let lockfile = NpmLockfile::load(include_bytes!("../fixtures/npm-lock.json"))?;
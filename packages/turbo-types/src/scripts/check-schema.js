@@ -0,0 +1,109 @@
+#!/usr/bin/env node
+
+// Fails when the types in `src/types/config.ts` have drifted from the
+// `schema.json` that turbo generates from its Rust config. Descriptions and
+// defaults are left out: the check compares which keys exist, their types,
+// and the values of enums.
+
+const tsj = require("ts-json-schema-generator");
+const path = require("path");
+
+const rustSchema = require("../../../../crates/turborepo-lib/schema.json");
+
+/** @type {import('ts-json-schema-generator/dist/src/Config').Config} */
+const config = {
+  path: path.join(__dirname, "../index.ts"),
+  tsconfig: path.join(__dirname, "../../tsconfig.json"),
+  type: "Schema",
+};
+const tsSchema = tsj.createGenerator(config).createSchema(config.type);
+
+function resolve(root, node) {
+  while (node && node.$ref) {
+    const name = decodeURIComponent(node.$ref.replace("#/definitions/", ""));
+    node = root.definitions[name];
+  }
+  return node;
+}
+
+/**
+ * Records the types and enum values of every key reachable from `node`.
+ * Object keys are joined with `.`, record values are `*` and array items
+ * are `[]`. Variants of a union are merged into the same paths, so a key
+ * that's only in the workspace schema is treated like any other key.
+ */
+function collect(root, node, keyPath, shape) {
+  node = resolve(root, node);
+  if (!node || typeof node !== "object") {
+    return;
+  }
+  const entry = shape.get(keyPath) || { types: new Set(), values: new Set() };
+  shape.set(keyPath, entry);
+
+  for (const variant of [...(node.anyOf || []), ...(node.oneOf || [])]) {
+    collect(root, variant, keyPath, shape);
+  }
+  for (const type of [].concat(node.type || [])) {
+    // Whether a key can be null or is an integer isn't expressible in both
+    if (type !== "null") {
+      entry.types.add(type === "integer" ? "number" : type);
+    }
+  }
+  for (const value of node.enum || []) {
+    entry.values.add(value);
+  }
+  if (node.const !== undefined) {
+    entry.values.add(node.const);
+  }
+  for (const [key, property] of Object.entries(node.properties || {})) {
+    collect(root, property, `${keyPath}.${key}`, shape);
+  }
+  if (typeof node.additionalProperties === "object") {
+    collect(root, node.additionalProperties, `${keyPath}.*`, shape);
+  }
+  if (node.items) {
+    collect(root, node.items, `${keyPath}[]`, shape);
+  }
+}
+
+function shapeOf(schema) {
+  const shape = new Map();
+  collect(schema, schema, "", shape);
+  return shape;
+}
+
+function format(set) {
+  return [...set].sort().join(" | ") || "(none)";
+}
+
+const expected = shapeOf(rustSchema);
+const actual = shapeOf(tsSchema);
+const errors = [];
+for (const keyPath of new Set([...expected.keys(), ...actual.keys()])) {
+  const want = expected.get(keyPath);
+  const got = actual.get(keyPath);
+  if (!got) {
+    errors.push(`${keyPath || "(root)"} is missing from config.ts`);
+  } else if (!want) {
+    errors.push(`${keyPath || "(root)"} is not in schema.json`);
+  } else {
+    for (const field of ["types", "values"]) {
+      if (format(want[field]) !== format(got[field])) {
+        errors.push(
+          `${keyPath || "(root)"} has ${field} ${format(got[field])} in ` +
+            `config.ts but ${format(want[field])} in schema.json`
+        );
+      }
+    }
+  }
+}
+
+if (errors.length > 0) {
+  console.error(
+    "config.ts is out of sync with crates/turborepo-lib/schema.json:"
+  );
+  for (const error of errors) {
+    console.error(`  ${error}`);
+  }
+  process.exit(1);
+}
@@ -1,4 +1,8 @@
-/* This file generates the `schema.json` file. */
+/*
+ * Types for turbo.json. The published `schema.json` is generated from turbo's
+ * Rust config (crates/turborepo-lib/schema.json), and `pnpm test` fails when
+ * these types no longer match it.
+ */
 export type Schema = RootSchema | WorkspaceSchema;
 
 export interface BaseSchema {
@@ -110,6 +114,20 @@ export interface RootSchema extends BaseSchema {
    * @default {}
    */
   remoteCache?: RemoteCache;
+
+  /**
+   * Configuration for reporting runs to a space.
+   *
+   * @default {}
+   */
+  experimentalSpaces?: Spaces;
+
+  /**
+   * Rules that `turbo boundaries` checks imports between workspaces against.
+   *
+   * @default {}
+   */
+  boundaries?: Boundaries;
 }
 
 export interface Pipeline {
@@ -248,9 +266,47 @@ export interface Pipeline {
    * @default false
    */
   persistent?: boolean;
+
+  /**
+   * How long a single run of the task may take before turbo kills it, along
+   * with any processes it started. Accepts a duration such as "90s" or "10m".
+   *
+   * @default no timeout
+   */
+  timeout?: string;
+
+  /**
+   * How many more times to run the task if it fails. Either a number of
+   * retries, or an object that also sets how long to wait between attempts.
+   * A task's outputs are only cached if its final attempt succeeds.
+   *
+   * @default 0
+   */
+  retries?: number | TaskRetries;
+}
+
+export interface TaskRetries {
+  /**
+   * How many more times to run the task if it fails.
+   *
+   * @default 0
+   */
+  count: number;
+
+  /**
+   * How long to wait before each retry, as a duration such as "500ms" or "5s".
+   *
+   * @default 0s
+   */
+  backoff?: string;
 }
 
 export interface RemoteCache {
+  /**
+   * The team id to use for remote caching.
+   */
+  teamId?: string;
+
   /**
    * Indicates if signature verification is enabled for requests to the remote cache. When
    * `true`, Turborepo will sign every uploaded artifact using the value of the environment
@@ -262,6 +318,37 @@ export interface RemoteCache {
   signature?: boolean;
 }
 
+export interface Spaces {
+  /**
+   * The id of the space that runs are reported to.
+   */
+  id?: string;
+}
+
+export interface Boundaries {
+  /**
+   * Tags to attach to workspaces, keyed by workspace name.
+   *
+   * @default {}
+   */
+  tags?: Record<string, string[]>;
+
+  /**
+   * Workspaces with any of these tags may not be imported by other workspaces.
+   *
+   * @default []
+   */
+  restrictedTags?: string[];
+
+  /**
+   * Whether workspaces marked `"private": true` may not be imported by other
+   * workspaces.
+   *
+   * @default false
+   */
+  restrictPrivate?: boolean;
+}
+
 export type OutputMode =
   | "full"
   | "hash-only"